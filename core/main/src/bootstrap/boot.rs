@@ -33,6 +33,7 @@ use super::{
     start_app_manager_step::StartAppManagerStep,
     start_communication_broker::{StartCommunicationBroker, StartOtherBrokers},
     start_fbgateway_step::FireboltGatewayStep,
+    start_services_step::StartServicesStep,
     start_ws_step::StartWsStep,
 };
 /// Starts up Ripple uses `PlatformState` to manage State
@@ -77,6 +78,8 @@ pub async fn boot(state: BootstrapState) -> RippleResponse {
         execute_step(LoadExtensionsStep, &bootstrap).await?;
     }
     log_memory_usage("After-LoadExtensionsStep");
+    execute_step(StartServicesStep, &bootstrap).await?;
+    log_memory_usage("After-StartServicesStep");
     execute_step(StartAppManagerStep, &bootstrap).await?;
     log_memory_usage("After-StartAppManagerStep");
     execute_step(StartOtherBrokers, &bootstrap).await?;
@@ -15,14 +15,15 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
-use std::thread;
-
 use ripple_sdk::{
-    api::manifest::extn_manifest::ExtnManifestEntry,
+    api::{
+        manifest::extn_manifest::{ExtnManifestEntry, ExtnRuntime},
+        status_update::{ExtnLibraryStatus, ExtnStatus},
+    },
     async_trait::async_trait,
-    extn::ffi::ffi_channel::load_channel_builder,
+    extn::ffi::ffi_abi::{load_abi_descriptor, ExtnAbiInfo},
     framework::bootstrap::Bootstep,
-    log::{debug, info, warn},
+    log::{debug, error, info, warn},
     utils::error::RippleError,
 };
 
@@ -49,7 +50,7 @@ impl LoadedLibrary {
 pub struct LoadExtensionsStep;
 
 impl LoadExtensionsStep {
-    unsafe fn load_extension_library<P: AsRef<OsStr>>(
+    pub(crate) unsafe fn load_extension_library<P: AsRef<OsStr>>(
         filename: P,
         entry: ExtnManifestEntry,
     ) -> Option<LoadedLibrary> {
@@ -62,6 +63,25 @@ impl LoadExtensionsStep {
         }
     }
 
+    /// Checks the ABI descriptor of the library before anything else is called in it
+    pub(crate) unsafe fn check_abi(
+        loaded: &LoadedLibrary,
+        host: &ExtnAbiInfo,
+    ) -> Result<(), String> {
+        let info = load_abi_descriptor(&loaded.library)
+            .map_err(|_| "library does not export an ABI descriptor".to_owned())?;
+        info.check_compatible(host)?;
+        let missing =
+            info.missing_contracts(loaded.entry.symbols.iter().flat_map(|s| s.fulfills.iter()));
+        if !missing.is_empty() {
+            return Err(format!(
+                "manifest declares contracts {:?} which the library does not fulfill",
+                missing
+            ));
+        }
+        Ok(())
+    }
+
     async fn pre_setup(&self, state: BootstrapState) -> Result<Vec<LoadedLibrary>, RippleError> {
         debug!("Starting Extension Library step");
         let manifest = state.platform_state.get_manifest();
@@ -70,12 +90,16 @@ impl LoadExtensionsStep {
         let extn_paths: Vec<(String, ExtnManifestEntry)> = manifest
             .extns
             .into_iter()
+            // WebAssembly extensions are loaded by the supervisor in setup
+            .filter(|f| matches!(f.runtime, ExtnRuntime::Native))
             .map(|f| {
                 (f.get_path(&default_path, &default_extn), f)
                 // TODO Add Resolution checks later on
             })
             .collect();
         let mut loaded_extns = Vec::new();
+        let host_abi = ExtnAbiInfo::current();
+        let client = state.platform_state.get_client().get_extn_client();
         unsafe {
             for (extn_path, entry) in extn_paths {
                 debug!("");
@@ -87,6 +111,15 @@ impl LoadExtensionsStep {
                 let r = Self::load_extension_library(extn_path.clone(), entry);
                 match r {
                     Some(loaded_extn) => {
+                        if let Err(reason) = Self::check_abi(&loaded_extn, &host_abi) {
+                            error!("Refusing incompatible extension {}: {}", extn_path, reason);
+                            let _ = client.event(ExtnLibraryStatus {
+                                path: extn_path.clone(),
+                                status: ExtnStatus::Error,
+                                reason: Some(reason),
+                            });
+                            continue;
+                        }
                         info!("Adding {}", loaded_extn.entry.path);
                         loaded_extns.push(loaded_extn);
                     }
@@ -116,16 +149,23 @@ impl Bootstep<BootstrapState> for LoadExtensionsStep {
     }
     async fn setup(&self, state: BootstrapState) -> Result<(), RippleError> {
         let loaded_extensions = self.pre_setup(state.clone()).await?;
-        for extn in loaded_extensions.iter() {
-            unsafe {
-                let path = &extn.entry.path;
-                let library = &extn.library;
-                info!("Starting library at  path {}", path);
-                if let Ok(builder) = load_channel_builder(library) {
-                    thread::spawn(move || {
-                        (builder.start)();
-                    });
-                }
+        let supervisor = state.platform_state.extn_supervisor.clone();
+        for extn in loaded_extensions {
+            supervisor.supervise(&state.platform_state, extn.library, extn.entry);
+        }
+        let wasm_extns = state
+            .platform_state
+            .get_manifest()
+            .extns
+            .into_iter()
+            .filter(|f| matches!(f.runtime, ExtnRuntime::Wasm(_)));
+        for entry in wasm_extns {
+            let path = entry.path.clone();
+            if let Err(e) = supervisor.load(&state.platform_state, entry) {
+                warn!(
+                    "file={} doesnt contain a valid extension module {:?}",
+                    path, e
+                );
             }
         }
 
@@ -15,6 +15,8 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
+use crate::processor::storage::storage_manager::StorageManager;
+use crate::service::privacy_sync::PrivacySync;
 use crate::state::cap::cap_state::CapState;
 use crate::state::platform_state::PlatformState;
 use crate::tokio;
@@ -48,6 +50,8 @@ impl Bootstep<BootstrapState> for LoadDistributorValuesStep {
 
     async fn setup(&self, s: BootstrapState) -> RippleResponse {
         remove_expired_and_inactive_entries(&s.platform_state);
+        warm_up_storage_cache(&s.platform_state);
+        PrivacySync::start(&s.platform_state);
 
         if !s.platform_state.supports_session() {
             return Ok(());
@@ -105,6 +109,8 @@ async fn handle_ripple_update_type(state: &PlatformState, ripple_context: &Rippl
                     state.session_state.insert_session_token(t.token.clone());
                 }
                 initialize_session(state).await;
+                // changes made while the session was not available are pushed now
+                PrivacySync::trigger(state);
             }
             RippleContextUpdateType::PowerStateChanged => {
                 handle_power_state(state, &ripple_context.system_power_state);
@@ -114,6 +120,13 @@ async fn handle_ripple_update_type(state: &PlatformState, ripple_context: &Rippl
     }
 }
 
+fn warm_up_storage_cache(state: &PlatformState) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        StorageManager::warm_up_cache(&state).await;
+    });
+}
+
 fn remove_expired_and_inactive_entries(state: &PlatformState) {
     state.cap_state.grant_state.cleanup_user_grants();
 }
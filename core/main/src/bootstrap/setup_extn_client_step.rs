@@ -16,7 +16,8 @@
 //
 
 use ripple_sdk::{
-    async_trait::async_trait, framework::bootstrap::Bootstep, utils::error::RippleError,
+    async_trait::async_trait, framework::bootstrap::Bootstep, tokio::time::Duration,
+    utils::error::RippleError,
 };
 
 use crate::processor::metrics_processor::OpMetricsProcessor;
@@ -28,6 +29,7 @@ use crate::{
         config_processor::ConfigRequestProcessor, keyboard_processor::KeyboardProcessor,
         pin_processor::PinProcessor, storage::storage_manager_processor::StorageManagerProcessor,
     },
+    service::telemetry_builder::TelemetryBuilder,
     state::bootstrap_state::BootstrapState,
 };
 
@@ -50,6 +52,17 @@ impl Bootstep<BootstrapState> for SetupExtnClientStep {
         client.add_request_processor(AuthorizedInfoProcessor::new(state.platform_state.clone()));
         client.add_request_processor(SettingsProcessor::new(state.platform_state.clone()));
         client.add_request_processor(OpMetricsProcessor::new(state.platform_state.clone()));
+        // 0 disables the periodic extension stats, they stay available through ripple.extnStats
+        let interval = state
+            .platform_state
+            .get_device_manifest()
+            .get_extn_stats_interval();
+        if interval > 0 {
+            TelemetryBuilder::start_extn_stats_reporter(
+                &state.platform_state,
+                Duration::from_secs(interval),
+            );
+        }
         Ok(())
     }
 }
@@ -0,0 +1,44 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use ripple_sdk::{
+    async_trait::async_trait, framework::bootstrap::Bootstep, log::info, utils::error::RippleError,
+};
+
+use crate::state::bootstrap_state::BootstrapState;
+
+/// Starts the service executables declared in the extension manifest, they connect back
+/// through the websocket started in [super::start_ws_step::StartWsStep]
+pub struct StartServicesStep;
+
+#[async_trait]
+impl Bootstep<BootstrapState> for StartServicesStep {
+    fn get_name(&self) -> String {
+        "StartServicesStep".into()
+    }
+
+    async fn setup(&self, state: BootstrapState) -> Result<(), RippleError> {
+        let services = state.platform_state.get_manifest().services;
+        info!("Starting {} services", services.len());
+        state
+            .platform_state
+            .service_controller_state
+            .service_supervisor
+            .start(services);
+        Ok(())
+    }
+}
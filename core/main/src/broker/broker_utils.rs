@@ -17,7 +17,13 @@
 
 use crate::state::platform_state::PlatformState;
 use jsonrpsee::core::RpcResult;
-use ripple_sdk::api::gateway::rpc_gateway_api::{CallContext, JsonRpcApiError, RpcRequest};
+use ripple_sdk::{
+    api::{
+        firebolt::fb_capabilities::CAPABILITY_NOT_PERMITTED,
+        gateway::rpc_gateway_api::{CallContext, JsonRpcApiError, RpcRequest},
+    },
+    utils::error::RippleError,
+};
 use serde_json::Value;
 
 use super::endpoint_broker::BrokerCallback;
@@ -64,6 +70,10 @@ impl BrokerUtils {
         let method = rpc_request.method.clone();
         match state.broker_rule_request(&rpc_request).await {
             Ok(res) => Ok(res),
+            Err(RippleError::Permission(_)) => Err(JsonRpcApiError::default()
+                .with_code(CAPABILITY_NOT_PERMITTED)
+                .with_message(format!("{} suppressed by data governance", method))
+                .into()),
             Err(e) => Err(JsonRpcApiError::default()
                 .with_code(-32100)
                 .with_message(format!("failed to get {} : {}", method, e))
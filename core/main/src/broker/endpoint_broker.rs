@@ -17,8 +17,10 @@
 
 use ripple_sdk::{
     api::{
+        distributor::distributor_privacy::DataEventType,
         firebolt::fb_capabilities::{
-            FireboltPermission, CAPABILITY_NOT_AVAILABLE, JSON_RPC_STANDARD_ERROR_INVALID_PARAMS,
+            DenyReason, FireboltPermission, CAPABILITY_NOT_AVAILABLE, CAPABILITY_NOT_PERMITTED,
+            JSON_RPC_STANDARD_ERROR_INVALID_PARAMS,
         },
         gateway::rpc_gateway_api::{
             ApiMessage, ApiProtocol, CallContext, JsonRpcApiRequest, JsonRpcApiResponse,
@@ -27,6 +29,7 @@ use ripple_sdk::{
         observability::log_signal::LogSignal,
         session::AccountSession,
     },
+    async_trait::async_trait,
     extn::extn_client_message::{ExtnEvent, ExtnMessage},
     framework::RippleResponse,
     log::{debug, error, info, trace},
@@ -54,7 +57,7 @@ use std::{
 use crate::{
     broker::broker_utils::BrokerUtils,
     firebolt::firebolt_gateway::JsonRpcError,
-    service::extn::ripple_client::RippleClient,
+    service::{data_governance::DataGovernance, extn::ripple_client::RippleClient},
     state::{
         ops_metrics_state::OpMetricState, platform_state::PlatformState, session_state::Session,
     },
@@ -293,6 +296,31 @@ impl BrokerCallback {
         };
         self.send_json_rpc_api_response(data).await;
     }
+    /// Sends the error for a request which was not sent by the request filter of the broker
+    pub async fn send_suppressed(&self, request: BrokerRequest) {
+        let value = serde_json::to_value(JsonRpcError {
+            code: CAPABILITY_NOT_PERMITTED,
+            message: "Suppressed by data governance".to_owned(),
+            data: None,
+        })
+        .unwrap();
+        let data = JsonRpcApiResponse {
+            jsonrpc: "2.0".to_owned(),
+            id: Some(request.rpc.ctx.call_id),
+            error: Some(value),
+            result: None,
+            method: None,
+            params: None,
+        };
+        self.send_json_rpc_api_response(data).await;
+    }
+}
+
+/// Filter applied to every request before it is sent to a broker endpoint, returns false when
+/// the request must not be sent
+#[async_trait]
+pub trait BrokerRequestFilter: Send + Sync + std::fmt::Debug {
+    async fn filter(&self, request: &mut RpcRequest) -> bool;
 }
 
 #[derive(Debug)]
@@ -381,6 +409,7 @@ pub struct EndpointBrokerState {
     reconnect_tx: Sender<BrokerConnectRequest>,
     provider_broker_state: ProvideBrokerState,
     metrics_state: OpMetricState,
+    request_filter: Arc<RwLock<Option<Arc<dyn BrokerRequestFilter>>>>,
 }
 
 #[derive(Debug)]
@@ -472,6 +501,7 @@ impl Default for EndpointBrokerState {
             reconnect_tx: mpsc::channel(2).0,
             provider_broker_state: ProvideBrokerState::default(),
             metrics_state: OpMetricState::default(),
+            request_filter: Arc::new(RwLock::new(None)),
         }
     }
 }
@@ -494,6 +524,7 @@ impl EndpointBrokerState {
             reconnect_tx,
             provider_broker_state: ProvideBrokerState::default(),
             metrics_state,
+            request_filter: Arc::new(RwLock::new(None)),
         };
         /*bobra: configuring this out for unit tests */
         #[cfg(not(test))]
@@ -511,6 +542,17 @@ impl EndpointBrokerState {
     pub fn has_rule(&self, rule: &str) -> bool {
         self.rule_engine.read().unwrap().has_rule(rule)
     }
+    pub fn set_request_filter(&self, filter: Arc<dyn BrokerRequestFilter>) {
+        let _ = self.request_filter.write().unwrap().insert(filter);
+    }
+    /// Applies the request filter, returns false when the request must not be sent
+    async fn filter_request(&self, rpc_request: &mut RpcRequest) -> bool {
+        let filter = self.request_filter.read().unwrap().clone();
+        match filter {
+            Some(filter) => filter.filter(rpc_request).await,
+            None => true,
+        }
+    }
     #[cfg(not(test))]
     fn reconnect_thread(&self, mut rx: Receiver<BrokerConnectRequest>, client: RippleClient) {
         use crate::firebolt::firebolt_gateway::FireboltGatewayCommand;
@@ -830,6 +872,15 @@ impl EndpointBrokerState {
     ) -> Result<RuleRetrieved, RuleRetrievalError> {
         self.rule_engine.read().unwrap().get_rule(rpc_request)
     }
+
+    /// Data governance policy of the rule brokering the request, rules without one use the
+    /// default data type of the method
+    pub fn get_rule_data_type(&self, rpc_request: &RpcRequest) -> Option<DataEventType> {
+        let rule = Rule::from(self.get_broker_rule(rpc_request).ok()?);
+        rule.data_type
+            .or_else(|| DataGovernance::default_data_type(&rpc_request.method))
+    }
+
     /// Main handler method which checks for brokerage and then sends the request for
     /// asynchronous processing
     pub fn handle_brokerage(
@@ -1057,8 +1108,15 @@ impl EndpointBrokerState {
                         method: Some(request.rpc.method.clone()),
                         params: request.rpc.get_params(),
                     };
-                    let request_for_spawn = request.clone();
-                    tokio::spawn(async move { endpoint.send_request(request_for_spawn).await });
+                    let mut request_for_spawn = request.clone();
+                    let state = self.clone();
+                    tokio::spawn(async move {
+                        if !state.filter_request(&mut request_for_spawn.rpc).await {
+                            broker_callback.send_suppressed(request_for_spawn).await;
+                            return Ok(());
+                        }
+                        endpoint.send_request(request_for_spawn).await
+                    });
 
                     Ok(RenderedRequest::ProviderJsonRpc(data))
                 }
@@ -1110,7 +1168,7 @@ impl EndpointBrokerState {
     /// Send a request through the broker and wait for response with a oneshot channel and custom timeout
     pub async fn send_with_response_timeout(
         &self,
-        rpc_request: RpcRequest,
+        mut rpc_request: RpcRequest,
         response_tx: ripple_sdk::tokio::sync::oneshot::Sender<Result<Value, Value>>,
         timeout_secs: u64,
     ) -> Result<(), RippleError> {
@@ -1120,6 +1178,10 @@ impl EndpointBrokerState {
             Err(_) => return Err(RippleError::NotAvailable),
         };
 
+        if !self.filter_request(&mut rpc_request).await {
+            return Err(RippleError::Permission(DenyReason::Unpermitted));
+        }
+
         // Create a custom callback that will send the response through the oneshot channel
         let (callback_tx, mut callback_rx) = mpsc::channel(1);
         let custom_callback = BrokerCallback {
@@ -2037,6 +2099,7 @@ mod endpoint_broker_tests {
                         filter: None,
                         event_handler: None,
                         sources: None,
+                        data_type: None,
                     },
                     subscription_processed: None,
                     workflow_callback: None,
@@ -2742,6 +2805,7 @@ mod endpoint_broker_tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                data_type: None,
             };
             state.update_request(&rpc_request, &rule, None, None, vec![]);
             apply_response(filter, &rpc_request.ctx.method, &mut output.data);
@@ -2787,6 +2851,7 @@ mod endpoint_broker_tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                data_type: None,
             };
             state.update_request(&rpc_request, &rule, None, None, vec![]);
             apply_response(filter, &rpc_request.ctx.method, &mut output.data);
@@ -2834,6 +2899,7 @@ mod endpoint_broker_tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                data_type: None,
             };
             state.update_request(&rpc_request, &rule, None, None, vec![]);
             apply_response(filter, &rpc_request.ctx.method, &mut output.data);
@@ -2880,6 +2946,7 @@ mod endpoint_broker_tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                data_type: None,
             };
             state.update_request(&rpc_request, &rule, None, None, vec![]);
             apply_response(filter, &rpc_request.ctx.method, &mut output.data);
@@ -2927,6 +2994,7 @@ mod endpoint_broker_tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                data_type: None,
             };
             state.update_request(&rpc_request, &rule, None, None, vec![]);
             apply_response(filter, &rpc_request.ctx.method, &mut output.data);
@@ -2965,6 +3033,7 @@ mod endpoint_broker_tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                data_type: None,
             };
             engine.add_rule(r);
 
@@ -3109,6 +3178,7 @@ mod endpoint_broker_tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                data_type: None,
             };
             engine.add_rule(rule);
             let mut under_test =
@@ -3162,6 +3232,7 @@ mod endpoint_broker_tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                data_type: None,
             };
             engine.add_rule(rule);
             let under_test = EndpointBrokerState::new(OpMetricState::default(), tx, engine, client);
@@ -3214,6 +3285,7 @@ mod endpoint_broker_tests {
                     filter: None,
                     event_handler: None,
                     sources: None,
+                    data_type: None,
                 };
 
                 let broker_request = state.update_request(&rpc_request, &rule, None, None, vec![]);
@@ -3243,6 +3315,7 @@ mod endpoint_broker_tests {
                     filter: None,
                     event_handler: None,
                     sources: None,
+                    data_type: None,
                 };
                 let extn_message = Some(ExtnMessage::default());
 
@@ -3274,6 +3347,7 @@ mod endpoint_broker_tests {
                     filter: None,
                     event_handler: None,
                     sources: None,
+                    data_type: None,
                 };
                 let workflow_callback = Some(BrokerCallback::default());
 
@@ -3311,6 +3385,7 @@ mod endpoint_broker_tests {
                     filter: None,
                     event_handler: None,
                     sources: None,
+                    data_type: None,
                 };
                 let telemetry_response_listeners = vec![channel(2).0];
 
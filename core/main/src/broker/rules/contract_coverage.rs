@@ -0,0 +1,608 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Generates Thunder consumer contracts (pact interactions) from rules files and reports which
+//! rule driven Thunder methods are not covered by a contract.
+//!
+//! Run it through the Ripple binary, which writes `interactions.json` and `coverage.json` to
+//! the output directory:
+//! `ripple contract-coverage <rules file> <contract tests dir> <output dir>
+//! [--open-rpc <file>] [--thunder-examples <file>]`
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use regex::Regex;
+use ripple_sdk::{
+    log::warn,
+    serde_json::{self, json, Map, Value},
+};
+use serde::{Deserialize, Serialize};
+
+use super::rules_engine::{jq_compile, Rule, RuleEndpointProtocol, RuleEngine, RuleSet, RuleType};
+
+const REGISTER: &str = "register";
+
+/// A Thunder call made by Ripple on behalf of a Firebolt method
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThunderCall {
+    pub firebolt_method: String,
+    pub callsign: String,
+    /// Thunder method, or the event name for event rules
+    pub method: String,
+    pub event: bool,
+    /// Method as sent by the thunder broker which is the rule alias
+    pub alias: String,
+    pub request_transform: Option<String>,
+}
+
+impl ThunderCall {
+    fn from_rule(firebolt_method: &str, rule: &Rule) -> Option<Self> {
+        let mut parts: Vec<&str> = rule.alias.trim().split('.').collect();
+        let method = parts.pop()?;
+        if parts
+            .last()
+            .map_or(false, |v| v.chars().all(char::is_numeric))
+        {
+            parts.pop();
+        }
+        if parts.is_empty() || method.is_empty() {
+            return None;
+        }
+        Some(Self {
+            firebolt_method: firebolt_method.to_owned(),
+            callsign: parts.join("."),
+            method: method.to_owned(),
+            event: is_event_rule(firebolt_method, rule),
+            alias: rule.alias.trim().to_owned(),
+            request_transform: rule.transform.request.clone(),
+        })
+    }
+
+    /// Key used to compare rule driven calls with the calls in contracts. Versions are ignored
+    /// and events are keyed by the callsign registration and the event name.
+    pub fn coverage_key(&self) -> String {
+        if self.event {
+            coverage_key(
+                &format!("{}.{}", self.callsign, REGISTER),
+                Some(&self.method),
+            )
+        } else {
+            coverage_key(&self.alias, None)
+        }
+    }
+
+    fn request_method(&self) -> String {
+        if self.event {
+            format!("{}.{}", self.callsign, REGISTER)
+        } else {
+            self.alias.clone()
+        }
+    }
+}
+
+fn is_event_rule(firebolt_method: &str, rule: &Rule) -> bool {
+    let name = firebolt_method.rsplit('.').next().unwrap_or_default();
+    rule.transform.event.is_some()
+        || rule.transform.rpcv2_event.is_some()
+        || rule.event_handler.is_some()
+        || (name.len() > 2
+            && name.starts_with("on")
+            && name[2..].starts_with(|c: char| c.is_ascii_uppercase()))
+}
+
+fn coverage_key(method: &str, event: Option<&str>) -> String {
+    let mut parts: Vec<&str> = method.split('.').collect();
+    let name = parts.pop().unwrap_or_default();
+    if parts
+        .last()
+        .map_or(false, |v| v.chars().all(char::is_numeric))
+    {
+        parts.pop();
+    }
+    parts.push(name);
+    let key = parts.join(".").to_lowercase();
+    match event {
+        Some(event) => format!("{}@{}", key, event.to_lowercase()),
+        None => key,
+    }
+}
+
+fn is_thunder_rule(rule_set: &RuleSet, rule: &Rule) -> bool {
+    if rule.rule_type() != RuleType::Endpoint {
+        return false;
+    }
+    match &rule.endpoint {
+        None => true,
+        Some(endpoint) => rule_set.endpoints.get(endpoint).map_or(false, |e| {
+            matches!(e.protocol, RuleEndpointProtocol::Thunder)
+        }),
+    }
+}
+
+/// Thunder calls of all rules which are sent to a Thunder endpoint, sorted by Firebolt method
+pub fn thunder_calls(rule_set: &RuleSet) -> Vec<ThunderCall> {
+    let mut calls: Vec<ThunderCall> = rule_set
+        .rules
+        .iter()
+        .filter(|(_, rule)| is_thunder_rule(rule_set, rule))
+        .filter_map(|(method, rule)| ThunderCall::from_rule(method, rule))
+        .collect();
+    calls.sort_by(|a, b| a.firebolt_method.cmp(&b.firebolt_method));
+    calls
+}
+
+/// Thunder request and result pair in the format of the mock device data file
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ThunderExample {
+    pub params: Option<Value>,
+    pub result: Option<Value>,
+}
+
+/// Examples used to fill generated interactions
+#[derive(Debug, Clone, Default)]
+pub struct ContractExamples {
+    /// Named Firebolt params keyed by the lowercase Firebolt method
+    firebolt: HashMap<String, Vec<Value>>,
+    /// Thunder examples keyed by the coverage key of the Thunder method
+    thunder: HashMap<String, Vec<ThunderExample>>,
+}
+
+impl ContractExamples {
+    /// Adds the params of the examples of every method in an OpenRPC document
+    pub fn add_open_rpc(&mut self, open_rpc: &Value) {
+        let methods = open_rpc.get("methods").and_then(Value::as_array);
+        for method in methods.into_iter().flatten() {
+            let Some(name) = method.get("name").and_then(Value::as_str) else {
+                continue;
+            };
+            let examples = method.get("examples").and_then(Value::as_array);
+            for example in examples.into_iter().flatten() {
+                let params: Map<String, Value> = example
+                    .get("params")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|p| {
+                        Some((p.get("name")?.as_str()?.to_owned(), p.get("value")?.clone()))
+                    })
+                    .collect();
+                self.add_firebolt_example(name, Value::Object(params));
+            }
+        }
+    }
+
+    pub fn add_firebolt_example(&mut self, method: &str, params: Value) {
+        self.firebolt
+            .entry(method.to_lowercase())
+            .or_default()
+            .push(params);
+    }
+
+    /// Adds Thunder examples keyed by Thunder method, versioned or not
+    pub fn add_thunder_examples(&mut self, examples: HashMap<String, Vec<ThunderExample>>) {
+        for (method, examples) in examples {
+            self.thunder
+                .entry(coverage_key(&method, None))
+                .or_default()
+                .extend(examples);
+        }
+    }
+
+    fn firebolt_params(&self, method: &str) -> Option<&Value> {
+        self.firebolt.get(&method.to_lowercase())?.first()
+    }
+
+    fn thunder_result(&self, call: &ThunderCall, params: Option<&Value>) -> Option<Value> {
+        let examples = self
+            .thunder
+            .get(&coverage_key(&call.request_method(), None))?;
+        let example = examples
+            .iter()
+            .find(|e| e.params.as_ref() == params)
+            .or_else(|| examples.first())?;
+        example.result.clone()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct GeneratedContracts {
+    /// Interactions in the format accepted by the `mock_websocket_server!` contract test macro
+    pub interactions: Vec<Value>,
+    /// Firebolt methods with a request transform but no example to run it on
+    pub missing_examples: Vec<String>,
+}
+
+/// Builds a pact interaction for every Thunder call made by the rules. Request params are the
+/// Firebolt example params passed through the rule request transform, the same way the thunder
+/// broker builds a request. Results come from the Thunder examples with a type matcher fallback.
+pub fn generate_contracts(rule_set: &RuleSet, examples: &ContractExamples) -> GeneratedContracts {
+    let mut generated = GeneratedContracts::default();
+    let mut seen = HashSet::new();
+
+    for call in thunder_calls(rule_set) {
+        let params = if call.event {
+            Some(json!({"event": call.method, "id": "matching(type, '0')"}))
+        } else {
+            match (
+                examples.firebolt_params(&call.firebolt_method),
+                &call.request_transform,
+            ) {
+                (Some(params), Some(filter)) => {
+                    match jq_compile(
+                        params.clone(),
+                        filter,
+                        format!("{}_contract", call.firebolt_method),
+                    ) {
+                        Ok(v) => Some(v).filter(|v| !v.is_null()),
+                        Err(e) => {
+                            warn!(
+                                "Request transform of {} failed {:?}",
+                                call.firebolt_method, e
+                            );
+                            generated.missing_examples.push(call.firebolt_method);
+                            continue;
+                        }
+                    }
+                }
+                (None, Some(_)) => {
+                    generated.missing_examples.push(call.firebolt_method);
+                    continue;
+                }
+                (Some(params), None) => Some(params.clone())
+                    .filter(|v| !v.is_null() && v.as_object().map_or(true, |o| !o.is_empty())),
+                (None, None) => None,
+            }
+        };
+
+        // Firebolt methods sharing a Thunder call need a single interaction
+        let key = format!(
+            "{}{}",
+            call.coverage_key(),
+            params.clone().unwrap_or_default()
+        );
+        if !seen.insert(key) {
+            continue;
+        }
+
+        let result = examples
+            .thunder_result(&call, params.as_ref())
+            .unwrap_or_else(|| {
+                if call.event {
+                    json!("matching(integer, 0)")
+                } else {
+                    json!({"success": "matching(boolean, true)"})
+                }
+            });
+
+        let mut request = json!({
+            "jsonrpc": "matching(type, '2.0')",
+            "id": "matching(integer, 0)",
+            "method": call.request_method(),
+        });
+        if let Some(params) = params {
+            request["params"] = params;
+        }
+        generated.interactions.push(json!({
+            "pact:content-type": "application/json",
+            "request": request,
+            "requestMetadata": {
+                "path": "/jsonrpc"
+            },
+            "response": [{
+                "jsonrpc": "matching(type, '2.0')",
+                "id": "matching(integer, 0)",
+                "result": result
+            }]
+        }));
+    }
+    generated
+}
+
+/// Coverage keys of the Thunder calls found in contract test sources or pact files
+pub fn contracted_methods(source: &str) -> HashSet<String> {
+    let method =
+        Regex::new(r#"(?:"method"\s*:\s*|get_pact(?:_with_params)?!\(\s*)"([^"]+)""#).unwrap();
+    let register = Regex::new(
+        r#""method"\s*:\s*"([^"]+\.register)"\s*,\s*"params"\s*:\s*\{\s*"event"\s*:\s*"([^"]+)""#,
+    )
+    .unwrap();
+
+    let mut methods: HashSet<String> = method
+        .captures_iter(source)
+        .map(|c| coverage_key(&c[1], None))
+        .collect();
+    methods.extend(
+        register
+            .captures_iter(source)
+            .map(|c| coverage_key(&c[1], Some(&c[2]))),
+    );
+    methods
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CoverageReport {
+    pub covered: BTreeSet<String>,
+    /// Uncovered Thunder calls keyed by coverage key, with the Firebolt methods using them
+    pub uncovered: HashMap<String, Vec<String>>,
+}
+
+pub fn contract_coverage(rule_set: &RuleSet, contracted: &HashSet<String>) -> CoverageReport {
+    let mut report = CoverageReport::default();
+    for call in thunder_calls(rule_set) {
+        let key = call.coverage_key();
+        if contracted.contains(&key) {
+            report.covered.insert(key);
+        } else {
+            report
+                .uncovered
+                .entry(key)
+                .or_default()
+                .push(call.firebolt_method);
+        }
+    }
+    report
+}
+
+pub const USAGE: &str = "ripple contract-coverage <rules file> <contract tests dir> <output dir> \
+[--open-rpc <file>] [--thunder-examples <file>]";
+
+/// Loads the rules, the contract test sources and the optional examples, then builds the
+/// coverage report and the interactions
+pub fn generate_report(
+    rules: &Path,
+    contracts: &Path,
+    open_rpc: Option<&Path>,
+    thunder_examples: Option<&Path>,
+) -> Result<(CoverageReport, GeneratedContracts), String> {
+    let read =
+        |path: &Path| fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e));
+    let (_, rule_set) = RuleEngine::load_from_content(read(rules)?)
+        .map_err(|e| format!("{}: {:?}", rules.display(), e))?;
+    let source: String = fs::read_dir(contracts)
+        .map_err(|e| format!("{}: {}", contracts.display(), e))?
+        .filter_map(|e| fs::read_to_string(e.ok()?.path()).ok())
+        .collect();
+
+    let mut examples = ContractExamples::default();
+    if let Some(path) = open_rpc {
+        let open_rpc =
+            serde_json::from_str(&read(path)?).map_err(|e| format!("{}: {}", path.display(), e))?;
+        examples.add_open_rpc(&open_rpc);
+    }
+    if let Some(path) = thunder_examples {
+        let thunder =
+            serde_json::from_str(&read(path)?).map_err(|e| format!("{}: {}", path.display(), e))?;
+        examples.add_thunder_examples(thunder);
+    }
+
+    let report = contract_coverage(&rule_set, &contracted_methods(&source));
+    Ok((report, generate_contracts(&rule_set, &examples)))
+}
+
+/// Entry point of `ripple contract-coverage`, `args` are the arguments after the command
+pub fn run(args: &[String]) -> Result<(), String> {
+    let mut positional = Vec::new();
+    let mut open_rpc = None;
+    let mut thunder_examples = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--open-rpc" => open_rpc = Some(args.next().ok_or(USAGE)?),
+            "--thunder-examples" => thunder_examples = Some(args.next().ok_or(USAGE)?),
+            _ => positional.push(arg),
+        }
+    }
+    let [rules, contracts, out] = positional[..] else {
+        return Err(USAGE.to_owned());
+    };
+
+    let (report, generated) = generate_report(
+        Path::new(rules),
+        Path::new(contracts),
+        open_rpc.map(Path::new),
+        thunder_examples.map(Path::new),
+    )?;
+    fs::create_dir_all(out).map_err(|e| format!("{}: {}", out, e))?;
+    let write = |name: &str, value: Value| {
+        let path = Path::new(out).join(name);
+        let contents = serde_json::to_string_pretty(&value).unwrap_or_default();
+        fs::write(&path, contents).map_err(|e| format!("{}: {}", path.display(), e))
+    };
+    write("interactions.json", Value::Array(generated.interactions))?;
+    write(
+        "coverage.json",
+        serde_json::to_value(&report).map_err(|e| e.to_string())?,
+    )?;
+    for method in &generated.missing_examples {
+        warn!("No example to build the request of {}", method);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule_set() -> RuleSet {
+        let (_, rule_set) = RuleEngine::load_from_content(
+            json!({
+                "endpoints": {
+                    "thunder": {"protocol": "thunder", "url": "ws://127.0.0.1:9998/jsonrpc"},
+                    "workflow": {"protocol": "workflow", "url": "http://localhost", "jsonrpc": false}
+                },
+                "rules": {
+                    "device.name": {"alias": "org.rdk.System.getFriendlyName"},
+                    "device.setName": {
+                        "alias": "org.rdk.System.1.setFriendlyName",
+                        "transform": {"request": "{ friendlyName: .value }"}
+                    },
+                    "device.onNameChanged": {
+                        "alias": "org.rdk.System.onFriendlyNameChanged",
+                        "transform": {"event": ".friendlyName"}
+                    },
+                    "localization.setLocale": {
+                        "alias": "org.rdk.UserSettings.setPresentationLanguage",
+                        "transform": {"request": "{ presentationLanguage: .value }"}
+                    },
+                    "localization.setLanguage": {"alias": "static", "transform": {"response": "null"}},
+                    "accessibility.voiceGuidance": {"alias": "workflow", "endpoint": "workflow"}
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+        rule_set
+    }
+
+    #[test]
+    fn test_thunder_calls() {
+        let calls = thunder_calls(&rule_set());
+        let keys: Vec<String> = calls.iter().map(ThunderCall::coverage_key).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "org.rdk.system.getfriendlyname",
+                "org.rdk.system.register@onfriendlynamechanged",
+                "org.rdk.system.setfriendlyname",
+                "org.rdk.usersettings.setpresentationlanguage"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_contracts() {
+        let mut examples = ContractExamples::default();
+        examples.add_open_rpc(&json!({
+            "methods": [{
+                "name": "device.setName",
+                "examples": [{"params": [{"name": "value", "value": "Kitchen"}]}]
+            }]
+        }));
+        examples.add_thunder_examples(HashMap::from([(
+            "org.rdk.System.1.getFriendlyName".to_owned(),
+            vec![ThunderExample {
+                params: None,
+                result: Some(json!({"friendlyName": "Living Room", "success": true})),
+            }],
+        )]));
+
+        let generated = generate_contracts(&rule_set(), &examples);
+        assert_eq!(generated.missing_examples, vec!["localization.setLocale"]);
+        assert_eq!(generated.interactions.len(), 3);
+
+        let get = &generated.interactions[0];
+        assert_eq!(get["request"]["method"], "org.rdk.System.getFriendlyName");
+        assert!(get["request"].get("params").is_none());
+        assert_eq!(get["response"][0]["result"]["friendlyName"], "Living Room");
+
+        let register = &generated.interactions[1];
+        assert_eq!(register["request"]["method"], "org.rdk.System.register");
+        assert_eq!(
+            register["request"]["params"]["event"],
+            "onFriendlyNameChanged"
+        );
+
+        let set = &generated.interactions[2];
+        assert_eq!(set["request"]["method"], "org.rdk.System.1.setFriendlyName");
+        assert_eq!(set["request"]["params"], json!({"friendlyName": "Kitchen"}));
+        assert_eq!(
+            set["response"][0]["result"],
+            json!({"success": "matching(boolean, true)"})
+        );
+    }
+
+    #[test]
+    fn test_contract_coverage() {
+        let source = r#"
+            "request": {"jsonrpc": "matching(type, '2.0')", "id": "matching(integer, 0)", "method": "org.rdk.System.1.setFriendlyName"},
+            i.contents_from(get_pact!(
+                "org.rdk.System.1.getFriendlyName",
+                ContractResult { result }
+            ))
+            "request": {"method": "org.rdk.System.1.register", "params": {"event": "onFriendlyNameChanged", "id": "client.events"}}
+        "#;
+        let contracted = contracted_methods(source);
+        let report = contract_coverage(&rule_set(), &contracted);
+        assert_eq!(report.covered.len(), 3);
+        assert_eq!(
+            report.uncovered,
+            HashMap::from([(
+                "org.rdk.usersettings.setpresentationlanguage".to_owned(),
+                vec!["localization.setLocale".to_owned()]
+            )])
+        );
+    }
+
+    #[test]
+    fn test_example_rules_coverage() {
+        let root = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../../"));
+        let rules = root.join("examples/rules/ripple.common.rules.json");
+        let contracts = root.join("device/thunder_ripple_sdk/src/tests/contracts");
+        let open_rpc = root.join("openrpc_validator/src/test/firebolt-open-rpc.json");
+        let (report, generated) =
+            generate_report(&rules, &contracts, Some(&open_rpc), None).unwrap();
+
+        // every Thunder call of the rules is reported once, either covered or not
+        let (_, rule_set) =
+            RuleEngine::load_from_content(std::fs::read_to_string(&rules).unwrap()).unwrap();
+        let keys: BTreeSet<String> = thunder_calls(&rule_set)
+            .iter()
+            .map(ThunderCall::coverage_key)
+            .collect();
+        let uncovered: BTreeSet<String> = report.uncovered.keys().cloned().collect();
+        assert!(report.covered.is_disjoint(&uncovered));
+        assert_eq!(
+            report
+                .covered
+                .union(&uncovered)
+                .cloned()
+                .collect::<BTreeSet<_>>(),
+            keys
+        );
+        assert!(!report.covered.is_empty());
+        assert!(report.uncovered.values().all(|methods| !methods.is_empty()));
+        assert!(!generated.interactions.is_empty());
+        assert!(generated
+            .interactions
+            .iter()
+            .all(|i| i["request"]["method"].is_string() && i["response"].is_array()));
+    }
+
+    #[test]
+    fn test_run() {
+        let root = concat!(env!("CARGO_MANIFEST_DIR"), "/../../");
+        let out = std::env::temp_dir().join(format!("contracts-{}", std::process::id()));
+        let args: Vec<String> = vec![
+            format!("{}examples/rules/ripple.common.rules.json", root),
+            format!("{}device/thunder_ripple_sdk/src/tests/contracts", root),
+            out.display().to_string(),
+        ];
+        assert_eq!(run(&args[..2]), Err(USAGE.to_owned()));
+        run(&args).unwrap();
+        let coverage: Value =
+            serde_json::from_str(&std::fs::read_to_string(out.join("coverage.json")).unwrap())
+                .unwrap();
+        assert!(coverage["covered"].is_array());
+        assert!(coverage["uncovered"].is_object());
+        assert!(out.join("interactions.json").exists());
+        let _ = std::fs::remove_dir_all(out);
+    }
+}
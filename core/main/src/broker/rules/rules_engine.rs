@@ -16,7 +16,8 @@
 //
 use jaq_interpret::{Ctx, FilterT, ParseCtx, RcIter, Val};
 use ripple_sdk::api::{
-    gateway::rpc_gateway_api::RpcRequest, manifest::extn_manifest::ExtnManifest,
+    distributor::distributor_privacy::DataEventType, gateway::rpc_gateway_api::RpcRequest,
+    manifest::extn_manifest::ExtnManifest,
 };
 
 use ripple_sdk::{
@@ -133,6 +134,9 @@ pub struct Rule {
     pub endpoint: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sources: Option<Vec<JsonDataSource>>,
+    /// Data governance policy applied to the params before they leave the device
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_type: Option<DataEventType>,
 }
 impl std::fmt::Display for Rule {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
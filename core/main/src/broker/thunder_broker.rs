@@ -840,6 +840,7 @@ mod tests {
                 filter: event_filter,
                 event_handler,
                 sources: None,
+                data_type: None,
             },
             subscription_processed: None,
             workflow_callback: None,
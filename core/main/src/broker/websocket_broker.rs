@@ -304,6 +304,7 @@ mod tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                data_type: None,
             },
             workflow_callback: None,
             subscription_processed: None,
@@ -351,6 +352,7 @@ mod tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                data_type: None,
             },
             workflow_callback: None,
             subscription_processed: None,
@@ -382,6 +384,7 @@ mod tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                data_type: None,
             },
             workflow_callback: None,
             subscription_processed: None,
@@ -426,6 +429,7 @@ mod tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                data_type: None,
             },
             workflow_callback: None,
             subscription_processed: None,
@@ -505,6 +509,7 @@ mod tests {
                 filter: None,
                 event_handler: None,
                 sources: None,
+                data_type: None,
             },
             workflow_callback: None,
             subscription_processed: None,
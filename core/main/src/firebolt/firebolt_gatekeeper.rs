@@ -18,12 +18,13 @@
 use std::collections::HashMap;
 
 use ripple_sdk::api::firebolt::fb_capabilities::{
-    DenyReason, DenyReasonWithCap, FireboltPermission,
+    AccessExplanation, AccessStage, AccessStageTrace, AccessVerdict, DenyReason, DenyReasonWithCap,
+    FireboltPermission, StageDecision,
 };
 use ripple_sdk::api::gateway::rpc_gateway_api::RpcRequest;
 use ripple_sdk::log::trace;
 
-use crate::service::user_grants::GrantState;
+use crate::service::user_grants::{GrantPolicyEnforcer, GrantState};
 use crate::state::openrpc_state::ApiSurface;
 use crate::state::{cap::permitted_state::PermissionHandler, platform_state::PlatformState};
 
@@ -127,6 +128,196 @@ impl FireboltGatekeeper {
         Ok(caps)
     }
 
+    /// Runs the stages of [FireboltGatekeeper::gate] for a method called by an app without side
+    /// effects. Permissions are not fetched when they are not cached and no user is prompted.
+    pub async fn explain(
+        state: &PlatformState,
+        app_id: &str,
+        method: &str,
+        secure: bool,
+    ) -> AccessExplanation {
+        let mut explanation = AccessExplanation {
+            app_id: app_id.to_owned(),
+            method: method.to_owned(),
+            stages: Vec::new(),
+            grants: Vec::new(),
+            verdict: AccessVerdict::Allowed,
+        };
+        let verdict = Self::explain_stages(state, app_id, method, secure, &mut explanation).await;
+        explanation.verdict = verdict;
+        explanation
+    }
+
+    async fn explain_stages(
+        state: &PlatformState,
+        app_id: &str,
+        method: &str,
+        secure: bool,
+        explanation: &mut AccessExplanation,
+    ) -> AccessVerdict {
+        let stages = &mut explanation.stages;
+        let mut api_surface = vec![ApiSurface::Firebolt];
+        if !secure {
+            api_surface.push(ApiSurface::Ripple);
+        }
+        let Some(perms) = state
+            .open_rpc_state
+            .get_perms_for_method(method, api_surface)
+        else {
+            stages.push(
+                AccessStageTrace::new(AccessStage::OpenRpc, StageDecision::Denied, Vec::new())
+                    .with_reason(DenyReason::NotFound)
+                    .with_detail("method is not in the OpenRPC spec of the api surface"),
+            );
+            return DenyReasonWithCap::new(DenyReason::NotFound, Vec::new()).into();
+        };
+        if perms.is_empty() {
+            stages.push(
+                AccessStageTrace::new(AccessStage::OpenRpc, StageDecision::Denied, Vec::new())
+                    .with_reason(DenyReason::Unsupported)
+                    .with_detail("method does not use any capability"),
+            );
+            return DenyReasonWithCap::new(DenyReason::Unsupported, Vec::new()).into();
+        }
+        stages.push(AccessStageTrace::new(
+            AccessStage::OpenRpc,
+            StageDecision::Passed,
+            perms.clone(),
+        ));
+
+        let caps = Self::resolve_dependencies(state, &perms);
+        stages.push(AccessStageTrace::new(
+            AccessStage::Dependencies,
+            StageDecision::Passed,
+            caps.clone(),
+        ));
+
+        let filtered_perm_list = state
+            .cap_state
+            .generic
+            .clear_non_negotiable_permission(state, &caps);
+        if filtered_perm_list.is_empty() {
+            stages.push(
+                AccessStageTrace::new(AccessStage::NonNegotiable, StageDecision::Passed, caps)
+                    .with_detail("all permissions are public and not negotiable"),
+            );
+            return AccessVerdict::Allowed;
+        }
+        stages.push(AccessStageTrace::new(
+            AccessStage::NonNegotiable,
+            StageDecision::Passed,
+            filtered_perm_list.clone(),
+        ));
+
+        let generic = &state.cap_state.generic;
+        for (stage, result) in [
+            (
+                AccessStage::Supported,
+                generic.check_supported(&filtered_perm_list),
+            ),
+            (
+                AccessStage::Available,
+                generic.check_available(&filtered_perm_list),
+            ),
+        ] {
+            if let Err(e) = result {
+                stages.push(
+                    AccessStageTrace::new(stage, StageDecision::Denied, filtered_perm_list.clone())
+                        .with_reason(e.reason.clone()),
+                );
+                return e.into();
+            }
+            stages.push(AccessStageTrace::new(
+                stage,
+                StageDecision::Passed,
+                filtered_perm_list.clone(),
+            ));
+        }
+
+        if state
+            .open_rpc_state
+            .is_excluded(method.to_owned(), app_id.to_owned())
+        {
+            stages.push(
+                AccessStageTrace::new(
+                    AccessStage::Permitted,
+                    StageDecision::Skipped,
+                    filtered_perm_list.clone(),
+                )
+                .with_detail("method or app is excluded from permission checks"),
+            );
+        } else if let Some(permitted) = state.cap_state.permitted_state.get_app_permissions(app_id)
+        {
+            if let Err(e) = PermissionHandler::is_all_permitted(&permitted, &filtered_perm_list) {
+                stages.push(
+                    AccessStageTrace::new(
+                        AccessStage::Permitted,
+                        StageDecision::Denied,
+                        filtered_perm_list.clone(),
+                    )
+                    .with_reason(e.reason.clone()),
+                );
+                return e.into();
+            }
+            stages.push(AccessStageTrace::new(
+                AccessStage::Permitted,
+                StageDecision::Passed,
+                filtered_perm_list.clone(),
+            ));
+        } else {
+            stages.push(
+                AccessStageTrace::new(
+                    AccessStage::Permitted,
+                    StageDecision::Denied,
+                    filtered_perm_list.clone(),
+                )
+                .with_reason(DenyReason::Unpermitted)
+                .with_detail("permissions of the app are not cached, the gate would fetch them"),
+            );
+            return DenyReasonWithCap::new(
+                DenyReason::Unpermitted,
+                filtered_perm_list.iter().map(|p| p.cap.clone()).collect(),
+            )
+            .into();
+        }
+
+        let (grants, result) =
+            GrantPolicyEnforcer::explain_grants(state, app_id, &filtered_perm_list).await;
+        let pending_caps: Vec<String> = grants
+            .iter()
+            .filter(|g| g.decision == StageDecision::Pending)
+            .map(|g| g.permission.cap.as_str())
+            .collect();
+        let permissions = grants.iter().map(|g| g.permission.clone()).collect();
+        explanation.grants = grants;
+        let stages = &mut explanation.stages;
+        match result {
+            Err(e) => {
+                stages.push(
+                    AccessStageTrace::new(AccessStage::Grants, StageDecision::Denied, permissions)
+                        .with_reason(e.reason.clone()),
+                );
+                e.into()
+            }
+            Ok(false) => {
+                stages.push(AccessStageTrace::new(
+                    AccessStage::Grants,
+                    StageDecision::Pending,
+                    permissions,
+                ));
+                AccessVerdict::PendingGrant { caps: pending_caps }
+            }
+            Ok(true) => {
+                stages.push(AccessStageTrace::new(
+                    AccessStage::Grants,
+                    StageDecision::Passed,
+                    permissions,
+                ));
+                AccessVerdict::Allowed
+            }
+        }
+    }
+
     async fn permissions_check(
         state: PlatformState,
         request: RpcRequest,
@@ -175,3 +366,91 @@ impl FireboltGatekeeper {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::{fb_perm, MockRuntime};
+    use ripple_sdk::{
+        api::firebolt::fb_capabilities::FireboltCap, api::firebolt::fb_openrpc::CapabilitySet,
+        tokio,
+    };
+
+    const METHOD: &str = "test.explain";
+    const CAP: &str = "xrn:firebolt:capability:test:explain";
+
+    fn setup() -> PlatformState {
+        let state = MockRuntime::new().platform_state;
+        state.open_rpc_state.extend_caps(HashMap::from([(
+            METHOD.to_owned(),
+            CapabilitySet {
+                use_caps: Some(vec![FireboltCap::Full(CAP.to_owned())]),
+                provide_cap: None,
+                manage_caps: None,
+            },
+        )]));
+        state
+    }
+
+    fn stages(explanation: &AccessExplanation) -> Vec<(AccessStage, StageDecision)> {
+        explanation
+            .stages
+            .iter()
+            .map(|s| (s.stage, s.decision))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_explain_unknown_method() {
+        let state = setup();
+        let explanation = FireboltGatekeeper::explain(&state, "app", "test.unknown", true).await;
+        assert_eq!(
+            stages(&explanation),
+            vec![(AccessStage::OpenRpc, StageDecision::Denied)]
+        );
+        assert!(matches!(
+            explanation.verdict,
+            AccessVerdict::Denied {
+                reason: DenyReason::NotFound,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_explain_stages() {
+        let state = setup();
+        let explanation = FireboltGatekeeper::explain(&state, "app", METHOD, true).await;
+        assert_eq!(
+            explanation.verdict,
+            AccessVerdict::Denied {
+                reason: DenyReason::Unsupported,
+                caps: vec![CAP.to_owned()]
+            }
+        );
+        assert_eq!(
+            explanation.stages.last().unwrap().stage,
+            AccessStage::Supported
+        );
+
+        state
+            .cap_state
+            .generic
+            .ingest_supported(vec![fb_perm(CAP, None)]);
+        let explanation = FireboltGatekeeper::explain(&state, "app", METHOD, true).await;
+        // the mock exclusory can not resolve the test method, so it skips permission checks
+        assert_eq!(explanation.verdict, AccessVerdict::Allowed);
+        assert_eq!(
+            stages(&explanation),
+            vec![
+                (AccessStage::OpenRpc, StageDecision::Passed),
+                (AccessStage::Dependencies, StageDecision::Passed),
+                (AccessStage::NonNegotiable, StageDecision::Passed),
+                (AccessStage::Supported, StageDecision::Passed),
+                (AccessStage::Available, StageDecision::Passed),
+                (AccessStage::Permitted, StageDecision::Skipped),
+                (AccessStage::Grants, StageDecision::Passed),
+            ]
+        );
+    }
+}
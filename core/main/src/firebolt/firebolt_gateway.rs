@@ -396,8 +396,8 @@ impl FireboltGateway {
                                 }
                             }
                             ApiProtocol::Service => {
-                                RpcRouter::route_service_protocol(&platform_state, request.clone())
-                                    .await
+                                // handlers are registered with the module in lower case
+                                RpcRouter::route_service_protocol(&platform_state, request_c).await
                             }
                             _ => {
                                 if let Some(session) = session {
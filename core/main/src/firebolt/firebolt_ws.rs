@@ -23,6 +23,7 @@ use std::{
 use super::firebolt_gateway::FireboltGatewayCommand;
 use crate::{
     service::apps::delegated_launcher_handler::{AppManagerState, AppManagerState2_0},
+    service::extn::extn_supervisor::ExtnSupervisor,
     service::ripple_service::service_controller_state::ServiceControllerState,
     state::{
         cap::permitted_state::PermissionHandler, platform_state::PlatformState,
@@ -75,10 +76,15 @@ struct ConnectionCallbackConfig {
     pub secure: bool,
     pub internal_app_id: Option<String>,
     extns: Vec<ExtnSymbol>,
+    extn_supervisor: ExtnSupervisor,
 }
 
 impl ConnectionCallbackConfig {
     fn get_extn(&self, id: &str) -> Option<ExtnSymbol> {
+        // extensions loaded at runtime are not in the manifest and replace the symbols of the ones they upgrade
+        if let Some(symbol) = self.extn_supervisor.get_symbol(id) {
+            return Some(symbol);
+        }
         for extn in &self.extns {
             if extn.id.eq(id) {
                 return Some(extn.clone());
@@ -264,6 +270,16 @@ impl FireboltWs {
         let try_socket = TcpListener::bind(&server_addr).await; //create the server on the address
         let listener = try_socket.unwrap_or_else(|_| panic!("Failed to bind {:?}", server_addr));
         info!("Listening on: {} secure={}", server_addr, secure);
+        Self::serve(listener, state, secure, internal_app_id).await
+    }
+
+    /// Accepts the connections on a listener which is already bound
+    pub async fn serve(
+        listener: TcpListener,
+        state: PlatformState,
+        secure: bool,
+        internal_app_id: Option<String>,
+    ) {
         let state_for_connection = state.clone();
         let extns = state.extn_manifest.get_all_extns();
         let app_state = state.app_manager_state.clone();
@@ -283,6 +299,7 @@ impl FireboltWs {
                 secure,
                 internal_app_id: internal_app_id.clone(),
                 extns: extns.clone(),
+                extn_supervisor: state.extn_supervisor.clone(),
             };
             match ripple_sdk::tokio_tungstenite::accept_hdr_async(stream, ConnectionCallback(cfg))
                 .await
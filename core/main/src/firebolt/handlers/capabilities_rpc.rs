@@ -16,7 +16,7 @@
 //
 
 use crate::{
-    firebolt::rpc::RippleRPCProvider,
+    firebolt::{firebolt_gatekeeper::FireboltGatekeeper, rpc::RippleRPCProvider},
     service::user_grants::GrantState,
     state::{
         cap::{cap_state::CapState, permitted_state::PermissionHandler},
@@ -34,8 +34,9 @@ use ripple_sdk::{
     api::{
         firebolt::{
             fb_capabilities::{
-                CapEvent, CapInfoRpcRequest, CapListenRPCRequest, CapRPCRequest,
-                CapRequestRpcRequest, CapabilityInfo, DenyReason, FireboltPermission, RoleInfo,
+                AccessExplanation, CapEvent, CapInfoRpcRequest, CapListenRPCRequest, CapRPCRequest,
+                CapRequestRpcRequest, CapabilityInfo, DenyReason, ExplainAccessRequest,
+                FireboltPermission, RoleInfo,
             },
             fb_general::ListenerResponse,
         },
@@ -48,6 +49,12 @@ use ripple_sdk::{
 pub trait Capability {
     #[method(name = "ripple.isPermitted")]
     async fn get_capability_permit(&self, ctx: CallContext, role: RoleInfo) -> RpcResult<bool>;
+    #[method(name = "ripple.explainAccess")]
+    async fn explain_access(
+        &self,
+        ctx: CallContext,
+        request: ExplainAccessRequest,
+    ) -> RpcResult<AccessExplanation>;
     #[method(name = "capabilities.supported")]
     async fn supported(&self, ctx: CallContext, cap: CapRPCRequest) -> RpcResult<bool>;
     #[method(name = "capabilities.available")]
@@ -120,6 +127,20 @@ impl CapabilityServer for CapabilityImpl {
         is_permitted(&self.state.clone(), &ctx, &role).await
     }
 
+    async fn explain_access(
+        &self,
+        _ctx: CallContext,
+        request: ExplainAccessRequest,
+    ) -> RpcResult<AccessExplanation> {
+        Ok(FireboltGatekeeper::explain(
+            &self.state,
+            &request.app_id,
+            &request.method,
+            request.secure,
+        )
+        .await)
+    }
+
     async fn supported(&self, _ctx: CallContext, cap: CapRPCRequest) -> RpcResult<bool> {
         Ok(self
             .state
@@ -35,6 +35,7 @@ use ripple_sdk::{
             provider::{ProviderRequestPayload, ProviderResponsePayload},
         },
         gateway::rpc_gateway_api::CallContext,
+        observability::extn_metrics::ExtnContractStats,
         settings::{SettingValue, SettingsRequest, SettingsRequestParam},
     },
     async_trait::async_trait,
@@ -47,6 +48,7 @@ use ripple_sdk::{
 use std::{
     collections::HashMap,
     sync::{Arc, RwLock},
+    time::Duration,
 };
 
 use crate::{
@@ -56,9 +58,13 @@ use crate::{
             app_events::AppEvents,
             provider_broker::{ProviderBroker, ProviderBrokerRequest},
         },
+        extn::extn_supervisor::{
+            ExtnHealth, ExtnLoadRequest, ExtnReplaceRequest, ExtnUnloadRequest, ExtnUnloadResult,
+        },
+        ripple_service::service_supervisor::ServiceHealth,
         telemetry_builder::TelemetryBuilder,
     },
-    state::platform_state::PlatformState,
+    state::{platform_state::PlatformState, ripple_cache::RippleCacheStats},
     utils::rpc_utils::rpc_await_oneshot,
 };
 use ripple_sdk::api::firebolt::fb_general::ListenRequest;
@@ -151,6 +157,35 @@ pub trait Internal {
         ctx: CallContext,
         request: SettingsRequestParam,
     ) -> RpcResult<()>;
+
+    #[method(name = "ripple.storageCacheStats")]
+    fn storage_cache_stats(&self, ctx: CallContext) -> RpcResult<RippleCacheStats>;
+
+    #[method(name = "ripple.serviceStatus")]
+    fn service_status(&self, ctx: CallContext) -> RpcResult<Vec<ServiceHealth>>;
+
+    #[method(name = "ripple.extnStats")]
+    fn extn_stats(&self, ctx: CallContext) -> RpcResult<Vec<ExtnContractStats>>;
+
+    #[method(name = "ripple.extensionStatus")]
+    fn extension_status(&self, ctx: CallContext) -> RpcResult<Vec<ExtnHealth>>;
+
+    #[method(name = "ripple.loadExtension")]
+    fn load_extension(&self, ctx: CallContext, request: ExtnLoadRequest) -> RpcResult<()>;
+
+    #[method(name = "ripple.unloadExtension")]
+    async fn unload_extension(
+        &self,
+        ctx: CallContext,
+        request: ExtnUnloadRequest,
+    ) -> RpcResult<ExtnUnloadResult>;
+
+    #[method(name = "ripple.replaceExtension")]
+    async fn replace_extension(
+        &self,
+        ctx: CallContext,
+        request: ExtnReplaceRequest,
+    ) -> RpcResult<ExtnUnloadResult>;
 }
 
 #[derive(Debug, Clone, Default)]
@@ -163,10 +198,24 @@ pub struct InternalImpl {
     pub state: PlatformState,
 }
 
+impl InternalImpl {
+    /// Libraries loaded at runtime are limited to the manifest entries and its default path
+    fn check_extn_path(&self, path: &str) -> RpcResult<()> {
+        if self.state.extn_manifest.allows_extn_path(path) {
+            Ok(())
+        } else {
+            Err(rpc_err(format!(
+                "{} is not in the manifest or under its default path",
+                path
+            )))
+        }
+    }
+}
+
 #[async_trait]
 impl InternalServer for InternalImpl {
     async fn send_telemetry(&self, _ctx: CallContext, payload: TelemetryPayload) -> RpcResult<()> {
-        let _ = TelemetryBuilder::send_telemetry(&self.state, payload);
+        let _ = TelemetryBuilder::send_telemetry_and_wait(&self.state, payload).await;
         Ok(())
     }
 
@@ -416,6 +465,81 @@ impl InternalServer for InternalImpl {
         subscribe_to_settings(&self.state, request).await;
         Ok(())
     }
+
+    fn storage_cache_stats(&self, _ctx: CallContext) -> RpcResult<RippleCacheStats> {
+        Ok(self.state.ripple_cache.get_stats())
+    }
+
+    fn service_status(&self, _ctx: CallContext) -> RpcResult<Vec<ServiceHealth>> {
+        Ok(self
+            .state
+            .service_controller_state
+            .service_supervisor
+            .get_health())
+    }
+
+    fn extn_stats(&self, _ctx: CallContext) -> RpcResult<Vec<ExtnContractStats>> {
+        Ok(self.state.get_client().get_extn_client().get_metrics())
+    }
+
+    fn extension_status(&self, _ctx: CallContext) -> RpcResult<Vec<ExtnHealth>> {
+        Ok(self.state.extn_supervisor.get_health())
+    }
+
+    fn load_extension(&self, _ctx: CallContext, request: ExtnLoadRequest) -> RpcResult<()> {
+        let entry = match request.entry {
+            Some(entry) => {
+                self.check_extn_path(&entry.path)?;
+                entry
+            }
+            None => self
+                .state
+                .extn_manifest
+                .extns
+                .iter()
+                .find(|e| e.path == request.path)
+                .cloned()
+                .ok_or_else(|| rpc_err(format!("{} is not in the manifest", request.path)))?,
+        };
+        self.state
+            .extn_supervisor
+            .load(&self.state, entry)
+            .map_err(|e| rpc_err(format!("Failed to load {}: {}", request.path, e)))
+    }
+
+    async fn unload_extension(
+        &self,
+        _ctx: CallContext,
+        request: ExtnUnloadRequest,
+    ) -> RpcResult<ExtnUnloadResult> {
+        self.state
+            .extn_supervisor
+            .unload(
+                &self.state,
+                &request.path,
+                Duration::from_millis(request.drain_timeout_ms),
+            )
+            .await
+            .map_err(|e| rpc_err(format!("Failed to unload {}: {}", request.path, e)))
+    }
+
+    async fn replace_extension(
+        &self,
+        _ctx: CallContext,
+        request: ExtnReplaceRequest,
+    ) -> RpcResult<ExtnUnloadResult> {
+        self.check_extn_path(&request.entry.path)?;
+        self.state
+            .extn_supervisor
+            .replace(
+                &self.state,
+                &request.path,
+                request.entry,
+                Duration::from_millis(request.drain_timeout_ms),
+            )
+            .await
+            .map_err(|e| rpc_err(format!("Failed to replace {}: {}", request.path, e)))
+    }
 }
 
 pub struct InternalProvider;
@@ -18,6 +18,7 @@
 use crate::broker::broker_utils::BrokerUtils;
 use crate::processor::storage::storage_manager::StorageManager;
 use crate::service::apps::app_events::AppEventDecorator;
+use crate::service::privacy_sync::PrivacySync;
 use crate::{
     firebolt::rpc::RippleRPCProvider, service::apps::app_events::AppEvents,
     state::platform_state::PlatformState,
@@ -639,7 +640,22 @@ impl PrivacyImpl {
                     ))),
                 }
             }
-            PrivacySettingsStorageType::Cloud | PrivacySettingsStorageType::Sync => {
+            PrivacySettingsStorageType::Sync => {
+                // Stored locally first so the change is not lost while offline, the sync
+                // pushes it to the cloud
+                match StorageManager::set_bool(platform_state, property.clone(), value, None).await
+                {
+                    Ok(_) => {
+                        PrivacySync::record_local_change(platform_state, &property, value);
+                        RpcResult::Ok(())
+                    }
+                    Err(e) => RpcResult::Err(jsonrpsee::core::Error::Custom(format!(
+                        "Unable to set privacy setting: {}",
+                        e
+                    ))),
+                }
+            }
+            PrivacySettingsStorageType::Cloud => {
                 if let Some(dist_session) = platform_state.session_state.get_account_session() {
                     if let Some(privacy_setting) = property.as_privacy_setting() {
                         let request_payload = json!({
@@ -655,12 +671,6 @@ impl PrivacyImpl {
                         )
                         .await;
 
-                        if PrivacySettingsStorageType::Sync == privacy_settings_storage_type
-                            && result.is_ok()
-                        {
-                            let _ = StorageManager::set_bool(platform_state, property, value, None)
-                                .await;
-                        }
                         if result.is_ok() {
                             return Ok(());
                         }
@@ -674,78 +684,18 @@ impl PrivacyImpl {
         }
     }
 
-    pub async fn get_settings_local(&self) -> RpcResult<PrivacySettings> {
-        let settings = PrivacySettings {
-            allow_acr_collection: self
-                .get_bool_storage_property(StorageProperty::AllowAcrCollection)
-                .await
-                .unwrap_or(false),
-            allow_resume_points: self
-                .get_bool_storage_property(StorageProperty::AllowResumePoints)
-                .await
-                .unwrap_or(false),
-            allow_app_content_ad_targeting: self
-                .get_bool_storage_property(StorageProperty::AllowAppContentAdTargeting)
-                .await
-                .unwrap_or(false),
-            allow_business_analytics: self
-                .get_bool_storage_property(StorageProperty::AllowBusinessAnalytics)
-                .await
-                .unwrap_or(true),
-            allow_camera_analytics: self
-                .get_bool_storage_property(StorageProperty::AllowCameraAnalytics)
-                .await
-                .unwrap_or(false),
-            allow_personalization: self
-                .get_bool_storage_property(StorageProperty::AllowPersonalization)
-                .await
-                .unwrap_or(false),
-            allow_primary_browse_ad_targeting: self
-                .get_bool_storage_property(StorageProperty::AllowPrimaryBrowseAdTargeting)
-                .await
-                .unwrap_or(false),
-            allow_primary_content_ad_targeting: self
-                .get_bool_storage_property(StorageProperty::AllowPrimaryContentAdTargeting)
-                .await
-                .unwrap_or(false),
-            allow_product_analytics: self
-                .get_bool_storage_property(StorageProperty::AllowProductAnalytics)
-                .await
-                .unwrap_or(false),
-            allow_remote_diagnostics: self
-                .get_bool_storage_property(StorageProperty::AllowRemoteDiagnostics)
-                .await
-                .unwrap_or(false),
-            allow_unentitled_personalization: self
-                .get_bool_storage_property(StorageProperty::AllowUnentitledPersonalization)
-                .await
-                .unwrap_or(false),
-            allow_unentitled_resume_points: self
-                .get_bool_storage_property(StorageProperty::AllowUnentitledResumePoints)
-                .await
-                .unwrap_or(false),
-            allow_watch_history: self
-                .get_bool_storage_property(StorageProperty::AllowWatchHistory)
-                .await
-                .unwrap_or(false),
-        };
-        Ok(settings)
-    }
-}
-
-#[async_trait]
-impl PrivacyServer for PrivacyImpl {
-    async fn set_privacy_settings(
-        &self,
-        _ctx: CallContext,
-        privacy_settings_data: PrivacySettingsData,
+    /// Stores the given privacy settings locally, settings which are not set are left as they are
+    pub async fn store_privacy_settings(
+        platform_state: &PlatformState,
+        privacy_settings_data: &PrivacySettingsData,
     ) -> RpcResult<bool> {
         debug!("set_privacy_settings: {:?}", privacy_settings_data);
         let mut err = false;
         macro_rules! set_property {
             ($property:ident, $value:expr) => {
                 if let Some(value) = $value {
-                    let res = StorageManager::set_bool(&self.state, $property, value, None).await;
+                    let res =
+                        StorageManager::set_bool(platform_state, $property, value, None).await;
                     if let Err(e) = res {
                         error!("Unable to set property {:?} error: {:?}", $property, e);
                         err = true;
@@ -811,6 +761,75 @@ impl PrivacyServer for PrivacyImpl {
         }
         Ok(true)
     }
+
+    pub async fn get_settings_local(&self) -> RpcResult<PrivacySettings> {
+        let settings = PrivacySettings {
+            allow_acr_collection: self
+                .get_bool_storage_property(StorageProperty::AllowAcrCollection)
+                .await
+                .unwrap_or(false),
+            allow_resume_points: self
+                .get_bool_storage_property(StorageProperty::AllowResumePoints)
+                .await
+                .unwrap_or(false),
+            allow_app_content_ad_targeting: self
+                .get_bool_storage_property(StorageProperty::AllowAppContentAdTargeting)
+                .await
+                .unwrap_or(false),
+            allow_business_analytics: self
+                .get_bool_storage_property(StorageProperty::AllowBusinessAnalytics)
+                .await
+                .unwrap_or(true),
+            allow_camera_analytics: self
+                .get_bool_storage_property(StorageProperty::AllowCameraAnalytics)
+                .await
+                .unwrap_or(false),
+            allow_personalization: self
+                .get_bool_storage_property(StorageProperty::AllowPersonalization)
+                .await
+                .unwrap_or(false),
+            allow_primary_browse_ad_targeting: self
+                .get_bool_storage_property(StorageProperty::AllowPrimaryBrowseAdTargeting)
+                .await
+                .unwrap_or(false),
+            allow_primary_content_ad_targeting: self
+                .get_bool_storage_property(StorageProperty::AllowPrimaryContentAdTargeting)
+                .await
+                .unwrap_or(false),
+            allow_product_analytics: self
+                .get_bool_storage_property(StorageProperty::AllowProductAnalytics)
+                .await
+                .unwrap_or(false),
+            allow_remote_diagnostics: self
+                .get_bool_storage_property(StorageProperty::AllowRemoteDiagnostics)
+                .await
+                .unwrap_or(false),
+            allow_unentitled_personalization: self
+                .get_bool_storage_property(StorageProperty::AllowUnentitledPersonalization)
+                .await
+                .unwrap_or(false),
+            allow_unentitled_resume_points: self
+                .get_bool_storage_property(StorageProperty::AllowUnentitledResumePoints)
+                .await
+                .unwrap_or(false),
+            allow_watch_history: self
+                .get_bool_storage_property(StorageProperty::AllowWatchHistory)
+                .await
+                .unwrap_or(false),
+        };
+        Ok(settings)
+    }
+}
+
+#[async_trait]
+impl PrivacyServer for PrivacyImpl {
+    async fn set_privacy_settings(
+        &self,
+        _ctx: CallContext,
+        privacy_settings_data: PrivacySettingsData,
+    ) -> RpcResult<bool> {
+        Self::store_privacy_settings(&self.state, &privacy_settings_data).await
+    }
     async fn privacy_allow_acr_collection(&self, ctx: CallContext) -> RpcResult<bool> {
         Self::handle_allow_get_requests(&ctx.method, &self.state).await
     }
@@ -25,15 +25,24 @@ use jsonrpsee::{
 use ripple_sdk::{
     api::{
         firebolt::{
+            fb_general::{ListenRequest, ListenerResponse},
             fb_pin::{PinChallengeRequestWithContext, PinSpace, PIN_CHALLENGE_CAPABILITY},
+            fb_profile::{
+                CreateProfileRequest, DeleteProfileRequest, ProfileList, SwitchProfileRequest,
+                UserProfile, PROFILE_CHANGED_EVENT,
+            },
             provider::ChallengeRequestor,
         },
         gateway::rpc_gateway_api::CallContext,
     },
     extn::extn_client_message::ExtnResponse,
+    utils::{error::RippleError, rpc_utils::rpc_err},
 };
 
-use crate::{firebolt::rpc::RippleRPCProvider, state::platform_state::PlatformState};
+use crate::{
+    firebolt::rpc::RippleRPCProvider, service::user_profiles::UserProfiles,
+    state::platform_state::PlatformState, utils::rpc_utils::rpc_add_event_listener,
+};
 
 #[rpc(server)]
 pub trait Profile {
@@ -48,6 +57,37 @@ pub trait Profile {
     */
     #[method(name = "profile.flags")]
     async fn profile_flags(&self, ctx: CallContext) -> RpcResult<HashMap<String, String>>;
+
+    #[method(name = "ripple.createProfile")]
+    async fn create_profile(
+        &self,
+        ctx: CallContext,
+        request: CreateProfileRequest,
+    ) -> RpcResult<UserProfile>;
+
+    #[method(name = "ripple.listProfiles")]
+    async fn list_profiles(&self, ctx: CallContext) -> RpcResult<ProfileList>;
+
+    #[method(name = "ripple.switchProfile")]
+    async fn switch_profile(
+        &self,
+        ctx: CallContext,
+        request: SwitchProfileRequest,
+    ) -> RpcResult<()>;
+
+    #[method(name = "ripple.deleteProfile")]
+    async fn delete_profile(
+        &self,
+        ctx: CallContext,
+        request: DeleteProfileRequest,
+    ) -> RpcResult<()>;
+
+    #[method(name = "ripple.onProfileChanged")]
+    async fn on_profile_changed(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse>;
 }
 
 pub struct ProfileImpl {
@@ -117,6 +157,52 @@ impl ProfileServer for ProfileImpl {
         result.insert("userExperience".to_string(), distributor_experience_id);
         Ok(result)
     }
+
+    async fn create_profile(
+        &self,
+        _ctx: CallContext,
+        request: CreateProfileRequest,
+    ) -> RpcResult<UserProfile> {
+        if request.name.trim().is_empty() {
+            return Err(rpc_err("profile name must not be empty"));
+        }
+        Ok(self.platform_state.profile_state.create(request.name))
+    }
+
+    async fn list_profiles(&self, _ctx: CallContext) -> RpcResult<ProfileList> {
+        Ok(self.platform_state.profile_state.list())
+    }
+
+    async fn switch_profile(
+        &self,
+        _ctx: CallContext,
+        request: SwitchProfileRequest,
+    ) -> RpcResult<()> {
+        UserProfiles::switch(&self.platform_state, request.profile_id)
+            .await
+            .map_err(|_| rpc_err("unknown profile"))
+    }
+
+    async fn delete_profile(
+        &self,
+        _ctx: CallContext,
+        request: DeleteProfileRequest,
+    ) -> RpcResult<()> {
+        UserProfiles::delete(&self.platform_state, &request.profile_id)
+            .await
+            .map_err(|e| match e {
+                RippleError::InvalidInput => rpc_err("the active profile can not be deleted"),
+                _ => rpc_err("unknown profile"),
+            })
+    }
+
+    async fn on_profile_changed(
+        &self,
+        ctx: CallContext,
+        request: ListenRequest,
+    ) -> RpcResult<ListenerResponse> {
+        rpc_add_event_listener(&self.platform_state, ctx, request, PROFILE_CHANGED_EVENT).await
+    }
 }
 
 pub struct ProfileRPCProvider;
@@ -24,13 +24,15 @@ use ripple_sdk::{
     api::{
         apps::{AppManagerResponse, AppMethod, AppRequest, AppResponse},
         device::device_user_grants_data::{
-            GrantEntry, GrantLifespan, GrantStateModify, PolicyPersistenceType,
+            GrantChangeSource, GrantEntry, GrantHistoryEntry, GrantLifespan, GrantStateModify,
+            PolicyPersistenceType,
         },
         firebolt::{
             fb_capabilities::{DenyReason, FireboltPermission, CAPABILITY_NOT_PERMITTED},
             fb_user_grants::{
-                AppInfo, GetUserGrantsByAppRequest, GetUserGrantsByCapabilityRequest, GrantInfo,
-                GrantRequest, UserGrantRequestParam,
+                AppInfo, GetUserGrantsByAppRequest, GetUserGrantsByCapabilityRequest,
+                GrantHistoryExport, GrantHistoryRequest, GrantInfo, GrantRequest,
+                SignedUserGrantsBundle, UserGrantRequestParam, UserGrantsImportResult,
             },
         },
         gateway::rpc_gateway_api::{AppIdentification, CallContext},
@@ -44,7 +46,7 @@ use ripple_sdk::{
 
 use crate::{
     firebolt::rpc::RippleRPCProvider,
-    service::user_grants::GrantState,
+    service::{user_grant_bundle::UserGrantBundles, user_grants::GrantState},
     state::platform_state::PlatformState,
     utils::rpc_utils::{rpc_await_oneshot, rpc_err},
 };
@@ -82,6 +84,12 @@ pub trait UserGrants {
         ctx: CallContext,
         request: UserGrantRequestParam,
     ) -> RpcResult<Vec<GrantInfo>>;
+    #[method(name = "usergrants.history")]
+    async fn usergrants_history(
+        &self,
+        ctx: CallContext,
+        request: GrantHistoryRequest,
+    ) -> RpcResult<Vec<GrantHistoryEntry>>;
     #[method(name = "ripple.clearUserGrants")]
     async fn clear_user_grants(&self, ctx: CallContext) -> RpcResult<()>;
     #[method(name = "ripple.setUserGrants")]
@@ -92,6 +100,20 @@ pub trait UserGrants {
     ) -> RpcResult<()>;
     #[method(name = "ripple.syncGrantsMap")]
     async fn sync_user_grants_map(&self, ctx: CallContext) -> RpcResult<()>;
+    #[method(name = "ripple.exportGrantHistory")]
+    async fn export_grant_history(
+        &self,
+        ctx: CallContext,
+        request: GrantHistoryRequest,
+    ) -> RpcResult<GrantHistoryExport>;
+    #[method(name = "ripple.exportUserGrants")]
+    async fn export_user_grants(&self, ctx: CallContext) -> RpcResult<SignedUserGrantsBundle>;
+    #[method(name = "ripple.importUserGrants")]
+    async fn import_user_grants(
+        &self,
+        ctx: CallContext,
+        bundle: SignedUserGrantsBundle,
+    ) -> RpcResult<UserGrantsImportResult>;
     #[method(name = "ripple.getUserGrants")]
     async fn get_user_grants(
         &self,
@@ -186,7 +208,7 @@ impl UserGrantsImpl {
 impl UserGrantsServer for UserGrantsImpl {
     async fn set_user_grants(
         &self,
-        _ctx: CallContext,
+        ctx: CallContext,
         user_grant_info: Vec<UserGrantInfo>,
     ) -> RpcResult<()> {
         debug!("Handling set user grant request: {:?}", user_grant_info);
@@ -208,11 +230,16 @@ impl UserGrantsServer for UserGrantsImpl {
                         .saturating_sub(grant_info.last_modified_time.as_secs())
                 }),
             };
-            let _ = self
-                .platform_state
+            self.platform_state
                 .cap_state
                 .grant_state
-                .update_grant_entry(app_id, grant_entry);
+                .update_grant_entry(
+                    app_id,
+                    grant_entry,
+                    GrantChangeSource::SetUserGrants {
+                        caller: Some(ctx.app_id.clone()),
+                    },
+                );
         }
 
         Ok(())
@@ -261,6 +288,51 @@ impl UserGrantsServer for UserGrantsImpl {
             }
         }
     }
+    async fn usergrants_history(
+        &self,
+        _ctx: CallContext,
+        request: GrantHistoryRequest,
+    ) -> RpcResult<Vec<GrantHistoryEntry>> {
+        Ok(self
+            .platform_state
+            .cap_state
+            .grant_state
+            .get_grant_history(&request))
+    }
+
+    async fn export_grant_history(
+        &self,
+        _ctx: CallContext,
+        request: GrantHistoryRequest,
+    ) -> RpcResult<GrantHistoryExport> {
+        debug!("Handling export grant history request: {:?}", request);
+        Ok(self
+            .platform_state
+            .cap_state
+            .grant_state
+            .export_grant_history(&request))
+    }
+
+    async fn export_user_grants(&self, _ctx: CallContext) -> RpcResult<SignedUserGrantsBundle> {
+        debug!("Handling export user grants request");
+        UserGrantBundles::export(&self.platform_state)
+            .await
+            .map_err(|e| rpc_err(format!("Unable to export user grants {:?}", e)))
+    }
+
+    async fn import_user_grants(
+        &self,
+        _ctx: CallContext,
+        bundle: SignedUserGrantsBundle,
+    ) -> RpcResult<UserGrantsImportResult> {
+        debug!("Handling import user grants request");
+        let result = UserGrantBundles::import(&self.platform_state, bundle)
+            .await
+            .map_err(|e| rpc_err(format!("Unable to import user grants {:?}", e)))?;
+        debug!("Imported user grants: {:?}", result);
+        Ok(result)
+    }
+
     async fn clear_user_grants(&self, _ctx: CallContext) -> RpcResult<()> {
         debug!("Handling clear user grants request");
         let _ = self
@@ -33,6 +33,14 @@ include!(concat!(env!("OUT_DIR"), "/version.rs"));
 
 #[tokio::main(worker_threads = 2)]
 async fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("contract-coverage") {
+        if let Err(e) = broker::rules::contract_coverage::run(&args[2..]) {
+            eprintln!("{}", e);
+            std::process::exit(exitcode::USAGE);
+        }
+        return;
+    }
     // Init logger
     if let Err(e) = init_and_configure_logger(SEMVER_LIGHTWEIGHT, "gateway".into(), None) {
         println!("{:?} logger init error", e);
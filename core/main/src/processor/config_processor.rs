@@ -103,6 +103,12 @@ impl ExtnRequestProcessor for ConfigRequestProcessor {
             Config::SavedDir => {
                 ExtnResponse::String(device_manifest.configuration.saved_dir.clone())
             }
+            Config::StoreEncryption => match device_manifest.get_store_encryption() {
+                Some(config) => {
+                    ExtnResponse::Value(serde_json::to_value(config).unwrap_or_default())
+                }
+                None => ExtnResponse::None(()),
+            },
             Config::FormFactor => ExtnResponse::String(device_manifest.get_form_factor()),
             Config::DistributorExperienceId => {
                 ExtnResponse::String(device_manifest.get_distributor_experience_id())
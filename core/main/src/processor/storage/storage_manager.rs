@@ -23,10 +23,10 @@ use ripple_sdk::{
             SetStorageProperty, StorageData,
         },
         firebolt::fb_capabilities::CAPABILITY_NOT_AVAILABLE,
-        storage_property::{StorageProperty, StoragePropertyData},
+        storage_property::{StorageProperty, StoragePropertyData, NAMESPACE_PRIVACY},
     },
     extn::extn_client_message::ExtnResponse,
-    log::trace,
+    log::{trace, warn},
     serde_json::{json, Value},
     tokio,
     utils::{error::RippleError, rpc_utils::rpc_error_with_code},
@@ -37,7 +37,7 @@ use std::collections::HashMap;
 use crate::{
     processor::storage::storage_manager_utils::{
         storage_to_bool_rpc_result, storage_to_f32_rpc_result, storage_to_string_rpc_result,
-        storage_to_u32_rpc_result,
+        storage_to_u32_rpc_result, storage_to_value_rpc_result,
     },
     service::apps::app_events::AppEvents,
     state::platform_state::PlatformState,
@@ -72,10 +72,64 @@ pub enum StorageManagerError {
     DataTypeMisMatch,
 }
 
+/// Namespaces whose properties are kept apart for every user profile
+const PROFILE_NAMESPACES: &[&str] = &[NAMESPACE_PRIVACY];
+
 #[derive(Clone)]
 pub struct StorageManager;
 
 impl StorageManager {
+    /// Namespace a property is persisted in for the active user profile
+    pub fn namespace_for_profile(profile_id: Option<&str>, namespace: &str) -> String {
+        match profile_id {
+            Some(id) if PROFILE_NAMESPACES.contains(&namespace) => {
+                format!("profile.{}.{}", id, namespace)
+            }
+            _ => namespace.to_owned(),
+        }
+    }
+
+    /// Namespace and key of every property persisted for a profile
+    pub fn profile_properties(profile_id: &str) -> Vec<(String, &'static str)> {
+        StorageProperty::all()
+            .iter()
+            .map(|property| property.as_data())
+            .filter(|data| PROFILE_NAMESPACES.contains(&data.namespace))
+            .map(|data| {
+                (
+                    Self::namespace_for_profile(Some(profile_id), data.namespace),
+                    data.key,
+                )
+            })
+            .collect()
+    }
+
+    /// Deletes every property persisted for a profile. Properties which could not be deleted
+    /// are logged, the profile is gone regardless.
+    pub async fn delete_profile_properties(state: &PlatformState, profile_id: &str) {
+        for (namespace, key) in Self::profile_properties(profile_id) {
+            let data = DeleteStorageProperty {
+                namespace,
+                key: key.to_owned(),
+                scope: None,
+            };
+            if let Err(e) = state
+                .get_client()
+                .send_extn_request(DevicePersistenceRequest::Delete(data.clone()))
+                .await
+            {
+                warn!(
+                    "could not delete {}.{} of a deleted profile: {:?}",
+                    data.namespace, data.key, e
+                );
+            }
+        }
+    }
+
+    fn active_namespace(state: &PlatformState, namespace: &str) -> String {
+        Self::namespace_for_profile(state.profile_state.get_active().as_deref(), namespace)
+    }
+
     pub async fn get_bool(state: &PlatformState, property: StorageProperty) -> RpcResult<bool> {
         if let Some(val) = state
             .ripple_cache
@@ -137,6 +191,9 @@ impl StorageManager {
     }
 
     pub async fn get_string(state: &PlatformState, property: StorageProperty) -> RpcResult<String> {
+        if let Some(val) = state.ripple_cache.get_cached(&property) {
+            return Ok(val);
+        }
         let data = property.as_data();
         match StorageManager::get_string_from_namespace(
             state,
@@ -146,7 +203,12 @@ impl StorageManager {
         )
         .await
         {
-            Ok(resp) => Ok(resp.as_value()),
+            Ok(StorageManagerResponse::Default(value)) => Ok(value),
+            Ok(resp) => {
+                let value = resp.as_value();
+                state.ripple_cache.update_cached(&property, &value);
+                Ok(value)
+            }
             Err(_) => Err(StorageManager::get_firebolt_error(&property)),
         }
     }
@@ -266,6 +328,7 @@ impl StorageManager {
         {
             Err(StorageManager::get_firebolt_error(&property))
         } else {
+            state.ripple_cache.update_cached(&property, &value);
             Ok(())
         }
     }
@@ -303,6 +366,9 @@ impl StorageManager {
         state: &PlatformState,
         property: StorageProperty,
     ) -> RpcResult<u32> {
+        if let Some(val) = state.ripple_cache.get_cached(&property) {
+            return Ok(val);
+        }
         let data = property.as_data();
         match StorageManager::get_number_as_u32_from_namespace(
             state,
@@ -311,7 +377,12 @@ impl StorageManager {
         )
         .await
         {
-            Ok(resp) => Ok(resp.as_value()),
+            Ok(StorageManagerResponse::Default(value)) => Ok(value),
+            Ok(resp) => {
+                let value = resp.as_value();
+                state.ripple_cache.update_cached(&property, &value);
+                Ok(value)
+            }
             Err(_) => Err(StorageManager::get_firebolt_error(&property)),
         }
     }
@@ -320,16 +391,25 @@ impl StorageManager {
         state: &PlatformState,
         property: StorageProperty,
     ) -> RpcResult<f32> {
+        if let Some(val) = state.ripple_cache.get_cached(&property) {
+            return Ok(val);
+        }
         let data = property.as_data();
-        StorageManager::get_number_as_f32_from_namespace(
+        match StorageManager::get_number_as_f32_from_namespace(
             state,
             data.namespace.to_string(),
             data.key,
         )
         .await
-        .map_or(Err(StorageManager::get_firebolt_error(&property)), |resp| {
-            Ok(resp.as_value())
-        })
+        {
+            Ok(StorageManagerResponse::Default(value)) => Ok(value),
+            Ok(resp) => {
+                let value = resp.as_value();
+                state.ripple_cache.update_cached(&property, &value);
+                Ok(value)
+            }
+            Err(_) => Err(StorageManager::get_firebolt_error(&property)),
+        }
     }
 
     pub async fn set_number_as_f32(
@@ -353,6 +433,7 @@ impl StorageManager {
         {
             return Err(StorageManager::get_firebolt_error(&property));
         }
+        state.ripple_cache.update_cached(&property, &value);
         Ok(())
     }
 
@@ -377,6 +458,7 @@ impl StorageManager {
         {
             return Err(StorageManager::get_firebolt_error(&property));
         }
+        state.ripple_cache.update_cached(&property, &value);
         Ok(())
     }
 
@@ -426,7 +508,7 @@ impl StorageManager {
         }
 
         let ssp = SetStorageProperty {
-            namespace,
+            namespace: Self::active_namespace(state, &namespace),
             key,
             data: StorageData::new(value.clone()),
             scope,
@@ -509,7 +591,7 @@ impl StorageManager {
             |_| {
                 DefaultStorageProperties::get_number_as_f32(state, &namespace, key)
                     .map_or(Err(StorageManagerError::NotFound), |val| {
-                        Ok(StorageManagerResponse::Ok(val))
+                        Ok(StorageManagerResponse::Default(val))
                     })
             },
             |val| Ok(StorageManagerResponse::Ok(val)),
@@ -537,6 +619,7 @@ impl StorageManager {
             .await
             {
                 Ok(_) => {
+                    state.ripple_cache.invalidate(&property);
                     StorageManager::notify(state, Value::Null, data.event_names, None).await;
                     Ok(())
                 }
@@ -555,7 +638,7 @@ impl StorageManager {
     ) -> Result<ExtnResponse, RippleError> {
         trace!("get: namespace={}, key={}", namespace, key);
         let data = GetStorageProperty {
-            namespace: namespace.clone(),
+            namespace: Self::active_namespace(state, namespace),
             key: key.clone(),
             scope,
         };
@@ -584,7 +667,7 @@ impl StorageManager {
     ) -> Result<ExtnResponse, RippleError> {
         trace!("delete: namespace={}, key={}", namespace, key);
         let data = DeleteStorageProperty {
-            namespace: namespace.clone(),
+            namespace: Self::active_namespace(state, namespace),
             key: key.clone(),
             scope,
         };
@@ -640,6 +723,7 @@ impl StorageManager {
         {
             return Err(StorageManager::get_firebolt_error(&property));
         }
+        state.ripple_cache.update_cached(&property, &value);
         Ok(())
     }
 
@@ -647,8 +731,11 @@ impl StorageManager {
         state: &PlatformState,
         property: StorageProperty,
     ) -> RpcResult<Vec<String>> {
+        if let Some(val) = state.ripple_cache.get_cached(&property) {
+            return Ok(val);
+        }
         let data = property.as_data();
-        storage_to_vec_string_rpc_result(
+        let value = storage_to_vec_string_rpc_result(
             StorageManager::get(
                 state,
                 &data.namespace.to_string(),
@@ -656,7 +743,33 @@ impl StorageManager {
                 None,
             )
             .await,
-        )
+        )?;
+        state.ripple_cache.update_cached(&property, &value);
+        Ok(value)
+    }
+
+    /// Loads the properties configured in `storage_cache_warm_up` of the device manifest into
+    /// the cache
+    pub async fn warm_up_cache(state: &PlatformState) {
+        let properties = state
+            .get_device_manifest()
+            .configuration
+            .storage_cache_warm_up
+            .clone();
+        for property in properties {
+            let data = property.as_data();
+            let resp = StorageManager::get(
+                state,
+                &data.namespace.to_string(),
+                &data.key.to_string(),
+                None,
+            )
+            .await;
+            match storage_to_value_rpc_result(resp) {
+                Ok(value) => state.ripple_cache.update_cached(&property, &value),
+                Err(_) => trace!("warm up: no stored value for {:?}", property),
+            }
+        }
     }
 
     async fn notify(
@@ -680,3 +793,25 @@ impl StorageManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_properties() {
+        let properties = StorageManager::profile_properties("kid");
+        let privacy_count = StorageProperty::all()
+            .iter()
+            .filter(|p| p.as_data().namespace == NAMESPACE_PRIVACY)
+            .count();
+        assert_eq!(properties.len(), privacy_count);
+        let expected = format!("profile.kid.{}", NAMESPACE_PRIVACY);
+        assert!(properties
+            .iter()
+            .all(|(namespace, _)| namespace.eq(&expected)));
+        assert!(properties
+            .iter()
+            .any(|(_, key)| *key == StorageProperty::AllowWatchHistory.as_data().key));
+    }
+}
@@ -96,6 +96,15 @@ impl ExtnRequestProcessor for StorageManagerProcessor {
                         .is_ok()
                 }
             }
+            StorageManagerRequest::InvalidateCache(property) => {
+                match property {
+                    Some(property) => state.ripple_cache.invalidate(&property),
+                    None => state.ripple_cache.clear(),
+                }
+                Self::respond(client, msg, ExtnResponse::None(()))
+                    .await
+                    .is_ok()
+            }
         }
     }
 }
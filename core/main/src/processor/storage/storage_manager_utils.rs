@@ -54,6 +54,10 @@ fn get_value(resp: Result<ExtnResponse, RippleError>) -> Result<Value, Error> {
     }
 }
 
+pub fn storage_to_value_rpc_result(resp: Result<ExtnResponse, RippleError>) -> RpcResult<Value> {
+    get_value(resp)
+}
+
 pub fn storage_to_string_rpc_result(resp: Result<ExtnResponse, RippleError>) -> RpcResult<String> {
     let value = get_value(resp)?;
 
@@ -0,0 +1,421 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::collections::HashMap;
+
+use ripple_sdk::{
+    api::{
+        distributor::distributor_privacy::DataEventType,
+        gateway::rpc_gateway_api::RpcRequest,
+        manifest::device_manifest::{DataGovernanceAction, DataGovernancePolicy},
+        storage_property::StorageProperty,
+    },
+    async_trait::async_trait,
+    log::{error, info},
+    serde_json::{self, Value},
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    broker::endpoint_broker::BrokerRequestFilter,
+    processor::storage::storage_manager::StorageManager, state::platform_state::PlatformState,
+};
+
+/// Marker used in the trace when the whole payload was dropped
+const WHOLE_PAYLOAD: &str = "*";
+
+/// Data types of the Firebolt methods sending user data, used when a method is brokered by a
+/// rule without a `data_type`. Operational metrics like `metrics.error` are not governed.
+const DEFAULT_DATA_TYPES: &[(&str, DataEventType)] = &[
+    ("discovery.watched", DataEventType::Watched),
+    ("metrics.startContent", DataEventType::BusinessIntelligence),
+    ("metrics.stopContent", DataEventType::BusinessIntelligence),
+    ("metrics.page", DataEventType::BusinessIntelligence),
+    ("metrics.action", DataEventType::BusinessIntelligence),
+    ("metrics.event", DataEventType::BusinessIntelligence),
+    (
+        "metrics.mediaLoadStart",
+        DataEventType::BusinessIntelligence,
+    ),
+    ("metrics.mediaPlay", DataEventType::BusinessIntelligence),
+    ("metrics.mediaPlaying", DataEventType::BusinessIntelligence),
+    ("metrics.mediaPause", DataEventType::BusinessIntelligence),
+    ("metrics.mediaWaiting", DataEventType::BusinessIntelligence),
+    ("metrics.mediaProgress", DataEventType::BusinessIntelligence),
+    ("metrics.mediaSeeking", DataEventType::BusinessIntelligence),
+    ("metrics.mediaSeeked", DataEventType::BusinessIntelligence),
+    (
+        "metrics.mediaRateChange",
+        DataEventType::BusinessIntelligence,
+    ),
+    (
+        "metrics.mediaRenditionChange",
+        DataEventType::BusinessIntelligence,
+    ),
+    ("metrics.mediaEnded", DataEventType::BusinessIntelligence),
+];
+
+/// Applies the data governance policies of the device manifest to data leaving the device.
+/// Payloads are dropped when a governing setting cannot be read.
+pub struct DataGovernance;
+
+impl DataGovernance {
+    pub fn default_data_type(method: &str) -> Option<DataEventType> {
+        DEFAULT_DATA_TYPES
+            .iter()
+            .find(|(m, _)| m.eq_ignore_ascii_case(method))
+            .map(|(_, data_type)| data_type.clone())
+    }
+
+    pub fn get_policy(
+        state: &PlatformState,
+        data_type: &DataEventType,
+    ) -> Option<DataGovernancePolicy> {
+        state
+            .get_device_manifest()
+            .configuration
+            .data_governance
+            .get_policy(data_type.clone())
+    }
+
+    fn is_enforced(
+        settings: &HashMap<StorageProperty, bool>,
+        setting: &StorageProperty,
+        enforcement_value: bool,
+    ) -> bool {
+        settings
+            .get(setting)
+            .map_or(false, |value| *value == enforcement_value)
+    }
+
+    fn suppress_path(value: &mut Value, path: &[&str], action: &DataGovernanceAction) -> bool {
+        let (key, rest) = match path.split_first() {
+            Some(split) => split,
+            None => return false,
+        };
+        match value {
+            Value::Array(items) => items.iter_mut().fold(false, |found, item| {
+                Self::suppress_path(item, path, action) || found
+            }),
+            Value::Object(map) if rest.is_empty() => match action {
+                DataGovernanceAction::Drop => map.remove(*key).is_some(),
+                DataGovernanceAction::Redact => match map.get_mut(*key) {
+                    Some(field) if !field.is_null() => {
+                        *field = Value::Null;
+                        true
+                    }
+                    _ => false,
+                },
+            },
+            Value::Object(map) => map
+                .get_mut(*key)
+                .map_or(false, |child| Self::suppress_path(child, rest, action)),
+            _ => false,
+        }
+    }
+
+    /// Applies the policy to the payload with the given setting values, a setting without a
+    /// value is not enforced. Returns None when the whole payload is dropped along with the
+    /// fields which were suppressed.
+    pub fn apply_policy(
+        policy: &DataGovernancePolicy,
+        settings: &HashMap<StorageProperty, bool>,
+        mut payload: Value,
+    ) -> (Option<Value>, Vec<String>) {
+        if policy.drop_on_all_tags
+            && !policy.setting_tags.is_empty()
+            && policy
+                .setting_tags
+                .iter()
+                .all(|t| Self::is_enforced(settings, &t.setting, t.enforcement_value))
+        {
+            return (None, vec![WHOLE_PAYLOAD.to_owned()]);
+        }
+        let mut suppressed = Vec::new();
+        for rule in &policy.fields {
+            if !Self::is_enforced(settings, &rule.setting, rule.enforcement_value) {
+                continue;
+            }
+            let path: Vec<&str> = rule.field.split('.').collect();
+            if Self::suppress_path(&mut payload, &path, &rule.action) {
+                suppressed.push(rule.field.clone());
+            }
+        }
+        (Some(payload), suppressed)
+    }
+
+    /// Reads every setting of the policy, returns the first setting which cannot be read
+    async fn resolve_settings(
+        state: &PlatformState,
+        policy: &DataGovernancePolicy,
+    ) -> Result<HashMap<StorageProperty, bool>, StorageProperty> {
+        let mut settings = HashMap::new();
+        let properties = policy
+            .setting_tags
+            .iter()
+            .map(|t| &t.setting)
+            .chain(policy.fields.iter().map(|f| &f.setting));
+        for property in properties {
+            if settings.contains_key(property) {
+                continue;
+            }
+            let value = StorageManager::get_bool(state, property.clone())
+                .await
+                .map_err(|_| property.clone())?;
+            settings.insert(property.clone(), value);
+        }
+        Ok(settings)
+    }
+
+    /// Filters the payload with the policy of the data type, None means it must not be sent
+    pub async fn filter(
+        state: &PlatformState,
+        data_type: &DataEventType,
+        payload: Value,
+    ) -> Option<Value> {
+        let policy = match Self::get_policy(state, data_type) {
+            Some(policy) => policy,
+            None => return Some(payload),
+        };
+        let settings = match Self::resolve_settings(state, &policy).await {
+            Ok(settings) => settings,
+            Err(setting) => {
+                error!(
+                    "data governance could not read {:?}, dropping {:?} payload",
+                    setting, data_type
+                );
+                return None;
+            }
+        };
+        let (payload, suppressed) = Self::apply_policy(&policy, &settings, payload);
+        if !suppressed.is_empty() {
+            info!(
+                "data governance suppressed {:?} of {:?} payload",
+                suppressed, data_type
+            );
+        }
+        payload
+    }
+
+    pub async fn filter_as<T: Serialize + DeserializeOwned>(
+        state: &PlatformState,
+        data_type: &DataEventType,
+        payload: T,
+    ) -> Option<T> {
+        let value = serde_json::to_value(payload).ok()?;
+        let filtered = Self::filter(state, data_type, value).await?;
+        match serde_json::from_value(filtered) {
+            Ok(payload) => Some(payload),
+            Err(e) => {
+                error!(
+                    "governed {:?} payload is not valid, dropping it {:?}",
+                    data_type, e
+                );
+                None
+            }
+        }
+    }
+
+    /// Filters the params of a request brokered by a rule, with the `data_type` of the rule or
+    /// the default data type of the method. Returns false when the request must not be sent.
+    pub async fn filter_request(state: &PlatformState, request: &mut RpcRequest) -> bool {
+        let data_type = match state.endpoint_state.get_rule_data_type(request) {
+            Some(data_type) => data_type,
+            None => return true,
+        };
+        let mut params: Vec<Value> = match serde_json::from_str(&request.params_json) {
+            Ok(params) => params,
+            Err(_) => return true,
+        };
+        let payload = match params.pop() {
+            Some(payload) => payload,
+            None => return true,
+        };
+        match Self::filter(state, &data_type, payload).await {
+            Some(payload) => {
+                params.push(payload);
+                if let Ok(params_json) = serde_json::to_string(&params) {
+                    request.params_json = params_json;
+                }
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Filters the requests sent by the endpoint broker with the data governance policies
+pub struct DataGovernanceFilter {
+    state: PlatformState,
+}
+
+impl DataGovernanceFilter {
+    pub fn new(state: &PlatformState) -> Self {
+        Self {
+            state: state.clone(),
+        }
+    }
+}
+
+// the state holds the endpoint broker which holds this filter
+impl std::fmt::Debug for DataGovernanceFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("DataGovernanceFilter")
+    }
+}
+
+#[async_trait]
+impl BrokerRequestFilter for DataGovernanceFilter {
+    async fn filter(&self, request: &mut RpcRequest) -> bool {
+        DataGovernance::filter_request(&self.state, request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::broker::rules::rules_engine::Rule;
+    use ripple_sdk::api::manifest::device_manifest::{
+        DataGovernanceFieldRule, DataGovernanceSettingTag,
+    };
+    use ripple_sdk::tokio;
+    use ripple_tdk::utils::test_utils::Mockable;
+    use serde_json::json;
+    use std::collections::HashSet;
+
+    fn policy() -> DataGovernancePolicy {
+        let mut policy = DataGovernancePolicy::new(
+            DataEventType::Watched,
+            vec![DataGovernanceSettingTag::new(
+                StorageProperty::AllowWatchHistory,
+                false,
+                HashSet::new(),
+            )],
+            true,
+        );
+        policy.fields = vec![
+            DataGovernanceFieldRule {
+                field: "content_id".into(),
+                setting: StorageProperty::AllowPersonalization,
+                enforcement_value: false,
+                action: DataGovernanceAction::Redact,
+            },
+            DataGovernanceFieldRule {
+                field: "entries.watched_on".into(),
+                setting: StorageProperty::AllowProductAnalytics,
+                enforcement_value: false,
+                action: DataGovernanceAction::Drop,
+            },
+        ];
+        policy
+    }
+
+    #[test]
+    fn test_apply_policy() {
+        let payload = json!({
+            "content_id": "abc",
+            "entries": [{"watched_on": "today", "progress": 1}, {"progress": 2}]
+        });
+        let mut settings = HashMap::new();
+        settings.insert(StorageProperty::AllowWatchHistory, true);
+        settings.insert(StorageProperty::AllowPersonalization, false);
+        settings.insert(StorageProperty::AllowProductAnalytics, false);
+
+        let (filtered, suppressed) =
+            DataGovernance::apply_policy(&policy(), &settings, payload.clone());
+        assert_eq!(
+            filtered,
+            Some(json!({
+                "content_id": null,
+                "entries": [{"progress": 1}, {"progress": 2}]
+            }))
+        );
+        assert_eq!(suppressed, vec!["content_id", "entries.watched_on"]);
+
+        // nothing is suppressed when the settings allow it
+        settings.insert(StorageProperty::AllowPersonalization, true);
+        settings.insert(StorageProperty::AllowProductAnalytics, true);
+        let (filtered, suppressed) =
+            DataGovernance::apply_policy(&policy(), &settings, payload.clone());
+        assert_eq!(filtered, Some(payload.clone()));
+        assert!(suppressed.is_empty());
+
+        // the whole payload is dropped when all the setting tags are enforced
+        settings.insert(StorageProperty::AllowWatchHistory, false);
+        let (filtered, suppressed) =
+            DataGovernance::apply_policy(&policy(), &settings, payload.clone());
+        assert!(filtered.is_none());
+        assert_eq!(suppressed, vec![WHOLE_PAYLOAD]);
+
+        // a setting without a value is not enforced
+        settings.remove(&StorageProperty::AllowWatchHistory);
+        let (filtered, _) = DataGovernance::apply_policy(&policy(), &settings, payload);
+        assert!(filtered.is_some());
+    }
+
+    #[test]
+    fn test_default_data_type() {
+        assert_eq!(
+            DataGovernance::default_data_type("Discovery.watched"),
+            Some(DataEventType::Watched)
+        );
+        assert_eq!(
+            DataGovernance::default_data_type("metrics.startContent"),
+            Some(DataEventType::BusinessIntelligence)
+        );
+        assert_eq!(
+            DataGovernance::default_data_type("Metrics.mediaPause"),
+            Some(DataEventType::BusinessIntelligence)
+        );
+        assert_eq!(
+            DataGovernance::default_data_type("discovery.watchedX"),
+            None
+        );
+        assert_eq!(DataGovernance::default_data_type("metrics.error"), None);
+        assert_eq!(DataGovernance::default_data_type("metrics.ready"), None);
+        assert_eq!(DataGovernance::default_data_type("device.name"), None);
+    }
+
+    #[tokio::test]
+    async fn test_filter_request() {
+        let state = PlatformState::mock();
+        let _ = state.endpoint_state.clone().add_rule(Rule {
+            alias: "discovery.watched".into(),
+            endpoint: Some("http".into()),
+            ..Default::default()
+        });
+        let mut request = RpcRequest::internal("discovery.watched", None)
+            .with_params(Some(json!({"entityId": "abc", "progress": 0.5})));
+        let params_json = request.params_json.clone();
+
+        state
+            .ripple_cache
+            .update_cached_bool_storage_property(&StorageProperty::AllowWatchHistory, true);
+        assert!(DataGovernance::filter_request(&state, &mut request).await);
+        assert_eq!(request.params_json, params_json);
+
+        state
+            .ripple_cache
+            .update_cached_bool_storage_property(&StorageProperty::AllowWatchHistory, false);
+        assert!(!DataGovernance::filter_request(&state, &mut request).await);
+
+        // methods which are not brokered are left to their handlers
+        let mut request = RpcRequest::internal("metrics.startContent", None)
+            .with_params(Some(json!({"entityId": "abc"})));
+        assert!(DataGovernance::filter_request(&state, &mut request).await);
+    }
+}
@@ -0,0 +1,853 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    any::Any,
+    collections::HashMap,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    thread,
+    time::Duration,
+};
+
+use ripple_sdk::{
+    api::{
+        firebolt::fb_capabilities::FireboltCap,
+        manifest::extn_manifest::{ExtnManifestEntry, ExtnRestartPolicy, ExtnRuntime, ExtnSymbol},
+        status_update::{ExtnLibraryStatus, ExtnStatus},
+    },
+    extn::{
+        client::extn_client::ExtnClient,
+        ffi::{ffi_abi::ExtnAbiInfo, ffi_channel::load_channel_builder},
+    },
+    framework::ripple_contract::RippleContract,
+    libloading::Library,
+    log::{error, info, warn},
+    tokio::{
+        runtime::Handle,
+        time::{sleep, Instant},
+    },
+    tokio_tungstenite::tungstenite::Message,
+    utils::error::RippleError,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bootstrap::extn::load_extn_step::LoadExtensionsStep, service::extn::wasm_runtime::WasmExtn,
+    state::platform_state::PlatformState,
+};
+
+/// How the thread of an extension stopped
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExtnExit {
+    /// `start` returned
+    Exited,
+    /// `start` panicked with the given message
+    Panicked(String),
+    /// `start` returned the given error, a WebAssembly module trapped for instance
+    Failed(String),
+}
+
+/// Runs an extension until it stops
+type ExtnStart = Box<dyn FnOnce() -> Result<(), String> + Send>;
+
+/// Builds the start of every attempt, so a restart gets a new channel from the library or a new
+/// instance of the WebAssembly module instead of the one which stopped
+type ExtnStarter = Box<dyn Fn() -> Result<ExtnStart, String> + Send>;
+
+/// Interval at which a started extension is checked for the registration of its symbols
+const REGISTRATION_POLL: Duration = Duration::from_millis(10);
+
+/// Liveness of a supervised extension library
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtnHealth {
+    pub path: String,
+    pub status: ExtnStatus,
+    pub restarts: u32,
+    pub contracts: Vec<String>,
+    pub last_error: Option<String>,
+}
+
+const DEFAULT_DRAIN_TIMEOUT_MS: u64 = 5000;
+
+fn default_drain_timeout_ms() -> u64 {
+    DEFAULT_DRAIN_TIMEOUT_MS
+}
+
+/// Parameters of `ripple.loadExtension`, the entry of the manifest with the same path is used
+/// when `entry` is not given
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExtnLoadRequest {
+    pub path: String,
+    pub entry: Option<ExtnManifestEntry>,
+}
+
+/// Parameters of `ripple.unloadExtension`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtnUnloadRequest {
+    pub path: String,
+    #[serde(default = "default_drain_timeout_ms")]
+    pub drain_timeout_ms: u64,
+}
+
+/// Parameters of `ripple.replaceExtension`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtnReplaceRequest {
+    pub path: String,
+    pub entry: ExtnManifestEntry,
+    #[serde(default = "default_drain_timeout_ms")]
+    pub drain_timeout_ms: u64,
+}
+
+/// Outcome of unloading an extension library
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtnUnloadResult {
+    pub path: String,
+    /// Requests which got their response while draining
+    pub drained: usize,
+    /// Requests which were still unanswered at the end of the drain timeout
+    pub failed: usize,
+    /// False when the thread of the extension did not return after its connection was closed
+    pub stopped: bool,
+}
+
+struct SupervisedExtn {
+    health: ExtnHealth,
+    entry: ExtnManifestEntry,
+    // set before an unload so the thread is not restarted
+    stopping: Arc<AtomicBool>,
+    exited: Arc<AtomicBool>,
+    // never unmapped, threads spawned by the library may outlive `start`. None for a
+    // WebAssembly module.
+    library: Option<Arc<Library>>,
+}
+
+/// Extension which is loaded and checked but not started yet
+struct PreparedExtn {
+    entry: ExtnManifestEntry,
+    library: Option<Arc<Library>>,
+    starter: ExtnStarter,
+}
+
+/// Runs every extension library on its own thread, catches panics at the FFI boundary and
+/// restarts the library as configured by the `restart` policy of its manifest entry. An
+/// extension is only reported ready once its symbols registered with Main.
+/// While an extension is down its senders are removed, so requests for its contracts fail
+/// right away, and the capabilities of these contracts are marked unavailable.
+#[derive(Clone, Default)]
+pub struct ExtnSupervisor {
+    extns: Arc<RwLock<HashMap<String, SupervisedExtn>>>,
+}
+
+impl std::fmt::Debug for ExtnSupervisor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExtnSupervisor")
+            .field("extns", &self.get_health())
+            .finish()
+    }
+}
+
+impl ExtnSupervisor {
+    pub fn get_health(&self) -> Vec<ExtnHealth> {
+        self.extns
+            .read()
+            .unwrap()
+            .values()
+            .map(|e| e.health.clone())
+            .collect()
+    }
+
+    /// Symbol of a loaded extension, the manifest of Main only knows the ones loaded at boot
+    pub fn get_symbol(&self, id: &str) -> Option<ExtnSymbol> {
+        self.extns
+            .read()
+            .unwrap()
+            .values()
+            .flat_map(|e| e.entry.symbols.iter())
+            .find(|s| s.id == id)
+            .cloned()
+    }
+
+    pub fn is_loaded(&self, path: &str) -> bool {
+        self.extns.read().unwrap().contains_key(path)
+    }
+
+    /// Returns the backoff before the next start or None when the extension stays down
+    pub fn next_restart(
+        policy: &ExtnRestartPolicy,
+        exit: &ExtnExit,
+        restarts: u32,
+    ) -> Option<Duration> {
+        policy.next_restart(
+            matches!(exit, ExtnExit::Panicked(_) | ExtnExit::Failed(_)),
+            restarts,
+        )
+    }
+
+    fn panic_message(payload: Box<dyn Any + Send>) -> String {
+        if let Some(s) = payload.downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = payload.downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "unknown panic".to_owned()
+        }
+    }
+
+    /// Runs `start` and reports how it stopped, a panic does not leave this function
+    pub fn run_guarded(start: impl FnOnce() -> Result<(), String>) -> ExtnExit {
+        match panic::catch_unwind(AssertUnwindSafe(start)) {
+            Ok(Ok(())) => ExtnExit::Exited,
+            Ok(Err(reason)) => ExtnExit::Failed(reason),
+            Err(payload) => ExtnExit::Panicked(Self::panic_message(payload)),
+        }
+    }
+
+    fn update(&self, path: &str, status: ExtnStatus, last_error: Option<String>, restarts: u32) {
+        if let Some(extn) = self
+            .extns
+            .write()
+            .unwrap()
+            .get_mut(path)
+            .filter(|e| !e.stopping.load(Ordering::SeqCst))
+        {
+            extn.health.status = status;
+            extn.health.restarts = restarts;
+            if last_error.is_some() {
+                extn.health.last_error = last_error;
+            }
+        }
+    }
+
+    fn set_available(
+        state: &PlatformState,
+        entry: &ExtnManifestEntry,
+        status: ExtnStatus,
+        reason: Option<String>,
+    ) {
+        let caps: Vec<FireboltCap> = entry
+            .symbols
+            .iter()
+            .flat_map(|s| s.fulfills.iter())
+            .filter_map(|c| RippleContract::from_manifest(c))
+            .flat_map(|c| c.get_capabilities())
+            .collect();
+        if !caps.is_empty() {
+            state
+                .cap_state
+                .generic
+                .ingest_availability(caps, status == ExtnStatus::Ready);
+        }
+        let _ = state
+            .get_client()
+            .get_extn_client()
+            .event(ExtnLibraryStatus {
+                path: entry.path.clone(),
+                status,
+                reason,
+            });
+    }
+
+    /// Stops routing to the symbols of a stopped extension and fails the requests it did not answer
+    fn remove_senders(state: &PlatformState, entry: &ExtnManifestEntry) {
+        let mut client = state.get_client().get_extn_client();
+        for symbol in &entry.symbols {
+            client.remove_sender(symbol.id.clone(), symbol.clone());
+            client.fail_in_flight(&symbol.id);
+        }
+    }
+
+    fn native_starter(library: Arc<Library>, entry: &ExtnManifestEntry) -> ExtnStarter {
+        let path = entry.path.clone();
+        Box::new(move || match unsafe { load_channel_builder(&library) } {
+            Ok(builder) => {
+                let start = builder.start;
+                Ok(Box::new(move || {
+                    start();
+                    Ok(())
+                }) as ExtnStart)
+            }
+            Err(e) => Err(format!("{} has no channel builder {:?}", path, e)),
+        })
+    }
+
+    fn wasm_starter(extn: WasmExtn) -> ExtnStarter {
+        // every run instantiates the module again
+        let extn = Arc::new(extn);
+        Box::new(move || {
+            let extn = extn.clone();
+            Ok(Box::new(move || extn.run()) as ExtnStart)
+        })
+    }
+
+    /// Starts the extension library on a supervised thread
+    pub fn supervise(&self, state: &PlatformState, library: Library, entry: ExtnManifestEntry) {
+        let library = Arc::new(library);
+        let starter = Self::native_starter(library.clone(), &entry);
+        if let Err(e) = starter() {
+            error!("{}", e);
+            return;
+        }
+        self.supervise_start(state, entry, Some(library), starter);
+    }
+
+    /// Starts a WebAssembly extension on a supervised thread
+    pub fn supervise_wasm(&self, state: &PlatformState, extn: WasmExtn, entry: ExtnManifestEntry) {
+        self.supervise_start(state, entry, None, Self::wasm_starter(extn));
+    }
+
+    /// Marks the extension ready once all its symbols registered with Main, unless the attempt
+    /// stopped before
+    fn ready_on_registration(
+        &self,
+        state: &PlatformState,
+        entry: &ExtnManifestEntry,
+        restarts: u32,
+        attempt_over: Arc<AtomicBool>,
+        runtime: Option<Handle>,
+    ) {
+        let supervisor = self.clone();
+        let state = state.clone();
+        let entry = entry.clone();
+        thread::spawn(move || {
+            let _runtime = runtime.as_ref().map(|r| r.enter());
+            let client = state.get_client().get_extn_client();
+            while !attempt_over.load(Ordering::SeqCst) {
+                if Self::is_registered(&client, &entry) {
+                    let mut extns = supervisor.extns.write().unwrap();
+                    // checked under the lock, the supervising thread marks a stopped attempt
+                    // before it updates the health
+                    if attempt_over.load(Ordering::SeqCst) {
+                        return;
+                    }
+                    if let Some(extn) = extns
+                        .get_mut(&entry.path)
+                        .filter(|e| !e.stopping.load(Ordering::SeqCst))
+                    {
+                        info!("{} is ready", entry.path);
+                        extn.health.status = ExtnStatus::Ready;
+                        extn.health.restarts = restarts;
+                        Self::set_available(&state, &entry, ExtnStatus::Ready, None);
+                    }
+                    return;
+                }
+                thread::sleep(REGISTRATION_POLL);
+            }
+        });
+    }
+
+    fn supervise_start(
+        &self,
+        state: &PlatformState,
+        entry: ExtnManifestEntry,
+        library: Option<Arc<Library>>,
+        starter: ExtnStarter,
+    ) {
+        let contracts = entry
+            .symbols
+            .iter()
+            .flat_map(|s| s.fulfills.iter().cloned())
+            .collect();
+        let stopping = Arc::new(AtomicBool::new(false));
+        let exited = Arc::new(AtomicBool::new(false));
+        self.extns.write().unwrap().insert(
+            entry.path.clone(),
+            SupervisedExtn {
+                health: ExtnHealth {
+                    path: entry.path.clone(),
+                    // until its symbols register
+                    status: ExtnStatus::Interrupted,
+                    restarts: 0,
+                    contracts,
+                    last_error: None,
+                },
+                entry: entry.clone(),
+                stopping: stopping.clone(),
+                exited: exited.clone(),
+                library: library.clone(),
+            },
+        );
+
+        let supervisor = self.clone();
+        let state = state.clone();
+        // failing the in flight requests of a crashed extension spawns their responses
+        let runtime = Handle::try_current().ok();
+        thread::spawn(move || {
+            let _runtime = runtime.as_ref().map(|r| r.enter());
+            let _library = library;
+            let mut restarts = 0;
+            loop {
+                info!("Starting library at path {}", entry.path);
+                let started = std::time::Instant::now();
+                let attempt_over = Arc::new(AtomicBool::new(false));
+                supervisor.ready_on_registration(
+                    &state,
+                    &entry,
+                    restarts,
+                    attempt_over.clone(),
+                    runtime.clone(),
+                );
+                let exit = match starter() {
+                    Ok(start) => Self::run_guarded(start),
+                    Err(reason) => ExtnExit::Failed(reason),
+                };
+                attempt_over.store(true, Ordering::SeqCst);
+                if stopping.load(Ordering::SeqCst) {
+                    info!("{} unloaded", entry.path);
+                    break;
+                }
+                let reason = match &exit {
+                    ExtnExit::Exited => format!("{} stopped", entry.path),
+                    ExtnExit::Panicked(message) => format!("{} panicked: {}", entry.path, message),
+                    ExtnExit::Failed(message) => format!("{} failed: {}", entry.path, message),
+                };
+                warn!("{}", reason);
+                restarts = entry.restart.restarts_after(restarts, started.elapsed());
+                Self::remove_senders(&state, &entry);
+                supervisor.update(
+                    &entry.path,
+                    ExtnStatus::Interrupted,
+                    Some(reason.clone()),
+                    restarts,
+                );
+                Self::set_available(&state, &entry, ExtnStatus::Interrupted, Some(reason));
+
+                match Self::next_restart(&entry.restart, &exit, restarts) {
+                    Some(backoff) => {
+                        thread::sleep(backoff);
+                        if stopping.load(Ordering::SeqCst) {
+                            info!("{} unloaded", entry.path);
+                            break;
+                        }
+                        restarts += 1;
+                        info!("Restarting {} attempt {}", entry.path, restarts);
+                        supervisor.update(&entry.path, ExtnStatus::Interrupted, None, restarts);
+                    }
+                    None => {
+                        error!("{} will not be restarted", entry.path);
+                        supervisor.update(&entry.path, ExtnStatus::Error, None, restarts);
+                        Self::set_available(
+                            &state,
+                            &entry,
+                            ExtnStatus::Error,
+                            Some("restart policy exhausted".to_owned()),
+                        );
+                        break;
+                    }
+                }
+            }
+            exited.store(true, Ordering::SeqCst);
+        });
+    }
+
+    /// Loads and checks an extension library without starting it, `entry.path` is resolved like
+    /// the entries of the manifest
+    fn prepare(
+        &self,
+        state: &PlatformState,
+        entry: ExtnManifestEntry,
+    ) -> Result<PreparedExtn, RippleError> {
+        let manifest = &state.extn_manifest;
+        let path = entry.get_path(&manifest.default_path, &manifest.default_extension);
+        if let ExtnRuntime::Wasm(limits) = entry.runtime.clone() {
+            let extn = WasmExtn::load(&path, &entry, limits).map_err(|e| {
+                if e == RippleError::ExtnError {
+                    Self::set_available(
+                        state,
+                        &entry,
+                        ExtnStatus::Error,
+                        Some("incompatible WebAssembly module".to_owned()),
+                    );
+                }
+                e
+            })?;
+            return Ok(PreparedExtn {
+                entry,
+                library: None,
+                starter: Self::wasm_starter(extn),
+            });
+        }
+        let loaded = unsafe { LoadExtensionsStep::load_extension_library(path.clone(), entry) }
+            .ok_or(RippleError::NotAvailable)?;
+        if let Err(reason) =
+            unsafe { LoadExtensionsStep::check_abi(&loaded, &ExtnAbiInfo::current()) }
+        {
+            error!("Refusing incompatible extension {}: {}", path, reason);
+            Self::set_available(state, &loaded.entry, ExtnStatus::Error, Some(reason));
+            return Err(RippleError::ExtnError);
+        }
+        let library = Arc::new(loaded.library);
+        let starter = Self::native_starter(library.clone(), &loaded.entry);
+        if let Err(e) = starter() {
+            error!("{}", e);
+            return Err(RippleError::ExtnError);
+        }
+        Ok(PreparedExtn {
+            entry: loaded.entry,
+            library: Some(library),
+            starter,
+        })
+    }
+
+    /// Loads an extension library at runtime, `entry.path` is resolved like the entries of the manifest
+    pub fn load(&self, state: &PlatformState, entry: ExtnManifestEntry) -> Result<(), RippleError> {
+        if self.is_loaded(&entry.path) {
+            error!("{} is already loaded", entry.path);
+            return Err(RippleError::InvalidInput);
+        }
+        let prepared = self.prepare(state, entry)?;
+        info!("Adding {}", prepared.entry.path);
+        self.supervise_start(state, prepared.entry, prepared.library, prepared.starter);
+        Ok(())
+    }
+
+    fn is_registered(client: &ExtnClient, entry: &ExtnManifestEntry) -> bool {
+        entry
+            .symbols
+            .iter()
+            .all(|s| client.get_extn_sender_with_extn_id(&s.id).is_some())
+    }
+
+    fn in_flight_count(client: &ExtnClient, ids: &[String]) -> usize {
+        ids.iter().map(|id| client.in_flight_count(id)).sum()
+    }
+
+    /// Unloads an extension library. Its contracts stop being routed right away, requests it
+    /// already got are given `drain` to complete before they fail, then its connection is closed
+    /// which lets `start` return.
+    pub async fn unload(
+        &self,
+        state: &PlatformState,
+        path: &str,
+        drain: Duration,
+    ) -> Result<ExtnUnloadResult, RippleError> {
+        let (entry, exited) = {
+            let extns = self.extns.read().unwrap();
+            let extn = extns.get(path).ok_or(RippleError::NotAvailable)?;
+            extn.stopping.store(true, Ordering::SeqCst);
+            (extn.entry.clone(), extn.exited.clone())
+        };
+        info!("Unloading {}", path);
+        let client = state.get_client().get_extn_client();
+        let ids: Vec<String> = entry.symbols.iter().map(|s| s.id.clone()).collect();
+        for id in &ids {
+            client.remove_contracts(id);
+        }
+        Self::set_available(
+            state,
+            &entry,
+            ExtnStatus::Interrupted,
+            Some(format!("{} unloaded", path)),
+        );
+
+        let pending = Self::in_flight_count(&client, &ids);
+        let deadline = Instant::now() + drain;
+        while Self::in_flight_count(&client, &ids) > 0 && Instant::now() < deadline {
+            sleep(Duration::from_millis(10)).await;
+        }
+        let failed: usize = ids.iter().map(|id| client.fail_in_flight(id)).sum();
+
+        for id in &ids {
+            if let Some(sender) = state
+                .service_controller_state
+                .get_sender(&id.to_string())
+                .await
+            {
+                let _ = sender.send(Message::Close(None)).await;
+            }
+        }
+        // wait for the connections to be cleaned up so a new version can register the same ids
+        let deadline = Instant::now() + drain;
+        while Instant::now() < deadline
+            && !(exited.load(Ordering::SeqCst)
+                && ids
+                    .iter()
+                    .all(|id| client.get_extn_sender_with_extn_id(id).is_none()))
+        {
+            sleep(Duration::from_millis(10)).await;
+        }
+        let stopped = exited.load(Ordering::SeqCst);
+        if !stopped {
+            warn!("{} did not return after its connection closed", path);
+        }
+        if let Some(library) = self
+            .extns
+            .write()
+            .unwrap()
+            .remove(path)
+            .and_then(|e| e.library)
+        {
+            // threads spawned by the library may still run its code, it is never unmapped
+            std::mem::forget(library);
+        }
+
+        Ok(ExtnUnloadResult {
+            path: path.to_owned(),
+            drained: pending.saturating_sub(failed),
+            failed,
+            stopped,
+        })
+    }
+
+    /// Loads `entry` in place of the library at `path`. The new library is loaded and checked
+    /// before the running one is touched, so a replacement which cannot be loaded leaves it
+    /// serving. When the versions use different channel ids the new one is started and has to
+    /// register before the running one is unloaded. Channel ids are unique, so a new version
+    /// reusing them is started right after the running one is unloaded.
+    pub async fn replace(
+        &self,
+        state: &PlatformState,
+        path: &str,
+        entry: ExtnManifestEntry,
+        drain: Duration,
+    ) -> Result<ExtnUnloadResult, RippleError> {
+        let previous = self
+            .extns
+            .read()
+            .unwrap()
+            .get(path)
+            .map(|e| e.entry.clone())
+            .ok_or(RippleError::NotAvailable)?;
+        if entry.path != path && self.is_loaded(&entry.path) {
+            error!("{} is already loaded", entry.path);
+            return Err(RippleError::InvalidInput);
+        }
+        let prepared = self.prepare(state, entry)?;
+        let next = prepared.entry.clone();
+        let shares_ids = next
+            .symbols
+            .iter()
+            .any(|s| previous.symbols.iter().any(|p| p.id == s.id));
+        if next.path == path || shares_ids {
+            let result = self.unload(state, path, drain).await?;
+            info!("Replacing {} with {}", path, next.path);
+            self.supervise_start(state, prepared.entry, prepared.library, prepared.starter);
+            return Ok(result);
+        }
+
+        info!("Replacing {} with {}", path, next.path);
+        self.supervise_start(state, prepared.entry, prepared.library, prepared.starter);
+        let client = state.get_client().get_extn_client();
+        let deadline = Instant::now() + drain;
+        while !Self::is_registered(&client, &next) && Instant::now() < deadline {
+            sleep(Duration::from_millis(10)).await;
+        }
+        if !Self::is_registered(&client, &next) {
+            error!("{} did not register, keeping {}", next.path, path);
+            let _ = self.unload(state, &next.path, Duration::ZERO).await;
+            Self::set_available(state, &previous, ExtnStatus::Ready, None);
+            return Err(RippleError::ExtnError);
+        }
+        let result = self.unload(state, path, drain).await?;
+        // the unload marked the capabilities both versions fulfill as unavailable
+        Self::set_available(state, &next, ExtnStatus::Ready, None);
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::{api::manifest::extn_manifest::ExtnRestartMode, tokio};
+    use ripple_tdk::utils::test_utils::Mockable;
+    use std::sync::atomic::AtomicU32;
+
+    fn crashing_start() -> Result<(), String> {
+        panic!("runtime died");
+    }
+
+    fn returning_start() -> Result<(), String> {
+        Ok(())
+    }
+
+    fn failing_start() -> Result<(), String> {
+        Err("out of fuel".to_owned())
+    }
+
+    #[test]
+    fn test_restart_policy() {
+        assert_eq!(
+            ExtnSupervisor::run_guarded(crashing_start),
+            ExtnExit::Panicked("runtime died".to_owned())
+        );
+        let exit = ExtnSupervisor::run_guarded(returning_start);
+        assert_eq!(exit, ExtnExit::Exited);
+        let failure = ExtnSupervisor::run_guarded(failing_start);
+        assert_eq!(failure, ExtnExit::Failed("out of fuel".to_owned()));
+
+        let crash = ExtnExit::Panicked("boom".to_owned());
+        let policy = ExtnRestartPolicy::default();
+        assert_eq!(
+            ExtnSupervisor::next_restart(&policy, &crash, 0),
+            Some(Duration::from_millis(1000))
+        );
+        assert_eq!(
+            ExtnSupervisor::next_restart(&policy, &crash, 2),
+            Some(Duration::from_millis(4000))
+        );
+        assert_eq!(ExtnSupervisor::next_restart(&policy, &crash, 3), None);
+        assert!(ExtnSupervisor::next_restart(&policy, &failure, 0).is_some());
+        // a clean exit is only restarted with the always mode
+        assert_eq!(ExtnSupervisor::next_restart(&policy, &exit, 0), None);
+        let always = ExtnRestartPolicy {
+            mode: ExtnRestartMode::Always,
+            ..Default::default()
+        };
+        assert!(ExtnSupervisor::next_restart(&always, &exit, 0).is_some());
+        let never = ExtnRestartPolicy {
+            mode: ExtnRestartMode::Never,
+            ..Default::default()
+        };
+        assert_eq!(ExtnSupervisor::next_restart(&never, &crash, 0), None);
+    }
+
+    fn status(supervisor: &ExtnSupervisor, path: &str) -> Option<(ExtnStatus, u32)> {
+        supervisor
+            .get_health()
+            .into_iter()
+            .find(|h| h.path == path)
+            .map(|h| (h.status, h.restarts))
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_restart_builds_a_new_start() {
+        let state = PlatformState::mock();
+        let supervisor = ExtnSupervisor::default();
+        let builds = Arc::new(AtomicU32::new(0));
+        let builds_c = builds.clone();
+        let entry = ExtnManifestEntry {
+            path: "/tmp/libcrashing.so".to_owned(),
+            symbols: Vec::new(),
+            resolution: None,
+            restart: ExtnRestartPolicy {
+                mode: ExtnRestartMode::OnFailure,
+                max_restarts: 1,
+                backoff_ms: 1,
+                healthy_uptime_ms: 60000,
+            },
+            runtime: ExtnRuntime::default(),
+        };
+        supervisor.supervise_start(
+            &state,
+            entry,
+            None,
+            Box::new(move || {
+                builds_c.fetch_add(1, Ordering::SeqCst);
+                Ok(Box::new(crashing_start) as ExtnStart)
+            }),
+        );
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while status(&supervisor, "/tmp/libcrashing.so").map(|s| s.0) != Some(ExtnStatus::Error)
+            && Instant::now() < deadline
+        {
+            sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(
+            status(&supervisor, "/tmp/libcrashing.so"),
+            Some((ExtnStatus::Error, 1))
+        );
+        assert_eq!(builds.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_ready_after_registration() {
+        let state = PlatformState::mock();
+        let supervisor = ExtnSupervisor::default();
+        let entry = ExtnManifestEntry {
+            path: "/tmp/libunregistered.so".to_owned(),
+            symbols: vec![ExtnSymbol {
+                id: "ripple:channel:device:unregistered".to_owned(),
+                ..Default::default()
+            }],
+            resolution: None,
+            restart: ExtnRestartPolicy {
+                mode: ExtnRestartMode::Never,
+                ..Default::default()
+            },
+            runtime: ExtnRuntime::default(),
+        };
+        let waiting_start = || {
+            Ok(Box::new(|| {
+                thread::sleep(Duration::from_millis(300));
+                Ok(())
+            }) as ExtnStart)
+        };
+        supervisor.supervise_start(&state, entry.clone(), None, Box::new(waiting_start));
+        // the symbol never registers
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(
+            status(&supervisor, "/tmp/libunregistered.so"),
+            Some((ExtnStatus::Interrupted, 0))
+        );
+
+        // an extension without symbols has nothing to register
+        let entry = ExtnManifestEntry {
+            path: "/tmp/libnosymbols.so".to_owned(),
+            symbols: Vec::new(),
+            ..entry
+        };
+        supervisor.supervise_start(&state, entry, None, Box::new(waiting_start));
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(
+            status(&supervisor, "/tmp/libnosymbols.so"),
+            Some((ExtnStatus::Ready, 0))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_and_unload_unknown_extension() {
+        let state = PlatformState::mock();
+        let supervisor = ExtnSupervisor::default();
+        let entry = ExtnManifestEntry {
+            path: "/tmp/libdoes_not_exist.so".to_owned(),
+            symbols: Vec::new(),
+            resolution: None,
+            restart: ExtnRestartPolicy::default(),
+            runtime: ExtnRuntime::default(),
+        };
+        assert_eq!(
+            supervisor.load(&state, entry.clone()),
+            Err(RippleError::NotAvailable)
+        );
+        assert!(!supervisor.is_loaded("/tmp/libdoes_not_exist.so"));
+        assert!(supervisor
+            .unload(&state, "/tmp/libdoes_not_exist.so", Duration::ZERO)
+            .await
+            .is_err());
+
+        let entry = ExtnManifestEntry {
+            path: "/tmp/does_not_exist.wasm".to_owned(),
+            symbols: vec![ExtnSymbol {
+                id: "ripple:channel:distributor:wasm".to_owned(),
+                ..Default::default()
+            }],
+            runtime: ExtnRuntime::Wasm(Default::default()),
+            ..entry
+        };
+        assert_eq!(
+            supervisor.load(&state, entry),
+            Err(RippleError::NotAvailable)
+        );
+    }
+}
@@ -0,0 +1,634 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Runs extensions compiled to WebAssembly in a sandbox within Main.
+//!
+//! A module gets the [ExtnClient] of its symbol through the functions of the `ripple` import
+//! module. Every argument is JSON passed as a pointer and a length into the memory of the module.
+//!
+//! | import | argument |
+//! |---|---|
+//! | `add_request_processor(ptr, len)` | contract fulfilled by the module |
+//! | `add_event_processor(ptr, len)` | contract of the events it listens to |
+//! | `request(ptr, len)` | `{"id", "contract", "payload": ExtnRequest}`, answered by a `response` message with the same id |
+//! | `respond(ptr, len)` | `{"id", "payload": ExtnResponse}` for a `request` message |
+//! | `event(ptr, len)` | `{"contract", "payload": ExtnEvent}` |
+//! | `log(level, ptr, len)` | text logged from 0 error to 4 trace |
+//!
+//! They return 0 or a negative [WasmHostError]. The module exports its `memory`,
+//! `ripple_abi_version() -> i32`, `ripple_alloc(len) -> ptr` and `ripple_on_message(ptr, len)`,
+//! which owns the buffer it is given. Messages are tagged by `type`: `start` with the id and
+//! config of the symbol, then `request`, `event` and `response`.
+
+use std::collections::HashMap;
+
+use ripple_sdk::{
+    api::manifest::extn_manifest::{ExtnManifestEntry, ExtnSymbol, WasmLimits},
+    extn::{
+        client::extn_client::ExtnClient,
+        extn_client_message::{
+            ExtnEvent, ExtnMessage, ExtnPayload, ExtnPayloadProvider, ExtnRequest, ExtnResponse,
+        },
+    },
+    framework::ripple_contract::RippleContract,
+    log::{debug, error, info, trace, warn},
+    tokio::{
+        self,
+        sync::mpsc::{self, Sender as MSender},
+    },
+    utils::{error::RippleError, extn_utils::ExtnUtils},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use wasmi::{
+    Caller, Config, Engine, Extern, Instance, Linker, Memory, Module, Store, StoreLimits,
+    StoreLimitsBuilder, TypedFunc,
+};
+
+/// Version of the host functions and exports expected from a module
+pub const WASM_ABI_VERSION: i32 = 1;
+
+const HOST_MODULE: &str = "ripple";
+
+/// Codes returned by the host functions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WasmHostError {
+    /// The pointer and length are outside of the memory of the module
+    OutOfBounds = -1,
+    /// The argument is not the expected JSON
+    InvalidInput = -2,
+    /// The contract is unknown or not fulfilled by the symbol
+    InvalidContract = -3,
+}
+
+/// Message passed to `ripple_on_message`
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum GuestMessage {
+    Start {
+        id: String,
+        config: HashMap<String, String>,
+    },
+    Request {
+        id: String,
+        contract: String,
+        payload: ExtnRequest,
+    },
+    Event {
+        contract: String,
+        payload: ExtnEvent,
+    },
+    Response {
+        id: String,
+        payload: ExtnResponse,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct GuestRequest {
+    id: String,
+    contract: String,
+    payload: ExtnRequest,
+}
+
+#[derive(Debug, Deserialize)]
+struct GuestResponse {
+    id: String,
+    payload: ExtnResponse,
+}
+
+#[derive(Debug, Deserialize)]
+struct GuestEvent {
+    contract: String,
+    payload: ExtnEvent,
+}
+
+/// Host function called by the module, performed once the module returns
+#[derive(Debug)]
+enum GuestCall {
+    AddRequestProcessor(RippleContract),
+    AddEventProcessor(RippleContract),
+    Request { id: String, payload: WasmPayload },
+    Respond { id: String, response: ExtnResponse },
+    Event(WasmPayload),
+}
+
+/// Payload of a module with the contract it named, which cannot be derived from the payload type
+#[derive(Debug, Clone)]
+struct WasmPayload {
+    contract: RippleContract,
+    payload: ExtnPayload,
+}
+
+impl ExtnPayloadProvider for WasmPayload {
+    fn get_extn_payload(&self) -> ExtnPayload {
+        self.payload.clone()
+    }
+
+    fn get_from_payload(_: ExtnPayload) -> Option<Self> {
+        None
+    }
+
+    fn get_contract(&self) -> RippleContract {
+        self.contract.clone()
+    }
+
+    fn contract() -> RippleContract {
+        RippleContract::Internal
+    }
+}
+
+struct WasmHost {
+    id: String,
+    limits: StoreLimits,
+    calls: Vec<GuestCall>,
+}
+
+fn guest_bytes<'a>(
+    caller: &'a Caller<'_, WasmHost>,
+    ptr: i32,
+    len: i32,
+) -> Result<&'a [u8], WasmHostError> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or(WasmHostError::OutOfBounds)?;
+    let start = usize::try_from(ptr).map_err(|_| WasmHostError::OutOfBounds)?;
+    let end = start
+        .checked_add(usize::try_from(len).map_err(|_| WasmHostError::OutOfBounds)?)
+        .ok_or(WasmHostError::OutOfBounds)?;
+    memory
+        .data(caller)
+        .get(start..end)
+        .ok_or(WasmHostError::OutOfBounds)
+}
+
+fn read_guest<T: DeserializeOwned>(
+    caller: &Caller<'_, WasmHost>,
+    ptr: i32,
+    len: i32,
+) -> Result<T, WasmHostError> {
+    serde_json::from_slice(guest_bytes(caller, ptr, len)?).map_err(|_| WasmHostError::InvalidInput)
+}
+
+fn parse_contract(contract: &str) -> Result<RippleContract, WasmHostError> {
+    RippleContract::from_manifest(contract).ok_or(WasmHostError::InvalidContract)
+}
+
+fn host_call(
+    mut caller: Caller<'_, WasmHost>,
+    ptr: i32,
+    len: i32,
+    f: impl FnOnce(&Caller<'_, WasmHost>, i32, i32) -> Result<GuestCall, WasmHostError>,
+) -> i32 {
+    match f(&caller, ptr, len) {
+        Ok(call) => {
+            caller.data_mut().calls.push(call);
+            0
+        }
+        Err(e) => {
+            warn!("{} host call failed {:?}", caller.data().id, e);
+            e as i32
+        }
+    }
+}
+
+fn add_host_functions(linker: &mut Linker<WasmHost>) -> Result<(), wasmi::Error> {
+    linker.func_wrap(
+        HOST_MODULE,
+        "add_request_processor",
+        |caller: Caller<'_, WasmHost>, ptr: i32, len: i32| {
+            host_call(caller, ptr, len, |caller, ptr, len| {
+                let contract: String = read_guest(caller, ptr, len)?;
+                Ok(GuestCall::AddRequestProcessor(parse_contract(&contract)?))
+            })
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "add_event_processor",
+        |caller: Caller<'_, WasmHost>, ptr: i32, len: i32| {
+            host_call(caller, ptr, len, |caller, ptr, len| {
+                let contract: String = read_guest(caller, ptr, len)?;
+                Ok(GuestCall::AddEventProcessor(parse_contract(&contract)?))
+            })
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "request",
+        |caller: Caller<'_, WasmHost>, ptr: i32, len: i32| {
+            host_call(caller, ptr, len, |caller, ptr, len| {
+                let request: GuestRequest = read_guest(caller, ptr, len)?;
+                Ok(GuestCall::Request {
+                    id: request.id,
+                    payload: WasmPayload {
+                        contract: parse_contract(&request.contract)?,
+                        payload: ExtnPayload::Request(request.payload),
+                    },
+                })
+            })
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "respond",
+        |caller: Caller<'_, WasmHost>, ptr: i32, len: i32| {
+            host_call(caller, ptr, len, |caller, ptr, len| {
+                let response: GuestResponse = read_guest(caller, ptr, len)?;
+                Ok(GuestCall::Respond {
+                    id: response.id,
+                    response: response.payload,
+                })
+            })
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "event",
+        |caller: Caller<'_, WasmHost>, ptr: i32, len: i32| {
+            host_call(caller, ptr, len, |caller, ptr, len| {
+                let event: GuestEvent = read_guest(caller, ptr, len)?;
+                Ok(GuestCall::Event(WasmPayload {
+                    contract: parse_contract(&event.contract)?,
+                    payload: ExtnPayload::Event(event.payload),
+                }))
+            })
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "log",
+        |caller: Caller<'_, WasmHost>, level: i32, ptr: i32, len: i32| -> i32 {
+            let text = match guest_bytes(&caller, ptr, len) {
+                Ok(bytes) => String::from_utf8_lossy(bytes),
+                Err(e) => return e as i32,
+            };
+            let id = &caller.data().id;
+            match level {
+                0 => error!("{}: {}", id, text),
+                1 => warn!("{}: {}", id, text),
+                2 => info!("{}: {}", id, text),
+                3 => debug!("{}: {}", id, text),
+                _ => trace!("{}: {}", id, text),
+            }
+            0
+        },
+    )?;
+    Ok(())
+}
+
+/// Instance of a module with its own store, memory and fuel
+struct WasmGuest {
+    store: Store<WasmHost>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    on_message: TypedFunc<(i32, i32), ()>,
+    fuel_per_call: u64,
+}
+
+impl WasmGuest {
+    fn new(
+        engine: &Engine,
+        module: &Module,
+        id: &str,
+        limits: &WasmLimits,
+    ) -> Result<Self, String> {
+        let host = WasmHost {
+            id: id.to_owned(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(limits.max_memory_bytes)
+                .build(),
+            calls: Vec::new(),
+        };
+        let mut store = Store::new(engine, host);
+        store.limiter(|host| &mut host.limits);
+        store
+            .set_fuel(limits.fuel_per_call)
+            .map_err(|e| e.to_string())?;
+        let mut linker = Linker::new(engine);
+        add_host_functions(&mut linker).map_err(|e| e.to_string())?;
+        let instance: Instance = linker
+            .instantiate(&mut store, module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(|e| format!("cannot instantiate module {}", e))?;
+
+        let version = instance
+            .get_typed_func::<(), i32>(&store, "ripple_abi_version")
+            .map_err(|_| "module does not export ripple_abi_version".to_owned())?
+            .call(&mut store, ())
+            .map_err(|e| e.to_string())?;
+        if version != WASM_ABI_VERSION {
+            return Err(format!(
+                "module ABI version {} is not supported, expected {}",
+                version, WASM_ABI_VERSION
+            ));
+        }
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| "module does not export its memory".to_owned())?;
+        let alloc = instance
+            .get_typed_func(&store, "ripple_alloc")
+            .map_err(|_| "module does not export ripple_alloc".to_owned())?;
+        let on_message = instance
+            .get_typed_func(&store, "ripple_on_message")
+            .map_err(|_| "module does not export ripple_on_message".to_owned())?;
+        Ok(WasmGuest {
+            store,
+            memory,
+            alloc,
+            on_message,
+            fuel_per_call: limits.fuel_per_call,
+        })
+    }
+
+    /// Passes a message to the module and returns the host functions it called. A trap,
+    /// including running out of fuel or memory, is returned as an error.
+    fn call(&mut self, message: &GuestMessage) -> Result<Vec<GuestCall>, String> {
+        let bytes = serde_json::to_vec(message).map_err(|e| e.to_string())?;
+        let len = i32::try_from(bytes.len()).map_err(|e| e.to_string())?;
+        self.store
+            .set_fuel(self.fuel_per_call)
+            .map_err(|e| e.to_string())?;
+        let ptr = self
+            .alloc
+            .call(&mut self.store, len)
+            .map_err(|e| format!("ripple_alloc trapped {}", e))?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, &bytes)
+            .map_err(|e| format!("ripple_alloc returned an invalid buffer {}", e))?;
+        self.on_message
+            .call(&mut self.store, (ptr, len))
+            .map_err(|e| format!("ripple_on_message trapped {}", e))?;
+        Ok(std::mem::take(&mut self.store.data_mut().calls))
+    }
+}
+
+/// Extension compiled to WebAssembly. It connects to Main like a native extension with the
+/// first symbol of its manifest entry.
+pub struct WasmExtn {
+    engine: Engine,
+    module: Module,
+    symbol: ExtnSymbol,
+    limits: WasmLimits,
+}
+
+impl std::fmt::Debug for WasmExtn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasmExtn")
+            .field("symbol", &self.symbol.id)
+            .field("limits", &self.limits)
+            .finish()
+    }
+}
+
+impl WasmExtn {
+    /// Compiles the module and checks that it can be instantiated
+    pub fn load(
+        path: &str,
+        entry: &ExtnManifestEntry,
+        limits: WasmLimits,
+    ) -> Result<WasmExtn, RippleError> {
+        let symbol = match entry.symbols.as_slice() {
+            [symbol] => symbol.clone(),
+            _ => {
+                error!("{} must declare exactly one symbol", entry.path);
+                return Err(RippleError::InvalidInput);
+            }
+        };
+        let bytes = std::fs::read(path).map_err(|e| {
+            debug!("Extn not found: {:?}", e);
+            RippleError::NotAvailable
+        })?;
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, &bytes[..]).map_err(|e| {
+            error!("{} is not a valid module {}", path, e);
+            RippleError::ExtnError
+        })?;
+        let extn = WasmExtn {
+            engine,
+            module,
+            symbol,
+            limits,
+        };
+        extn.instantiate().map_err(|e| {
+            error!("Refusing extension {}: {}", path, e);
+            RippleError::ExtnError
+        })?;
+        Ok(extn)
+    }
+
+    fn instantiate(&self) -> Result<WasmGuest, String> {
+        WasmGuest::new(&self.engine, &self.module, &self.symbol.id, &self.limits)
+    }
+
+    /// Runs the module until its connection to Main is closed or it traps
+    pub fn run(&self) -> Result<(), String> {
+        let runtime = ExtnUtils::get_runtime(format!("e-wasm-{}", self.symbol.id), None);
+        runtime.block_on(self.serve())
+    }
+
+    async fn serve(&self) -> Result<(), String> {
+        let mut guest = self.instantiate()?;
+        let (mut client, tr) = ExtnClient::new_extn(self.symbol.clone());
+        let (message_tx, mut message_rx) = mpsc::channel::<ExtnMessage>(32);
+        let (response_tx, mut response_rx) = mpsc::channel::<(String, ExtnResponse)>(32);
+        // requests given to the module by id until it responds
+        let mut requests: HashMap<String, ExtnMessage> = HashMap::new();
+
+        let start = GuestMessage::Start {
+            id: self.symbol.id.clone(),
+            config: self.symbol.config.clone().unwrap_or_default(),
+        };
+        let calls = guest.call(&start)?;
+        Self::dispatch(&mut client, calls, &mut requests, &message_tx, &response_tx).await;
+
+        let client_c = client.clone();
+        let mut connection = tokio::spawn(async move { client_c.initialize(tr).await });
+        loop {
+            let message = tokio::select! {
+                result = &mut connection => match result {
+                    // the runtime swallows the panic, it is resumed for the supervisor
+                    Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+                    _ => break,
+                },
+                Some(message) = message_rx.recv() => match Self::to_guest_message(message, &mut requests) {
+                    Some(message) => message,
+                    None => continue,
+                },
+                Some((id, payload)) = response_rx.recv() => GuestMessage::Response { id, payload },
+            };
+            let calls = guest.call(&message)?;
+            Self::dispatch(&mut client, calls, &mut requests, &message_tx, &response_tx).await;
+        }
+        info!("{} disconnected", self.symbol.id);
+        Ok(())
+    }
+
+    fn to_guest_message(
+        message: ExtnMessage,
+        requests: &mut HashMap<String, ExtnMessage>,
+    ) -> Option<GuestMessage> {
+        let contract = message.target.as_clear_string();
+        match message.payload.clone() {
+            ExtnPayload::Request(payload) => {
+                let id = message.id.clone();
+                requests.insert(id.clone(), message);
+                Some(GuestMessage::Request {
+                    id,
+                    contract,
+                    payload,
+                })
+            }
+            ExtnPayload::Event(payload) => Some(GuestMessage::Event { contract, payload }),
+            ExtnPayload::Response(_) => None,
+        }
+    }
+
+    async fn dispatch(
+        client: &mut ExtnClient,
+        calls: Vec<GuestCall>,
+        requests: &mut HashMap<String, ExtnMessage>,
+        message_tx: &MSender<ExtnMessage>,
+        response_tx: &MSender<(String, ExtnResponse)>,
+    ) {
+        for call in calls {
+            match call {
+                GuestCall::AddRequestProcessor(contract) => {
+                    if !client.add_request_channel(contract.clone(), message_tx.clone()) {
+                        error!(
+                            "{} is not fulfilled by the extension",
+                            contract.as_clear_string()
+                        );
+                    }
+                }
+                GuestCall::AddEventProcessor(contract) => {
+                    if !client.add_event_channel(contract.clone(), message_tx.clone()) {
+                        error!(
+                            "{} events are not permitted for the extension",
+                            contract.as_clear_string()
+                        );
+                    }
+                }
+                GuestCall::Request { id, payload } => {
+                    let mut client = client.clone();
+                    let response_tx = response_tx.clone();
+                    tokio::spawn(async move {
+                        let response = match client.request(payload).await {
+                            Ok(message) => message
+                                .payload
+                                .as_response()
+                                .unwrap_or(ExtnResponse::Error(RippleError::ParseError)),
+                            Err(e) => ExtnResponse::Error(e),
+                        };
+                        let _ = response_tx.send((id, response)).await;
+                    });
+                }
+                GuestCall::Respond { id, response } => match requests.remove(&id) {
+                    Some(request) => {
+                        if let Err(e) = client.respond(request, response).await {
+                            error!("Error responding to {} {:?}", id, e);
+                        }
+                    }
+                    None => warn!("No pending request {} to respond to", id),
+                },
+                GuestCall::Event(payload) => {
+                    if let Err(e) = client.event(payload) {
+                        error!("Error sending event {:?}", e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // answers every request with the contract it was sent on and fails on the `trap` event
+    const ECHO_MODULE: &str = r#"
+        (module
+            (import "ripple" "add_request_processor" (func $add_request_processor (param i32 i32) (result i32)))
+            (import "ripple" "respond" (func $respond (param i32 i32) (result i32)))
+            (memory (export "memory") 1 2)
+            (data (i32.const 0) "\"internal\"")
+            (data (i32.const 16) "{\"id\":\"r1\",\"payload\":{\"String\":\"ok\"}}")
+            (func (export "ripple_abi_version") (result i32) i32.const 1)
+            (func (export "ripple_alloc") (param i32) (result i32) i32.const 1024)
+            (func (export "ripple_on_message") (param $ptr i32) (param $len i32)
+                ;; the type tag is the first field, `{"type":"s` for start
+                (if (i32.eq (i32.load8_u (i32.const 1033)) (i32.const 115))
+                    (then (drop (call $add_request_processor (i32.const 0) (i32.const 10)))))
+                (if (i32.eq (i32.load8_u (i32.const 1033)) (i32.const 114))
+                    (then (drop (call $respond (i32.const 16) (i32.const 37)))))
+                (if (i32.eq (i32.load8_u (i32.const 1033)) (i32.const 101))
+                    (then (loop $spin (br $spin)))))
+        )
+    "#;
+
+    fn guest(fuel_per_call: u64) -> WasmGuest {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, &wat::parse_str(ECHO_MODULE).unwrap()[..]).unwrap();
+        let limits = WasmLimits {
+            fuel_per_call,
+            ..Default::default()
+        };
+        WasmGuest::new(&engine, &module, "ripple:channel:distributor:wasm", &limits).unwrap()
+    }
+
+    #[test]
+    fn test_guest_calls_and_fuel() {
+        let mut guest = guest(10_000);
+        let calls = guest
+            .call(&GuestMessage::Start {
+                id: "ripple:channel:distributor:wasm".into(),
+                config: HashMap::new(),
+            })
+            .unwrap();
+        assert!(matches!(
+            calls.as_slice(),
+            [GuestCall::AddRequestProcessor(RippleContract::Internal)]
+        ));
+
+        let calls = guest
+            .call(&GuestMessage::Request {
+                id: "r1".into(),
+                contract: "internal".into(),
+                payload: ExtnRequest::Extn(serde_json::Value::Null),
+            })
+            .unwrap();
+        match calls.as_slice() {
+            [GuestCall::Respond { id, response }] => {
+                assert_eq!(id, "r1");
+                assert_eq!(response, &ExtnResponse::String("ok".into()));
+            }
+            _ => panic!("unexpected calls {:?}", calls),
+        }
+
+        // the spinning event runs out of fuel instead of blocking the host
+        assert!(guest
+            .call(&GuestMessage::Event {
+                contract: "internal".into(),
+                payload: ExtnEvent::String("trap".into()),
+            })
+            .is_err());
+    }
+}
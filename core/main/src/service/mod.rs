@@ -16,8 +16,13 @@
 //
 
 pub mod apps;
+pub mod data_governance;
 pub mod extn;
+pub mod privacy_sync;
 pub mod ripple_service;
 pub mod settings_processor;
 pub mod telemetry_builder;
+pub mod user_grant_bundle;
+pub mod user_grant_history;
 pub mod user_grants;
+pub mod user_profiles;
@@ -0,0 +1,454 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use ripple_sdk::{
+    api::{
+        distributor::distributor_privacy::{
+            PrivacyChangesResponse, PrivacySettingChange, PullPrivacyChangesParams,
+            PushPrivacyChangesParams, SetPropertyParams,
+        },
+        manifest::device_manifest::PrivacySettingsStorageType,
+        storage_property::StorageProperty,
+    },
+    async_trait::async_trait,
+    log::{debug, error, info},
+    serde_json::{self, Value},
+    tokio,
+    utils::error::RippleError,
+};
+
+use crate::{
+    broker::broker_utils::BrokerUtils,
+    processor::storage::storage_manager::StorageManager,
+    state::{platform_state::PlatformState, privacy_sync_state::PrivacySyncState},
+};
+
+pub const PRIVACY_PUSH_CHANGES_METHOD: &str = "distributor.privacy.pushChanges";
+pub const PRIVACY_PULL_CHANGES_METHOD: &str = "distributor.privacy.pullChanges";
+pub const PRIVACY_SET_PROPERTY_METHOD: &str = "distributor.privacy.setProperty";
+
+/// Transport to the cloud service holding the privacy settings of the account
+#[async_trait]
+pub trait PrivacySyncTransport: Send + Sync {
+    async fn push(&self, changes: Vec<PrivacySettingChange>) -> Result<(), RippleError>;
+    /// Returns None when the cloud service can not list its changes
+    async fn pull(&self, since: u64) -> Result<Option<PrivacyChangesResponse>, RippleError>;
+}
+
+/// Syncs through the distributor privacy contract using the account session. Distributors
+/// without rules for `pushChanges` and `pullChanges` get every change through `setProperty`
+/// and nothing is pulled.
+pub struct DistributorSyncTransport {
+    state: PlatformState,
+    profile_id: Option<String>,
+}
+
+impl DistributorSyncTransport {
+    pub fn new(state: &PlatformState) -> Self {
+        DistributorSyncTransport {
+            state: state.clone(),
+            profile_id: state.profile_state.get_active(),
+        }
+    }
+
+    async fn request(&self, method: &str, params: Value) -> Result<Value, RippleError> {
+        BrokerUtils::process_internal_main_request(&self.state, method, Some(params))
+            .await
+            .map_err(|e| {
+                error!("privacy sync {} failed {:?}", method, e);
+                RippleError::ServiceError
+            })
+    }
+
+    fn to_params<T: serde::Serialize>(params: T) -> Result<Value, RippleError> {
+        serde_json::to_value(params).map_err(|_| RippleError::ParseError)
+    }
+}
+
+#[async_trait]
+impl PrivacySyncTransport for DistributorSyncTransport {
+    async fn push(&self, changes: Vec<PrivacySettingChange>) -> Result<(), RippleError> {
+        let dist_session = self
+            .state
+            .session_state
+            .get_account_session()
+            .ok_or(RippleError::NotAvailable)?;
+        if self
+            .state
+            .endpoint_state
+            .has_rule(PRIVACY_PUSH_CHANGES_METHOD)
+        {
+            let params = Self::to_params(PushPrivacyChangesParams {
+                changes,
+                profile_id: self.profile_id.clone(),
+                dist_session,
+            })?;
+            return self
+                .request(PRIVACY_PUSH_CHANGES_METHOD, params)
+                .await
+                .map(|_| ());
+        }
+        for change in changes {
+            let params = Self::to_params(SetPropertyParams {
+                setting: change.setting,
+                value: change.value,
+                dist_session: dist_session.clone(),
+            })?;
+            self.request(PRIVACY_SET_PROPERTY_METHOD, params).await?;
+        }
+        Ok(())
+    }
+
+    async fn pull(&self, since: u64) -> Result<Option<PrivacyChangesResponse>, RippleError> {
+        if !self
+            .state
+            .endpoint_state
+            .has_rule(PRIVACY_PULL_CHANGES_METHOD)
+        {
+            return Ok(None);
+        }
+        let dist_session = self
+            .state
+            .session_state
+            .get_account_session()
+            .ok_or(RippleError::NotAvailable)?;
+        let params = Self::to_params(PullPrivacyChangesParams {
+            since,
+            profile_id: self.profile_id.clone(),
+            dist_session,
+        })?;
+        let response = self.request(PRIVACY_PULL_CHANGES_METHOD, params).await?;
+        serde_json::from_value(response)
+            .map(Some)
+            .map_err(|_| RippleError::ParseError)
+    }
+}
+
+pub struct PrivacySync;
+
+impl PrivacySync {
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default()
+    }
+
+    fn is_enabled(state: &PlatformState) -> bool {
+        state
+            .get_device_manifest()
+            .configuration
+            .features
+            .privacy_settings_storage_type
+            == PrivacySettingsStorageType::Sync
+    }
+
+    /// Journals a privacy setting changed on this device and pushes it in the background.
+    /// The change stays in the journal when the cloud is not reachable.
+    pub fn record_local_change(state: &PlatformState, property: &StorageProperty, value: bool) {
+        if let Some(setting) = property.as_privacy_setting() {
+            state
+                .privacy_sync_state
+                .record_local(setting, value, Self::now());
+            Self::trigger(state);
+        }
+    }
+
+    /// Starts a sync in the background
+    pub fn trigger(state: &PlatformState) {
+        if !Self::is_enabled(state) {
+            return;
+        }
+        let state = state.clone();
+        tokio::spawn(async move {
+            if let Err(e) = Self::sync(&state).await {
+                debug!("privacy sync did not complete {:?}", e);
+            }
+        });
+    }
+
+    /// Syncs periodically as configured by `privacy_sync_interval_secs`
+    pub fn start(state: &PlatformState) {
+        if !Self::is_enabled(state) {
+            return;
+        }
+        let interval_secs = state
+            .get_device_manifest()
+            .configuration
+            .features
+            .privacy_sync_interval_secs
+            .max(1);
+        let state = state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+            loop {
+                interval.tick().await;
+                if let Err(e) = Self::sync(&state).await {
+                    debug!("periodic privacy sync did not complete {:?}", e);
+                }
+            }
+        });
+    }
+
+    /// Pulls and pushes the changes of the active profile through the distributor and applies
+    /// the remote changes which won over the local ones, apps are notified through the storage
+    /// events
+    pub async fn sync(state: &PlatformState) -> Result<(), RippleError> {
+        let lock = state.privacy_sync_state.get_sync_lock();
+        let _guard = lock.lock().await;
+        let transport = DistributorSyncTransport::new(state);
+        let accepted = Self::exchange(&state.privacy_sync_state, &transport).await?;
+        for change in accepted {
+            if let Some(property) = StorageProperty::from_privacy_setting(&change.setting) {
+                if let Err(e) =
+                    StorageManager::set_bool(state, property.clone(), change.value, None).await
+                {
+                    error!("unable to apply remote change of {:?} {:?}", property, e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Pulls the remote changes, merges them into the journal and pushes the local changes
+    /// which are still pending. Returns the remote changes to apply locally. The caller holds
+    /// the sync lock.
+    pub async fn exchange(
+        sync_state: &PrivacySyncState,
+        transport: &dyn PrivacySyncTransport,
+    ) -> Result<Vec<PrivacySettingChange>, RippleError> {
+        let accepted = match transport.pull(sync_state.get_last_pull()).await? {
+            Some(response) => sync_state.merge_remote(response.changes, response.server_time),
+            None => Vec::new(),
+        };
+        let pending = sync_state.get_pending();
+        if !pending.is_empty() {
+            transport.push(pending.clone()).await?;
+            sync_state.acknowledge(&pending);
+        }
+        info!(
+            "privacy sync applied {} remote and pushed {} local changes",
+            accepted.len(),
+            pending.len()
+        );
+        Ok(accepted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::broker::rules::rules_engine::RuleEngine;
+    use httpmock::prelude::*;
+    use hyper::{Body, Client, Method, Request, Uri};
+    use ripple_sdk::api::distributor::distributor_privacy::PrivacySetting;
+    use serde_json::json;
+
+    /// Talks to a local HTTP stand-in of the cloud service
+    struct HttpSyncTransport {
+        base_url: String,
+        lists_changes: bool,
+    }
+
+    impl HttpSyncTransport {
+        async fn send(
+            &self,
+            method: Method,
+            path: String,
+            body: Body,
+        ) -> Result<Vec<u8>, RippleError> {
+            let uri: Uri = format!("{}{}", self.base_url, path)
+                .parse()
+                .map_err(|_| RippleError::InvalidInput)?;
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("Content-Type", "application/json")
+                .body(body)
+                .map_err(|_| RippleError::InvalidInput)?;
+            let response = Client::new()
+                .request(request)
+                .await
+                .map_err(|_| RippleError::ServiceError)?;
+            if !response.status().is_success() {
+                return Err(RippleError::ServiceError);
+            }
+            hyper::body::to_bytes(response.into_body())
+                .await
+                .map(|b| b.to_vec())
+                .map_err(|_| RippleError::ServiceError)
+        }
+    }
+
+    #[async_trait]
+    impl PrivacySyncTransport for HttpSyncTransport {
+        async fn push(&self, changes: Vec<PrivacySettingChange>) -> Result<(), RippleError> {
+            let body = serde_json::to_string(&changes).unwrap();
+            self.send(Method::POST, "/privacy/changes".into(), Body::from(body))
+                .await
+                .map(|_| ())
+        }
+
+        async fn pull(&self, since: u64) -> Result<Option<PrivacyChangesResponse>, RippleError> {
+            if !self.lists_changes {
+                return Ok(None);
+            }
+            let body = self
+                .send(
+                    Method::GET,
+                    format!("/privacy/changes?since={}", since),
+                    Body::empty(),
+                )
+                .await?;
+            serde_json::from_slice(&body)
+                .map(Some)
+                .map_err(|_| RippleError::ParseError)
+        }
+    }
+
+    fn saved_dir(name: &str) -> String {
+        let mut path = std::env::temp_dir();
+        path.push(format!(
+            "privacy_sync_service_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[tokio::test]
+    async fn test_exchange_with_cloud_stand_in() {
+        let server = MockServer::start();
+        let pull = server.mock(|when, then| {
+            when.method(GET)
+                .path("/privacy/changes")
+                .query_param("since", "0");
+            then.status(200).json_body(json!({
+                "changes": [
+                    {"setting": "Acr", "value": true, "updated_at": 500},
+                    {"setting": "WatchHistory", "value": true, "updated_at": 100}
+                ],
+                "server_time": 600
+            }));
+        });
+        let push = server.mock(|when, then| {
+            when.method(POST).path("/privacy/changes").json_body(json!([
+                {"setting": "WatchHistory", "value": false, "updated_at": 200}
+            ]));
+            then.status(200);
+        });
+
+        let dir = saved_dir("exchange");
+        let sync_state = PrivacySyncState::new(&dir, None, None);
+        // changed while offline, newer than the remote watch history change
+        sync_state.record_local(PrivacySetting::WatchHistory, false, 200);
+
+        let transport = HttpSyncTransport {
+            base_url: server.base_url(),
+            lists_changes: true,
+        };
+        let accepted = PrivacySync::exchange(&sync_state, &transport)
+            .await
+            .unwrap();
+        pull.assert();
+        push.assert();
+        assert_eq!(
+            accepted,
+            vec![PrivacySettingChange {
+                setting: PrivacySetting::Acr,
+                value: true,
+                updated_at: 500
+            }]
+        );
+        assert!(sync_state.get_pending().is_empty());
+        assert_eq!(sync_state.get_last_pull(), 600);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_exchange_keeps_pending_when_offline() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/privacy/changes");
+            then.status(200)
+                .json_body(json!({"changes": [], "server_time": 10}));
+        });
+        server.mock(|when, then| {
+            when.method(POST).path("/privacy/changes");
+            then.status(503);
+        });
+
+        let dir = saved_dir("offline");
+        let sync_state = PrivacySyncState::new(&dir, None, None);
+        sync_state.record_local(PrivacySetting::Acr, true, 5);
+        let transport = HttpSyncTransport {
+            base_url: server.base_url(),
+            lists_changes: true,
+        };
+        assert!(PrivacySync::exchange(&sync_state, &transport)
+            .await
+            .is_err());
+        assert_eq!(sync_state.get_pending().len(), 1);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_exchange_pushes_without_pull() {
+        let server = MockServer::start();
+        let push = server.mock(|when, then| {
+            when.method(POST).path("/privacy/changes").json_body(json!([
+                {"setting": "Acr", "value": true, "updated_at": 5}
+            ]));
+            then.status(200);
+        });
+
+        let dir = saved_dir("push_only");
+        let sync_state = PrivacySyncState::new(&dir, None, None);
+        sync_state.record_local(PrivacySetting::Acr, true, 5);
+        let transport = HttpSyncTransport {
+            base_url: server.base_url(),
+            lists_changes: false,
+        };
+        assert!(PrivacySync::exchange(&sync_state, &transport)
+            .await
+            .unwrap()
+            .is_empty());
+        push.assert();
+        assert!(sync_state.get_pending().is_empty());
+        assert_eq!(sync_state.get_last_pull(), 0);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_example_rules() {
+        let engine = RuleEngine::load_from_string_literal(
+            include_str!("../../../../examples/rules/privacy-sync.rules.json").to_owned(),
+        )
+        .unwrap();
+        for method in [
+            PRIVACY_PUSH_CHANGES_METHOD,
+            PRIVACY_PULL_CHANGES_METHOD,
+            PRIVACY_SET_PROPERTY_METHOD,
+        ] {
+            assert!(engine.has_rule(method));
+        }
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Runs the WebSocket endpoint of Main in-process with scripted services of the mock_app_gw
+//! harness connected to it.
+
+use std::{sync::Arc, time::Duration};
+
+use jsonrpsee::core::{server::rpc_module::Methods, RpcResult};
+use jsonrpsee::RpcModule;
+use mock_app_gw::harness::ScriptedService;
+use ripple_sdk::{
+    service::service_routing::REGISTER_SERVICE_METHODS,
+    tokio::{self, net::TcpListener, time::timeout},
+};
+use serde_json::json;
+
+use crate::{
+    firebolt::{firebolt_gateway::FireboltGateway, firebolt_ws::FireboltWs},
+    state::{
+        bootstrap_state::{BootstrapState, ChannelsState},
+        platform_state::PlatformState,
+    },
+};
+
+const PROVIDER: &str = "ripple:channel:test:provider";
+// one of the services Main takes requests from
+const CALLER: &str = "ripple:channel:gateway:badger";
+const DEVICE_NAME: &str = "Living Room";
+const WAIT: Duration = Duration::from_secs(5);
+
+/// Starts the gateway of Main with a handler for `device.name` and its WebSocket endpoint on a
+/// free port
+async fn start_main() -> (PlatformState, String) {
+    let channels_state = ChannelsState::new();
+    let mut state = PlatformState::mock_with_channels(channels_state.clone());
+    let mut manifest = (*state.extn_manifest).clone();
+    manifest
+        .service_calls
+        .insert(CALLER.into(), vec!["Custom.".into(), "Device.".into()]);
+    state.extn_manifest = Arc::new(manifest);
+
+    let mut module = RpcModule::new(());
+    module
+        .register_async_method("device.name", |_, _| async {
+            RpcResult::Ok(DEVICE_NAME.to_owned())
+        })
+        .unwrap();
+    let mut methods = Methods::new();
+    methods.merge(module).unwrap();
+    let gateway = FireboltGateway::new(
+        BootstrapState {
+            start_time: std::time::Instant::now(),
+            platform_state: state.clone(),
+            channels_state,
+        },
+        methods,
+    );
+    tokio::spawn(async move { gateway.start().await });
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let main_state = state.clone();
+    tokio::spawn(async move {
+        FireboltWs::serve(listener, main_state, false, None).await;
+    });
+    (state, address)
+}
+
+async fn wait_for_service(state: &PlatformState, id: &str) {
+    timeout(WAIT, async {
+        while state
+            .service_controller_state
+            .get_sender(&id.to_owned())
+            .await
+            .is_none()
+        {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("{} did not connect", id));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_service_methods_routed_by_main() {
+    let (state, address) = start_main().await;
+    let provider = ScriptedService::new(PROVIDER)
+        .with_result("Custom.echo", json!("pong"))
+        .with_result("Device.name", json!("hijacked"))
+        .with_service_message()
+        .start(&address);
+    let mut caller = ScriptedService::new(CALLER)
+        .with_service_message()
+        .start(&address);
+    wait_for_service(&state, PROVIDER).await;
+    wait_for_service(&state, CALLER).await;
+
+    provider
+        .send(json!({
+            "jsonrpc": "2.0",
+            "method": REGISTER_SERVICE_METHODS,
+            "params": { "methods": ["Custom.echo", "Device.name"] }
+        }))
+        .await;
+    timeout(WAIT, async {
+        while state
+            .service_controller_state
+            .service_methods
+            .get_providers("Custom.echo", CALLER)
+            .is_empty()
+        {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    let response = caller.call("Custom.echo", None).await.unwrap();
+    assert_eq!(response["result"], json!("pong"));
+
+    // Main serves Device.name itself so the provider never gets it
+    let response = caller.call("Device.name", None).await.unwrap();
+    assert_eq!(response["result"], json!(DEVICE_NAME), "{}", response);
+    assert_eq!(provider.methods_received(), vec!["Custom.echo"]);
+
+    caller.stop().await;
+    provider.stop().await;
+}
@@ -16,5 +16,10 @@
 //
 
 pub mod service_controller_state;
+pub mod service_method_registry;
 pub mod service_notification_processor;
 pub mod service_registry;
+pub mod service_supervisor;
+
+#[cfg(test)]
+mod mock_app_gw_tests;
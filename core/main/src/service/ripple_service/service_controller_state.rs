@@ -29,6 +29,7 @@ use ripple_sdk::{
     service::{
         service_event_state::ServiceEventState,
         service_message::{Id, JsonRpcMessage, ServiceMessage},
+        service_routing::{ServiceMethods, REGISTER_SERVICE_METHODS},
     },
     tokio::{
         self,
@@ -48,7 +49,10 @@ use crate::{
     state::{platform_state::PlatformState, session_state::Session},
 };
 
-use super::service_registry::ServiceRegistry;
+use super::{
+    service_method_registry::ServiceMethodRegistry, service_registry::ServiceRegistry,
+    service_supervisor::ServiceSupervisor,
+};
 use serde_json::Value;
 const ALLOWED_SERVICES_LIST: [&str; 2] = [
     "ripple:channel:gateway:badger",
@@ -68,6 +72,8 @@ pub struct ServiceControllerState {
     pub service_info: Arc<Mutex<ServiceRegistry>>,
     pub service_event_state: ServiceEventState,
     pub service_notification_processor: ServiceNotificationProcessor,
+    pub service_supervisor: ServiceSupervisor,
+    pub service_methods: ServiceMethodRegistry,
 }
 
 impl ServiceInfo {
@@ -122,6 +128,8 @@ impl ServiceControllerState {
             service_info: Arc::new(Mutex::new(ServiceRegistry::default())),
             service_event_state: ServiceEventState::new(),
             service_notification_processor: ServiceNotificationProcessor::new(),
+            service_supervisor: ServiceSupervisor::default(),
+            service_methods: ServiceMethodRegistry::default(),
         }
     }
     // Ripple Main processing the inbound ServiceMessage received from a service.
@@ -135,6 +143,32 @@ impl ServiceControllerState {
     ) {
         match &sm.message {
             JsonRpcMessage::Request(json_rpc_request) => {
+                // Requests for a method registered by other services are routed to them, unless
+                // Main serves the method itself
+                let providers =
+                    if ServiceMethodRegistry::is_served_by_main(state, &json_rpc_request.method) {
+                        Vec::new()
+                    } else {
+                        state
+                            .service_controller_state
+                            .service_methods
+                            .get_providers(&json_rpc_request.method, &app_id)
+                    };
+                if !providers.is_empty() {
+                    // routed off the read loop of the connection, the provider may be waiting
+                    // on a response from this service
+                    let state = state.clone();
+                    let sm = sm.clone();
+                    let request = json_rpc_request.clone();
+                    tokio::spawn(async move {
+                        let response =
+                            ServiceMethodRegistry::route(&state, &app_id, &sm, &request, providers)
+                                .await;
+                        Self::send_to_service(&state, &app_id, response).await;
+                    });
+                    return;
+                }
+
                 // In Ripple Service Architecture Ripple Main will not honor any request originated from any connected service that is not included in `ALLOWED_SERVICES_LIST`
                 // other than service registration and unregistration request
                 // (TBD) Handling register/unregister
@@ -178,6 +212,22 @@ impl ServiceControllerState {
                     error!("failed to send request {:?}", e);
                 };
             }
+            JsonRpcMessage::Notification(json_rpc_notification)
+                if json_rpc_notification.method == REGISTER_SERVICE_METHODS =>
+            {
+                // registered under the id of the connection so a service can only register itself
+                match json_rpc_notification
+                    .params
+                    .clone()
+                    .map(serde_json::from_value::<ServiceMethods>)
+                {
+                    Some(Ok(methods)) => state
+                        .service_controller_state
+                        .service_methods
+                        .register(&app_id, methods.methods),
+                    _ => error!("Invalid method registration from service {}", app_id),
+                }
+            }
             JsonRpcMessage::Notification(json_rpc_notification) => {
                 state
                     .service_controller_state
@@ -207,6 +257,21 @@ impl ServiceControllerState {
         }
     }
 
+    async fn send_to_service(state: &PlatformState, service_id: &str, message: ServiceMessage) {
+        match state
+            .service_controller_state
+            .get_sender(&service_id.to_string())
+            .await
+        {
+            Some(sender) => {
+                if let Err(err) = sender.send(Message::Text(message.into())).await {
+                    error!("Failed to send message to service {}: {}", service_id, err);
+                }
+            }
+            None => error!("No sender found for service {}", service_id),
+        }
+    }
+
     fn is_contract_used_for_routing(symbol: &ExtnSymbol) -> bool {
         !symbol.uses.is_empty() || !symbol.fulfills.is_empty()
     }
@@ -271,6 +336,11 @@ impl ServiceControllerState {
         let (message_tx, mut message_rx) = mpsc::channel::<Message>(32);
         let (api_message_tx, mut api_message_rx) = mpsc::channel::<ApiMessage>(32);
 
+        state
+            .service_controller_state
+            .service_supervisor
+            .on_connected(&app_id);
+
         let _ = Self::register_service_channel(
             &state,
             app_id.clone(),
@@ -459,6 +529,14 @@ impl ServiceControllerState {
                 .get_extn_client()
                 .remove_sender(app_id.to_string(), symbol);
         }
+        state
+            .service_controller_state
+            .service_supervisor
+            .on_disconnected(app_id);
+        state
+            .service_controller_state
+            .service_methods
+            .unregister(app_id);
 
         let _ = state
             .service_controller_state
@@ -0,0 +1,401 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use futures::future::join_all;
+use ripple_sdk::{
+    log::{debug, error, warn},
+    service::service_message::{Id, JsonRpcRequest, ServiceMessage},
+    tokio::{sync::mpsc, time::timeout},
+    tokio_tungstenite::tungstenite::Message,
+};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    broker::endpoint_broker::{BrokerCallback, BrokerOutput, EndpointBrokerState},
+    state::platform_state::PlatformState,
+};
+
+const SERVICE_CALL_NOT_ALLOWED_ERROR_CODE: i64 = -32600;
+const SERVICE_UNAVAILABLE_ERROR_CODE: i64 = -32001;
+
+fn error_value(code: i64, message: String) -> Value {
+    json!({"code": code, "message": message})
+}
+
+/// Methods registered by the connected services through `set_service_rpc_route`. A request of a
+/// service for one of these methods is routed by Main to the services which registered it, as
+/// long as the `service_calls` of the extension manifest allow it. When several services
+/// registered the method the request is sent to all of them and the result has the outcome of
+/// each service by service id.
+#[derive(Debug, Clone, Default)]
+pub struct ServiceMethodRegistry {
+    methods: Arc<RwLock<HashMap<String, Vec<String>>>>,
+}
+
+impl ServiceMethodRegistry {
+    /// Replaces the methods registered by the service
+    pub fn register(&self, service_id: &str, methods: Vec<String>) {
+        debug!("Service {} registered methods {:?}", service_id, methods);
+        self.methods
+            .write()
+            .unwrap()
+            .insert(service_id.to_owned(), methods);
+    }
+
+    pub fn unregister(&self, service_id: &str) {
+        self.methods.write().unwrap().remove(service_id);
+    }
+
+    /// Whether Main serves the method itself, with a handler or a rule. These are never routed
+    /// to a service so a service cannot take over a method of Main.
+    pub fn is_served_by_main(state: &PlatformState, method: &str) -> bool {
+        // handlers are registered with the module in lower case, `device.name` for `Device.name`
+        let handler_method = match method.split_once('.') {
+            Some((module, name)) => format!("{}.{}", module.to_lowercase(), name),
+            None => method.to_owned(),
+        };
+        state.router_state.get_method_entry(method).is_some()
+            || state
+                .router_state
+                .get_method_entry(&handler_method)
+                .is_some()
+            || state.endpoint_state.has_rule(method)
+    }
+
+    /// Services other than the caller which registered the method
+    pub fn get_providers(&self, method: &str, caller: &str) -> Vec<String> {
+        let mut providers: Vec<String> = self
+            .methods
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(id, methods)| id.as_str() != caller && methods.iter().any(|m| m == method))
+            .map(|(id, _)| id.clone())
+            .collect();
+        providers.sort();
+        providers
+    }
+
+    /// Sends the request to a provider and waits for its result or error
+    async fn call(
+        state: &PlatformState,
+        provider: &str,
+        request: &JsonRpcRequest,
+        context: Option<Value>,
+        timeout_ms: u64,
+    ) -> Result<Value, Value> {
+        let controller = &state.service_controller_state;
+        let provider_id = provider.to_owned();
+        let sender = controller.get_sender(&provider_id).await.ok_or_else(|| {
+            error_value(
+                SERVICE_UNAVAILABLE_ERROR_CODE,
+                format!("Service {} is not connected", provider),
+            )
+        })?;
+
+        // the response of the provider is matched like a brokered one, by a broker request id
+        let request_id = EndpointBrokerState::get_next_id();
+        let (tx, mut rx) = mpsc::channel::<BrokerOutput>(1);
+        controller
+            .set_broker_callback(&provider_id, request_id, BrokerCallback { sender: tx })
+            .await
+            .map_err(|_| {
+                error_value(
+                    SERVICE_UNAVAILABLE_ERROR_CODE,
+                    format!("Service {} is not connected", provider),
+                )
+            })?;
+        let mut routed = ServiceMessage::new_request(
+            request.method.clone(),
+            request.params.clone(),
+            Id::Number(request_id as i64),
+        );
+        routed.set_context(context);
+        let sent = sender.send(Message::Text(routed.into())).await;
+
+        let output = match sent {
+            Ok(_) => timeout(Duration::from_millis(timeout_ms), rx.recv()).await,
+            Err(e) => {
+                error!("Failed to send request to service {}: {:?}", provider, e);
+                Ok(None)
+            }
+        };
+        match output {
+            Ok(Some(output)) => match output.data.error {
+                Some(error) => Err(error),
+                None => Ok(output.data.result.unwrap_or(Value::Null)),
+            },
+            Ok(None) => Err(error_value(
+                SERVICE_UNAVAILABLE_ERROR_CODE,
+                format!("Service {} is not connected", provider),
+            )),
+            Err(_) => {
+                let _ = controller
+                    .extract_broker_callback(&provider_id, request_id)
+                    .await;
+                Err(error_value(
+                    SERVICE_UNAVAILABLE_ERROR_CODE,
+                    format!(
+                        "Service {} did not respond within {}ms",
+                        provider, timeout_ms
+                    ),
+                ))
+            }
+        }
+    }
+
+    fn to_response(result: Result<Value, Value>, id: Id) -> ServiceMessage {
+        match result {
+            Ok(result) => ServiceMessage::new_success(result, id),
+            Err(error) => ServiceMessage::new_error(
+                error
+                    .get("code")
+                    .and_then(|c| c.as_i64())
+                    .unwrap_or(SERVICE_UNAVAILABLE_ERROR_CODE),
+                error
+                    .get("message")
+                    .and_then(|m| m.as_str())
+                    .unwrap_or_default()
+                    .to_owned(),
+                error.get("data").cloned(),
+                id,
+            ),
+        }
+    }
+
+    /// Result of a request sent to several providers, the result or error of each by service id
+    fn aggregate(results: Vec<(String, Result<Value, Value>)>) -> Value {
+        let mut aggregate = Map::new();
+        for (provider, result) in results {
+            let outcome = match result {
+                Ok(result) => json!({ "result": result }),
+                Err(error) => json!({ "error": error }),
+            };
+            aggregate.insert(provider, outcome);
+        }
+        Value::Object(aggregate)
+    }
+
+    /// Routes the request of the `caller` service to the `providers` and returns the response to
+    /// send back to the caller
+    pub async fn route(
+        state: &PlatformState,
+        caller: &str,
+        sm: &ServiceMessage,
+        request: &JsonRpcRequest,
+        providers: Vec<String>,
+    ) -> ServiceMessage {
+        let mut response = if !state
+            .extn_manifest
+            .is_service_call_allowed(caller, &request.method)
+        {
+            warn!(
+                "Service {} is not allowed to call {}",
+                caller, request.method
+            );
+            Self::to_response(
+                Err(error_value(
+                    SERVICE_CALL_NOT_ALLOWED_ERROR_CODE,
+                    format!(
+                        "Service {} is not allowed to call {}",
+                        caller, request.method
+                    ),
+                )),
+                request.id.clone(),
+            )
+        } else {
+            let timeout_ms = state.extn_manifest.get_timeout();
+            let calls = providers.iter().map(|provider| {
+                Self::call(state, provider, request, sm.context.clone(), timeout_ms)
+            });
+            let mut results: Vec<(String, Result<Value, Value>)> = providers
+                .iter()
+                .cloned()
+                .zip(join_all(calls).await)
+                .collect();
+            if results.len() == 1 {
+                Self::to_response(results.remove(0).1, request.id.clone())
+            } else {
+                ServiceMessage::new_success(Self::aggregate(results), request.id.clone())
+            }
+        };
+        response.set_context(sm.context.clone());
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        broker::rules::rules_engine::Rule,
+        service::ripple_service::service_controller_state::ServiceInfo,
+    };
+    use ripple_sdk::{
+        api::gateway::rpc_gateway_api::JsonRpcApiResponse,
+        service::service_message::JsonRpcMessage, tokio,
+    };
+    use ripple_tdk::utils::test_utils::Mockable;
+
+    async fn add_provider(state: &PlatformState, id: &str, respond: bool) {
+        let (tx, mut rx) = mpsc::channel::<Message>(4);
+        state
+            .service_controller_state
+            .add_service_info(id.to_owned(), ServiceInfo::new(id.to_owned(), tx, true))
+            .await
+            .unwrap();
+        let state = state.clone();
+        let id = id.to_owned();
+        tokio::spawn(async move {
+            while let Some(Message::Text(text)) = rx.recv().await {
+                let request: ServiceMessage = serde_json::from_str(&text).unwrap();
+                let callback = state
+                    .service_controller_state
+                    .extract_broker_callback(&id, request.get_request_id())
+                    .await
+                    .unwrap();
+                if let (true, Some(callback)) = (respond, callback) {
+                    let response = JsonRpcApiResponse {
+                        result: Some(json!(format!("{} name", id))),
+                        ..Default::default()
+                    };
+                    let _ = callback.sender.send(BrokerOutput::new(response)).await;
+                }
+            }
+        });
+    }
+
+    #[tokio::test]
+    async fn test_route() {
+        let mut state = PlatformState::mock();
+        let mut manifest = (*state.extn_manifest).clone();
+        manifest
+            .service_calls
+            .insert("caller".into(), vec!["Device.".into()]);
+        manifest.timeout = Some(200);
+        state.extn_manifest = Arc::new(manifest);
+        add_provider(&state, "service1", true).await;
+        add_provider(&state, "service2", true).await;
+        add_provider(&state, "service3", false).await;
+
+        let sm = ServiceMessage::new_request("Device.name".into(), None, Id::String("1".into()));
+        let request = match &sm.message {
+            JsonRpcMessage::Request(request) => request.clone(),
+            _ => unreachable!(),
+        };
+        let response =
+            ServiceMethodRegistry::route(&state, "caller", &sm, &request, vec!["service1".into()])
+                .await;
+        match response.message {
+            JsonRpcMessage::Success(success) => assert_eq!(success.result, json!("service1 name")),
+            _ => panic!("expected the result of service1"),
+        }
+
+        // fan out, a provider which does not respond in time fails on its own
+        let providers = vec!["service2".into(), "service3".into()];
+        let response =
+            ServiceMethodRegistry::route(&state, "caller", &sm, &request, providers).await;
+        match response.message {
+            JsonRpcMessage::Success(success) => {
+                assert_eq!(success.result["service2"]["result"], json!("service2 name"));
+                assert_eq!(
+                    success.result["service3"]["error"]["code"],
+                    json!(SERVICE_UNAVAILABLE_ERROR_CODE)
+                );
+            }
+            _ => panic!("expected the aggregated results"),
+        }
+
+        let response =
+            ServiceMethodRegistry::route(&state, "other", &sm, &request, vec!["service1".into()])
+                .await;
+        match response.message {
+            JsonRpcMessage::Error(error) => {
+                assert_eq!(error.error.code, SERVICE_CALL_NOT_ALLOWED_ERROR_CODE)
+            }
+            _ => panic!("expected the call to be refused"),
+        }
+    }
+
+    #[test]
+    fn test_get_providers() {
+        let registry = ServiceMethodRegistry::default();
+        registry.register("service2", vec!["Device.name".into()]);
+        registry.register(
+            "service1",
+            vec!["Device.name".into(), "Device.model".into()],
+        );
+        assert_eq!(
+            registry.get_providers("Device.name", "caller"),
+            vec!["service1", "service2"]
+        );
+        // a service is not routed its own requests
+        assert_eq!(
+            registry.get_providers("Device.model", "service1"),
+            Vec::<String>::new()
+        );
+        registry.register("service1", vec![]);
+        registry.unregister("service2");
+        assert!(registry.get_providers("Device.name", "caller").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_methods_served_by_main() {
+        let state = PlatformState::mock();
+        let _ = state.endpoint_state.clone().add_rule(Rule {
+            alias: "device.name".into(),
+            endpoint: Some("thunder".into()),
+            ..Default::default()
+        });
+        assert!(ServiceMethodRegistry::is_served_by_main(
+            &state,
+            "Device.name"
+        ));
+        assert!(!ServiceMethodRegistry::is_served_by_main(
+            &state,
+            "Custom.name"
+        ));
+    }
+
+    #[test]
+    fn test_responses() {
+        let aggregate = ServiceMethodRegistry::aggregate(vec![
+            ("service1".into(), Ok(json!("Living Room"))),
+            ("service2".into(), Err(error_value(-32001, "gone".into()))),
+        ]);
+        assert_eq!(
+            aggregate,
+            json!({
+                "service1": {"result": "Living Room"},
+                "service2": {"error": {"code": -32001, "message": "gone"}}
+            })
+        );
+
+        let error = ServiceMethodRegistry::to_response(
+            Err(json!({"code": -32602, "message": "bad params"})),
+            Id::Number(1),
+        );
+        let error: Value = serde_json::to_value(error.message).unwrap();
+        assert_eq!(error["error"]["code"], json!(-32602));
+    }
+}
@@ -0,0 +1,319 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use ripple_sdk::{
+    api::manifest::extn_manifest::ServiceManifestEntry,
+    log::{error, info, warn},
+    tokio::{
+        self,
+        process::{Child, Command},
+        sync::Notify,
+        time::{sleep, sleep_until, Instant},
+    },
+};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceProcessState {
+    /// Process is running and has not connected yet
+    Starting,
+    Connected,
+    /// Process is running and its connection was closed
+    Disconnected,
+    /// Process stopped and is waiting for the restart backoff
+    Restarting,
+    /// Process stopped and will not be started again
+    Stopped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceHealth {
+    pub id: String,
+    pub state: ServiceProcessState,
+    pub pid: Option<u32>,
+    pub restarts: u32,
+    pub last_exit: Option<String>,
+}
+
+struct ServiceExit {
+    failed: bool,
+    reason: String,
+}
+
+/// Starts the service executables declared in the extension manifest, restarts them as
+/// configured by their restart policy and tracks whether they are connected to Main. A service
+/// which does not connect, or reconnect after its connection closed, within its
+/// `connect_timeout_ms` is killed and restarted.
+#[derive(Debug, Clone, Default)]
+pub struct ServiceSupervisor {
+    services: Arc<RwLock<HashMap<String, ServiceHealth>>>,
+    disconnects: Arc<RwLock<HashMap<String, Arc<Notify>>>>,
+}
+
+impl ServiceSupervisor {
+    pub fn get_health(&self) -> Vec<ServiceHealth> {
+        let mut health: Vec<ServiceHealth> =
+            self.services.read().unwrap().values().cloned().collect();
+        health.sort_by(|a, b| a.id.cmp(&b.id));
+        health
+    }
+
+    pub fn get_service_health(&self, id: &str) -> Option<ServiceHealth> {
+        self.services.read().unwrap().get(id).cloned()
+    }
+
+    fn is_connected(&self, id: &str) -> bool {
+        matches!(
+            self.get_service_health(id),
+            Some(ServiceHealth {
+                state: ServiceProcessState::Connected,
+                ..
+            })
+        )
+    }
+
+    fn update(&self, id: &str, f: impl FnOnce(&mut ServiceHealth)) {
+        if let Some(health) = self.services.write().unwrap().get_mut(id) {
+            f(health);
+        }
+    }
+
+    /// Called when a service completes its `service_handshake`
+    pub fn on_connected(&self, id: &str) {
+        self.update(id, |h| h.state = ServiceProcessState::Connected);
+    }
+
+    /// Called when the connection of a service is closed
+    pub fn on_disconnected(&self, id: &str) {
+        let mut disconnected = false;
+        self.update(id, |h| {
+            if h.state == ServiceProcessState::Connected {
+                h.state = ServiceProcessState::Disconnected;
+                disconnected = true;
+            }
+        });
+        if disconnected {
+            if let Some(notify) = self.disconnects.read().unwrap().get(id) {
+                notify.notify_one();
+            }
+        }
+    }
+
+    /// Starts all the services in the background
+    pub fn start(&self, services: Vec<ServiceManifestEntry>) {
+        for entry in services {
+            let supervisor = self.clone();
+            tokio::spawn(async move {
+                supervisor.run(entry).await;
+            });
+        }
+    }
+
+    fn spawn(entry: &ServiceManifestEntry) -> std::io::Result<Child> {
+        Command::new(&entry.executable)
+            .args(&entry.args)
+            .envs(&entry.env)
+            .kill_on_drop(true)
+            .spawn()
+    }
+
+    async fn wait(&self, entry: &ServiceManifestEntry, mut child: Child) -> ServiceExit {
+        let connect_timeout = Duration::from_millis(entry.connect_timeout_ms);
+        let disconnected = self
+            .disconnects
+            .write()
+            .unwrap()
+            .entry(entry.id.clone())
+            .or_default()
+            .clone();
+        // armed until the service connects and again whenever its connection closes
+        let mut deadline = Some(Instant::now() + connect_timeout);
+        let mut connected_before = false;
+        let status = loop {
+            tokio::select! {
+                status = child.wait() => break status,
+                _ = sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                    if !self.is_connected(&entry.id) {
+                        let _ = child.kill().await;
+                        let reason = if connected_before {
+                            format!("did not reconnect within {}ms", entry.connect_timeout_ms)
+                        } else {
+                            format!("did not connect within {}ms", entry.connect_timeout_ms)
+                        };
+                        return ServiceExit {
+                            failed: true,
+                            reason,
+                        };
+                    }
+                    connected_before = true;
+                    deadline = None;
+                }
+                _ = disconnected.notified() => {
+                    connected_before = true;
+                    deadline = Some(Instant::now() + connect_timeout);
+                }
+            }
+        };
+        match status {
+            Ok(status) => ServiceExit {
+                failed: !status.success(),
+                reason: status.to_string(),
+            },
+            Err(e) => ServiceExit {
+                failed: true,
+                reason: e.to_string(),
+            },
+        }
+    }
+
+    /// Runs the service until its restart policy gives up on it
+    pub async fn run(&self, entry: ServiceManifestEntry) {
+        self.services.write().unwrap().insert(
+            entry.id.clone(),
+            ServiceHealth {
+                id: entry.id.clone(),
+                state: ServiceProcessState::Starting,
+                pid: None,
+                restarts: 0,
+                last_exit: None,
+            },
+        );
+        let mut restarts = 0;
+        loop {
+            let started = Instant::now();
+            let exit = match Self::spawn(&entry) {
+                Ok(child) => {
+                    info!("Started service {} pid={:?}", entry.id, child.id());
+                    let pid = child.id();
+                    self.update(&entry.id, |h| {
+                        h.state = ServiceProcessState::Starting;
+                        h.pid = pid;
+                    });
+                    self.wait(&entry, child).await
+                }
+                Err(e) => ServiceExit {
+                    failed: true,
+                    reason: format!("unable to start {}: {}", entry.executable, e),
+                },
+            };
+            warn!("Service {} stopped: {}", entry.id, exit.reason);
+
+            restarts = entry.restart.restarts_after(restarts, started.elapsed());
+            let next = entry.restart.next_restart(exit.failed, restarts);
+            self.update(&entry.id, |h| {
+                h.pid = None;
+                h.last_exit = Some(exit.reason);
+                h.state = match next {
+                    Some(_) => ServiceProcessState::Restarting,
+                    None => ServiceProcessState::Stopped,
+                };
+            });
+            match next {
+                Some(backoff) => {
+                    sleep(backoff).await;
+                    restarts += 1;
+                    info!("Restarting service {} attempt {}", entry.id, restarts);
+                    self.update(&entry.id, |h| h.restarts = restarts);
+                }
+                None => {
+                    error!("Service {} will not be restarted", entry.id);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::api::manifest::extn_manifest::{ExtnRestartMode, ExtnRestartPolicy};
+
+    fn entry(id: &str, script: &str, connect_timeout_ms: u64) -> ServiceManifestEntry {
+        ServiceManifestEntry {
+            id: id.to_owned(),
+            executable: "sh".to_owned(),
+            args: vec!["-c".to_owned(), script.to_owned()],
+            env: HashMap::new(),
+            restart: ExtnRestartPolicy {
+                mode: ExtnRestartMode::OnFailure,
+                max_restarts: 1,
+                backoff_ms: 10,
+                ..Default::default()
+            },
+            connect_timeout_ms,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_restart_on_failure() {
+        let supervisor = ServiceSupervisor::default();
+        supervisor.run(entry("failing", "exit 3", 5000)).await;
+        let health = supervisor.get_service_health("failing").unwrap();
+        assert_eq!(health.state, ServiceProcessState::Stopped);
+        assert_eq!(health.restarts, 1);
+        assert!(health.last_exit.unwrap().contains('3'));
+
+        // a clean exit is not restarted on failure
+        supervisor.run(entry("clean", "exit 0", 5000)).await;
+        assert_eq!(supervisor.get_service_health("clean").unwrap().restarts, 0);
+    }
+
+    #[tokio::test]
+    async fn test_restart_when_not_connected() {
+        let supervisor = ServiceSupervisor::default();
+        supervisor.run(entry("silent", "sleep 5", 50)).await;
+        let health = supervisor.get_service_health("silent").unwrap();
+        assert_eq!(health.state, ServiceProcessState::Stopped);
+        assert_eq!(health.restarts, 1);
+        assert!(health.last_exit.unwrap().contains("did not connect"));
+    }
+
+    #[tokio::test]
+    async fn test_restart_when_disconnected_without_exiting() {
+        let supervisor = ServiceSupervisor::default();
+        let mut flaky = entry("flaky", "sleep 5", 100);
+        flaky.restart.max_restarts = 0;
+        let supervisor_c = supervisor.clone();
+        let run = tokio::spawn(async move { supervisor_c.run(flaky).await });
+
+        sleep(Duration::from_millis(20)).await;
+        supervisor.on_connected("flaky");
+        // stays up past the connect timeout while connected
+        sleep(Duration::from_millis(200)).await;
+        assert_eq!(
+            supervisor.get_service_health("flaky").unwrap().state,
+            ServiceProcessState::Connected
+        );
+
+        let disconnected = Instant::now();
+        supervisor.on_disconnected("flaky");
+        run.await.unwrap();
+        assert!(disconnected.elapsed() < Duration::from_secs(2));
+        let health = supervisor.get_service_health("flaky").unwrap();
+        assert_eq!(health.state, ServiceProcessState::Stopped);
+        assert!(health.last_exit.unwrap().contains("did not reconnect"));
+    }
+}
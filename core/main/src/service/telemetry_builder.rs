@@ -17,12 +17,13 @@
 
 use ripple_sdk::{
     api::{
+        distributor::distributor_privacy::DataEventType,
         firebolt::{
             fb_metrics::{ErrorParams, InternalInitializeParams, SystemErrorParams},
             fb_telemetry::{
-                AppLoadStart, AppLoadStop, FireboltEvent, FireboltInteraction, InternalInitialize,
-                TelemetryAppError, TelemetryPayload, TelemetrySignIn, TelemetrySignOut,
-                TelemetrySystemError,
+                AppLoadStart, AppLoadStop, ExtnStats, FireboltEvent, FireboltInteraction,
+                InternalInitialize, TelemetryAppError, TelemetryPayload, TelemetrySignIn,
+                TelemetrySignOut, TelemetrySystemError,
             },
         },
         gateway::rpc_gateway_api::{ApiMessage, CallContext, RpcRequest},
@@ -30,10 +31,19 @@ use ripple_sdk::{
     chrono::{DateTime, Utc},
     framework::RippleResponse,
     log::{error, trace},
+    tokio::{
+        self,
+        sync::{mpsc, oneshot},
+        time::Duration,
+    },
+    utils::error::RippleError,
 };
 use serde_json::Value;
 
-use crate::state::platform_state::PlatformState;
+use crate::{
+    service::data_governance::DataGovernance,
+    state::{ops_metrics_state::GovernedTelemetry, platform_state::PlatformState},
+};
 
 pub struct TelemetryBuilder;
 include!(concat!(env!("OUT_DIR"), "/version.rs"));
@@ -87,9 +97,75 @@ impl TelemetryBuilder {
         Self::send_telemetry(ps, t)
     }
 
+    /// Data type governing the payload, operational telemetry is not governed
+    fn governed_data_type(t: &TelemetryPayload) -> Option<DataEventType> {
+        match t {
+            TelemetryPayload::FireboltInteraction(_) | TelemetryPayload::FireboltEvent(_) => {
+                Some(DataEventType::BusinessIntelligence)
+            }
+            _ => None,
+        }
+    }
+
+    fn has_governed_policy(ps: &PlatformState) -> bool {
+        DataGovernance::get_policy(ps, &DataEventType::BusinessIntelligence).is_some()
+    }
+
+    /// Sends the payload to the telemetry listeners. When the device manifest governs
+    /// telemetry, every payload goes through one queue so the governed payloads keep their
+    /// order with the others.
     pub fn send_telemetry(ps: &PlatformState, t: TelemetryPayload) -> RippleResponse {
         trace!("send_telemetry: t={:?}", t);
+        if !Self::has_governed_policy(ps) {
+            return Self::send_to_listeners(ps, t);
+        }
+        Self::enqueue(ps, (t, None))
+    }
+
+    /// Sends the payload like `send_telemetry` and waits until it was delivered or dropped
+    pub async fn send_telemetry_and_wait(
+        ps: &PlatformState,
+        t: TelemetryPayload,
+    ) -> RippleResponse {
+        trace!("send_telemetry_and_wait: t={:?}", t);
+        if !Self::has_governed_policy(ps) {
+            return Self::send_to_listeners(ps, t);
+        }
+        let (tx, rx) = oneshot::channel();
+        Self::enqueue(ps, (t, Some(tx)))?;
+        rx.await.unwrap_or(Err(RippleError::SendFailure))
+    }
+
+    fn enqueue(ps: &PlatformState, item: GovernedTelemetry) -> RippleResponse {
+        let queue = ps.metrics.get_telemetry_queue(|| {
+            let (tx, mut rx) = mpsc::unbounded_channel::<GovernedTelemetry>();
+            let ps = ps.clone();
+            tokio::spawn(async move {
+                while let Some((t, result_tx)) = rx.recv().await {
+                    let result = Self::forward(&ps, t).await;
+                    if let Some(result_tx) = result_tx {
+                        let _ = result_tx.send(result);
+                    }
+                }
+            });
+            tx
+        });
+        queue.send(item).map_err(|_| RippleError::SendFailure)
+    }
+
+    /// Applies the policy of the data type governing the payload before sending it
+    async fn forward(ps: &PlatformState, t: TelemetryPayload) -> RippleResponse {
+        let data_type = match Self::governed_data_type(&t) {
+            Some(data_type) => data_type,
+            None => return Self::send_to_listeners(ps, t),
+        };
+        match DataGovernance::filter_as(ps, &data_type, t).await {
+            Some(t) => Self::send_to_listeners(ps, t),
+            None => Ok(()),
+        }
+    }
 
+    fn send_to_listeners(ps: &PlatformState, t: TelemetryPayload) -> RippleResponse {
         let listeners = ps.metrics.get_listeners();
         let client = ps.get_client().get_extn_client();
         let mut result = Ok(());
@@ -102,6 +178,39 @@ impl TelemetryBuilder {
         result
     }
 
+    /// Sends the request stats per contract and extension of the `ExtnClient` of Main
+    pub fn send_extn_stats(ps: &PlatformState) {
+        let stats = ps.get_client().get_extn_client().get_metrics();
+        if stats.is_empty() {
+            return;
+        }
+        if let Err(e) = Self::send_telemetry(
+            ps,
+            TelemetryPayload::ExtnStats(ExtnStats {
+                ripple_session_id: ps.metrics.get_device_session_id(),
+                stats,
+            }),
+        ) {
+            error!("send_telemetry={:?}", e)
+        }
+    }
+
+    /// Sends the extension request stats to the operational metrics listeners at every interval
+    pub fn start_extn_stats_reporter(ps: &PlatformState, interval: Duration) {
+        let ps = ps.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            // the first tick completes right away
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                if !ps.metrics.get_listeners().is_empty() {
+                    Self::send_extn_stats(&ps);
+                }
+            }
+        });
+    }
+
     pub fn send_ripple_telemetry(ps: &PlatformState) {
         Self::send_app_load_start(
             ps,
@@ -229,3 +338,69 @@ impl TelemetryBuilder {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::api::storage_property::StorageProperty;
+    use ripple_tdk::utils::test_utils::Mockable;
+
+    fn interaction() -> TelemetryPayload {
+        TelemetryPayload::FireboltInteraction(FireboltInteraction {
+            app_id: "app".into(),
+            method: "metrics.startContent".into(),
+            params: None,
+            tt: 1,
+            success: true,
+            ripple_session_id: String::default(),
+            app_session_id: None,
+            response: String::default(),
+        })
+    }
+
+    fn app_error() -> TelemetryPayload {
+        TelemetryPayload::AppError(TelemetryAppError {
+            app_id: "app".into(),
+            error_type: "other".into(),
+            code: "1".into(),
+            description: String::default(),
+            visible: false,
+            parameters: None,
+            ripple_session_id: String::default(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_only_mapped_telemetry_is_governed() {
+        let state = PlatformState::mock();
+        // a listener without a sender makes every delivery attempt fail
+        state
+            .metrics
+            .operational_telemetry_listener("ripple:extn:jsonrpsee:listener", true);
+        state
+            .ripple_cache
+            .update_cached_bool_storage_property(&StorageProperty::AllowBusinessAnalytics, false);
+
+        // firebolt interactions are dropped without a delivery attempt
+        assert!(
+            TelemetryBuilder::send_telemetry_and_wait(&state, interaction())
+                .await
+                .is_ok()
+        );
+        // operational telemetry is still delivered
+        assert!(
+            TelemetryBuilder::send_telemetry_and_wait(&state, app_error())
+                .await
+                .is_err()
+        );
+
+        state
+            .ripple_cache
+            .update_cached_bool_storage_property(&StorageProperty::AllowBusinessAnalytics, true);
+        assert!(
+            TelemetryBuilder::send_telemetry_and_wait(&state, interaction())
+                .await
+                .is_err()
+        );
+    }
+}
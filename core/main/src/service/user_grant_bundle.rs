@@ -0,0 +1,404 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::BTreeMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ripple_sdk::{
+    api::{
+        device::device_user_grants_data::{
+            GrantChangeSource, GrantEntry, GrantLifespan, GrantPolicy, GrantScope, GrantStatus,
+            PolicyPersistenceType,
+        },
+        distributor::distributor_privacy::PrivacySettingsData,
+        firebolt::{
+            fb_capabilities::{CapEvent, FireboltCap, FireboltPermission},
+            fb_user_grants::{
+                DroppedGrant, SignedUserGrantsBundle, UserGrantsBundle, UserGrantsImportResult,
+                USER_GRANTS_BUNDLE_VERSION,
+            },
+        },
+    },
+    framework::store_encryption::{self, StoreKeyProvider},
+    log::{debug, error},
+    utils::error::RippleError,
+};
+
+use crate::{
+    firebolt::handlers::privacy_rpc::PrivacyImpl,
+    state::{cap::cap_state::CapState, platform_state::PlatformState},
+};
+
+use super::user_grants::{GrantPolicyEnforcer, GrantState};
+
+/// Exports and imports user grants and privacy settings as a signed bundle. Bundles are signed
+/// with the provisioned `bundle_signing` keys of the manifest, so they can be imported on any
+/// device sharing those keys, including the same device after a factory reset.
+pub struct UserGrantBundles;
+
+impl UserGrantBundles {
+    fn signing_keys(
+        platform_state: &PlatformState,
+    ) -> Result<std::sync::Arc<dyn StoreKeyProvider>, RippleError> {
+        let config = platform_state
+            .get_device_manifest()
+            .get_bundle_signing()
+            .ok_or_else(|| {
+                error!("user grant bundles need bundle_signing keys to be configured");
+                RippleError::NotAvailable
+            })?;
+        if !config.is_portable() {
+            error!(
+                "bundle_signing keys must be provisioned, keystore keys do not leave the device"
+            );
+            return Err(RippleError::NotAvailable);
+        }
+        Ok(config.key_provider())
+    }
+
+    pub fn create_bundle(
+        grant_state: &GrantState,
+        privacy_settings: PrivacySettingsData,
+    ) -> UserGrantsBundle {
+        let mut device_grants: Vec<GrantEntry> =
+            grant_state.get_device_entries().into_iter().collect();
+        device_grants.sort_by(|a, b| a.capability.cmp(&b.capability));
+        let mut profile_grants: Vec<GrantEntry> =
+            grant_state.get_profile_entries().into_iter().collect();
+        profile_grants.sort_by(|a, b| a.capability.cmp(&b.capability));
+        let app_grants = grant_state
+            .get_app_grant_entries()
+            .into_iter()
+            .filter(|(_, entries)| !entries.is_empty())
+            .map(|(app_id, entries)| {
+                let mut entries: Vec<GrantEntry> = entries.into_iter().collect();
+                entries.sort_by(|a, b| a.capability.cmp(&b.capability));
+                (app_id, entries)
+            })
+            .collect::<BTreeMap<_, _>>();
+        UserGrantsBundle {
+            version: USER_GRANTS_BUNDLE_VERSION,
+            exported_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default(),
+            device_grants,
+            profile_grants,
+            app_grants,
+            privacy_settings,
+        }
+    }
+
+    pub fn sign(
+        keys: &dyn StoreKeyProvider,
+        bundle: UserGrantsBundle,
+    ) -> Result<SignedUserGrantsBundle, RippleError> {
+        let signature = store_encryption::sign(keys, &bundle.signed_content()?)?;
+        Ok(SignedUserGrantsBundle { bundle, signature })
+    }
+
+    pub fn verify(
+        keys: &dyn StoreKeyProvider,
+        signed: &SignedUserGrantsBundle,
+    ) -> Result<(), RippleError> {
+        if !signed.bundle.is_supported_version() {
+            error!(
+                "unsupported user grants bundle version {}",
+                signed.bundle.version
+            );
+            return Err(RippleError::InvalidInput);
+        }
+        store_encryption::verify(keys, &signed.bundle.signed_content()?, &signed.signature)
+    }
+
+    pub async fn export(
+        platform_state: &PlatformState,
+    ) -> Result<SignedUserGrantsBundle, RippleError> {
+        let keys = Self::signing_keys(platform_state)?;
+        let privacy_settings = PrivacyImpl {
+            state: platform_state.clone(),
+        }
+        .get_settings_local()
+        .await
+        .map(PrivacySettingsData::from)
+        .map_err(|e| {
+            error!("could not read privacy settings for export {:?}", e);
+            RippleError::NotAvailable
+        })?;
+        let bundle = Self::create_bundle(&platform_state.cap_state.grant_state, privacy_settings);
+        Self::sign(keys.as_ref(), bundle)
+    }
+
+    /// Returns why an entry of a bundle exported with the given scope can not be applied with
+    /// the given grant policy
+    pub fn check_entry(
+        scope: &GrantScope,
+        entry: &GrantEntry,
+        policy: Option<&GrantPolicy>,
+    ) -> Result<(), &'static str> {
+        let policy = policy.ok_or("no grant policy for the capability")?;
+        if entry.status.is_none() {
+            return Err("grant has no status");
+        }
+        if entry.has_expired() {
+            return Err("grant has expired");
+        }
+        if policy.scope != *scope {
+            return Err("grant policy scope changed");
+        }
+        // Scheduled policies store their grants with the time left in the schedule
+        let scheduled = policy.schedule.is_some()
+            && matches!(policy.lifespan, GrantLifespan::Forever)
+            && entry.lifespan == Some(GrantLifespan::Seconds);
+        if entry.lifespan.as_ref() != Some(&policy.lifespan) && !scheduled {
+            return Err("grant policy lifespan changed");
+        }
+        if matches!(
+            policy.lifespan,
+            GrantLifespan::Once | GrantLifespan::AppActive | GrantLifespan::PowerActive
+        ) {
+            return Err("grant does not outlive the session it was given in");
+        }
+        Ok(())
+    }
+
+    /// Applies the grants of a bundle whose policy has not changed along with its privacy
+    /// settings, capability events are emitted for every grant which changed
+    pub async fn import(
+        platform_state: &PlatformState,
+        signed: SignedUserGrantsBundle,
+    ) -> Result<UserGrantsImportResult, RippleError> {
+        let keys = Self::signing_keys(platform_state)?;
+        Self::verify(keys.as_ref(), &signed)?;
+        let bundle = signed.bundle;
+
+        let mut result = UserGrantsImportResult::default();
+        let entries = bundle
+            .device_grants
+            .into_iter()
+            .map(|entry| (GrantScope::Device, None, entry))
+            .chain(
+                bundle
+                    .profile_grants
+                    .into_iter()
+                    .map(|entry| (GrantScope::Profile, None, entry)),
+            )
+            .chain(bundle.app_grants.into_iter().flat_map(|(app_id, entries)| {
+                entries
+                    .into_iter()
+                    .map(move |entry| (GrantScope::App, Some(app_id.clone()), entry))
+            }));
+        for (scope, app_id, entry) in entries {
+            let permission = FireboltPermission {
+                cap: FireboltCap::Full(entry.capability.clone()),
+                role: entry.role,
+            };
+            let policy = GrantState::get_grant_policy(platform_state, &permission, &app_id)
+                .filter(|policy| GrantPolicyEnforcer::is_policy_valid(platform_state, policy));
+            if let Err(reason) = Self::check_entry(&scope, &entry, policy.as_ref()) {
+                debug!(
+                    "dropping imported grant {:?} {:?}: {}",
+                    app_id, entry, reason
+                );
+                result.dropped.push(DroppedGrant {
+                    app_id,
+                    capability: entry.capability,
+                    role: entry.role,
+                    reason: reason.to_owned(),
+                });
+                continue;
+            }
+            let policy = policy.unwrap();
+
+            let previous = platform_state
+                .cap_state
+                .grant_state
+                .update_scoped_grant_entry(
+                    &scope,
+                    app_id.clone(),
+                    entry.clone(),
+                    GrantChangeSource::Import,
+                );
+            result.imported += 1;
+            if policy.persistence == PolicyPersistenceType::Account {
+                GrantPolicyEnforcer::send_usergrants_for_cloud_storage(
+                    platform_state,
+                    Some(&policy),
+                    &entry,
+                    &app_id,
+                )
+                .await;
+            }
+            if previous.and_then(|p| p.status) != entry.status {
+                let event = match entry.status {
+                    Some(GrantStatus::Allowed) => CapEvent::OnGranted,
+                    _ => CapEvent::OnRevoked,
+                };
+                CapState::emit(
+                    platform_state,
+                    &event,
+                    permission.cap,
+                    Some(permission.role),
+                )
+                .await;
+            }
+        }
+
+        result.privacy_settings_applied =
+            PrivacyImpl::store_privacy_settings(platform_state, &bundle.privacy_settings)
+                .await
+                .is_ok();
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::{
+        api::{
+            device::device_user_grants_data::GrantPolicy, firebolt::fb_capabilities::CapabilityRole,
+        },
+        framework::store_encryption::KeystoreKeyProvider,
+        serde_json,
+    };
+
+    fn keys(name: &str) -> KeystoreKeyProvider {
+        let mut path = std::env::temp_dir();
+        path.push(format!("grant_bundle_keys_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        KeystoreKeyProvider::new(path.to_string_lossy().into_owned())
+    }
+
+    fn entry(lifespan: GrantLifespan) -> GrantEntry {
+        let mut entry = GrantEntry::get(
+            CapabilityRole::Use,
+            "xrn:firebolt:capability:localization:postal-code".to_owned(),
+        );
+        entry.status = Some(GrantStatus::Allowed);
+        entry.lifespan = Some(lifespan);
+        entry
+    }
+
+    fn bundle() -> UserGrantsBundle {
+        UserGrantsBundle {
+            version: USER_GRANTS_BUNDLE_VERSION,
+            exported_at: 1,
+            device_grants: vec![],
+            profile_grants: vec![],
+            app_grants: BTreeMap::from([("app1".to_owned(), vec![entry(GrantLifespan::Forever)])]),
+            privacy_settings: PrivacySettingsData {
+                allow_watch_history: Some(true),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_signed_bundle_round_trip() {
+        let keys = keys("round_trip");
+        let signed = UserGrantBundles::sign(&keys, bundle()).unwrap();
+        let json = serde_json::to_string(&signed).unwrap();
+        let parsed: SignedUserGrantsBundle = serde_json::from_str(&json).unwrap();
+        assert!(UserGrantBundles::verify(&keys, &parsed).is_ok());
+
+        let tampered = json.replace("\"Allowed\"", "\"Denied\"");
+        let parsed: SignedUserGrantsBundle = serde_json::from_str(&tampered).unwrap();
+        assert!(UserGrantBundles::verify(&keys, &parsed).is_err());
+
+        let mut newer = signed.clone();
+        newer.bundle.version += 1;
+        assert!(matches!(
+            UserGrantBundles::verify(&keys, &newer),
+            Err(RippleError::InvalidInput)
+        ));
+    }
+
+    #[test]
+    fn test_bundle_without_profiles_verifies() {
+        let keys = keys("no_profiles");
+        // Layout of bundles exported before profiles were added
+        let bundle = format!(
+            concat!(
+                r#"{{"version":1,"exportedAt":1,"deviceGrants":[],"appGrants":{{"app1":[{{"#,
+                r#""role":"use","capability":"xrn:firebolt:capability:localization:postal-code","#,
+                r#""status":"Allowed","lifespan":"forever","#,
+                r#""last_modified_time":{{"secs":1,"nanos":0}},"lifespan_ttl_in_secs":null}}]}},"#,
+                r#""privacySettings":{}}}"#
+            ),
+            serde_json::to_string(&PrivacySettingsData::default()).unwrap()
+        );
+        let signature = store_encryption::sign(&keys, bundle.as_bytes()).unwrap();
+        let signed = format!(
+            r#"{{"bundle":{},"keyId":"{}","signature":"{}"}}"#,
+            bundle, signature.key_id, signature.signature
+        );
+        let parsed: SignedUserGrantsBundle = serde_json::from_str(&signed).unwrap();
+        assert!(parsed.bundle.profile_grants.is_empty());
+        assert!(UserGrantBundles::verify(&keys, &parsed).is_ok());
+
+        // Profile grants can not be added to a bundle signed without them
+        let mut tampered = parsed.clone();
+        tampered.bundle.version = USER_GRANTS_BUNDLE_VERSION;
+        tampered.bundle.profile_grants = vec![entry(GrantLifespan::Forever)];
+        assert!(UserGrantBundles::verify(&keys, &tampered).is_err());
+    }
+
+    #[test]
+    fn test_check_entry() {
+        let policy = GrantPolicy {
+            lifespan: GrantLifespan::Forever,
+            scope: GrantScope::App,
+            ..Default::default()
+        };
+        let forever = entry(GrantLifespan::Forever);
+        let app = GrantScope::App;
+        assert!(UserGrantBundles::check_entry(&app, &forever, Some(&policy)).is_ok());
+        assert!(UserGrantBundles::check_entry(&app, &forever, None).is_err());
+        // policy is app scoped
+        assert!(
+            UserGrantBundles::check_entry(&GrantScope::Device, &forever, Some(&policy)).is_err()
+        );
+        assert!(
+            UserGrantBundles::check_entry(&GrantScope::Profile, &forever, Some(&policy)).is_err()
+        );
+
+        let changed = GrantPolicy {
+            lifespan: GrantLifespan::Seconds,
+            scope: GrantScope::App,
+            ..Default::default()
+        };
+        assert_eq!(
+            UserGrantBundles::check_entry(&app, &forever, Some(&changed)),
+            Err("grant policy lifespan changed")
+        );
+
+        let session = GrantPolicy {
+            lifespan: GrantLifespan::PowerActive,
+            scope: GrantScope::App,
+            ..Default::default()
+        };
+        assert!(UserGrantBundles::check_entry(
+            &app,
+            &entry(GrantLifespan::PowerActive),
+            Some(&session)
+        )
+        .is_err());
+    }
+}
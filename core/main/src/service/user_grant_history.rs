@@ -0,0 +1,343 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use ripple_sdk::{
+    api::{
+        device::device_user_grants_data::{GrantChangeSource, GrantEntry, GrantHistoryEntry},
+        firebolt::fb_user_grants::{GrantHistoryExport, GrantHistoryRequest},
+    },
+    framework::{file_store::FileStore, store_encryption::StoreKeyProvider},
+    log::error,
+    tokio::{self, runtime::Handle},
+};
+
+pub const GRANT_HISTORY_MAX_ENTRIES: usize = 1000;
+const GRANT_HISTORY_EXPORT_VERSION: u32 = 1;
+/// Changes recorded within this delay are written to the store together
+const GRANT_HISTORY_FLUSH_DELAY: Duration = Duration::from_millis(500);
+
+/// Append only log of user grant changes for the active profile. Oldest entries are dropped
+/// once the log holds `max_entries`.
+#[derive(Debug, Clone)]
+pub struct GrantHistory {
+    store: Arc<RwLock<FileStore<VecDeque<GrantHistoryEntry>>>>,
+    keys: Option<Arc<dyn StoreKeyProvider>>,
+    max_entries: usize,
+    flush_pending: Arc<AtomicBool>,
+}
+
+impl GrantHistory {
+    pub fn new(path: String, keys: Option<Arc<dyn StoreKeyProvider>>) -> GrantHistory {
+        Self::with_max_entries(path, keys, GRANT_HISTORY_MAX_ENTRIES)
+    }
+
+    pub fn with_max_entries(
+        path: String,
+        keys: Option<Arc<dyn StoreKeyProvider>>,
+        max_entries: usize,
+    ) -> GrantHistory {
+        let store = Self::load(path, &keys, max_entries);
+        GrantHistory {
+            store: Arc::new(RwLock::new(store)),
+            keys,
+            max_entries,
+            flush_pending: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    fn load(
+        path: String,
+        keys: &Option<Arc<dyn StoreKeyProvider>>,
+        max_entries: usize,
+    ) -> FileStore<VecDeque<GrantHistoryEntry>> {
+        let mut store =
+            FileStore::load_or_new_encrypted(path.clone(), VecDeque::new(), keys.clone())
+                .unwrap_or_else(|e| {
+                    error!(
+                        "grant history {} is corrupt {:?}, starting a new history",
+                        path, e
+                    );
+                    FileStore::new_encrypted(path, VecDeque::new(), keys.clone())
+                });
+        if Self::trim(&mut store.value, max_entries) {
+            store.sync();
+        }
+        store
+    }
+
+    /// Writes pending changes and continues with the history stored at `path`, used when the
+    /// active profile changes.
+    pub fn switch(&self, path: String) {
+        let store = Self::load(path, &self.keys, self.max_entries);
+        let mut current = self.store.write().unwrap();
+        if self.flush_pending.swap(false, Ordering::SeqCst) {
+            current.sync();
+        }
+        *current = store;
+    }
+
+    /// Writes the changes recorded since the last flush
+    pub fn flush(&self) {
+        if self.flush_pending.swap(false, Ordering::SeqCst) {
+            self.store.write().unwrap().sync();
+        }
+    }
+
+    fn schedule_flush(&self) {
+        if self.flush_pending.swap(true, Ordering::SeqCst) {
+            // A flush is already scheduled and will pick up this change
+            return;
+        }
+        match Handle::try_current() {
+            Ok(handle) => {
+                let history = self.clone();
+                handle.spawn(async move {
+                    tokio::time::sleep(GRANT_HISTORY_FLUSH_DELAY).await;
+                    history.flush();
+                });
+            }
+            Err(_) => self.flush(),
+        }
+    }
+
+    fn trim(entries: &mut VecDeque<GrantHistoryEntry>, max_entries: usize) -> bool {
+        let excess = entries.len().saturating_sub(max_entries);
+        entries.drain(..excess);
+        excess > 0
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default()
+    }
+
+    /// Records a change of `entry`, `entry.status` of None means the grant was removed.
+    /// Nothing is recorded when the status did not change.
+    pub fn record(
+        &self,
+        app_id: Option<String>,
+        previous: Option<&GrantEntry>,
+        entry: &GrantEntry,
+        change: GrantChangeSource,
+    ) {
+        let previous = previous.and_then(|p| p.status.clone());
+        if previous == entry.status {
+            return;
+        }
+        let history_entry = GrantHistoryEntry {
+            timestamp: Self::now(),
+            app_id,
+            capability: entry.capability.clone(),
+            role: entry.role,
+            previous,
+            status: entry.status.clone(),
+            change,
+        };
+        {
+            let mut store = self.store.write().unwrap();
+            store.value.push_back(history_entry);
+            Self::trim(&mut store.value, self.max_entries);
+        }
+        self.schedule_flush();
+    }
+
+    /// Returns the entries matching the request, oldest first
+    pub fn query(&self, request: &GrantHistoryRequest) -> Vec<GrantHistoryEntry> {
+        self.store
+            .read()
+            .unwrap()
+            .value
+            .iter()
+            .filter(|e| {
+                request
+                    .app_id
+                    .as_ref()
+                    .map_or(true, |app_id| e.app_id.as_ref() == Some(app_id))
+            })
+            .filter(|e| {
+                request
+                    .capability
+                    .as_ref()
+                    .map_or(true, |capability| e.capability.eq(capability))
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub fn export(&self, request: &GrantHistoryRequest) -> GrantHistoryExport {
+        GrantHistoryExport {
+            version: GRANT_HISTORY_EXPORT_VERSION,
+            exported_at: Self::now(),
+            entries: self.query(request),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ripple_sdk::{
+        api::{
+            device::device_user_grants_data::GrantStatus, firebolt::fb_capabilities::CapabilityRole,
+        },
+        serde_json,
+    };
+
+    fn history_path(name: &str) -> String {
+        let mut path = std::env::temp_dir();
+        path.push(format!("grant_history_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path.to_string_lossy().into_owned()
+    }
+
+    fn entry(capability: &str, status: Option<GrantStatus>) -> GrantEntry {
+        let mut entry = GrantEntry::get(CapabilityRole::Use, capability.to_owned());
+        entry.status = status;
+        entry
+    }
+
+    #[test]
+    fn test_record_and_query() {
+        let history = GrantHistory::new(history_path("query"), None);
+        let allowed = entry(
+            "xrn:firebolt:capability:localization:postal-code",
+            Some(GrantStatus::Allowed),
+        );
+        history.record(
+            Some("app1".into()),
+            None,
+            &allowed,
+            GrantChangeSource::UserPrompt,
+        );
+        let cleared = entry("xrn:firebolt:capability:localization:postal-code", None);
+        history.record(
+            Some("app1".into()),
+            Some(&allowed),
+            &cleared,
+            GrantChangeSource::UserGrantsApi {
+                caller: Some("settings".into()),
+            },
+        );
+        history.record(
+            None,
+            None,
+            &entry(
+                "xrn:firebolt:capability:privacy:content",
+                Some(GrantStatus::Denied),
+            ),
+            GrantChangeSource::AutoApply,
+        );
+        // A no-op change is not recorded
+        history.record(
+            Some("app1".into()),
+            None,
+            &cleared,
+            GrantChangeSource::PolicySync,
+        );
+
+        let app_entries = history.query(&GrantHistoryRequest {
+            app_id: Some("app1".into()),
+            capability: None,
+        });
+        assert_eq!(app_entries.len(), 2);
+        assert_eq!(app_entries[1].previous, Some(GrantStatus::Allowed));
+        assert_eq!(app_entries[1].status, None);
+
+        let cap_entries = history.query(&GrantHistoryRequest {
+            app_id: None,
+            capability: Some("xrn:firebolt:capability:privacy:content".into()),
+        });
+        assert_eq!(cap_entries.len(), 1);
+        assert_eq!(cap_entries[0].change, GrantChangeSource::AutoApply);
+
+        let export = serde_json::to_value(history.export(&GrantHistoryRequest::default())).unwrap();
+        assert_eq!(export["entries"].as_array().unwrap().len(), 3);
+        assert_eq!(export["entries"][1]["source"], "userGrantsApi");
+        assert_eq!(export["entries"][1]["caller"], "settings");
+    }
+
+    #[test]
+    fn test_history_is_bounded_and_persisted() {
+        let path = history_path("bounded");
+        let history = GrantHistory::with_max_entries(path.clone(), None, 3);
+        for i in 0..5 {
+            history.record(
+                Some(format!("app{}", i)),
+                None,
+                &entry("cap", Some(GrantStatus::Allowed)),
+                GrantChangeSource::UserPrompt,
+            );
+        }
+        let entries = history.query(&GrantHistoryRequest::default());
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].app_id, Some("app2".into()));
+
+        let reloaded = GrantHistory::with_max_entries(path.clone(), None, 2);
+        let entries = reloaded.query(&GrantHistoryRequest::default());
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].app_id, Some("app3".into()));
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_history_writes_are_batched_and_per_profile() {
+        let path = history_path("batched");
+        let other_path = history_path("batched_other");
+        let history = GrantHistory::new(path.clone(), None);
+        for i in 0..3 {
+            history.record(
+                Some(format!("app{}", i)),
+                None,
+                &entry("cap", Some(GrantStatus::Allowed)),
+                GrantChangeSource::UserPrompt,
+            );
+        }
+        // Nothing is written until the scheduled flush runs
+        assert!(!std::path::Path::new(&path).exists());
+        tokio::time::sleep(GRANT_HISTORY_FLUSH_DELAY * 2).await;
+        let reloaded = GrantHistory::new(path.clone(), None);
+        assert_eq!(reloaded.query(&GrantHistoryRequest::default()).len(), 3);
+
+        history.record(
+            Some("app3".into()),
+            None,
+            &entry("cap", Some(GrantStatus::Denied)),
+            GrantChangeSource::UserPrompt,
+        );
+        // Switching profiles writes the pending change and starts from the other history
+        history.switch(other_path.clone());
+        assert!(history.query(&GrantHistoryRequest::default()).is_empty());
+        let reloaded = GrantHistory::new(path.clone(), None);
+        assert_eq!(reloaded.query(&GrantHistoryRequest::default()).len(), 4);
+
+        history.switch(path.clone());
+        assert_eq!(history.query(&GrantHistoryRequest::default()).len(), 4);
+        let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_file(other_path);
+    }
+}
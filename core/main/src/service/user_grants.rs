@@ -24,7 +24,7 @@ use std::{
 
 use crate::{
     firebolt::{firebolt_gatekeeper::FireboltGatekeeper, handlers::privacy_rpc::PrivacyImpl},
-    state::{cap::cap_state::CapState, platform_state::PlatformState},
+    state::{cap::cap_state::CapState, platform_state::PlatformState, profile_state::ProfileState},
 };
 use ripple_sdk::api::gateway::rpc_gateway_api::CallContext;
 use ripple_sdk::api::observability::log_signal::LogSignal;
@@ -34,20 +34,21 @@ use ripple_sdk::{
         device::{
             device_peristence::SetBoolProperty,
             device_user_grants_data::{
-                AutoApplyPolicy, GrantActiveState, GrantEntry, GrantLifespan, GrantPolicy,
-                GrantPrivacySetting, GrantScope, GrantStateModify, GrantStatus, GrantStep,
-                PolicyPersistenceType,
+                AutoApplyPolicy, GrantActiveState, GrantChangeSource, GrantEntry,
+                GrantHistoryEntry, GrantLifespan, GrantPolicy, GrantPrivacySetting, GrantScope,
+                GrantStateModify, GrantStatus, GrantStep, PolicyPersistenceType, ScheduleDecision,
             },
         },
         distributor::distributor_usergrants::UserGrantsCloudSetParams,
         firebolt::{
             fb_capabilities::{
                 CapEvent, CapabilityRole, DenyReason, DenyReasonWithCap, FireboltCap,
-                FireboltPermission, RoleInfo,
+                FireboltPermission, GrantTrace, RoleInfo, StageDecision,
             },
             fb_lifecycle::LifecycleState,
             fb_openrpc::{CapabilitySet, FireboltOpenRpcMethod},
             fb_pin::{PinChallengeConfiguration, PinChallengeRequest},
+            fb_user_grants::{GrantHistoryExport, GrantHistoryRequest},
             provider::{
                 Challenge, ChallengeRequestor, ProviderRequestPayload, ProviderResponsePayload,
             },
@@ -56,15 +57,19 @@ use ripple_sdk::{
         manifest::device_manifest::DeviceManifest,
         usergrant_entry::UserGrantInfo,
     },
-    framework::file_store::FileStore,
+    chrono::{self, NaiveDateTime, Utc},
+    framework::{file_store::FileStore, store_encryption::StoreKeyProvider},
     log::{debug, error, trace, warn},
     serde_json::Value,
     tokio::sync::oneshot,
     utils::error::RippleError,
 };
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use super::apps::provider_broker::{ProviderBroker, ProviderBrokerRequest};
+use super::{
+    apps::provider_broker::{ProviderBroker, ProviderBrokerRequest},
+    user_grant_history::GrantHistory,
+};
 
 pub struct UserGrants {}
 
@@ -73,8 +78,13 @@ type GrantAppMap = Arc<RwLock<FileStore<HashMap<String, HashSet<GrantEntry>>>>>;
 #[derive(Debug, Clone)]
 pub struct GrantState {
     device_grants: Arc<RwLock<FileStore<HashSet<GrantEntry>>>>,
+    // App and profile scoped grants belong to the active profile
     grant_app_map: GrantAppMap,
+    profile_grants: Arc<RwLock<FileStore<HashSet<GrantEntry>>>>,
     caps_needing_grants: Vec<String>,
+    history: GrantHistory,
+    saved_dir: String,
+    keys: Option<Arc<dyn StoreKeyProvider>>,
 }
 
 impl GrantState {
@@ -82,24 +92,89 @@ impl GrantState {
         let saved_dir = manifest.clone().configuration.saved_dir;
         let dir_path = Path::new(&saved_dir).join("device_grants");
         let device_grant_path = dir_path.into_os_string().into_string();
-        let dev_grant_store = if let Ok(v) = FileStore::load(device_grant_path.clone().unwrap()) {
-            v
-        } else {
-            FileStore::new(device_grant_path.unwrap(), HashSet::new())
-        };
-        let dir_path = Path::new(&saved_dir).join("app_grants");
-        let app_grant_path = dir_path.into_os_string().into_string();
-        let app_grant_store = if let Ok(v) = FileStore::load(app_grant_path.clone().unwrap()) {
-            v
-        } else {
-            FileStore::new(app_grant_path.unwrap(), HashMap::new())
-        };
+        let keys = manifest.get_store_encryption().map(|c| c.key_provider());
+        let dev_grant_store = Self::load_store(device_grant_path.unwrap(), HashSet::new(), &keys);
+        let app_grant_store = Self::load_store(
+            Self::grant_store_path(&saved_dir, None, "app_grants"),
+            HashMap::new(),
+            &keys,
+        );
+        let profile_grant_store = Self::load_store(
+            Self::grant_store_path(&saved_dir, None, "profile_grants"),
+            HashSet::new(),
+            &keys,
+        );
+        let history = GrantHistory::new(
+            Self::grant_store_path(&saved_dir, None, "grant_history"),
+            keys.clone(),
+        );
 
         GrantState {
             grant_app_map: Arc::new(RwLock::new(app_grant_store)),
+            profile_grants: Arc::new(RwLock::new(profile_grant_store)),
             caps_needing_grants: manifest.get_caps_requiring_grant(),
             device_grants: Arc::new(RwLock::new(dev_grant_store)),
+            history,
+            saved_dir,
+            keys,
+        }
+    }
+
+    /// Loads a grant store. Grants which can not be recovered are asked for again, the corrupt
+    /// files are kept aside.
+    fn load_store<S>(
+        path: String,
+        value: S,
+        keys: &Option<Arc<dyn StoreKeyProvider>>,
+    ) -> FileStore<S>
+    where
+        S: Serialize + DeserializeOwned + Clone,
+    {
+        FileStore::load_or_new_encrypted(path.clone(), value.clone(), keys.clone()).unwrap_or_else(
+            |e| {
+                error!("grant store {} is corrupt {:?}, starting empty", path, e);
+                FileStore::new_encrypted(path, value, keys.clone())
+            },
+        )
+    }
+
+    fn grant_store_path(saved_dir: &str, profile_id: Option<&str>, name: &str) -> String {
+        match profile_id {
+            Some(id) => ProfileState::profile_dir(saved_dir, id).join(name),
+            None => Path::new(saved_dir).join(name),
         }
+        .to_string_lossy()
+        .into_owned()
+    }
+
+    /// Loads the app and profile scoped grants and the grant history of a profile, None loads
+    /// those used without profiles
+    pub fn switch_profile(&self, profile_id: Option<&str>) {
+        let app_grant_store = Self::load_store(
+            Self::grant_store_path(&self.saved_dir, profile_id, "app_grants"),
+            HashMap::new(),
+            &self.keys,
+        );
+        let profile_grant_store = Self::load_store(
+            Self::grant_store_path(&self.saved_dir, profile_id, "profile_grants"),
+            HashSet::new(),
+            &self.keys,
+        );
+        *self.grant_app_map.write().unwrap() = app_grant_store;
+        *self.profile_grants.write().unwrap() = profile_grant_store;
+        self.history.switch(Self::grant_store_path(
+            &self.saved_dir,
+            profile_id,
+            "grant_history",
+        ));
+    }
+
+    pub fn get_grant_history(&self, request: &GrantHistoryRequest) -> Vec<GrantHistoryEntry> {
+        self.history.query(request)
+    }
+
+    pub fn export_grant_history(&self, request: &GrantHistoryRequest) -> GrantHistoryExport {
+        self.history.export(request)
     }
 
     pub fn cleanup_user_grants(&self) {
@@ -145,6 +220,13 @@ impl GrantState {
         for entry in grant_entries_to_remove {
             Self::force_delete_user_grant_from_local_sources(platform_state, None, &entry).await;
         }
+
+        //Remove profile user grants
+        let grant_policies_map = platform_state
+            .get_device_manifest()
+            .get_grant_policies()
+            .unwrap_or_default();
+        self.retain_profile_entries(|entry| grant_policies_map.contains_key(&entry.capability));
     }
 
     fn fetch_app_grant_entry_to_remove(
@@ -234,7 +316,14 @@ impl GrantState {
                 .retain(|entry: &GrantEntry| entry.capability != entry.capability);
             device_grant_map_write.sync();
         }
+        let previous = gc.clone();
         gc.status = None;
+        platform_state.cap_state.grant_state.history.record(
+            app_id_opt.clone(),
+            Some(&previous),
+            &gc,
+            GrantChangeSource::PolicySync,
+        );
 
         // Remove grant entry from cloud
         GrantPolicyEnforcer::send_usergrants_for_cloud_storage(
@@ -273,22 +362,64 @@ impl GrantState {
         &self,
         app_id: Option<String>, // None is for device
         new_entry: GrantEntry,
-    ) {
-        if let Some(app_id) = app_id {
+        change: GrantChangeSource,
+    ) -> Option<GrantEntry> {
+        let previous = if let Some(app_id) = &app_id {
             let mut grant_state = self.grant_app_map.write().unwrap();
             //Get a mutable reference to the value associated with a key, create it if it doesn't exist,
-            let entries = grant_state.value.entry(app_id).or_default();
+            let entries = grant_state.value.entry(app_id.clone()).or_default();
 
-            if entries.contains(&new_entry) {
-                entries.remove(&new_entry);
-            }
+            let previous = entries.take(&new_entry);
             if new_entry.status.is_some() {
-                entries.insert(new_entry);
+                entries.insert(new_entry.clone());
             }
             grant_state.sync();
+            previous
         } else {
-            self.add_device_entry(new_entry)
+            self.add_device_entry(new_entry.clone())
+        };
+        self.history
+            .record(app_id, previous.as_ref(), &new_entry, change);
+        previous
+    }
+
+    /// Updates the entry in the store of the given scope, `app_id` is ignored for profile scope
+    pub fn update_scoped_grant_entry(
+        &self,
+        scope: &GrantScope,
+        app_id: Option<String>,
+        new_entry: GrantEntry,
+        change: GrantChangeSource,
+    ) -> Option<GrantEntry> {
+        if *scope != GrantScope::Profile {
+            return self.update_grant_entry(app_id, new_entry, change);
         }
+        let previous = {
+            let mut profile_grants = self.profile_grants.write().unwrap();
+            let previous = profile_grants.value.take(&new_entry);
+            if new_entry.status.is_some() {
+                profile_grants.value.insert(new_entry.clone());
+            }
+            profile_grants.sync();
+            previous
+        };
+        self.history
+            .record(None, previous.as_ref(), &new_entry, change);
+        previous
+    }
+
+    fn retain_profile_entries<F>(&self, restrict_function: F) -> bool
+    where
+        F: FnMut(&GrantEntry) -> bool,
+    {
+        let mut profile_grants = self.profile_grants.write().unwrap();
+        let prev_len = profile_grants.value.len();
+        profile_grants.value.retain(restrict_function);
+        let deleted = profile_grants.value.len() < prev_len;
+        if deleted {
+            profile_grants.sync();
+        }
+        deleted
     }
 
     pub fn clear_local_entries(&self, ps: &PlatformState, persistence_type: PolicyPersistenceType) {
@@ -315,6 +446,15 @@ impl GrantState {
             )
         });
         device_grant_state.sync();
+
+        self.retain_profile_entries(|entry| {
+            !self.check_grant_policy_persistence(
+                ps,
+                entry.capability.clone(),
+                entry.role,
+                persistence_type.clone(),
+            )
+        });
     }
 
     pub fn check_grant_policy_persistence(
@@ -394,6 +534,11 @@ impl GrantState {
                 grant_state.sync()
             }
         }
+        if self.retain_profile_entries(|entry| {
+            entry.lifespan.as_ref().map_or(false, |l| l != lifespan)
+        }) {
+            deleted = true;
+        }
 
         deleted
     }
@@ -434,19 +579,21 @@ impl GrantState {
         }
         grant_state.sync();
 
-        // delete expired entries for device
+        // delete expired entries for device and profile
         self.delete_expired_entries_for_device();
+        self.retain_profile_entries(|entry| !entry.has_expired());
         true
     }
 
-    fn add_device_entry(&self, entry: GrantEntry) {
+    fn add_device_entry(&self, entry: GrantEntry) -> Option<GrantEntry> {
         let mut device_grants = self.device_grants.write().unwrap();
-        if entry.status.is_none() {
-            device_grants.value.remove(&entry);
+        let previous = if entry.status.is_none() {
+            device_grants.value.take(&entry)
         } else {
-            device_grants.value.replace(entry);
-        }
+            device_grants.value.replace(entry)
+        };
         device_grants.sync();
+        previous
     }
 
     pub fn get_grant_status(
@@ -484,9 +631,14 @@ impl GrantState {
         role: CapabilityRole,
         capability: &str,
     ) -> Option<GrantStatus> {
-        let grant_state = self.device_grants.read().unwrap();
+        let device_grants = self.device_grants.read().unwrap();
+        let profile_grants = self.profile_grants.read().unwrap();
 
-        for entry in grant_state.value.iter() {
+        for entry in device_grants
+            .value
+            .iter()
+            .chain(profile_grants.value.iter())
+        {
             if !entry.has_expired() && (entry.role == role) && (entry.capability == capability) {
                 debug!("Stored grant status: {:?}", entry.status);
                 return entry.status.clone();
@@ -665,13 +817,15 @@ impl GrantState {
         }
 
         if grant_policy.lifespan != GrantLifespan::Once {
-            self.update_grant_entry(
+            self.update_scoped_grant_entry(
+                &grant_policy.scope,
                 match grant_policy.scope {
                     GrantScope::App => Some(app_id.into()),
-                    GrantScope::Device => None,
+                    GrantScope::Device | GrantScope::Profile => None,
                 },
                 grant_entry,
-            )
+                GrantChangeSource::UserPrompt,
+            );
         }
     }
 
@@ -684,6 +838,12 @@ impl GrantState {
         }
     }
 
+    // Returns all active and denied user grant entries by app id.
+    pub fn get_app_grant_entries(&self) -> HashMap<String, HashSet<GrantEntry>> {
+        self.delete_all_expired_entries();
+        self.grant_app_map.read().unwrap().value.clone()
+    }
+
     // Returns all active and denied user grant entries for the given `app_id`.
     // Pass None for device scope
     pub fn get_device_entries(&self) -> HashSet<GrantEntry> {
@@ -691,6 +851,12 @@ impl GrantState {
         self.device_grants.read().unwrap().value.clone()
     }
 
+    // Returns all active and denied profile scoped user grant entries of the active profile.
+    pub fn get_profile_entries(&self) -> HashSet<GrantEntry> {
+        self.retain_profile_entries(|entry| !entry.has_expired());
+        self.profile_grants.read().unwrap().value.clone()
+    }
+
     // Returns all active and denied user grant entries for the given `capability`
     pub fn get_grant_entries_for_capability(
         &self,
@@ -717,6 +883,16 @@ impl GrantState {
             .cloned()
             .collect();
         grant_entry_map.insert("device".to_owned(), grant_sets);
+        let profile_grants = self.profile_grants.read().unwrap();
+        let grant_sets: HashSet<GrantEntry> = profile_grants
+            .value
+            .iter()
+            .filter(|elem| elem.capability == capability)
+            .cloned()
+            .collect();
+        if !grant_sets.is_empty() {
+            grant_entry_map.insert("profile".to_owned(), grant_sets);
+        }
         grant_entry_map
     }
 
@@ -768,6 +944,7 @@ impl GrantState {
         app_id: Option<String>,
         role: CapabilityRole,
         capability: String,
+        change: GrantChangeSource,
     ) -> bool {
         // Get the GrantEntry from UserGrantState matching app_id, role & capability
         let mut entry_modified = false;
@@ -824,7 +1001,12 @@ impl GrantState {
                     platform_state
                         .cap_state
                         .grant_state
-                        .update_grant_entry(app_id.clone(), new_entry.clone());
+                        .update_scoped_grant_entry(
+                            &grant_policy.scope,
+                            app_id.clone(),
+                            new_entry.clone(),
+                            change,
+                        );
 
                     debug!(
                         "Sync user grant modified with new entry:{:?} to cloud",
@@ -919,7 +1101,7 @@ impl GrantState {
             );
         }
         if grant_policy.scope == GrantScope::App && app_id.is_none()
-            || grant_policy.scope == GrantScope::Device && app_id.is_some()
+            || grant_policy.scope != GrantScope::App && app_id.is_some()
         {
             error!("Grant policy scope and request scope doesn't match!");
             return Err("Grant policy scope and request scope doesn't match!");
@@ -946,6 +1128,9 @@ impl GrantState {
                     &result,
                     app_id,
                     &grant_policy,
+                    GrantChangeSource::UserGrantsApi {
+                        caller: Some(ctx.app_id.clone()),
+                    },
                 )
                 .await;
 
@@ -966,6 +1151,9 @@ impl GrantState {
                     &result,
                     app_id,
                     &grant_policy,
+                    GrantChangeSource::UserGrantsApi {
+                        caller: Some(ctx.app_id.clone()),
+                    },
                 )
                 .await;
 
@@ -1016,7 +1204,14 @@ impl GrantState {
             platform_state
                 .cap_state
                 .grant_state
-                .update_grant_entry(app_id.clone(), new_entry.clone());
+                .update_scoped_grant_entry(
+                    &grant_policy.scope,
+                    app_id.clone(),
+                    new_entry.clone(),
+                    GrantChangeSource::UserGrantsApi {
+                        caller: Some(ctx.app_id.clone()),
+                    },
+                );
 
             debug!(
                 "Sync user grant modified with new entry:{:?} to cloud",
@@ -1218,6 +1413,7 @@ impl GrantPolicyEnforcer {
         result: &Result<(), DenyReasonWithCap>,
         app_id: &Option<String>,
         grant_policy: &GrantPolicy,
+        change: GrantChangeSource,
     ) -> bool {
         let mut ret_val = false;
         let mut grant_entry = GrantEntry::get(permission.role, permission.cap.as_str());
@@ -1239,18 +1435,19 @@ impl GrantPolicyEnforcer {
             match grant_policy.scope {
                 GrantScope::App => {
                     if app_id.is_some() {
-                        platform_state
-                            .cap_state
-                            .grant_state
-                            .update_grant_entry(app_id.to_owned(), grant_entry);
+                        platform_state.cap_state.grant_state.update_grant_entry(
+                            app_id.to_owned(),
+                            grant_entry,
+                            change,
+                        );
                         ret_val = true;
                     }
                 }
-                GrantScope::Device => {
+                GrantScope::Device | GrantScope::Profile => {
                     platform_state
                         .cap_state
                         .grant_state
-                        .update_grant_entry(None, grant_entry);
+                        .update_scoped_grant_entry(&grant_policy.scope, None, grant_entry, change);
                     ret_val = true;
                 }
             }
@@ -1273,6 +1470,7 @@ impl GrantPolicyEnforcer {
         result: &Result<(), DenyReasonWithCap>,
         app_id: &Option<String>,
         grant_policy: &GrantPolicy,
+        change: GrantChangeSource,
     ) {
         // Updating privacy settings
         if let Some(privacy_setting) = &grant_policy.privacy_setting {
@@ -1286,7 +1484,15 @@ impl GrantPolicyEnforcer {
             }
         }
 
-        Self::store_user_grants(platform_state, permission, result, app_id, grant_policy).await;
+        Self::store_user_grants(
+            platform_state,
+            permission,
+            result,
+            app_id,
+            grant_policy,
+            change,
+        )
+        .await;
     }
 
     // Helper function to check
@@ -1351,6 +1557,117 @@ impl GrantPolicyEnforcer {
         check_status
     }
 
+    /// Works out how [GrantState::check_with_roles] would decide the user grants of the given
+    /// permissions without prompting the user or storing anything. Returns the decision of each
+    /// permission along with the overall result, a pending grant is reported as `Ok(false)`.
+    pub async fn explain_grants(
+        platform_state: &PlatformState,
+        app_id: &str,
+        fb_perms: &[FireboltPermission],
+    ) -> (Vec<GrantTrace>, Result<bool, DenyReasonWithCap>) {
+        let grant_state = &platform_state.cap_state.grant_state;
+        let caps_needing_grants: Vec<FireboltPermission> = fb_perms
+            .iter()
+            .filter(|&x| grant_state.caps_needing_grants.contains(&x.cap.as_str()))
+            .cloned()
+            .collect();
+        let caps_needing_grants =
+            Self::apply_grant_exclusion_filters(platform_state, app_id, None, &caps_needing_grants)
+                .await;
+
+        let mut traces = Vec::new();
+        let mut decided = true;
+        for permission in caps_needing_grants {
+            let trace = Self::explain_grant(platform_state, app_id, &permission).await;
+            match trace.decision {
+                StageDecision::Denied => {
+                    let reason = trace.reason.clone().unwrap_or(DenyReason::GrantDenied);
+                    traces.push(trace);
+                    // check_with_roles stops at the first error
+                    return (
+                        traces,
+                        Err(DenyReasonWithCap::new(reason, vec![permission.cap])),
+                    );
+                }
+                StageDecision::Pending => decided = false,
+                _ => {}
+            }
+            traces.push(trace);
+        }
+        (traces, Ok(decided))
+    }
+
+    async fn explain_grant(
+        platform_state: &PlatformState,
+        app_id: &str,
+        permission: &FireboltPermission,
+    ) -> GrantTrace {
+        let mut trace = GrantTrace {
+            permission: permission.clone(),
+            decision: StageDecision::Passed,
+            reason: None,
+            policy: None,
+            detail: String::new(),
+        };
+        if let GrantActiveState::ActiveGrant(grant) = platform_state
+            .cap_state
+            .grant_state
+            .get_grant_state(app_id, permission, None)
+        {
+            trace.detail = "user grant is stored".into();
+            if let Err(reason) = grant {
+                trace.decision = StageDecision::Denied;
+                trace.reason = Some(reason);
+            }
+            return trace;
+        }
+
+        let policy = platform_state
+            .get_device_manifest()
+            .get_grant_policies()
+            .and_then(|map| map.get(&permission.cap.as_str()).cloned())
+            .and_then(|policies| policies.get_policy(permission));
+        let Some(policy) = policy else {
+            trace.detail = "no grant policy applies to the permission".into();
+            return trace;
+        };
+        trace.policy = Some(policy.clone());
+        if !Self::is_policy_valid(platform_state, &policy) {
+            trace.decision = StageDecision::Denied;
+            trace.reason = Some(DenyReason::Disabled);
+            trace.detail = "grant policy is not valid".into();
+            return trace;
+        }
+        let Some(policy) = Self::apply_schedule(policy, Self::local_now(platform_state)) else {
+            trace.decision = StageDecision::Denied;
+            trace.reason = Some(DenyReason::GrantDenied);
+            trace.detail = "denied outside the windows of the grant policy schedule".into();
+            return trace;
+        };
+        trace.policy = Some(policy.clone());
+        if let Some(privacy_setting) = &policy.privacy_setting {
+            if privacy_setting.auto_apply_policy != AutoApplyPolicy::Never {
+                if let Some(result) =
+                    Self::evaluate_privacy_settings(platform_state, privacy_setting).await
+                {
+                    trace.detail = format!(
+                        "applied automatically from privacy setting {}",
+                        privacy_setting.property
+                    );
+                    if let Err(reason) = result {
+                        trace.decision = StageDecision::Denied;
+                        trace.reason = Some(reason);
+                    }
+                    return trace;
+                }
+            }
+        }
+        trace.decision = StageDecision::Pending;
+        trace.detail =
+            "the user would be prompted, the app has to be in the foreground for the prompt".into();
+        trace
+    }
+
     pub async fn determine_grant_policies_for_permission(
         platform_state: &PlatformState,
         caller_session: &CallerSession,
@@ -1440,7 +1757,20 @@ impl GrantPolicyEnforcer {
                 reason: DenyReason::Disabled,
             });
         }
-        let result = GrantPolicyEnforcer::execute(
+        let policy = match Self::apply_schedule(policy, Self::local_now(platform_state)) {
+            Some(policy) => policy,
+            None => {
+                debug!(
+                    "Grant for cap: {} is denied outside the windows of its schedule",
+                    permission.cap.as_str()
+                );
+                return Err(DenyReasonWithCap {
+                    caps: vec![permission.clone().cap],
+                    reason: DenyReason::GrantDenied,
+                });
+            }
+        };
+        let (result, change) = GrantPolicyEnforcer::execute(
             platform_state,
             caller_session,
             app_requested_for,
@@ -1468,13 +1798,78 @@ impl GrantPolicyEnforcer {
             &result,
             &Some(app_requested_for.app_id.to_owned()),
             &policy,
+            change,
         )
         .await;
 
         result
     }
 
-    fn is_policy_valid(platform_state: &PlatformState, policy: &GrantPolicy) -> bool {
+    /// Current time in the device timezone of the ripple context, UTC when it is not known
+    pub fn local_now(platform_state: &PlatformState) -> NaiveDateTime {
+        let offset = platform_state
+            .service_controller_state
+            .service_event_state
+            .ripple_context
+            .read()
+            .ok()
+            .and_then(|context| context.time_zone.as_ref().map(|tz| tz.offset))
+            .unwrap_or_default();
+        (Utc::now() + chrono::Duration::seconds(offset)).naive_utc()
+    }
+
+    /// Applies the schedule of the policy at the local time `now`. Returns None when the grant
+    /// is denied by the schedule. Otherwise the options of an active window replace those of the
+    /// policy, and stored grants of every lifespan are set to expire at the next window boundary
+    /// or schedule expiry so they are evaluated again then. Once the schedule has expired grants
+    /// are only used for the current request.
+    pub fn apply_schedule(mut policy: GrantPolicy, now: NaiveDateTime) -> Option<GrantPolicy> {
+        let Some(schedule) = &policy.schedule else {
+            return Some(policy);
+        };
+        match schedule.evaluate(now) {
+            ScheduleDecision::Deny => None,
+            ScheduleDecision::Apply {
+                options,
+                valid_for_secs,
+            } => {
+                if let Some(options) = options {
+                    policy.options = options;
+                }
+                match valid_for_secs {
+                    Some(0) => {
+                        policy.lifespan = GrantLifespan::Once;
+                        policy.lifespan_ttl = None;
+                    }
+                    Some(valid_for_secs) => match policy.lifespan {
+                        GrantLifespan::Forever => {
+                            policy.lifespan = GrantLifespan::Seconds;
+                            policy.lifespan_ttl = Some(valid_for_secs);
+                        }
+                        GrantLifespan::Seconds
+                        | GrantLifespan::AppActive
+                        | GrantLifespan::PowerActive => {
+                            policy.lifespan_ttl = Some(
+                                policy
+                                    .lifespan_ttl
+                                    .map_or(valid_for_secs, |ttl| ttl.min(valid_for_secs)),
+                            );
+                        }
+                        GrantLifespan::Once => {}
+                    },
+                    None => {}
+                }
+                Some(policy)
+            }
+        }
+    }
+
+    pub fn is_policy_valid(platform_state: &PlatformState, policy: &GrantPolicy) -> bool {
+        if let Some(schedule) = &policy.schedule {
+            if !schedule.is_valid() {
+                return false;
+            }
+        }
         // Privacy settings in a policy takes higher precedence and we are
         // evaluating first.
         if let Some(privacy) = &policy.privacy_setting {
@@ -1729,28 +2124,30 @@ impl GrantPolicyEnforcer {
         app_requested_for: &AppIdentification,
         permission: &FireboltPermission,
         policy: &GrantPolicy,
-    ) -> Result<(), DenyReasonWithCap> {
+    ) -> (Result<(), DenyReasonWithCap>, GrantChangeSource) {
         if let Some(privacy_setting) = &policy.privacy_setting {
             if privacy_setting.auto_apply_policy != AutoApplyPolicy::Never {
                 if let Some(priv_sett_response) =
                     Self::evaluate_privacy_settings(platform_state, privacy_setting).await
                 {
-                    return priv_sett_response.map_err(|err| DenyReasonWithCap {
+                    let result = priv_sett_response.map_err(|err| DenyReasonWithCap {
                         reason: err,
                         caps: vec![permission.cap.clone()],
                     });
+                    return (result, GrantChangeSource::AutoApply);
                 }
             }
         }
 
-        Self::evaluate_options(
+        let result = Self::evaluate_options(
             platform_state,
             caller_session,
             app_requested_for,
             permission,
             policy,
         )
-        .await
+        .await;
+        (result, GrantChangeSource::UserPrompt)
     }
 
     fn get_app_content_catalog(platform_state: &PlatformState, app_id: &str) -> Option<String> {
@@ -2085,7 +2482,7 @@ mod tests {
         };
         use ripple_sdk::{
             api::{
-                device::device_user_grants_data::GrantRequirements,
+                device::device_user_grants_data::{GrantRequirements, GrantSchedule},
                 firebolt::{
                     fb_general::ListenRequest,
                     fb_pin::{
@@ -2604,5 +3001,127 @@ mod tests {
 
             assert!(evaluate_options.is_ok());
         }
+
+        #[test]
+        fn test_apply_schedule() {
+            let at = |time: &str| {
+                NaiveDateTime::parse_from_str(&format!("2024-01-05 {}", time), "%Y-%m-%d %H:%M")
+                    .unwrap()
+            };
+            let schedule: GrantSchedule = serde_json::from_value(json!({
+                "windows": [{"start": "08:00", "end": "20:00"}],
+                "outsideWindows": "deny"
+            }))
+            .unwrap();
+            let policy = GrantPolicy {
+                lifespan: GrantLifespan::Forever,
+                schedule: Some(schedule),
+                ..Default::default()
+            };
+
+            let applied = GrantPolicyEnforcer::apply_schedule(policy.clone(), at("19:00")).unwrap();
+            assert_eq!(applied.lifespan, GrantLifespan::Seconds);
+            assert_eq!(applied.lifespan_ttl, Some(3600));
+
+            let shorter = GrantPolicy {
+                lifespan: GrantLifespan::Seconds,
+                lifespan_ttl: Some(60),
+                ..policy.clone()
+            };
+            let applied = GrantPolicyEnforcer::apply_schedule(shorter, at("19:00")).unwrap();
+            assert_eq!(applied.lifespan_ttl, Some(60));
+
+            assert!(GrantPolicyEnforcer::apply_schedule(policy.clone(), at("21:00")).is_none());
+
+            // The window bounds app and power scoped grants too
+            for lifespan in [GrantLifespan::AppActive, GrantLifespan::PowerActive] {
+                let scoped = GrantPolicy {
+                    lifespan: lifespan.clone(),
+                    ..policy.clone()
+                };
+                let applied = GrantPolicyEnforcer::apply_schedule(scoped, at("19:00")).unwrap();
+                assert_eq!(applied.lifespan, lifespan);
+                assert_eq!(applied.lifespan_ttl, Some(3600));
+            }
+        }
+
+        #[test]
+        fn test_apply_expired_schedule() {
+            let now = NaiveDateTime::parse_from_str("2024-03-01 10:00", "%Y-%m-%d %H:%M").unwrap();
+            let schedule: GrantSchedule = serde_json::from_value(json!({
+                "expires": {"on": "2024-02-01"}
+            }))
+            .unwrap();
+            let policy = GrantPolicy {
+                lifespan: GrantLifespan::Forever,
+                schedule: Some(schedule),
+                ..Default::default()
+            };
+            let applied = GrantPolicyEnforcer::apply_schedule(policy, now).unwrap();
+            assert_eq!(applied.lifespan, GrantLifespan::Once);
+            assert_eq!(applied.lifespan_ttl, None);
+        }
+    }
+
+    #[test]
+    fn test_profile_grants_are_isolated() {
+        let mut saved_dir = std::env::temp_dir();
+        saved_dir.push(format!("profile_grants_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&saved_dir);
+        let mut manifest = DeviceManifest::default();
+        manifest.configuration.saved_dir = saved_dir.to_string_lossy().into_owned();
+        let grant_state = GrantState::new(manifest);
+
+        let capability = "xrn:firebolt:capability:usergrant:purchase";
+        let permission = FireboltPermission {
+            cap: FireboltCap::Full(capability.to_owned()),
+            role: CapabilityRole::Use,
+        };
+        let mut entry = GrantEntry::get(CapabilityRole::Use, capability.to_owned());
+        entry.status = Some(GrantStatus::Allowed);
+        entry.lifespan = Some(GrantLifespan::Forever);
+
+        grant_state.switch_profile(Some("kid"));
+        grant_state.update_scoped_grant_entry(
+            &GrantScope::Profile,
+            None,
+            entry.clone(),
+            GrantChangeSource::UserPrompt,
+        );
+        grant_state.update_scoped_grant_entry(
+            &GrantScope::App,
+            Some("app1".to_owned()),
+            entry.clone(),
+            GrantChangeSource::UserPrompt,
+        );
+        assert_eq!(
+            grant_state.get_grant_status("app2", &permission),
+            Some(GrantStatus::Allowed)
+        );
+        assert_eq!(
+            grant_state
+                .get_grant_entries_for_app_id("app1".into())
+                .len(),
+            1
+        );
+
+        grant_state.switch_profile(Some("parent"));
+        assert_eq!(grant_state.get_grant_status("app1", &permission), None);
+        assert!(grant_state.get_profile_entries().is_empty());
+
+        grant_state.switch_profile(Some("kid"));
+        assert_eq!(grant_state.get_profile_entries().len(), 1);
+        assert_eq!(
+            grant_state
+                .get_grant_entries_for_app_id("app1".into())
+                .len(),
+            1
+        );
+        assert!(
+            ProfileState::profile_dir(&saved_dir.to_string_lossy(), "kid")
+                .join("profile_grants")
+                .exists()
+        );
+        let _ = std::fs::remove_dir_all(saved_dir);
     }
 }
@@ -0,0 +1,98 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use ripple_sdk::{
+    api::{
+        context::RippleContextUpdateRequest,
+        firebolt::fb_profile::{ProfileChangedEvent, PROFILE_CHANGED_EVENT},
+    },
+    log::info,
+    serde_json,
+    utils::error::RippleError,
+};
+
+use crate::{
+    processor::storage::storage_manager::StorageManager, state::platform_state::PlatformState,
+};
+
+use super::{
+    apps::app_events::AppEvents,
+    ripple_service::service_notification_processor::ServiceNotificationProcessor,
+};
+
+pub struct UserProfiles;
+
+impl UserProfiles {
+    /// Makes the given profile active. Grants and privacy settings of the profile take effect,
+    /// the ripple context is updated and apps are notified.
+    pub async fn switch(
+        platform_state: &PlatformState,
+        profile_id: Option<String>,
+    ) -> Result<(), RippleError> {
+        // A privacy sync in flight applies its changes to the profile it started with
+        let lock = platform_state.privacy_sync_state.get_sync_lock();
+        let sync_guard = lock.lock().await;
+        let previous = platform_state
+            .profile_state
+            .set_active(profile_id.clone())?;
+        if previous == profile_id {
+            return Ok(());
+        }
+        info!("switching profile from {:?} to {:?}", previous, profile_id);
+        platform_state
+            .cap_state
+            .grant_state
+            .switch_profile(profile_id.as_deref());
+        platform_state
+            .privacy_sync_state
+            .switch_profile(profile_id.as_deref());
+        drop(sync_guard);
+        platform_state.ripple_cache.clear();
+
+        let request = RippleContextUpdateRequest::Profile(profile_id.clone());
+        platform_state
+            .get_client()
+            .get_extn_client()
+            .context_update(request.clone());
+        ServiceNotificationProcessor::context_update(
+            "RippleContextProfileChangedEvent",
+            request,
+            platform_state,
+            None,
+        );
+
+        let event = ProfileChangedEvent {
+            profile_id,
+            previous_profile_id: previous,
+        };
+        if let Ok(value) = serde_json::to_value(event) {
+            AppEvents::emit(platform_state, PROFILE_CHANGED_EVENT, &value).await;
+        }
+        Ok(())
+    }
+
+    /// Deletes a profile which is not active along with its grants and the properties
+    /// persisted for it
+    pub async fn delete(
+        platform_state: &PlatformState,
+        profile_id: &str,
+    ) -> Result<(), RippleError> {
+        platform_state.profile_state.delete(profile_id)?;
+        StorageManager::delete_profile_properties(platform_state, profile_id).await;
+        Ok(())
+    }
+}
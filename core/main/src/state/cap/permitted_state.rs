@@ -51,11 +51,11 @@ pub struct PermittedState {
 impl PermittedState {
     pub fn new(manifest: DeviceManifest) -> PermittedState {
         let path = get_permissions_path(manifest.configuration.saved_dir);
-        let store = if let Ok(v) = FileStore::load(path.clone()) {
-            v
-        } else {
+        let store = FileStore::load_or_new(path.clone(), HashMap::new()).unwrap_or_else(|e| {
+            // permissions are fetched again from the distributor
+            error!("permissions {} are corrupt {:?}, starting empty", path, e);
             FileStore::new(path, HashMap::new())
-        };
+        });
 
         PermittedState {
             permitted: Arc::new(RwLock::new(store)),
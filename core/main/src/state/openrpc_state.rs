@@ -102,7 +102,7 @@ use ripple_sdk::{
             fb_capabilities::FireboltPermission,
             fb_openrpc::{
                 CapabilitySet, FireboltOpenRpc, FireboltOpenRpcMethod, FireboltSemanticVersion,
-                FireboltVersionManifest,
+                FireboltVersionManifest, OpenRPCParser,
             },
             provider::ProviderAttributes,
         },
@@ -116,6 +116,9 @@ use std::{
     sync::{Arc, RwLock},
 };
 
+/// Methods served by Ripple on top of the Firebolt spec
+const RIPPLE_OPEN_RPC: &str = include_str!("ripple-rpc.json");
+
 #[derive(Debug, Clone)]
 pub enum ApiSurface {
     Firebolt,
@@ -229,6 +232,7 @@ impl OpenRpcState {
             json_schema_cache: Arc::new(RwLock::new(HashMap::new())),
         };
         v.build_provider_relation_sets(&firebolt_open_rpc.methods);
+        v.add_ripple_methods();
         for path in extn_sdks {
             if v.add_extension_open_rpc(&path).is_err() {
                 error!("Error adding extn_sdk from {path}");
@@ -241,6 +245,28 @@ impl OpenRpcState {
         v
     }
 
+    /// Gates the Ripple methods which the Firebolt spec does not define, the spec stays
+    /// authoritative for the methods it has
+    fn add_ripple_methods(&self) {
+        let ripple_open_rpc: FireboltOpenRpc =
+            match serde_json::from_str::<OpenRPCParser>(RIPPLE_OPEN_RPC) {
+                Ok(parser) => parser.into(),
+                Err(e) => {
+                    error!("Failed parsing the Ripple OpenRPC {:?}", e);
+                    return;
+                }
+            };
+        let caps: HashMap<String, CapabilitySet> = {
+            let firebolt_caps = self.firebolt_cap_map.read().unwrap();
+            ripple_open_rpc
+                .get_methods_caps()
+                .into_iter()
+                .filter(|(method, _)| !firebolt_caps.contains_key(method))
+                .collect()
+        };
+        self.extend_caps(caps);
+    }
+
     pub fn add_open_rpc(&self, open_rpc: FireboltOpenRpc) {
         self.extend_caps(open_rpc.get_methods_caps());
         self.extend_policies(open_rpc.get_capability_policy());
@@ -643,9 +669,30 @@ fn load_extension_open_rpc(path: String) -> Option<String> {
 
 #[cfg(test)]
 mod tests {
-    use ripple_sdk::api::manifest::extn_manifest::default_providers;
+    use ripple_sdk::api::{
+        firebolt::fb_capabilities::{CapabilityRole, FireboltCap},
+        manifest::extn_manifest::default_providers,
+    };
 
-    use crate::state::openrpc_state::OpenRpcState;
+    use crate::state::openrpc_state::{ApiSurface, OpenRpcState};
+
+    #[test]
+    fn test_ripple_methods_are_gated() {
+        let state = OpenRpcState::new(None, Vec::new(), default_providers());
+        let perms = state
+            .get_perms_for_method("usergrants.history", vec![ApiSurface::Firebolt])
+            .unwrap();
+        assert_eq!(perms.len(), 1);
+        assert_eq!(
+            perms[0].cap,
+            FireboltCap::Full("xrn:firebolt:capability:grants:state".to_owned())
+        );
+        assert_eq!(perms[0].role, CapabilityRole::Use);
+        // the Firebolt spec wins for the methods it defines
+        assert!(state
+            .get_perms_for_method("usergrants.app", vec![ApiSurface::Firebolt])
+            .is_some());
+    }
 
     #[test]
     fn test_provider_support() {
@@ -21,21 +21,27 @@ use std::{
 };
 
 use ripple_sdk::{
-    api::observability::metrics_util::ApiStats,
+    api::{firebolt::fb_telemetry::TelemetryPayload, observability::metrics_util::ApiStats},
     chrono::{DateTime, Utc},
+    framework::RippleResponse,
     log::trace,
+    tokio::sync::{mpsc::UnboundedSender, oneshot},
 };
 
 include!(concat!(env!("OUT_DIR"), "/version.rs"));
 
 const API_STATS_MAP_SIZE_WARNING: usize = 10;
 
+/// Telemetry payload waiting to be governed, with the sender of its delivery result
+pub type GovernedTelemetry = (TelemetryPayload, Option<oneshot::Sender<RippleResponse>>);
+
 #[derive(Debug, Clone, Default)]
 pub struct OpMetricState {
     pub start_time: DateTime<Utc>,
     operational_telemetry_listeners: Arc<RwLock<HashSet<String>>>,
     api_stats_map: Arc<RwLock<HashMap<String, ApiStats>>>,
     device_session_id: Arc<RwLock<Option<String>>>,
+    telemetry_queue: Arc<RwLock<Option<UnboundedSender<GovernedTelemetry>>>>,
 }
 
 impl OpMetricState {
@@ -65,6 +71,18 @@ impl OpMetricState {
             .collect()
     }
 
+    /// Queue of the telemetry forwarded in order, started with `start` on first use
+    pub fn get_telemetry_queue(
+        &self,
+        start: impl FnOnce() -> UnboundedSender<GovernedTelemetry>,
+    ) -> UnboundedSender<GovernedTelemetry> {
+        let mut queue = self.telemetry_queue.write().unwrap();
+        match queue.as_ref() {
+            Some(sender) if !sender.is_closed() => sender.clone(),
+            _ => queue.insert(start()).clone(),
+        }
+    }
+
     pub fn update_session_id(&self, value: Option<String>) {
         let value = value.unwrap_or_default();
         {
@@ -19,6 +19,7 @@ use crate::firebolt::handlers::internal_rpc::PolicyState;
 use ripple_sdk::{
     api::{
         config::FEATURE_DISTRIBUTOR_SESSION,
+        context::RippleContextUpdateRequest,
         firebolt::fb_discovery::{AgePolicy, PolicyIdentifierAlias},
         gateway::rpc_gateway_api::RpcRequest,
         manifest::{
@@ -51,14 +52,16 @@ use crate::{
             delegated_launcher_handler::{AppManagerState, AppManagerState2_0},
             provider_broker::ProviderBrokerState,
         },
-        extn::ripple_client::RippleClient,
+        data_governance::DataGovernanceFilter,
+        extn::{extn_supervisor::ExtnSupervisor, ripple_client::RippleClient},
         ripple_service::service_controller_state::ServiceControllerState,
     },
 };
 
 use super::{
     cap::cap_state::CapState, openrpc_state::OpenRpcState, ops_metrics_state::OpMetricState,
-    ripple_cache::RippleCache, session_state::SessionState,
+    privacy_sync_state::PrivacySyncState, profile_state::ProfileState, ripple_cache::RippleCache,
+    session_state::SessionState,
 };
 
 /// Platform state encapsulates the internal state of the Ripple Main application.
@@ -118,6 +121,9 @@ pub struct PlatformState {
     pub lifecycle2_app_state: AppManagerState2_0,
     pub service_controller_state: ServiceControllerState,
     pub policy_state: PolicyState,
+    pub profile_state: ProfileState,
+    pub privacy_sync_state: PrivacySyncState,
+    pub extn_supervisor: ExtnSupervisor,
 }
 
 impl PlatformState {
@@ -134,7 +140,14 @@ impl PlatformState {
         let extn_sdks = extn_manifest.extn_sdks.clone();
         let provider_registations = extn_manifest.provider_registrations.clone();
         let metrics_state = OpMetricState::default();
-        Self {
+        let keys = manifest.get_store_encryption().map(|c| c.key_provider());
+        let profile_state = ProfileState::new(&manifest.configuration.saved_dir, keys.clone());
+        let privacy_sync_state = PrivacySyncState::new(
+            &manifest.configuration.saved_dir,
+            profile_state.get_active().as_deref(),
+            keys,
+        );
+        let state = Self {
             extn_manifest: Arc::new(extn_manifest),
             cap_state: CapState::new(manifest.clone()),
             session_state: SessionState::default(),
@@ -159,7 +172,31 @@ impl PlatformState {
             lifecycle2_app_state: AppManagerState2_0::new(),
             service_controller_state: ServiceControllerState::new(),
             policy_state: PolicyState::default(),
+            profile_state,
+            privacy_sync_state,
+            extn_supervisor: ExtnSupervisor::default(),
+        };
+        if let Some(profile_id) = state.profile_state.get_active() {
+            state
+                .cap_state
+                .grant_state
+                .switch_profile(Some(&profile_id));
+            state.get_client().get_extn_client().context_update(
+                RippleContextUpdateRequest::Profile(Some(profile_id.clone())),
+            );
+            if let Ok(mut context) = state
+                .service_controller_state
+                .service_event_state
+                .ripple_context
+                .write()
+            {
+                context.profile_id = Some(profile_id);
+            }
         }
+        state
+            .endpoint_state
+            .set_request_filter(Arc::new(DataGovernanceFilter::new(&state)));
+        state
     }
 
     pub fn get_policy_identifier_alias(&self) -> Vec<AgePolicy> {
@@ -311,13 +348,19 @@ impl PlatformState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::state::bootstrap_state::ChannelsState;
     use ripple_sdk::api::manifest::extn_manifest::default_providers;
     use ripple_tdk::utils::test_utils::Mockable;
 
     impl Mockable for PlatformState {
         fn mock() -> Self {
-            use crate::state::bootstrap_state::ChannelsState;
+            Self::mock_with_channels(ChannelsState::new())
+        }
+    }
 
+    impl PlatformState {
+        /// Mock state with a client on the channels, so a test can start the gateway with them
+        pub fn mock_with_channels(channels_state: ChannelsState) -> Self {
             let (_, manifest) = DeviceManifest::load_from_content(
                 include_str!("../../../../examples/manifest/device-manifest-example.json")
                     .to_string(),
@@ -332,7 +375,7 @@ mod tests {
             Self::new(
                 extn_manifest,
                 manifest,
-                RippleClient::new(ChannelsState::new()),
+                RippleClient::new(channels_state),
                 vec![],
                 None,
             )
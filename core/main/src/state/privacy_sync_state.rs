@@ -0,0 +1,291 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+use ripple_sdk::{
+    api::distributor::distributor_privacy::{PrivacySetting, PrivacySettingChange},
+    framework::{file_store::FileStore, store_encryption::StoreKeyProvider},
+    log::error,
+    tokio::sync::Mutex,
+};
+use serde::{Deserialize, Serialize};
+
+use super::profile_state::ProfileState;
+
+const PRIVACY_SYNC_FILE_NAME: &str = "privacy_sync_journal";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PrivacySyncJournal {
+    /// Local changes which are not yet pushed to the cloud
+    pending: Vec<PrivacySettingChange>,
+    /// Last known change of every setting, local or remote
+    latest: Vec<PrivacySettingChange>,
+    /// `server_time` of the last successful pull
+    last_pull: u64,
+}
+
+/// Journal of the privacy settings changes of the active profile used to sync the local
+/// settings with the cloud. Conflicts are resolved per setting, the change with the highest
+/// `updated_at` wins. `updated_at` of a local change is above every version known to the
+/// device, so changes are ordered even when the device clock goes back.
+#[derive(Debug, Clone)]
+pub struct PrivacySyncState {
+    journal: Arc<RwLock<FileStore<PrivacySyncJournal>>>,
+    sync_lock: Arc<Mutex<()>>,
+    saved_dir: String,
+    keys: Option<Arc<dyn StoreKeyProvider>>,
+}
+
+impl PrivacySyncState {
+    pub fn new(
+        saved_dir: &str,
+        profile_id: Option<&str>,
+        keys: Option<Arc<dyn StoreKeyProvider>>,
+    ) -> PrivacySyncState {
+        let journal = Self::load(saved_dir, profile_id, &keys);
+        PrivacySyncState {
+            journal: Arc::new(RwLock::new(journal)),
+            sync_lock: Arc::new(Mutex::new(())),
+            saved_dir: saved_dir.to_owned(),
+            keys,
+        }
+    }
+
+    fn load(
+        saved_dir: &str,
+        profile_id: Option<&str>,
+        keys: &Option<Arc<dyn StoreKeyProvider>>,
+    ) -> FileStore<PrivacySyncJournal> {
+        let path = match profile_id {
+            Some(id) => ProfileState::profile_dir(saved_dir, id).join(PRIVACY_SYNC_FILE_NAME),
+            None => Path::new(saved_dir).join(PRIVACY_SYNC_FILE_NAME),
+        };
+        let path = path.to_string_lossy().into_owned();
+        FileStore::load_or_new_encrypted(path.clone(), PrivacySyncJournal::default(), keys.clone())
+            .unwrap_or_else(|e| {
+                // a new journal pulls the cloud settings on the next sync
+                error!(
+                    "privacy sync journal {} is corrupt {:?}, starting a new one",
+                    path, e
+                );
+                FileStore::new_encrypted(path, PrivacySyncJournal::default(), keys.clone())
+            })
+    }
+
+    /// Lock held for the duration of a sync so push and pull are not interleaved, and the
+    /// active profile does not change while a sync applies its changes
+    pub fn get_sync_lock(&self) -> Arc<Mutex<()>> {
+        self.sync_lock.clone()
+    }
+
+    /// Continues with the journal of the given profile, the caller holds the sync lock
+    pub fn switch_profile(&self, profile_id: Option<&str>) {
+        let journal = Self::load(&self.saved_dir, profile_id, &self.keys);
+        *self.journal.write().unwrap() = journal;
+    }
+
+    fn replace_change(changes: &mut Vec<PrivacySettingChange>, change: PrivacySettingChange) {
+        changes.retain(|c| c.setting != change.setting);
+        changes.push(change);
+    }
+
+    fn latest_update(journal: &PrivacySyncJournal, setting: &PrivacySetting) -> u64 {
+        journal
+            .latest
+            .iter()
+            .find(|c| c.setting.eq(setting))
+            .map_or(0, |c| c.updated_at)
+    }
+
+    /// Records a change made on this device at the local time `now`, it is kept until
+    /// acknowledged by the cloud. Returns the version given to the change, which is `now`
+    /// unless a newer version is already known.
+    pub fn record_local(&self, setting: PrivacySetting, value: bool, now: u64) -> u64 {
+        let mut journal = self.journal.write().unwrap();
+        let known = journal
+            .value
+            .latest
+            .iter()
+            .map(|c| c.updated_at)
+            .max()
+            .unwrap_or_default()
+            .max(journal.value.last_pull);
+        let change = PrivacySettingChange {
+            setting,
+            value,
+            updated_at: now.max(known + 1),
+        };
+        let updated_at = change.updated_at;
+        Self::replace_change(&mut journal.value.pending, change.clone());
+        Self::replace_change(&mut journal.value.latest, change);
+        journal.sync();
+        updated_at
+    }
+
+    pub fn get_pending(&self) -> Vec<PrivacySettingChange> {
+        self.journal.read().unwrap().value.pending.clone()
+    }
+
+    pub fn get_last_pull(&self) -> u64 {
+        self.journal.read().unwrap().value.last_pull
+    }
+
+    /// Drops the pending changes which were pushed, a change made while the push was in flight
+    /// stays pending
+    pub fn acknowledge(&self, pushed: &[PrivacySettingChange]) {
+        let mut journal = self.journal.write().unwrap();
+        journal
+            .value
+            .pending
+            .retain(|p| !pushed.iter().any(|c| c.eq(p)));
+        journal.sync();
+    }
+
+    /// Merges the changes pulled from the cloud and returns the ones which are newer than what
+    /// is known locally and have to be applied to the local storage
+    pub fn merge_remote(
+        &self,
+        mut changes: Vec<PrivacySettingChange>,
+        server_time: u64,
+    ) -> Vec<PrivacySettingChange> {
+        changes.sort_by_key(|c| c.updated_at);
+        let mut journal = self.journal.write().unwrap();
+        let mut accepted: Vec<PrivacySettingChange> = Vec::new();
+        for change in changes {
+            if change.updated_at <= Self::latest_update(&journal.value, &change.setting) {
+                continue;
+            }
+            journal
+                .value
+                .pending
+                .retain(|p| p.setting != change.setting);
+            Self::replace_change(&mut journal.value.latest, change.clone());
+            Self::replace_change(&mut accepted, change);
+        }
+        journal.value.last_pull = server_time;
+        journal.sync();
+        accepted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved_dir(name: &str) -> String {
+        let mut path = std::env::temp_dir();
+        path.push(format!("privacy_sync_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn change(setting: PrivacySetting, value: bool, updated_at: u64) -> PrivacySettingChange {
+        PrivacySettingChange {
+            setting,
+            value,
+            updated_at,
+        }
+    }
+
+    #[test]
+    fn test_last_writer_wins_per_setting() {
+        let dir = saved_dir("lww");
+        let state = PrivacySyncState::new(&dir, None, None);
+        // offline changes on this device
+        state.record_local(PrivacySetting::Acr, true, 100);
+        state.record_local(PrivacySetting::WatchHistory, false, 300);
+
+        let accepted = state.merge_remote(
+            vec![
+                change(PrivacySetting::Acr, false, 200),
+                change(PrivacySetting::WatchHistory, true, 250),
+                change(PrivacySetting::Personalization, true, 50),
+                change(PrivacySetting::Personalization, false, 60),
+            ],
+            400,
+        );
+        assert_eq!(
+            accepted,
+            vec![
+                change(PrivacySetting::Personalization, false, 60),
+                change(PrivacySetting::Acr, false, 200),
+            ]
+        );
+        // the remote Acr change is newer so only the local watch history change is pushed
+        assert_eq!(
+            state.get_pending(),
+            vec![change(PrivacySetting::WatchHistory, false, 300)]
+        );
+
+        // journal survives a restart
+        let reloaded = PrivacySyncState::new(&dir, None, None);
+        assert_eq!(reloaded.get_last_pull(), 400);
+        reloaded.acknowledge(&reloaded.get_pending());
+        assert!(reloaded.get_pending().is_empty());
+        assert!(reloaded
+            .merge_remote(vec![change(PrivacySetting::Acr, true, 150)], 500)
+            .is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_local_versions_do_not_go_back() {
+        let dir = saved_dir("versions");
+        let state = PrivacySyncState::new(&dir, None, None);
+        state.merge_remote(vec![change(PrivacySetting::Acr, true, 1000)], 1200);
+        // the device clock is behind the cloud
+        assert_eq!(state.record_local(PrivacySetting::Acr, false, 900), 1201);
+        assert_eq!(
+            state.record_local(PrivacySetting::WatchHistory, true, 900),
+            1202
+        );
+        assert_eq!(state.record_local(PrivacySetting::Acr, true, 5000), 5000);
+        // a remote change made before the local one does not win
+        assert!(state
+            .merge_remote(vec![change(PrivacySetting::Acr, false, 4000)], 4500)
+            .is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_journal_is_kept_per_profile() {
+        let dir = saved_dir("profiles");
+        let state = PrivacySyncState::new(&dir, None, None);
+        state.record_local(PrivacySetting::Acr, true, 100);
+
+        state.switch_profile(Some("kid"));
+        assert!(state.get_pending().is_empty());
+        state.record_local(PrivacySetting::WatchHistory, false, 200);
+
+        state.switch_profile(None);
+        assert_eq!(
+            state.get_pending(),
+            vec![change(PrivacySetting::Acr, true, 100)]
+        );
+        let kid = PrivacySyncState::new(&dir, Some("kid"), None);
+        assert_eq!(
+            kid.get_pending(),
+            vec![change(PrivacySetting::WatchHistory, false, 200)]
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
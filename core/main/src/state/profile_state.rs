@@ -0,0 +1,192 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ripple_sdk::{
+    api::firebolt::fb_profile::{ProfileList, UserProfile},
+    framework::{file_store::FileStore, store_encryption::StoreKeyProvider},
+    log::{error, warn},
+    utils::error::RippleError,
+    uuid::Uuid,
+};
+use serde::{Deserialize, Serialize};
+
+const PROFILES_DIR_NAME: &str = "profiles";
+const PROFILES_FILE_NAME: &str = "profile_list";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ProfileStore {
+    profiles: Vec<UserProfile>,
+    active: Option<String>,
+}
+
+/// Profiles of the viewers of a shared device along with the active one. Everything stored for
+/// a profile lives under its own directory, see [ProfileState::profile_dir].
+#[derive(Debug, Clone)]
+pub struct ProfileState {
+    store: Arc<RwLock<FileStore<ProfileStore>>>,
+    saved_dir: String,
+}
+
+impl ProfileState {
+    pub fn new(saved_dir: &str, keys: Option<Arc<dyn StoreKeyProvider>>) -> ProfileState {
+        let path = Path::new(saved_dir)
+            .join(PROFILES_DIR_NAME)
+            .join(PROFILES_FILE_NAME);
+        let path = path.to_string_lossy().into_owned();
+        let mut store =
+            FileStore::load_or_new_encrypted(path.clone(), ProfileStore::default(), keys.clone())
+                .unwrap_or_else(|e| {
+                    error!(
+                        "profiles {} are corrupt {:?}, starting without profiles",
+                        path, e
+                    );
+                    FileStore::new_encrypted(path, ProfileStore::default(), keys)
+                });
+        let active_is_known = store
+            .value
+            .active
+            .as_ref()
+            .map_or(true, |id| store.value.profiles.iter().any(|p| p.id.eq(id)));
+        if !active_is_known {
+            warn!("active profile is not known, falling back to the device profile");
+            store.value.active = None;
+            store.sync();
+        }
+        ProfileState {
+            store: Arc::new(RwLock::new(store)),
+            saved_dir: saved_dir.to_owned(),
+        }
+    }
+
+    /// Directory holding everything stored for a profile
+    pub fn profile_dir(saved_dir: &str, profile_id: &str) -> PathBuf {
+        Path::new(saved_dir)
+            .join(PROFILES_DIR_NAME)
+            .join(profile_id)
+    }
+
+    pub fn get_active(&self) -> Option<String> {
+        self.store.read().unwrap().value.active.clone()
+    }
+
+    pub fn list(&self) -> ProfileList {
+        let store = self.store.read().unwrap();
+        ProfileList {
+            active_profile_id: store.value.active.clone(),
+            profiles: store.value.profiles.clone(),
+        }
+    }
+
+    pub fn create(&self, name: String) -> UserProfile {
+        let profile = UserProfile {
+            id: Uuid::new_v4().to_string(),
+            name,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default(),
+        };
+        let mut store = self.store.write().unwrap();
+        store.value.profiles.push(profile.clone());
+        store.sync();
+        profile
+    }
+
+    /// Makes the given profile active and returns the previously active one
+    pub fn set_active(&self, profile_id: Option<String>) -> Result<Option<String>, RippleError> {
+        let mut store = self.store.write().unwrap();
+        if let Some(id) = &profile_id {
+            if !store.value.profiles.iter().any(|p| p.id.eq(id)) {
+                return Err(RippleError::NotAvailable);
+            }
+        }
+        let previous = std::mem::replace(&mut store.value.active, profile_id);
+        store.sync();
+        Ok(previous)
+    }
+
+    /// Deletes a profile which is not active along with everything stored for it
+    pub fn delete(&self, profile_id: &str) -> Result<(), RippleError> {
+        let mut store = self.store.write().unwrap();
+        if store.value.active.as_deref() == Some(profile_id) {
+            return Err(RippleError::InvalidInput);
+        }
+        let count = store.value.profiles.len();
+        store.value.profiles.retain(|p| !p.id.eq(profile_id));
+        if store.value.profiles.len() == count {
+            return Err(RippleError::NotAvailable);
+        }
+        store.sync();
+        let dir = Self::profile_dir(&self.saved_dir, profile_id);
+        if dir.exists() {
+            if let Err(e) = std::fs::remove_dir_all(&dir) {
+                error!("could not remove profile storage {:?}: {:?}", dir, e);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved_dir(name: &str) -> String {
+        let mut path = std::env::temp_dir();
+        path.push(format!("profile_state_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_profile_lifecycle() {
+        let dir = saved_dir("lifecycle");
+        let state = ProfileState::new(&dir, None);
+        let kid = state.create("kid".into());
+        let parent = state.create("parent".into());
+        assert_eq!(state.list().profiles.len(), 2);
+
+        assert!(state.set_active(Some("unknown".into())).is_err());
+        assert_eq!(state.set_active(Some(kid.id.clone())).unwrap(), None);
+        assert!(matches!(
+            state.delete(&kid.id),
+            Err(RippleError::InvalidInput)
+        ));
+
+        let profile_dir = ProfileState::profile_dir(&dir, &parent.id);
+        std::fs::create_dir_all(&profile_dir).unwrap();
+        assert!(state.delete(&parent.id).is_ok());
+        assert!(!profile_dir.exists());
+        assert!(!state.list().profiles.iter().any(|p| p.id == parent.id));
+        assert!(matches!(
+            state.delete(&parent.id),
+            Err(RippleError::NotAvailable)
+        ));
+
+        // active profile is restored on start up
+        let reloaded = ProfileState::new(&dir, None);
+        assert_eq!(reloaded.get_active(), Some(kid.id));
+        assert_eq!(reloaded.list().profiles.len(), 1);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
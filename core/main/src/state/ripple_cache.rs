@@ -14,36 +14,149 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 //
-use std::sync::{Arc, RwLock};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+};
 
-use ripple_sdk::api::{
-    distributor::distributor_privacy::PrivacySettingsData, storage_property::StorageProperty,
+use ripple_sdk::{
+    api::storage_property::StorageProperty,
+    log::trace,
+    serde_json::{self, Value},
 };
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RippleCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
 
+/// Write-through cache of storage property values. Values are kept as they are persisted and
+/// converted to the requested type on read, a value which can not be converted counts as a miss.
 #[derive(Debug, Clone, Default)]
 pub struct RippleCache {
-    // Cache for privacy settings
-    privacy_settings_cache: Arc<RwLock<PrivacySettingsData>>,
-    // Add more caches for other settings as required
+    values: Arc<RwLock<HashMap<StorageProperty, Value>>>,
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
 }
 
 impl RippleCache {
-    pub fn get_cached_bool_storage_property(&self, property: &StorageProperty) -> Option<bool> {
-        if property.is_a_privacy_setting_property() {
-            // check if the privacy setting property is available in cache
-            let cache = self.privacy_settings_cache.read().unwrap();
-            property.get_privacy_setting_value(&cache)
+    pub fn get_cached<T: DeserializeOwned>(&self, property: &StorageProperty) -> Option<T> {
+        let value = self
+            .values
+            .read()
+            .unwrap()
+            .get(property)
+            .and_then(|v| serde_json::from_value(v.clone()).ok());
+        let counter = if value.is_some() {
+            &self.hits
         } else {
-            // We can add caching support for non-privacy setting properties in future
-            None
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+
+    pub fn update_cached<T: Serialize>(&self, property: &StorageProperty, value: &T) {
+        match serde_json::to_value(value) {
+            Ok(value) => {
+                trace!("Updating cache for {:?} with {:?}", property, value);
+                self.values.write().unwrap().insert(property.clone(), value);
+            }
+            Err(_) => self.invalidate(property),
         }
     }
 
-    pub fn update_cached_bool_storage_property(&self, property: &StorageProperty, value: bool) {
-        if property.is_a_privacy_setting_property() {
-            // update the privacy setting property in cache
-            let mut cache = self.privacy_settings_cache.write().unwrap();
-            property.set_privacy_setting_value(&mut cache, value);
+    pub fn invalidate(&self, property: &StorageProperty) {
+        self.values.write().unwrap().remove(property);
+    }
+
+    /// Drops every cached value, used when the values in storage change underneath the cache
+    pub fn clear(&self) {
+        self.values.write().unwrap().clear();
+    }
+
+    pub fn get_stats(&self) -> RippleCacheStats {
+        RippleCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.values.read().unwrap().len(),
         }
     }
+
+    pub fn get_cached_bool_storage_property(&self, property: &StorageProperty) -> Option<bool> {
+        self.get_cached(property)
+    }
+
+    pub fn update_cached_bool_storage_property(&self, property: &StorageProperty, value: bool) {
+        self.update_cached(property, &value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typed_values_and_stats() {
+        let cache = RippleCache::default();
+        assert_eq!(
+            cache.get_cached::<String>(&StorageProperty::ClosedCaptionsFontFamily),
+            None
+        );
+        cache.update_cached(
+            &StorageProperty::ClosedCaptionsFontFamily,
+            &"monospaced_serif".to_owned(),
+        );
+        cache.update_cached(&StorageProperty::ClosedCaptionsFontSize, &1.5f32);
+        cache.update_cached(
+            &StorageProperty::CCPreferredLanguages,
+            &vec!["eng".to_owned()],
+        );
+        cache.update_cached_bool_storage_property(&StorageProperty::AllowWatchHistory, true);
+
+        assert_eq!(
+            cache.get_cached::<String>(&StorageProperty::ClosedCaptionsFontFamily),
+            Some("monospaced_serif".to_owned())
+        );
+        assert_eq!(
+            cache.get_cached::<f32>(&StorageProperty::ClosedCaptionsFontSize),
+            Some(1.5)
+        );
+        assert_eq!(
+            cache.get_cached::<Vec<String>>(&StorageProperty::CCPreferredLanguages),
+            Some(vec!["eng".to_owned()])
+        );
+        assert_eq!(
+            cache.get_cached_bool_storage_property(&StorageProperty::AllowWatchHistory),
+            Some(true)
+        );
+        // a value of another type is a miss
+        assert_eq!(
+            cache.get_cached::<HashMap<String, Value>>(&StorageProperty::ClosedCaptionsFontFamily),
+            None
+        );
+
+        cache.invalidate(&StorageProperty::AllowWatchHistory);
+        assert_eq!(
+            cache.get_cached_bool_storage_property(&StorageProperty::AllowWatchHistory),
+            None
+        );
+        assert_eq!(
+            cache.get_stats(),
+            RippleCacheStats {
+                hits: 4,
+                misses: 3,
+                entries: 3
+            }
+        );
+        cache.clear();
+        assert_eq!(cache.get_stats().entries, 0);
+    }
 }
@@ -64,6 +64,8 @@ pub enum Config {
     LauncherConfig,
     RippleFeatures,
     SavedDir,
+    /// Key provider configuration of the stores encrypted at rest
+    StoreEncryption,
     SupportsDistributorSession,
     Firebolt,
     RFC(String),
@@ -72,6 +72,9 @@ pub struct RippleContext {
     pub time_zone: Option<TimeZone>,
     pub update_type: Option<RippleContextUpdateType>,
     pub features: Vec<String>,
+    /// Id of the active user profile, None when the device is used without profiles
+    #[serde(default)]
+    pub profile_id: Option<String>,
 }
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize, Eq, Hash)]
@@ -82,6 +85,7 @@ pub enum RippleContextUpdateType {
     PowerStateChanged,
     TimeZoneChanged,
     FeaturesChanged,
+    ProfileChanged,
 }
 
 impl RippleContext {
@@ -100,6 +104,7 @@ impl RippleContext {
             time_zone,
             update_type,
             features,
+            profile_id: None,
         }
     }
 
@@ -126,6 +131,9 @@ impl RippleContext {
                 RippleContextUpdateType::TimeZoneChanged => {
                     self.time_zone = context.time_zone.clone()
                 }
+                RippleContextUpdateType::ProfileChanged => {
+                    self.profile_id = context.profile_id.clone()
+                }
             }
         }
     }
@@ -208,6 +216,14 @@ impl RippleContext {
                 }
                 changed
             }
+            RippleContextUpdateRequest::Profile(profile_id) => {
+                if self.profile_id == profile_id {
+                    return false;
+                }
+                self.profile_id = profile_id;
+                self.update_type = Some(RippleContextUpdateType::ProfileChanged);
+                true
+            }
         }
     }
 
@@ -216,6 +232,7 @@ impl RippleContext {
         self.internet_connectivity = context.internet_connectivity;
         self.time_zone = context.time_zone;
         self.features = context.features;
+        self.profile_id = context.profile_id;
     }
 
     pub fn get_event_message(&self) -> ExtnMessage {
@@ -278,6 +295,7 @@ pub enum RippleContextUpdateRequest {
     PowerState(SystemPowerState),
     TimeZone(TimeZone),
     UpdateFeatures(Vec<FeatureUpdate>),
+    Profile(Option<String>),
 }
 
 impl RippleContextUpdateRequest {
@@ -345,6 +363,7 @@ mod tests {
             }),
             update_type: None,
             features: Vec::default(),
+            profile_id: None,
         };
 
         let context2 = RippleContext {
@@ -357,6 +376,7 @@ mod tests {
             }),
             update_type: None,
             features: Vec::default(),
+            profile_id: None,
         };
 
         assert_eq!(
@@ -365,6 +385,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_ripple_context_profile_update() {
+        let mut context = RippleContext::default();
+        assert!(context.update(RippleContextUpdateRequest::Profile(Some("p1".into()))));
+        assert_eq!(context.profile_id, Some("p1".into()));
+        assert_eq!(
+            context.update_type,
+            Some(RippleContextUpdateType::ProfileChanged)
+        );
+        assert!(!context.update(RippleContextUpdateRequest::Profile(Some("p1".into()))));
+
+        let mut other = RippleContext::default();
+        other.update_with_context(&context);
+        assert_eq!(other.profile_id, Some("p1".into()));
+    }
+
     #[test]
     fn test_extn_request_ripple_context_update() {
         let activation_request = RippleContextUpdateRequest::Activation(true);
@@ -387,6 +423,7 @@ mod tests {
             }),
             update_type: None,
             features: Vec::default(),
+            profile_id: None,
         };
 
         let contract_type: RippleContract = RippleContract::RippleContext;
@@ -18,6 +18,7 @@
 use crate::api::firebolt::fb_capabilities::{
     CapabilityRole, DenyReason, FireboltCap, FireboltPermission,
 };
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::{
@@ -104,6 +105,8 @@ pub struct GrantPrivacySetting {
 pub enum GrantScope {
     App,
     Device,
+    /// Applies to every app for the active user profile
+    Profile,
 }
 
 impl Hash for GrantScope {
@@ -111,6 +114,7 @@ impl Hash for GrantScope {
         state.write_u8(match self {
             GrantScope::App => 0,
             GrantScope::Device => 1,
+            GrantScope::Profile => 2,
         });
     }
 }
@@ -143,6 +147,8 @@ pub struct GrantPolicy {
     pub privacy_setting: Option<GrantPrivacySetting>,
     #[serde(default = "default_policy_persistence_type")]
     pub persistence: PolicyPersistenceType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<GrantSchedule>,
 }
 pub fn default_evaluate_at() -> Vec<EvaluateAt> {
     vec![EvaluateAt::Invocation]
@@ -163,6 +169,211 @@ impl Default for GrantPolicy {
             privacy_setting: None,
             persistence: PolicyPersistenceType::Device,
             evaluate_at: vec![EvaluateAt::ActiveSession],
+            schedule: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GrantWeekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl From<Weekday> for GrantWeekday {
+    fn from(day: Weekday) -> Self {
+        match day {
+            Weekday::Mon => GrantWeekday::Monday,
+            Weekday::Tue => GrantWeekday::Tuesday,
+            Weekday::Wed => GrantWeekday::Wednesday,
+            Weekday::Thu => GrantWeekday::Thursday,
+            Weekday::Fri => GrantWeekday::Friday,
+            Weekday::Sat => GrantWeekday::Saturday,
+            Weekday::Sun => GrantWeekday::Sunday,
+        }
+    }
+}
+
+/// Time of day window in the device timezone, `start` and `end` are `HH:MM`. A window whose end
+/// is before its start runs past midnight and belongs to the day it starts on.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+#[serde(rename_all = "camelCase")]
+pub struct GrantTimeWindow {
+    /// Days the window starts on, every day when empty
+    #[serde(default)]
+    pub days: Vec<GrantWeekday>,
+    pub start: String,
+    pub end: String,
+    /// Replaces the options of the policy while the window is active
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options: Option<Vec<GrantRequirements>>,
+}
+
+impl GrantTimeWindow {
+    fn parse_time(time: &str) -> Option<NaiveTime> {
+        NaiveTime::parse_from_str(time, "%H:%M").ok()
+    }
+
+    fn times(&self) -> Option<(NaiveTime, NaiveTime)> {
+        Some((Self::parse_time(&self.start)?, Self::parse_time(&self.end)?))
+    }
+
+    fn starts_on(&self, date: NaiveDate) -> bool {
+        self.days.is_empty() || self.days.contains(&date.weekday().into())
+    }
+
+    /// Start and end of the occurrence of the window starting on `date`
+    fn occurrence(&self, date: NaiveDate) -> Option<(NaiveDateTime, NaiveDateTime)> {
+        let (start, end) = self.times()?;
+        if !self.starts_on(date) {
+            return None;
+        }
+        let end_date = if end <= start { date.succ_opt()? } else { date };
+        Some((date.and_time(start), end_date.and_time(end)))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum OutsideWindows {
+    /// The options of the policy apply
+    #[default]
+    Policy,
+    /// The grant is denied without prompting
+    Deny,
+}
+
+/// Bounds the lifetime of grants stored under the policy
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum GrantExpiry {
+    /// Grants expire every day at the given `HH:MM`
+    DailyAt(String),
+    /// Grants expire at the start of the given `YYYY-MM-DD`
+    On(String),
+}
+
+impl GrantExpiry {
+    fn next(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        match self {
+            GrantExpiry::DailyAt(time) => {
+                let time = GrantTimeWindow::parse_time(time)?;
+                let today = now.date().and_time(time);
+                if today > now {
+                    Some(today)
+                } else {
+                    Some(now.date().succ_opt()?.and_time(time))
+                }
+            }
+            GrantExpiry::On(date) => {
+                let expiry = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                    .ok()?
+                    .and_time(NaiveTime::MIN);
+                (expiry > now).then_some(expiry)
+            }
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        match self {
+            GrantExpiry::DailyAt(time) => GrantTimeWindow::parse_time(time).is_some(),
+            GrantExpiry::On(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok(),
+        }
+    }
+}
+
+/// Time constraints of a grant policy, evaluated in the device timezone
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(test, derive(PartialEq))]
+#[serde(rename_all = "camelCase")]
+pub struct GrantSchedule {
+    #[serde(default)]
+    pub windows: Vec<GrantTimeWindow>,
+    #[serde(default)]
+    pub outside_windows: OutsideWindows,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<GrantExpiry>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub enum ScheduleDecision {
+    /// The grant is decided with the given options, or those of the policy when None. A grant
+    /// stored with this decision has to be evaluated again after `valid_for_secs`, 0 means the
+    /// schedule has expired and the grant must not be stored.
+    Apply {
+        options: Option<Vec<GrantRequirements>>,
+        valid_for_secs: Option<u64>,
+    },
+    Deny,
+}
+
+impl GrantSchedule {
+    pub fn is_valid(&self) -> bool {
+        self.windows.iter().all(|w| w.times().is_some())
+            && self.expires.as_ref().map_or(true, |e| e.is_valid())
+    }
+
+    fn active_window(&self, now: NaiveDateTime) -> Option<&GrantTimeWindow> {
+        let today = now.date();
+        let dates = [today.pred_opt(), Some(today)];
+        self.windows.iter().find(|window| {
+            dates.iter().flatten().any(|date| {
+                window
+                    .occurrence(*date)
+                    .map_or(false, |(start, end)| start <= now && now < end)
+            })
+        })
+    }
+
+    /// Next time a window starts or ends after `now`
+    fn next_boundary(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        let today = now.date();
+        self.windows
+            .iter()
+            .flat_map(|window| {
+                (-1..=7)
+                    .filter_map(move |offset| {
+                        today.checked_add_signed(chrono::Duration::days(offset))
+                    })
+                    .filter_map(|date| window.occurrence(date))
+                    .flat_map(|(start, end)| [start, end])
+            })
+            .filter(|boundary| *boundary > now)
+            .min()
+    }
+
+    /// Decides how the policy applies at the local time `now`
+    pub fn evaluate(&self, now: NaiveDateTime) -> ScheduleDecision {
+        let active = self.active_window(now);
+        if active.is_none()
+            && !self.windows.is_empty()
+            && self.outside_windows == OutsideWindows::Deny
+        {
+            return ScheduleDecision::Deny;
+        }
+        // An expiry date in the past expires grants immediately
+        let expiry = self.expires.as_ref().map(|e| e.next(now).unwrap_or(now));
+        let valid_until = match (self.next_boundary(now), expiry) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        ScheduleDecision::Apply {
+            options: active.and_then(|w| w.options.clone()),
+            valid_for_secs: valid_until.map(|t| {
+                if t <= now {
+                    0
+                } else {
+                    (t - now).num_seconds().max(1) as u64
+                }
+            }),
         }
     }
 }
@@ -202,7 +413,13 @@ impl GrantPolicies {
 impl GrantPolicy {
     pub fn get_steps_without_grant(&self) -> Option<Vec<GrantStep>> {
         let mut grant_steps = Vec::new();
-        for grant_requirements in &self.options {
+        let window_options = self
+            .schedule
+            .iter()
+            .flat_map(|schedule| &schedule.windows)
+            .filter_map(|window| window.options.as_ref())
+            .flatten();
+        for grant_requirements in self.options.iter().chain(window_options) {
             for step in &grant_requirements.steps {
                 if !step
                     .capability
@@ -317,11 +534,63 @@ impl GrantEntry {
                 }
             },
             Some(GrantLifespan::Once) => true,
+            // A ttl bounds app and power scoped grants decided under a schedule
+            Some(GrantLifespan::AppActive) | Some(GrantLifespan::PowerActive) => {
+                self.lifespan_ttl_in_secs.map_or(false, |ttl| {
+                    SystemTime::now()
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .unwrap()
+                        .checked_sub(self.last_modified_time)
+                        .unwrap_or(Duration::from_secs(0))
+                        > Duration::from_secs(ttl)
+                })
+            }
             _ => false,
         }
     }
 }
 
+/// How a user grant was changed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "camelCase")]
+pub enum GrantChangeSource {
+    /// Decision of the user on a grant prompt
+    UserPrompt,
+    /// Applied from a privacy setting by the `AutoApplyPolicy` of the grant policy
+    AutoApply,
+    /// `usergrants.grant`, `usergrants.deny` or `usergrants.clear`
+    UserGrantsApi {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        caller: Option<String>,
+    },
+    /// `ripple.setUserGrants`
+    SetUserGrants {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        caller: Option<String>,
+    },
+    /// Removed because the grant policy of the capability changed
+    PolicySync,
+    /// `ripple.importUserGrants`
+    Import,
+}
+
+/// Record of a change to a user grant, `status` is None when the grant was cleared
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrantHistoryEntry {
+    /// Milliseconds since the epoch
+    pub timestamp: u64,
+    /// None for device scoped grants
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_id: Option<String>,
+    pub capability: String,
+    pub role: CapabilityRole,
+    pub previous: Option<GrantStatus>,
+    pub status: Option<GrantStatus>,
+    #[serde(flatten)]
+    pub change: GrantChangeSource,
+}
+
 #[derive(Debug, Clone)]
 pub enum GrantActiveState {
     ActiveGrant(Result<(), DenyReason>),
@@ -423,6 +692,7 @@ mod tests {
             lifespan_ttl: Some(3600),
             privacy_setting: None,
             persistence: PolicyPersistenceType::Device,
+            schedule: None,
         }
     }
 
@@ -532,6 +802,10 @@ mod tests {
     #[case(Some(GrantLifespan::Seconds), None, 0, true)]
     #[case(Some(GrantLifespan::Seconds), Some(3600), 3610, true)]
     #[case(Some(GrantLifespan::Seconds), Some(3600), 0, false)]
+    #[case(Some(GrantLifespan::AppActive), None, 3610, false)]
+    #[case(Some(GrantLifespan::AppActive), Some(3600), 3610, true)]
+    #[case(Some(GrantLifespan::PowerActive), Some(3600), 0, false)]
+    #[case(Some(GrantLifespan::PowerActive), Some(3600), 3610, true)]
     fn test_has_expired(
         #[case] lifespan: Option<GrantLifespan>,
         #[case] lifespan_ttl_in_secs: Option<u64>,
@@ -583,4 +857,134 @@ mod tests {
 
         assert_eq!(errors.get_reason(&cap), expected_reason);
     }
+
+    #[test]
+    fn test_grant_history_entry_serde() {
+        let entry = GrantHistoryEntry {
+            timestamp: 1700000000000,
+            app_id: Some("app1".to_owned()),
+            capability: "xrn:firebolt:capability:localization:postal-code".to_owned(),
+            role: CapabilityRole::Use,
+            previous: None,
+            status: Some(GrantStatus::Allowed),
+            change: GrantChangeSource::SetUserGrants {
+                caller: Some("cloud".to_owned()),
+            },
+        };
+        let value = serde_json::to_value(&entry).unwrap();
+        assert_eq!(value["appId"], "app1");
+        assert_eq!(value["role"], "use");
+        assert_eq!(value["source"], "setUserGrants");
+        assert_eq!(value["caller"], "cloud");
+        let parsed: GrantHistoryEntry = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed, entry);
+
+        let value = serde_json::json!({
+            "timestamp": 1,
+            "capability": "cap",
+            "role": "manage",
+            "previous": "Allowed",
+            "status": null,
+            "source": "policySync"
+        });
+        let parsed: GrantHistoryEntry = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.app_id, None);
+        assert_eq!(parsed.change, GrantChangeSource::PolicySync);
+    }
+
+    fn at(date: &str, time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_schedule_window_overrides_options() {
+        // 2024-01-05 is a friday
+        let schedule: GrantSchedule = serde_json::from_value(serde_json::json!({
+            "windows": [{
+                "days": ["friday"],
+                "start": "20:00",
+                "end": "06:00",
+                "options": [{"steps": [{"capability": "xrn:firebolt:capability:usergrant:pinchallenge"}]}]
+            }]
+        }))
+        .unwrap();
+        assert!(schedule.is_valid());
+
+        match schedule.evaluate(at("2024-01-05", "19:00")) {
+            ScheduleDecision::Apply {
+                options,
+                valid_for_secs,
+            } => {
+                assert!(options.is_none());
+                assert_eq!(valid_for_secs, Some(3600));
+            }
+            ScheduleDecision::Deny => panic!("outside windows falls back to the policy"),
+        }
+        // window started on friday is still active on saturday morning
+        match schedule.evaluate(at("2024-01-06", "05:30")) {
+            ScheduleDecision::Apply {
+                options,
+                valid_for_secs,
+            } => {
+                assert_eq!(options.unwrap()[0].steps.len(), 1);
+                assert_eq!(valid_for_secs, Some(1800));
+            }
+            ScheduleDecision::Deny => panic!("window is active"),
+        }
+        // no window starts on saturday
+        assert!(matches!(
+            schedule.evaluate(at("2024-01-06", "21:00")),
+            ScheduleDecision::Apply { options: None, .. }
+        ));
+    }
+
+    #[test]
+    fn test_schedule_deny_outside_windows_and_expiry() {
+        let schedule = GrantSchedule {
+            windows: vec![GrantTimeWindow {
+                days: vec![],
+                start: "08:00".to_owned(),
+                end: "20:00".to_owned(),
+                options: None,
+            }],
+            outside_windows: OutsideWindows::Deny,
+            expires: Some(GrantExpiry::DailyAt("12:00".to_owned())),
+        };
+        assert_eq!(
+            schedule.evaluate(at("2024-01-05", "21:00")),
+            ScheduleDecision::Deny
+        );
+        assert_eq!(
+            schedule.evaluate(at("2024-01-05", "11:00")),
+            ScheduleDecision::Apply {
+                options: None,
+                valid_for_secs: Some(3600)
+            }
+        );
+
+        let expiring = GrantSchedule {
+            expires: Some(GrantExpiry::On("2024-02-01".to_owned())),
+            ..Default::default()
+        };
+        assert_eq!(
+            expiring.evaluate(at("2024-01-31", "23:00")),
+            ScheduleDecision::Apply {
+                options: None,
+                valid_for_secs: Some(3600)
+            }
+        );
+        assert_eq!(
+            expiring.evaluate(at("2024-02-02", "00:00")),
+            ScheduleDecision::Apply {
+                options: None,
+                valid_for_secs: Some(0)
+            }
+        );
+
+        let invalid = GrantSchedule {
+            expires: Some(GrantExpiry::DailyAt("25:00".to_owned())),
+            ..Default::default()
+        };
+        assert!(!invalid.is_valid());
+    }
 }
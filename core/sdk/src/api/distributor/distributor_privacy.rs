@@ -215,6 +215,39 @@ pub struct SetPropertyParams {
     pub dist_session: AccountSession,
 }
 
+/// Value of a privacy setting along with the version of the change. Versions are milliseconds
+/// since epoch, raised above every version known to the device when its clock is behind.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PrivacySettingChange {
+    pub setting: PrivacySetting,
+    pub value: bool,
+    pub updated_at: u64,
+}
+
+/// Changes of the settings of a profile, or of the device when `profile_id` is not given
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PushPrivacyChangesParams {
+    pub changes: Vec<PrivacySettingChange>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile_id: Option<String>,
+    pub dist_session: AccountSession,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PullPrivacyChangesParams {
+    pub since: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile_id: Option<String>,
+    pub dist_session: AccountSession,
+}
+
+/// Changes made in the cloud after `since`, `server_time` is used as `since` of the next pull
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct PrivacyChangesResponse {
+    pub changes: Vec<PrivacySettingChange>,
+    pub server_time: u64,
+}
+
 #[derive(Debug, PartialEq, Default, Serialize, Deserialize, Clone)]
 pub struct ExclusionPolicyData {
     pub data_events: Vec<DataEventType>,
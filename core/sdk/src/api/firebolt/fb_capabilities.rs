@@ -22,7 +22,7 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::json;
 
 use super::fb_openrpc::CapabilitySet;
-use crate::api::gateway::rpc_error::RpcError;
+use crate::api::{device::device_user_grants_data::GrantPolicy, gateway::rpc_error::RpcError};
 
 /// There are many types of Firebolt Cap enums
 /// 1. Short: `device:model` becomes = `xrn:firebolt:capability:account:session` its just a handy cap which helps us write less code
@@ -607,6 +607,136 @@ impl CapEvent {
     }
 }
 
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplainAccessRequest {
+    pub app_id: String,
+    pub method: String,
+    /// Whether the app is connected through the secure gateway, which hides the Ripple api surface
+    #[serde(default = "default_explain_secure")]
+    pub secure: bool,
+}
+
+fn default_explain_secure() -> bool {
+    true
+}
+
+/// Stages of the gate, in the order they run
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AccessStage {
+    /// Permissions from the `x-uses` of the method in the OpenRPC spec
+    OpenRpc,
+    /// Capability dependencies configured in the device manifest
+    Dependencies,
+    /// Permissions which are public and not negotiable are cleared
+    NonNegotiable,
+    Supported,
+    Available,
+    /// Permissions of the app
+    Permitted,
+    /// User grants
+    Grants,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum StageDecision {
+    Passed,
+    Denied,
+    /// The stage did not run because an earlier stage decided the outcome or it does not apply
+    Skipped,
+    /// The stage would need a user prompt to decide
+    Pending,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessStageTrace {
+    pub stage: AccessStage,
+    pub decision: StageDecision,
+    pub permissions: Vec<FireboltPermission>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<DenyReason>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl AccessStageTrace {
+    pub fn new(
+        stage: AccessStage,
+        decision: StageDecision,
+        permissions: Vec<FireboltPermission>,
+    ) -> AccessStageTrace {
+        AccessStageTrace {
+            stage,
+            decision,
+            permissions,
+            reason: None,
+            detail: None,
+        }
+    }
+
+    pub fn with_reason(mut self, reason: DenyReason) -> AccessStageTrace {
+        self.reason = Some(reason);
+        self
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<String>) -> AccessStageTrace {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+/// How the user grant of a permission would be decided
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrantTrace {
+    pub permission: FireboltPermission,
+    pub decision: StageDecision,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<DenyReason>,
+    /// Grant policy of the permission, None when the grant is already stored
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy: Option<GrantPolicy>,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "verdict", rename_all = "camelCase")]
+pub enum AccessVerdict {
+    Allowed,
+    Denied {
+        reason: DenyReason,
+        caps: Vec<String>,
+    },
+    /// Access depends on the answer of the user to a grant prompt
+    PendingGrant {
+        caps: Vec<String>,
+    },
+}
+
+impl From<DenyReasonWithCap> for AccessVerdict {
+    fn from(deny: DenyReasonWithCap) -> Self {
+        AccessVerdict::Denied {
+            reason: deny.reason,
+            caps: deny.caps.iter().map(|c| c.as_str()).collect(),
+        }
+    }
+}
+
+/// Trace of the gate for a method called by an app
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessExplanation {
+    pub app_id: String,
+    pub method: String,
+    pub stages: Vec<AccessStageTrace>,
+    pub grants: Vec<GrantTrace>,
+    #[serde(flatten)]
+    pub verdict: AccessVerdict,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
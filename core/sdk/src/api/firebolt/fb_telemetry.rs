@@ -20,7 +20,7 @@ use serde_json::Value;
 use std::collections::HashMap;
 
 use crate::{
-    api::gateway::rpc_gateway_api::CallContext,
+    api::{gateway::rpc_gateway_api::CallContext, observability::extn_metrics::ExtnContractStats},
     extn::extn_client_message::{ExtnEvent, ExtnPayload, ExtnPayloadProvider},
     framework::ripple_contract::RippleContract,
     service::service_client::ServiceClient,
@@ -166,7 +166,17 @@ pub struct FireboltEvent {
     pub result: Value,
 }
 
+/// Periodic report of the requests Main sent to its extensions
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct ExtnStats {
+    pub ripple_session_id: String,
+    pub stats: Vec<ExtnContractStats>,
+}
+
+/// Telemetry sent to the extensions, new payloads can be added so matches outside the SDK need a
+/// wildcard arm
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[non_exhaustive]
 pub enum TelemetryPayload {
     AppLoadStart(AppLoadStart),
     AppLoadStop(AppLoadStop),
@@ -178,6 +188,7 @@ pub enum TelemetryPayload {
     InternalInitialize(InternalInitialize),
     FireboltInteraction(FireboltInteraction), // External Service failures (service, error)
     FireboltEvent(FireboltEvent),
+    ExtnStats(ExtnStats),
 }
 
 impl TelemetryPayload {
@@ -193,6 +204,7 @@ impl TelemetryPayload {
             Self::InternalInitialize(i) => i.ripple_session_id = session_id,
             Self::FireboltInteraction(f) => f.ripple_session_id = session_id,
             Self::FireboltEvent(_) => {}
+            Self::ExtnStats(e) => e.ripple_session_id = session_id,
         }
     }
 }
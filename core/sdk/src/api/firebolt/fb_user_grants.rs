@@ -15,8 +15,19 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    api::{
+        device::device_user_grants_data::{GrantEntry, GrantHistoryEntry},
+        distributor::distributor_privacy::PrivacySettingsData,
+    },
+    framework::store_encryption::ContentSignature,
+    utils::error::RippleError,
+};
+
 use super::fb_capabilities::{CapabilityRole, FireboltCap, FireboltPermission};
 
 #[derive(Debug, Deserialize, Clone)]
@@ -31,6 +42,106 @@ pub struct GetUserGrantsByAppRequest {
     pub app_id: String,
 }
 
+/// Filters grant history, entries of every app and device grants are returned when no filter is
+/// given
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GrantHistoryRequest {
+    pub app_id: Option<String>,
+    pub capability: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GrantHistoryExport {
+    pub version: u32,
+    /// Milliseconds since the epoch
+    pub exported_at: u64,
+    pub entries: Vec<GrantHistoryEntry>,
+}
+
+pub const USER_GRANTS_BUNDLE_VERSION: u32 = 2;
+/// Bundles of this version were exported before profiles and carry no profile grants
+pub const USER_GRANTS_BUNDLE_VERSION_NO_PROFILES: u32 = 1;
+
+/// User grants and privacy settings of a device, used to back up a user's choices or move them
+/// to another device
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserGrantsBundle {
+    pub version: u32,
+    /// Milliseconds since the epoch
+    pub exported_at: u64,
+    pub device_grants: Vec<GrantEntry>,
+    /// Grants of the profile which was active during the export
+    #[serde(default)]
+    pub profile_grants: Vec<GrantEntry>,
+    pub app_grants: BTreeMap<String, Vec<GrantEntry>>,
+    pub privacy_settings: PrivacySettingsData,
+}
+
+/// Layout of bundles of `USER_GRANTS_BUNDLE_VERSION_NO_PROFILES`, their signature covers these
+/// fields only
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UserGrantsBundleNoProfiles<'a> {
+    version: u32,
+    exported_at: u64,
+    device_grants: &'a Vec<GrantEntry>,
+    app_grants: &'a BTreeMap<String, Vec<GrantEntry>>,
+    privacy_settings: &'a PrivacySettingsData,
+}
+
+impl UserGrantsBundle {
+    pub fn is_supported_version(&self) -> bool {
+        self.version == USER_GRANTS_BUNDLE_VERSION
+            || self.version == USER_GRANTS_BUNDLE_VERSION_NO_PROFILES
+    }
+
+    /// Bytes covered by the signature of the bundle
+    pub fn signed_content(&self) -> Result<Vec<u8>, RippleError> {
+        if self.version == USER_GRANTS_BUNDLE_VERSION_NO_PROFILES {
+            serde_json::to_vec(&UserGrantsBundleNoProfiles {
+                version: self.version,
+                exported_at: self.exported_at,
+                device_grants: &self.device_grants,
+                app_grants: &self.app_grants,
+                privacy_settings: &self.privacy_settings,
+            })
+        } else {
+            serde_json::to_vec(self)
+        }
+        .map_err(|_| RippleError::ParseError)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedUserGrantsBundle {
+    pub bundle: UserGrantsBundle,
+    #[serde(flatten)]
+    pub signature: ContentSignature,
+}
+
+/// Grant of an imported bundle which was not applied
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DroppedGrant {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_id: Option<String>,
+    pub capability: String,
+    pub role: CapabilityRole,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UserGrantsImportResult {
+    pub imported: usize,
+    pub dropped: Vec<DroppedGrant>,
+    pub privacy_settings_applied: bool,
+}
+
 #[derive(Debug, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct AppInfo {
@@ -36,16 +36,18 @@ use crate::{
         firebolt::fb_capabilities::FireboltPermission,
         storage_property::StorageProperty,
     },
+    framework::store_encryption::StoreEncryptionConfig,
     utils::error::RippleError,
 };
 
 use super::{
     device_manifest::{
         ApplicationDefaultsConfiguration, ApplicationsConfiguration, CapabilityConfiguration,
-        CaptionStyle, DataGovernanceConfig, DataGovernancePolicy, DataGovernanceSettingTag,
-        DefaultValues, DeviceManifest, DistributionConfiguration, IdSalt, IntentValidation,
-        InternetMonitoringConfiguration, LifecycleConfiguration, PrivacySettingsStorageType,
-        RippleConfiguration, RippleFeatures, VoiceGuidance, WsConfiguration,
+        CaptionStyle, DataGovernanceConfig, DataGovernanceFieldRule, DataGovernancePolicy,
+        DataGovernanceSettingTag, DefaultValues, DeviceManifest, DistributionConfiguration, IdSalt,
+        IntentValidation, InternetMonitoringConfiguration, LifecycleConfiguration,
+        PrivacySettingsStorageType, RippleConfiguration, RippleFeatures, VoiceGuidance,
+        WsConfiguration,
     },
     exclusory::{AppAuthorizationRules, ExclusoryImpl},
     remote_feature::FeatureFlag,
@@ -122,7 +124,11 @@ pub struct CascadedRippleConfiguration {
     pub data_governance: Option<CascadedDataGovernanceConfig>,
     pub partner_exclusion_refresh_timeout: Option<u32>,
     pub metrics_logging_percentage: Option<u32>,
+    pub extn_stats_interval_secs: Option<u64>,
     pub internet_monitoring_configuration: Option<InternetMonitoringConfiguration>,
+    pub store_encryption: Option<StoreEncryptionConfig>,
+    pub bundle_signing: Option<StoreEncryptionConfig>,
+    pub storage_cache_warm_up: Option<Vec<StorageProperty>>,
 }
 
 impl MergeConfig<CascadedRippleConfiguration> for RippleConfiguration {
@@ -194,9 +200,21 @@ impl MergeConfig<CascadedRippleConfiguration> for RippleConfiguration {
         if let Some(cas_metrics_logging_percentage) = cascaded.metrics_logging_percentage {
             self.metrics_logging_percentage = cas_metrics_logging_percentage
         }
+        if let Some(cas_extn_stats_interval_secs) = cascaded.extn_stats_interval_secs {
+            self.extn_stats_interval_secs = cas_extn_stats_interval_secs
+        }
         if let Some(cas_internet_monitering_conf) = cascaded.internet_monitoring_configuration {
             self.internet_monitoring_configuration = cas_internet_monitering_conf;
         }
+        if let Some(cas_store_encryption) = cascaded.store_encryption {
+            self.store_encryption = Some(cas_store_encryption);
+        }
+        if let Some(cas_bundle_signing) = cascaded.bundle_signing {
+            self.bundle_signing = Some(cas_bundle_signing);
+        }
+        if let Some(cas_storage_cache_warm_up) = cascaded.storage_cache_warm_up {
+            self.storage_cache_warm_up = cas_storage_cache_warm_up;
+        }
     }
 }
 
@@ -380,6 +398,7 @@ pub struct CascadedRippleFeatures {
     pub privacy_settings_storage_type: Option<PrivacySettingsStorageType>,
     pub intent_validation: Option<IntentValidation>,
     pub cloud_permissions: Option<bool>,
+    pub privacy_sync_interval_secs: Option<u64>,
 }
 
 impl MergeConfig<CascadedRippleFeatures> for RippleFeatures {
@@ -393,6 +412,9 @@ impl MergeConfig<CascadedRippleFeatures> for RippleFeatures {
         if let Some(cas_cloud_permission) = cascaded.cloud_permissions {
             self.cloud_permissions = cas_cloud_permission
         }
+        if let Some(cas_privacy_sync_interval_secs) = cascaded.privacy_sync_interval_secs {
+            self.privacy_sync_interval_secs = cas_privacy_sync_interval_secs
+        }
     }
 }
 
@@ -895,6 +917,7 @@ impl MergeConfig<CascadedDataGovernanceConfig> for DataGovernanceConfig {
                                 })
                                 .collect(),
                             drop_on_all_tags: cascaded_policy.drop_on_all_tags.unwrap_or_default(),
+                            fields: cascaded_policy.fields.clone().unwrap_or_default(),
                         };
                         self.policies.push(new_policy);
                     }
@@ -911,6 +934,7 @@ pub struct CascadedDataGovernancePolicy {
     pub data_type: Option<DataEventType>,
     pub setting_tags: Option<Vec<CascadedDataGovernanceSettingTag>>,
     pub drop_on_all_tags: Option<bool>,
+    pub fields: Option<Vec<DataGovernanceFieldRule>>,
 }
 
 impl MergeConfig<CascadedDataGovernancePolicy> for DataGovernancePolicy {
@@ -921,6 +945,9 @@ impl MergeConfig<CascadedDataGovernancePolicy> for DataGovernancePolicy {
         if let Some(cas_drop_on_all_tags) = cascaded.drop_on_all_tags {
             self.drop_on_all_tags = cas_drop_on_all_tags;
         }
+        if let Some(cas_fields) = cascaded.fields {
+            self.fields = cas_fields;
+        }
         if let Some(cas_setting_tags) = cascaded.setting_tags {
             for cascaded_tag in cas_setting_tags {
                 // Try to find a matching existing tag based on the setting
@@ -1217,7 +1244,8 @@ mod tests {
                 privacy_settings_storage_type: PrivacySettingsStorageType::Local,
                 intent_validation: IntentValidation::Fail,
                 cloud_permissions: true,
-                thunder_plugin_status_check_at_broker_start_up: true
+                thunder_plugin_status_check_at_broker_start_up: true,
+                privacy_sync_interval_secs: 900
             }
         );
     }
@@ -14,7 +14,10 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 //
-use super::extn_manifest::{ExtnManifest, ExtnManifestEntry, ExtnResolutionEntry, ExtnSymbol};
+use super::extn_manifest::{
+    ExtnManifest, ExtnManifestEntry, ExtnResolutionEntry, ExtnRestartPolicy, ExtnRuntime,
+    ExtnSymbol, ServiceManifestEntry,
+};
 use super::MergeConfig;
 use crate::utils::error::RippleError;
 use log::{info, warn};
@@ -35,6 +38,8 @@ pub struct CascadedExtnManifest {
     pub rules_path: Option<Vec<String>>,
     pub extn_sdks: Option<Vec<String>>,
     pub provider_registrations: Option<Vec<String>>,
+    pub services: Option<Vec<ServiceManifestEntry>>,
+    pub service_calls: Option<HashMap<String, Vec<String>>>,
 }
 impl MergeConfig<CascadedExtnManifest> for ExtnManifest {
     fn merge_config(&mut self, cascaded: CascadedExtnManifest) {
@@ -88,6 +93,20 @@ impl MergeConfig<CascadedExtnManifest> for ExtnManifest {
             self.provider_registrations.sort();
             self.provider_registrations.dedup();
         }
+        if let Some(cas_services) = cascaded.services {
+            for service in cas_services {
+                self.services.retain(|s| s.id != service.id);
+                self.services.push(service);
+            }
+        }
+        if let Some(cas_service_calls) = cascaded.service_calls {
+            for (key, values) in cas_service_calls {
+                let calls = self.service_calls.entry(key).or_default();
+                calls.extend(values);
+                calls.sort();
+                calls.dedup();
+            }
+        }
     }
 }
 
@@ -138,6 +157,8 @@ impl ExtnManifestEntry {
                 })
                 .collect(),
             resolution: cascaded.resolution,
+            restart: cascaded.restart.unwrap_or_default(),
+            runtime: cascaded.runtime.unwrap_or_default(),
         })
     }
 }
@@ -147,6 +168,8 @@ pub struct CascadedExtnManifestEntry {
     pub path: Option<String>,
     pub symbols: Option<Vec<CascadedExtnSymbol>>,
     pub resolution: Option<Vec<ExtnResolutionEntry>>,
+    pub restart: Option<ExtnRestartPolicy>,
+    pub runtime: Option<ExtnRuntime>,
 }
 
 impl MergeConfig<CascadedExtnManifestEntry> for ExtnManifestEntry {
@@ -198,6 +221,12 @@ impl MergeConfig<CascadedExtnManifestEntry> for ExtnManifestEntry {
                 }
             }
         }
+        if let Some(restart) = cascaded.restart {
+            self.restart = restart;
+        }
+        if let Some(runtime) = cascaded.runtime {
+            self.runtime = runtime;
+        }
     }
 }
 
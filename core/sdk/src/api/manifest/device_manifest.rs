@@ -31,12 +31,14 @@ use crate::{
         firebolt::fb_capabilities::FireboltPermission,
         storage_property::StorageProperty,
     },
+    framework::store_encryption::StoreEncryptionConfig,
     utils::error::RippleError,
 };
 
 use super::{apps::AppManifest, exclusory::ExclusoryImpl};
 pub const PARTNER_EXCLUSION_REFRESH_TIMEOUT: u32 = 12 * 60 * 60; // 12 hours
 pub const METRICS_LOGGING_PERCENTAGE_DEFAULT: u32 = 10;
+pub const EXTN_STATS_INTERVAL_SECS_DEFAULT: u64 = 300;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RippleConfiguration {
@@ -68,8 +70,21 @@ pub struct RippleConfiguration {
     pub partner_exclusion_refresh_timeout: u32,
     #[serde(default = "metrics_logging_percentage_default")]
     pub metrics_logging_percentage: u32,
+    /// Interval of the extension request stats in the operational metrics, 0 disables them
+    #[serde(default = "extn_stats_interval_secs_default")]
+    pub extn_stats_interval_secs: u64,
     #[serde(default)]
     pub internet_monitoring_configuration: InternetMonitoringConfiguration,
+    /// Key provider used to encrypt user grants at rest, stores are plaintext when not set
+    #[serde(default)]
+    pub store_encryption: Option<StoreEncryptionConfig>,
+    /// Provisioned key provider used to sign user grant bundles, bundles can be imported on
+    /// any device sharing its keys
+    #[serde(default)]
+    pub bundle_signing: Option<StoreEncryptionConfig>,
+    /// Storage properties loaded into the cache at start up
+    #[serde(default)]
+    pub storage_cache_warm_up: Vec<StorageProperty>,
 }
 
 fn partner_exclusion_refresh_timeout_default() -> u32 {
@@ -80,6 +95,10 @@ fn metrics_logging_percentage_default() -> u32 {
     METRICS_LOGGING_PERCENTAGE_DEFAULT
 }
 
+fn extn_stats_interval_secs_default() -> u64 {
+    EXTN_STATS_INTERVAL_SECS_DEFAULT
+}
+
 pub fn log_signal_default_level() -> String {
     "OFF".to_string()
 }
@@ -379,8 +398,11 @@ fn country_postal_code_default() -> HashMap<String, String> {
     HashMap::default()
 }
 
+/// No data is governed unless the manifest has a `data_governance` entry with its policies
 pub fn data_governance_default() -> DataGovernanceConfig {
-    DataGovernanceConfig { policies: vec![] }
+    DataGovernanceConfig {
+        policies: Vec::new(),
+    }
 }
 
 #[derive(Deserialize, Debug, Clone, Default)]
@@ -548,6 +570,8 @@ pub struct RippleFeatures {
     pub cloud_permissions: bool,
     #[serde(default = "default_thunder_plugin_status_check_at_broker_start_up")]
     pub thunder_plugin_status_check_at_broker_start_up: bool,
+    #[serde(default = "default_privacy_sync_interval_secs")]
+    pub privacy_sync_interval_secs: u64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -583,6 +607,7 @@ impl Default for RippleFeatures {
             cloud_permissions: default_cloud_permissions(),
             thunder_plugin_status_check_at_broker_start_up:
                 default_thunder_plugin_status_check_at_broker_start_up(),
+            privacy_sync_interval_secs: default_privacy_sync_interval_secs(),
         }
     }
 }
@@ -595,6 +620,10 @@ fn default_privacy_settings_storage_type() -> PrivacySettingsStorageType {
     PrivacySettingsStorageType::Local
 }
 
+fn default_privacy_sync_interval_secs() -> u64 {
+    900
+}
+
 fn default_cloud_permissions() -> bool {
     true
 }
@@ -617,6 +646,8 @@ pub struct DataGovernancePolicy {
     pub setting_tags: Vec<DataGovernanceSettingTag>,
     #[serde(default = "default_drop_on_all_tags")]
     pub drop_on_all_tags: bool,
+    #[serde(default)]
+    pub fields: Vec<DataGovernanceFieldRule>,
 }
 
 impl DataGovernancePolicy {
@@ -629,10 +660,33 @@ impl DataGovernancePolicy {
             data_type,
             setting_tags,
             drop_on_all_tags,
+            fields: Vec::new(),
         }
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum DataGovernanceAction {
+    /// Removes the field from the payload
+    #[default]
+    Drop,
+    /// Keeps the field with a null value
+    Redact,
+}
+
+/// Field of an outgoing payload governed by a privacy setting. `field` is a dot separated
+/// path into the payload, the field is suppressed when the setting has the `enforcement_value`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DataGovernanceFieldRule {
+    pub field: String,
+    pub setting: StorageProperty,
+    #[serde(default = "default_enforcement_value")]
+    pub enforcement_value: bool,
+    #[serde(default)]
+    pub action: DataGovernanceAction,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DataGovernanceSettingTag {
     pub setting: StorageProperty,
@@ -683,8 +737,12 @@ impl Default for RippleConfiguration {
             data_governance: data_governance_default(),
             partner_exclusion_refresh_timeout: partner_exclusion_refresh_timeout_default(),
             metrics_logging_percentage: metrics_logging_percentage_default(),
+            extn_stats_interval_secs: extn_stats_interval_secs_default(),
             internet_monitoring_configuration: Default::default(),
             log_signal_log_level: log_signal_default_level(),
+            store_encryption: None,
+            bundle_signing: None,
+            storage_cache_warm_up: Vec::new(),
         }
     }
 }
@@ -802,6 +860,18 @@ impl DeviceManifest {
             .internet_monitoring_configuration
             .default_monitoring_interval_seconds
     }
+
+    pub fn get_extn_stats_interval(&self) -> u64 {
+        self.configuration.extn_stats_interval_secs
+    }
+
+    pub fn get_store_encryption(&self) -> Option<StoreEncryptionConfig> {
+        self.configuration.store_encryption.clone()
+    }
+
+    pub fn get_bundle_signing(&self) -> Option<StoreEncryptionConfig> {
+        self.configuration.bundle_signing.clone()
+    }
 }
 
 #[cfg(test)]
@@ -898,6 +968,7 @@ pub(crate) mod tests {
                         intent_validation: IntentValidation::Fail,
                         cloud_permissions: true,
                         thunder_plugin_status_check_at_broker_start_up: true,
+                        privacy_sync_interval_secs: 900,
                     },
                     internal_app_id: Some("test".to_string()),
                     saved_dir: "/opt/persistent/ripple".to_string(),
@@ -906,9 +977,13 @@ pub(crate) mod tests {
                     },
                     partner_exclusion_refresh_timeout: 43200,
                     metrics_logging_percentage: 10,
+                    extn_stats_interval_secs: 300,
                     internet_monitoring_configuration: InternetMonitoringConfiguration {
                         default_monitoring_interval_seconds: 180,
                     },
+                    store_encryption: None,
+                    bundle_signing: None,
+                    storage_cache_warm_up: Vec::new(),
                 },
                 capabilities: CapabilityConfiguration {
                     supported: vec!["main[manage]".to_string(), "test".to_string()],
@@ -1058,7 +1133,8 @@ pub(crate) mod tests {
                 privacy_settings_storage_type: PrivacySettingsStorageType::Local,
                 intent_validation: IntentValidation::Fail,
                 cloud_permissions: true,
-                thunder_plugin_status_check_at_broker_start_up: true
+                thunder_plugin_status_check_at_broker_start_up: true,
+                privacy_sync_interval_secs: 900
             }
         );
     }
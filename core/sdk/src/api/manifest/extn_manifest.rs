@@ -18,7 +18,11 @@
 use log::{info, warn};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::{fs, path::Path};
+use std::{
+    fs,
+    path::{Component, Path},
+    time::Duration,
+};
 
 use crate::{extn::extn_id::ExtnId, utils::error::RippleError};
 
@@ -41,6 +45,12 @@ pub struct ExtnManifest {
     pub extn_sdks: Vec<String>,
     #[serde(default = "default_providers")]
     pub provider_registrations: Vec<String>,
+    #[serde(default)]
+    pub services: Vec<ServiceManifestEntry>,
+    /// Methods of other services which a service is allowed to call, by service id. An entry
+    /// ending with `.` allows all the methods of a module.
+    #[serde(default)]
+    pub service_calls: HashMap<String, Vec<String>>,
 }
 
 /// Some unit tests which use defaults are failing because we need default providers for unit testing
@@ -57,6 +67,8 @@ impl Default for ExtnManifest {
             rules_path: Vec::new(),
             extn_sdks: Vec::new(),
             provider_registrations: default_providers(),
+            services: Vec::new(),
+            service_calls: HashMap::new(),
         }
     }
 }
@@ -91,6 +103,151 @@ pub struct ExtnManifestEntry {
     pub path: String,
     pub symbols: Vec<ExtnSymbol>,
     pub resolution: Option<Vec<ExtnResolutionEntry>>,
+    #[serde(default)]
+    pub restart: ExtnRestartPolicy,
+    #[serde(default)]
+    pub runtime: ExtnRuntime,
+}
+
+/// How the library of an extension is run
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(test, derive(PartialEq))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExtnRuntime {
+    /// Shared library loaded into Main
+    #[default]
+    Native,
+    /// WebAssembly module run in a sandbox within Main
+    Wasm(WasmLimits),
+}
+
+/// Resources given to a WebAssembly extension, a module going over them is stopped and
+/// restarted as configured by the `restart` policy
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct WasmLimits {
+    /// Upper bound of the linear memory of the module
+    #[serde(default = "default_wasm_max_memory_bytes")]
+    pub max_memory_bytes: usize,
+    /// Fuel given to every call into the module, roughly one unit per instruction
+    #[serde(default = "default_wasm_fuel_per_call")]
+    pub fuel_per_call: u64,
+}
+
+fn default_wasm_max_memory_bytes() -> usize {
+    16 * 1024 * 1024
+}
+
+fn default_wasm_fuel_per_call() -> u64 {
+    10_000_000
+}
+
+impl Default for WasmLimits {
+    fn default() -> Self {
+        WasmLimits {
+            max_memory_bytes: default_wasm_max_memory_bytes(),
+            fuel_per_call: default_wasm_fuel_per_call(),
+        }
+    }
+}
+
+/// When a supervised extension is started again after its thread stopped
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtnRestartMode {
+    Never,
+    #[default]
+    OnFailure,
+    Always,
+}
+
+/// Restart policy of an extension library. The backoff doubles with every restart.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct ExtnRestartPolicy {
+    #[serde(default)]
+    pub mode: ExtnRestartMode,
+    #[serde(default = "default_max_restarts")]
+    pub max_restarts: u32,
+    #[serde(default = "default_restart_backoff_ms")]
+    pub backoff_ms: u64,
+    /// Restarts are counted again from 0 once the extension ran this long before stopping
+    #[serde(default = "default_healthy_uptime_ms")]
+    pub healthy_uptime_ms: u64,
+}
+
+impl ExtnRestartPolicy {
+    /// Returns the backoff before the next start or None when it stays down. `failed` tells
+    /// whether it stopped on a failure rather than a clean exit.
+    pub fn next_restart(&self, failed: bool, restarts: u32) -> Option<Duration> {
+        let restart = match self.mode {
+            ExtnRestartMode::Never => false,
+            ExtnRestartMode::OnFailure => failed,
+            ExtnRestartMode::Always => true,
+        };
+        if !restart || restarts >= self.max_restarts {
+            return None;
+        }
+        let factor = 2u64.saturating_pow(restarts);
+        Some(Duration::from_millis(
+            self.backoff_ms.saturating_mul(factor),
+        ))
+    }
+
+    /// Returns the restarts to count for a stop after running for `uptime`, an occasional crash
+    /// of a long running extension does not use up its restarts.
+    pub fn restarts_after(&self, restarts: u32, uptime: Duration) -> u32 {
+        if uptime >= Duration::from_millis(self.healthy_uptime_ms) {
+            0
+        } else {
+            restarts
+        }
+    }
+}
+
+/// Service executable started and supervised by Main. The service connects back to Main with
+/// its `id` as the `service_handshake`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct ServiceManifestEntry {
+    pub id: String,
+    pub executable: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub restart: ExtnRestartPolicy,
+    /// A service which did not connect within this time is restarted as failed
+    #[serde(default = "default_connect_timeout_ms")]
+    pub connect_timeout_ms: u64,
+}
+
+fn default_connect_timeout_ms() -> u64 {
+    10000
+}
+
+fn default_max_restarts() -> u32 {
+    3
+}
+
+fn default_restart_backoff_ms() -> u64 {
+    1000
+}
+
+fn default_healthy_uptime_ms() -> u64 {
+    60000
+}
+
+impl Default for ExtnRestartPolicy {
+    fn default() -> Self {
+        ExtnRestartPolicy {
+            mode: ExtnRestartMode::default(),
+            max_restarts: default_max_restarts(),
+            backoff_ms: default_restart_backoff_ms(),
+            healthy_uptime_ms: default_healthy_uptime_ms(),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -168,6 +325,27 @@ impl ExtnManifest {
         }
     }
 
+    /// True when a library at `path` may be loaded at runtime, it has to be an entry of the
+    /// manifest or resolve under `default_path`
+    pub fn allows_extn_path(&self, path: &str) -> bool {
+        if self.extns.iter().any(|e| e.path == path) {
+            return true;
+        }
+        let path = Path::new(path);
+        let relative = if path.is_absolute() {
+            match path.strip_prefix(&self.default_path) {
+                Ok(relative) => relative,
+                Err(_) => return false,
+            }
+        } else {
+            path
+        };
+        relative.components().next().is_some()
+            && relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)))
+    }
+
     pub fn get_launcher_capability(&self) -> Option<ExtnId> {
         for extn in self.extns.clone() {
             for symbol in extn.symbols {
@@ -208,6 +386,14 @@ impl ExtnManifest {
         self.timeout.unwrap_or(10000)
     }
 
+    pub fn is_service_call_allowed(&self, service_id: &str, method: &str) -> bool {
+        self.service_calls.get(service_id).map_or(false, |allowed| {
+            allowed
+                .iter()
+                .any(|a| a == method || (a.ends_with('.') && method.starts_with(a.as_str())))
+        })
+    }
+
     pub fn has_rpc_override_method(&self, method: &str) -> Option<String> {
         self.rpc_overrides.get(method).cloned()
     }
@@ -249,6 +435,8 @@ pub(crate) mod tests {
                 rules_path: Vec::new(),
                 extn_sdks: Vec::new(),
                 provider_registrations: Vec::new(),
+                services: Vec::new(),
+                service_calls: HashMap::new(),
             }
         }
     }
@@ -259,6 +447,8 @@ pub(crate) mod tests {
             path: "/absolute/path".to_string(),
             symbols: vec![],
             resolution: None,
+            restart: ExtnRestartPolicy::default(),
+            runtime: ExtnRuntime::default(),
         };
 
         assert_eq!(
@@ -273,6 +463,26 @@ pub(crate) mod tests {
         );
     }
 
+    #[test]
+    fn test_allows_extn_path() {
+        let mut manifest = ExtnManifest::mock();
+        manifest.default_path = "/usr/lib/rust/".to_owned();
+        manifest.extns.push(ExtnManifestEntry {
+            path: "/opt/extns/libthunder.so".to_owned(),
+            symbols: vec![],
+            resolution: None,
+            restart: ExtnRestartPolicy::default(),
+            runtime: ExtnRuntime::default(),
+        });
+        assert!(manifest.allows_extn_path("/opt/extns/libthunder.so"));
+        assert!(manifest.allows_extn_path("libdistributor"));
+        assert!(manifest.allows_extn_path("/usr/lib/rust/libdistributor.so"));
+        assert!(!manifest.allows_extn_path("/tmp/libother.so"));
+        assert!(!manifest.allows_extn_path("../../tmp/libother.so"));
+        assert!(!manifest.allows_extn_path("/usr/lib/rust/../../../tmp/libother.so"));
+        assert!(!manifest.allows_extn_path(""));
+    }
+
     #[test]
     fn test_get_symbol() {
         let dist_channel = ExtnId::new_channel(ExtnClassId::Distributor, "test".into()).to_string();
@@ -286,6 +496,8 @@ pub(crate) mod tests {
             path: "relative/path".to_string(),
             symbols: vec![symbol.clone()],
             resolution: None,
+            restart: ExtnRestartPolicy::default(),
+            runtime: ExtnRuntime::default(),
         };
         assert_eq!(
             extn_manifest_entry.get_symbol(ExtnId::try_from(dist_channel).unwrap()),
@@ -314,6 +526,112 @@ pub(crate) mod tests {
         );
     }
 
+    #[test]
+    fn test_restart_policy_defaults() {
+        let entry: ExtnManifestEntry =
+            serde_json::from_str(r#"{"path": "libthunder", "symbols": [], "resolution": null}"#)
+                .unwrap();
+        assert_eq!(entry.restart, ExtnRestartPolicy::default());
+
+        let entry: ExtnManifestEntry = serde_json::from_str(
+            r#"{"path": "libthunder", "symbols": [], "resolution": null,
+                "restart": {"mode": "always"}}"#,
+        )
+        .unwrap();
+        assert_eq!(entry.restart.mode, ExtnRestartMode::Always);
+        assert_eq!(entry.restart.max_restarts, 3);
+        assert_eq!(entry.restart.healthy_uptime_ms, 60000);
+    }
+
+    #[test]
+    fn test_restarts_reset_after_healthy_uptime() {
+        let policy = ExtnRestartPolicy {
+            healthy_uptime_ms: 100,
+            ..Default::default()
+        };
+        assert_eq!(policy.restarts_after(3, Duration::from_millis(99)), 3);
+        assert_eq!(policy.restarts_after(3, Duration::from_millis(100)), 0);
+        let restarts = policy.restarts_after(3, Duration::from_secs(1));
+        assert!(policy.next_restart(true, restarts).is_some());
+    }
+
+    #[test]
+    fn test_runtime() {
+        let entry: ExtnManifestEntry =
+            serde_json::from_str(r#"{"path": "libthunder", "symbols": [], "resolution": null}"#)
+                .unwrap();
+        assert_eq!(entry.runtime, ExtnRuntime::Native);
+
+        let entry: ExtnManifestEntry = serde_json::from_str(
+            r#"{"path": "tm_wasm_extn.wasm", "symbols": [], "resolution": null,
+                "runtime": {"type": "wasm", "fuel_per_call": 1000}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            entry.runtime,
+            ExtnRuntime::Wasm(WasmLimits {
+                max_memory_bytes: 16 * 1024 * 1024,
+                fuel_per_call: 1000,
+            })
+        );
+    }
+
+    #[test]
+    fn test_load_services() {
+        let contents = r#"
+            {
+                "default_path": "",
+                "default_extension": "",
+                "extns": [],
+                "required_contracts": [],
+                "rpc_aliases": {},
+                "timeout": null,
+                "services": [
+                    {
+                        "id": "ripple:channel:distributor:eos",
+                        "executable": "/usr/bin/eos",
+                        "args": ["--verbose"],
+                        "restart": {"max_restarts": 1, "backoff_ms": 10}
+                    }
+                ]
+            }
+        "#;
+        let (_, manifest) = ExtnManifest::load_from_content(contents.to_string()).unwrap();
+        let service = &manifest.services[0];
+        assert_eq!(service.args, vec!["--verbose"]);
+        assert!(service.env.is_empty());
+        assert_eq!(service.connect_timeout_ms, 10000);
+        assert_eq!(
+            service.restart.next_restart(true, 0),
+            Some(Duration::from_millis(10))
+        );
+        assert_eq!(service.restart.next_restart(true, 1), None);
+        assert_eq!(service.restart.next_restart(false, 0), None);
+    }
+
+    #[test]
+    fn test_service_calls() {
+        let contents = r#"
+            {
+                "default_path": "",
+                "default_extension": "",
+                "extns": [],
+                "required_contracts": [],
+                "rpc_aliases": {},
+                "timeout": null,
+                "service_calls": {
+                    "ripple:channel:gateway:badger": ["Device.name", "Localization."]
+                }
+            }
+        "#;
+        let (_, manifest) = ExtnManifest::load_from_content(contents.to_string()).unwrap();
+        let badger = "ripple:channel:gateway:badger";
+        assert!(manifest.is_service_call_allowed(badger, "Device.name"));
+        assert!(!manifest.is_service_call_allowed(badger, "Device.model"));
+        assert!(manifest.is_service_call_allowed(badger, "Localization.countryCode"));
+        assert!(!manifest.is_service_call_allowed("ripple:channel:distributor:eos", "Device.name"));
+    }
+
     #[test]
     fn test_load_from_content_invalid() {
         let contents = "invalid_json";
@@ -381,6 +699,8 @@ pub(crate) mod tests {
             path: "relative/path".to_string(),
             symbols: vec![symbol],
             resolution: None,
+            restart: ExtnRestartPolicy::default(),
+            runtime: ExtnRuntime::default(),
         };
         manifest.extns = vec![extn_manifest_entry];
 
@@ -405,6 +725,8 @@ pub(crate) mod tests {
             path: "relative/path".to_string(),
             symbols: vec![symbol],
             resolution: None,
+            restart: ExtnRestartPolicy::default(),
+            runtime: ExtnRuntime::default(),
         };
 
         manifest.extns = vec![extn_manifest_entry];
@@ -429,6 +751,8 @@ pub(crate) mod tests {
             path: "relative/path".to_string(),
             symbols: vec![symbol],
             resolution: None,
+            restart: ExtnRestartPolicy::default(),
+            runtime: ExtnRuntime::default(),
         };
 
         manifest.extns = vec![extn_manifest_entry];
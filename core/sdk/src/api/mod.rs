@@ -58,6 +58,7 @@ pub mod firebolt {
     pub mod fb_openrpc;
     pub mod fb_parameters;
     pub mod fb_pin;
+    pub mod fb_profile;
     pub mod fb_secondscreen;
     pub mod fb_telemetry;
     pub mod fb_user_grants;
@@ -65,6 +66,7 @@ pub mod firebolt {
 }
 
 pub mod observability {
+    pub mod extn_metrics;
     pub mod log_signal;
     pub mod metrics_util;
     pub mod operational_metrics;
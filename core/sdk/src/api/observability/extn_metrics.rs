@@ -0,0 +1,246 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Instant,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Upper bounds in milliseconds of the latency buckets, the last bucket counts everything slower
+pub const LATENCY_BUCKETS_MS: [u64; 10] = [5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000];
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyHistogram {
+    /// Count per bucket of [LATENCY_BUCKETS_MS] followed by the count above the last bound
+    pub buckets: Vec<u64>,
+    pub count: u64,
+    pub sum_ms: u64,
+    pub max_ms: u64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: vec![0; LATENCY_BUCKETS_MS.len() + 1],
+            count: 0,
+            sum_ms: 0,
+            max_ms: 0,
+        }
+    }
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, latency_ms: u64) {
+        let index = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| latency_ms <= *bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.buckets[index] += 1;
+        self.count += 1;
+        self.sum_ms += latency_ms;
+        self.max_ms = self.max_ms.max(latency_ms);
+    }
+
+    /// Upper bound of the bucket holding the given percentile, None without samples or when it
+    /// falls above the last bound
+    pub fn percentile_ms(&self, percentile: f64) -> Option<u64> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((self.count as f64) * percentile / 100.0).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (index, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return LATENCY_BUCKETS_MS.get(index).cloned();
+            }
+        }
+        None
+    }
+}
+
+/// Counters and latency of the requests for one contract sent to one target
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtnContractStats {
+    pub contract: String,
+    /// Id of the extension fulfilling the contract or `main`
+    pub target: String,
+    pub requests: u64,
+    pub errors: u64,
+    /// Requests which did not get a response in time or were abandoned by the caller
+    pub timeouts: u64,
+    pub in_flight: u64,
+    pub latency: LatencyHistogram,
+}
+
+/// How a request measured by [ExtnMetrics] ended
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ExtnRequestOutcome {
+    Success,
+    Error,
+    Timeout,
+}
+
+#[derive(Clone, Debug)]
+struct PendingRequest {
+    contract: String,
+    target: String,
+    start: Instant,
+}
+
+/// Per contract and target request metrics of an `ExtnClient`
+#[derive(Clone, Debug, Default)]
+pub struct ExtnMetrics {
+    stats: Arc<RwLock<HashMap<(String, String), ExtnContractStats>>>,
+    // requests waiting for a response by request id
+    pending: Arc<RwLock<HashMap<String, PendingRequest>>>,
+}
+
+impl ExtnMetrics {
+    fn update(&self, contract: &str, target: &str, f: impl FnOnce(&mut ExtnContractStats)) {
+        let mut stats = self.stats.write().unwrap();
+        let entry = stats
+            .entry((contract.to_owned(), target.to_owned()))
+            .or_insert_with(|| ExtnContractStats {
+                contract: contract.to_owned(),
+                target: target.to_owned(),
+                ..Default::default()
+            });
+        f(entry)
+    }
+
+    /// Counts the request with the given id and keeps it in flight until it is finished
+    pub fn start(&self, id: &str, contract: &str, target: &str) {
+        let pending = PendingRequest {
+            contract: contract.to_owned(),
+            target: target.to_owned(),
+            start: Instant::now(),
+        };
+        if self
+            .pending
+            .write()
+            .unwrap()
+            .insert(id.to_owned(), pending)
+            .is_some()
+        {
+            // already counted when it passed through an earlier send
+            return;
+        }
+        self.update(contract, target, |s| {
+            s.requests += 1;
+            s.in_flight += 1;
+        });
+    }
+
+    /// Counts a request which does not expect a response
+    pub fn record_transient(&self, contract: &str, target: &str, sent: bool) {
+        self.update(contract, target, |s| {
+            s.requests += 1;
+            if !sent {
+                s.errors += 1;
+            }
+        });
+    }
+
+    /// Ends the request with the given id, nothing is recorded for a request which was not
+    /// started or already ended
+    pub fn finish(&self, id: &str, outcome: ExtnRequestOutcome) {
+        let Some(pending) = self.pending.write().unwrap().remove(id) else {
+            return;
+        };
+        let latency_ms = pending.start.elapsed().as_millis() as u64;
+        self.update(&pending.contract, &pending.target, |s| {
+            s.in_flight = s.in_flight.saturating_sub(1);
+            match outcome {
+                ExtnRequestOutcome::Success => s.latency.record(latency_ms),
+                ExtnRequestOutcome::Error => {
+                    s.errors += 1;
+                    s.latency.record(latency_ms);
+                }
+                ExtnRequestOutcome::Timeout => s.timeouts += 1,
+            }
+        });
+    }
+
+    /// Snapshot of the stats sorted by contract and target
+    pub fn get_stats(&self) -> Vec<ExtnContractStats> {
+        let mut stats: Vec<ExtnContractStats> =
+            self.stats.read().unwrap().values().cloned().collect();
+        stats.sort_by(|a, b| (&a.contract, &a.target).cmp(&(&b.contract, &b.target)));
+        stats
+    }
+
+    pub fn clear(&self) {
+        self.stats.write().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_histogram() {
+        let mut histogram = LatencyHistogram::default();
+        assert_eq!(histogram.percentile_ms(50.0), None);
+        for latency in [1, 3, 20, 20, 40, 90, 400, 10000] {
+            histogram.record(latency);
+        }
+        assert_eq!(histogram.count, 8);
+        assert_eq!(histogram.max_ms, 10000);
+        assert_eq!(histogram.buckets[0], 2);
+        assert_eq!(histogram.buckets[2], 2);
+        assert_eq!(histogram.buckets[LATENCY_BUCKETS_MS.len()], 1);
+        assert_eq!(histogram.percentile_ms(50.0), Some(25));
+        assert_eq!(histogram.percentile_ms(87.5), Some(500));
+        assert_eq!(histogram.percentile_ms(100.0), None);
+    }
+
+    #[test]
+    fn test_extn_metrics() {
+        let metrics = ExtnMetrics::default();
+        metrics.start("1", "device_info", "ripple:extn:device:thunder");
+        assert_eq!(metrics.get_stats()[0].in_flight, 1);
+        metrics.finish("1", ExtnRequestOutcome::Success);
+        // a request is counted and ended once
+        metrics.start("2", "device_info", "ripple:extn:device:thunder");
+        metrics.start("2", "device_info", "ripple:extn:device:thunder");
+        metrics.finish("2", ExtnRequestOutcome::Error);
+        metrics.finish("2", ExtnRequestOutcome::Success);
+        metrics.start("3", "device_info", "ripple:extn:device:thunder");
+        metrics.finish("3", ExtnRequestOutcome::Timeout);
+        metrics.finish("unknown", ExtnRequestOutcome::Success);
+        metrics.record_transient("account.session", "main", false);
+
+        let stats = metrics.get_stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].contract, "account.session");
+        assert_eq!(stats[0].requests, 1);
+        assert_eq!(stats[0].errors, 1);
+        let device_info = &stats[1];
+        assert_eq!(device_info.requests, 3);
+        assert_eq!(device_info.errors, 1);
+        assert_eq!(device_info.timeouts, 1);
+        assert_eq!(device_info.in_flight, 0);
+        assert_eq!(device_info.latency.count, 2);
+    }
+}
@@ -46,6 +46,34 @@ impl ExtnPayloadProvider for ExtnStatus {
     }
 }
 
+/// Status of an extension library published by Main while loading it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ExtnLibraryStatus {
+    pub path: String,
+    pub status: ExtnStatus,
+    pub reason: Option<String>,
+}
+
+impl ExtnPayloadProvider for ExtnLibraryStatus {
+    fn get_extn_payload(&self) -> ExtnPayload {
+        ExtnPayload::Event(ExtnEvent::Value(
+            serde_json::to_value(self.clone()).unwrap_or_default(),
+        ))
+    }
+
+    fn get_from_payload(payload: ExtnPayload) -> Option<ExtnLibraryStatus> {
+        if let ExtnPayload::Event(ExtnEvent::Value(v)) = payload {
+            return serde_json::from_value(v).ok();
+        }
+
+        None
+    }
+
+    fn contract() -> RippleContract {
+        RippleContract::ExtnStatus
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -57,4 +85,15 @@ mod tests {
         let contract_type: RippleContract = RippleContract::ExtnStatus;
         test_extn_payload_provider(extn_status, contract_type);
     }
+
+    #[test]
+    fn test_extn_payload_provider_for_extn_library_status() {
+        let library_status = ExtnLibraryStatus {
+            path: "libextn.so".to_owned(),
+            status: ExtnStatus::Error,
+            reason: Some("built with a different rustc version".to_owned()),
+        };
+        let contract_type: RippleContract = RippleContract::ExtnStatus;
+        test_extn_payload_provider(library_status, contract_type);
+    }
 }
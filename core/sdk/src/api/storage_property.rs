@@ -398,7 +398,7 @@ pub struct PropertyData {
     pub event_names: Option<&'static [&'static str]>,
 }
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub enum StorageProperty {
     ClosedCaptionsFontFamily,
     ClosedCaptionsFontSize,
@@ -436,6 +436,43 @@ pub enum StorageProperty {
     CCPreferredLanguages,
 }
 
+const STORAGE_PROPERTIES: [StorageProperty; 34] = [
+    StorageProperty::ClosedCaptionsFontFamily,
+    StorageProperty::ClosedCaptionsFontSize,
+    StorageProperty::ClosedCaptionsFontColor,
+    StorageProperty::ClosedCaptionsFontEdge,
+    StorageProperty::ClosedCaptionsFontEdgeColor,
+    StorageProperty::ClosedCaptionsFontOpacity,
+    StorageProperty::ClosedCaptionsBackgroundColor,
+    StorageProperty::ClosedCaptionsBackgroundOpacity,
+    StorageProperty::ClosedCaptionsWindowColor,
+    StorageProperty::ClosedCaptionsWindowOpacity,
+    StorageProperty::ClosedCaptionsTextAlign,
+    StorageProperty::ClosedCaptionsTextAlignVertical,
+    StorageProperty::Locality,
+    StorageProperty::PostalCode,
+    StorageProperty::Locale,
+    StorageProperty::LatLon,
+    StorageProperty::AdditionalInfo,
+    StorageProperty::AllowAcrCollection,
+    StorageProperty::AllowAppContentAdTargeting,
+    StorageProperty::AllowBusinessAnalytics,
+    StorageProperty::AllowCameraAnalytics,
+    StorageProperty::AllowPersonalization,
+    StorageProperty::AllowPrimaryBrowseAdTargeting,
+    StorageProperty::AllowPrimaryContentAdTargeting,
+    StorageProperty::AllowProductAnalytics,
+    StorageProperty::AllowRemoteDiagnostics,
+    StorageProperty::AllowResumePoints,
+    StorageProperty::AllowUnentitledPersonalization,
+    StorageProperty::AllowUnentitledResumePoints,
+    StorageProperty::AllowWatchHistory,
+    StorageProperty::PartnerExclusions,
+    StorageProperty::SkipRestriction,
+    StorageProperty::AudioDescriptionEnabled,
+    StorageProperty::CCPreferredLanguages,
+];
+
 impl TryFrom<PrivacySetting> for StorageProperty {
     type Error = RippleError;
     fn try_from(value: PrivacySetting) -> Result<Self, RippleError> {
@@ -522,6 +559,26 @@ impl StorageProperty {
         }
     }
 
+    pub fn all() -> &'static [StorageProperty] {
+        &STORAGE_PROPERTIES
+    }
+
+    /// Property stored under a namespace and key, profile namespaces
+    /// (`profile.<profile id>.<namespace>`) match the properties of their base namespace
+    pub fn from_namespace_key(namespace: &str, key: &str) -> Option<StorageProperty> {
+        let namespace = namespace
+            .strip_prefix("profile.")
+            .and_then(|n| n.split_once('.'))
+            .map_or(namespace, |(_, n)| n);
+        STORAGE_PROPERTIES
+            .iter()
+            .find(|p| {
+                let data = p.as_data();
+                data.namespace == namespace && data.key == key
+            })
+            .cloned()
+    }
+
     pub fn as_privacy_setting(&self) -> Option<PrivacySetting> {
         match self {
             StorageProperty::AllowAcrCollection => Some(PrivacySetting::Acr),
@@ -550,6 +607,34 @@ impl StorageProperty {
         }
     }
 
+    pub fn from_privacy_setting(setting: &PrivacySetting) -> Option<StorageProperty> {
+        match setting {
+            PrivacySetting::Acr => Some(StorageProperty::AllowAcrCollection),
+            PrivacySetting::AppContentAdTargeting => {
+                Some(StorageProperty::AllowAppContentAdTargeting)
+            }
+            PrivacySetting::CameraAnalytics => Some(StorageProperty::AllowCameraAnalytics),
+            PrivacySetting::Personalization => Some(StorageProperty::AllowPersonalization),
+            PrivacySetting::PrimaryBrowseAdTargeting => {
+                Some(StorageProperty::AllowPrimaryBrowseAdTargeting)
+            }
+            PrivacySetting::PrimaryContentAdTargeting => {
+                Some(StorageProperty::AllowPrimaryContentAdTargeting)
+            }
+            PrivacySetting::ProductAnalytics => Some(StorageProperty::AllowProductAnalytics),
+            PrivacySetting::RemoteDiagnostics => Some(StorageProperty::AllowRemoteDiagnostics),
+            PrivacySetting::ContinueWatching => Some(StorageProperty::AllowResumePoints),
+            PrivacySetting::UnentitledPersonalization => {
+                Some(StorageProperty::AllowUnentitledPersonalization)
+            }
+            PrivacySetting::UnentitledContinueWatching => {
+                Some(StorageProperty::AllowUnentitledResumePoints)
+            }
+            PrivacySetting::WatchHistory => Some(StorageProperty::AllowWatchHistory),
+            _ => None,
+        }
+    }
+
     pub fn get_privacy_setting_value(&self, settings: &PrivacySettingsData) -> Option<bool> {
         match self {
             StorageProperty::AllowAcrCollection => settings.allow_acr_collection,
@@ -632,6 +717,9 @@ impl StorageProperty {
 pub enum StorageManagerRequest {
     GetBool(StorageProperty, bool),
     GetString(StorageProperty),
+    /// Sent when a property changed in storage without going through the storage manager,
+    /// None invalidates every cached property
+    InvalidateCache(Option<StorageProperty>),
 }
 
 impl ExtnPayloadProvider for StorageManagerRequest {
@@ -670,6 +758,28 @@ impl ContractAdjective for StorageAdjective {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_storage_property_from_namespace_key() {
+        for property in STORAGE_PROPERTIES {
+            let data = property.as_data();
+            assert_eq!(
+                StorageProperty::from_namespace_key(data.namespace, data.key),
+                Some(property)
+            );
+        }
+        assert_eq!(
+            StorageProperty::from_namespace_key(
+                &format!("profile.kid.{}", NAMESPACE_PRIVACY),
+                KEY_ALLOW_WATCH_HISTORY
+            ),
+            Some(StorageProperty::AllowWatchHistory)
+        );
+        assert_eq!(
+            StorageProperty::from_namespace_key(NAMESPACE_PRIVACY, "unknown"),
+            None
+        );
+    }
+
     #[test]
     fn test_storage_property_as_privacy_setting() {
         let property = StorageProperty::AllowAcrCollection;
@@ -677,6 +787,20 @@ mod tests {
         assert_eq!(privacy_setting, Some(PrivacySetting::Acr));
     }
 
+    #[test]
+    fn test_storage_property_from_privacy_setting() {
+        let property = StorageProperty::AllowWatchHistory;
+        let setting = property.as_privacy_setting().unwrap();
+        assert_eq!(
+            StorageProperty::from_privacy_setting(&setting),
+            Some(property)
+        );
+        assert_eq!(
+            StorageProperty::from_privacy_setting(&PrivacySetting::AppDataCollection("app".into())),
+            None
+        );
+    }
+
     #[test]
     fn test_storage_property_get_privacy_setting_value() {
         let property = StorageProperty::AllowAcrCollection;
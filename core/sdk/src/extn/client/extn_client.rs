@@ -40,6 +40,7 @@ use crate::{
         device::device_request::{InternetConnectionStatus, TimeZone},
         gateway::rpc_gateway_api::ApiMessage,
         manifest::extn_manifest::ExtnSymbol,
+        observability::extn_metrics::{ExtnContractStats, ExtnMetrics, ExtnRequestOutcome},
     },
     extn::{
         extn_client_message::{ExtnMessage, ExtnPayloadProvider, ExtnResponse},
@@ -67,6 +68,8 @@ use crate::utils::mock_utils::get_next_mock_response;
 /// 4. `response_processors` - Map of response processors which are used for Response processor handling
 /// 5. `request_processors` - Map of request processors used for Request process handling
 /// 6. `event_processors` - Map of event processors used for Event Process handling
+/// 7. `in_flight_requests` - Requests `Main` forwarded to an extension which did not get a response yet
+/// 8. `metrics` - Request counters and latency per contract and target
 ///
 
 #[derive(Clone, Debug)]
@@ -77,9 +80,80 @@ pub struct ExtnClient {
     response_processors: Arc<RwLock<HashMap<String, OSender<ExtnMessage>>>>,
     request_processors: Arc<RwLock<HashMap<String, MSender<ExtnMessage>>>>,
     event_processors: Arc<RwLock<HashMap<String, Vec<MSender<ExtnMessage>>>>>,
+    in_flight_requests: Arc<RwLock<InFlightRequests>>,
+    metrics: ExtnMetrics,
     ripple_context: Arc<RwLock<RippleContext>>,
 }
 
+/// Requests forwarded by `Main` to the extensions by extension id
+#[derive(Debug, Default)]
+struct InFlightRequests {
+    by_extn: HashMap<String, HashMap<String, ExtnMessage>>,
+    extn_by_request: HashMap<String, String>,
+}
+
+impl InFlightRequests {
+    fn insert(&mut self, extn_id: String, request: ExtnMessage) {
+        self.extn_by_request
+            .insert(request.id.clone(), extn_id.clone());
+        self.by_extn
+            .entry(extn_id)
+            .or_default()
+            .insert(request.id.clone(), request);
+    }
+
+    fn remove(&mut self, request_id: &str) {
+        if let Some(extn_id) = self.extn_by_request.remove(request_id) {
+            if let Some(requests) = self.by_extn.get_mut(&extn_id) {
+                requests.remove(request_id);
+                if requests.is_empty() {
+                    self.by_extn.remove(&extn_id);
+                }
+            }
+        }
+    }
+
+    fn count(&self, extn_id: &str) -> usize {
+        self.by_extn.get(extn_id).map_or(0, |r| r.len())
+    }
+
+    fn drain(&mut self, extn_id: &str) -> Vec<ExtnMessage> {
+        let requests = self.by_extn.remove(extn_id).unwrap_or_default();
+        for request_id in requests.keys() {
+            self.extn_by_request.remove(request_id);
+        }
+        requests.into_values().collect()
+    }
+}
+
+/// Removes the in flight entry and the response processor of a request when its caller stops
+/// waiting, on a response as well as on a timeout. A request still measured at that point timed out.
+struct InFlightGuard {
+    id: String,
+    in_flight_requests: Arc<RwLock<InFlightRequests>>,
+    response_processors: Arc<RwLock<HashMap<String, OSender<ExtnMessage>>>>,
+    metrics: ExtnMetrics,
+}
+
+impl InFlightGuard {
+    fn new(client: &ExtnClient, id: &str) -> Self {
+        Self {
+            id: id.to_owned(),
+            in_flight_requests: client.in_flight_requests.clone(),
+            response_processors: client.response_processors.clone(),
+            metrics: client.metrics.clone(),
+        }
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.in_flight_requests.write().unwrap().remove(&self.id);
+        self.response_processors.write().unwrap().remove(&self.id);
+        self.metrics.finish(&self.id, ExtnRequestOutcome::Timeout);
+    }
+}
+
 fn add_stream_processor<P>(id: String, context: P, map: Arc<RwLock<HashMap<String, P>>>) {
     let mut processor_state = map.write().unwrap();
     processor_state.insert(id, context);
@@ -123,6 +197,8 @@ impl ExtnClient {
             response_processors: Arc::new(RwLock::new(HashMap::new())),
             request_processors: Arc::new(RwLock::new(HashMap::new())),
             event_processors: Arc::new(RwLock::new(HashMap::new())),
+            in_flight_requests: Arc::new(RwLock::new(InFlightRequests::default())),
+            metrics: ExtnMetrics::default(),
             ripple_context: Arc::new(RwLock::new(RippleContext::default())),
         }
     }
@@ -136,6 +212,8 @@ impl ExtnClient {
             response_processors: Arc::new(RwLock::new(HashMap::new())),
             request_processors: Arc::new(RwLock::new(HashMap::new())),
             event_processors: Arc::new(RwLock::new(HashMap::new())),
+            in_flight_requests: Arc::new(RwLock::new(InFlightRequests::default())),
+            metrics: ExtnMetrics::default(),
             ripple_context: Arc::new(RwLock::new(RippleContext::default())),
         };
 
@@ -203,6 +281,46 @@ impl ExtnClient {
         });
     }
 
+    /// Adds a channel receiving the requests of a contract fulfilled by this extension, for
+    /// runtimes which dispatch the messages themselves like the WebAssembly extensions.
+    ///
+    /// Returns false when the contract is not fulfilled by this extension.
+    pub fn add_request_channel(
+        &mut self,
+        contract: RippleContract,
+        sender: MSender<ExtnMessage>,
+    ) -> bool {
+        if !self.sender.check_contract_fulfillment(contract.clone()) {
+            return false;
+        }
+        info!("adding request channel {}", contract.as_clear_string());
+        add_stream_processor(
+            contract.as_clear_string(),
+            sender,
+            self.request_processors.clone(),
+        );
+        true
+    }
+
+    /// Adds a channel receiving the events of a contract, see [ExtnClient::add_request_channel]
+    ///
+    /// Returns false when the extension is not permitted to receive the events of the contract.
+    pub fn add_event_channel(
+        &mut self,
+        contract: RippleContract,
+        sender: MSender<ExtnMessage>,
+    ) -> bool {
+        if !self.sender.check_event_permission(contract.clone()) {
+            return false;
+        }
+        add_vec_stream_processor(
+            contract.as_clear_string(),
+            sender,
+            self.event_processors.clone(),
+        );
+        true
+    }
+
     /// Removes an event processor reference on the internal map of processors
     pub fn cleanup_event_stream(&mut self, capability: ExtnId) {
         Self::cleanup_vec_stream(capability.to_string(), None, self.event_processors.clone());
@@ -240,13 +358,55 @@ impl ExtnClient {
         }
 
         {
+            // contracts are keyed by their clear string and may have been taken over by another extension
             let mut contract_map = self.contract_map.write().unwrap();
             for contract in symbol.fulfills {
-                contract_map.remove(&contract);
+                if let Some(v) = RippleContract::from_manifest(&contract) {
+                    let key = v.as_clear_string();
+                    if contract_map.get(&key) == Some(&id) {
+                        contract_map.remove(&key);
+                    }
+                }
             }
         }
     }
 
+    /// Stops routing all the contracts fulfilled by the extension in a single update.
+    /// Requests for these contracts fail with a processor error until another extension fulfills them.
+    ///
+    /// Returns the contracts which were routed to the extension
+    pub fn remove_contracts(&self, id: &str) -> Vec<String> {
+        let mut contract_map = self.contract_map.write().unwrap();
+        let contracts: Vec<String> = contract_map
+            .iter()
+            .filter(|(_, extn_id)| extn_id.as_str() == id)
+            .map(|(contract, _)| contract.clone())
+            .collect();
+        for contract in &contracts {
+            info!("{} no longer fulfills {}", id, contract);
+            contract_map.remove(contract);
+        }
+        contracts
+    }
+
+    /// Number of requests forwarded to the extension which are still waiting for a response
+    pub fn in_flight_count(&self, id: &str) -> usize {
+        self.in_flight_requests.read().unwrap().count(id)
+    }
+
+    /// Answers the requests still waiting for a response from the extension with a processor error
+    ///
+    /// Returns the number of failed requests
+    pub fn fail_in_flight(&self, id: &str) -> usize {
+        let failed = self.in_flight_requests.write().unwrap().drain(id);
+        let count = failed.len();
+        for request in failed {
+            warn!("Failing request {} which {} did not answer", request.id, id);
+            self.handle_no_processor_error(request);
+        }
+        count
+    }
+
     pub fn get_other_senders(&self) -> Vec<MSender<ApiMessage>> {
         self.extn_sender_map
             .read()
@@ -314,6 +474,8 @@ impl ExtnClient {
     pub fn handle_message(&self, message: ExtnMessage) -> ControlFlow<()> {
         trace!("IEC recv: {:#?}", message);
         if message.payload.is_response() {
+            self.in_flight_requests.write().unwrap().remove(&message.id);
+            self.finish_request_metrics(&message);
             Self::handle_single(message, self.response_processors.clone());
         } else if message.payload.is_event() {
             let is_main = self.sender.get_cap().is_main();
@@ -352,50 +514,7 @@ impl ExtnClient {
                 Self::handle_vec_stream(message, self.event_processors.clone());
             }
         } else {
-            let current_cap = self.sender.get_cap();
-            let target_contract = message.clone().target;
-            if current_cap.is_main() {
-                if let Some(request) =
-                    RippleContextUpdateRequest::is_ripple_context_update(&message.payload)
-                {
-                    self.context_update(request);
-                }
-                // if its a request coming as an extn provider the extension is calling on itself.
-                // for eg an extension has a RPC Method provider and also a channel to process the
-                // requests this below impl will take care of sending the data back to the Extension
-                else if let Some(extn_id) = target_contract.is_extn_provider() {
-                    if let Some(s) = self.get_extn_sender_with_extn_id(&extn_id) {
-                        let new_message = message.clone();
-                        tokio::spawn(async move {
-                            if let Err(e) = s.send(new_message.into()).await {
-                                error!("Error forwarding request {:?}", e)
-                            }
-                        });
-                    } else {
-                        error!("couldn't find the extension id registered the extn channel {:?} is not available", extn_id);
-                        self.handle_no_processor_error(message);
-                    }
-                }
-                // Forward the message to an extn sender
-                else if let Some(sender) =
-                    self.get_extn_sender_with_contract(target_contract.clone())
-                {
-                    let new_message = message.clone();
-
-                    tokio::spawn(async move {
-                        if let Err(e) = sender.try_send(new_message.into()) {
-                            error!("Error forwarding request {:?}", e)
-                        }
-                    });
-                } else {
-                    // could be main contract
-                    if !Self::handle_stream(message.clone(), self.request_processors.clone()) {
-                        self.handle_no_processor_error(message);
-                    }
-                }
-            } else if !Self::handle_stream(message.clone(), self.request_processors.clone()) {
-                self.handle_no_processor_error(message);
-            }
+            self.handle_request(message, false);
         }
         ControlFlow::Continue(())
     }
@@ -444,13 +563,79 @@ impl ExtnClient {
         processors.contains_key(input)
     }
 
+    /// Routes a request, `Main` measures the ones it forwards or serves unless they are transient
+    fn handle_request(&self, message: ExtnMessage, transient: bool) {
+        let current_cap = self.sender.get_cap();
+        let target_contract = message.clone().target;
+        if current_cap.is_main() {
+            if let Some(request) =
+                RippleContextUpdateRequest::is_ripple_context_update(&message.payload)
+            {
+                self.context_update(request);
+            }
+            // if its a request coming as an extn provider the extension is calling on itself.
+            // for eg an extension has a RPC Method provider and also a channel to process the
+            // requests this below impl will take care of sending the data back to the Extension
+            else if let Some(extn_id) = target_contract.is_extn_provider() {
+                if let Some(s) = self.get_extn_sender_with_extn_id(&extn_id) {
+                    self.forward_request(extn_id, s, message, transient);
+                } else {
+                    error!("couldn't find the extension id registered the extn channel {:?} is not available", extn_id);
+                    self.handle_no_processor_error(message);
+                }
+            }
+            // Forward the message to an extn sender
+            else if let Some((extn_id, sender)) =
+                self.get_extn_id_and_sender_with_contract(target_contract.clone())
+            {
+                self.forward_request(extn_id, sender, message, transient);
+            } else {
+                // could be main contract
+                if !transient {
+                    self.start_request_metrics(&message, "main");
+                }
+                if !Self::handle_stream(message.clone(), self.request_processors.clone()) {
+                    self.handle_no_processor_error(message);
+                }
+            }
+        } else if !Self::handle_stream(message.clone(), self.request_processors.clone()) {
+            self.handle_no_processor_error(message);
+        }
+    }
+
+    /// Forwards a request to the extension and keeps it in flight until its response passes
+    /// through `Main` or the extension is unloaded, whoever the requestor is
+    fn forward_request(
+        &self,
+        extn_id: String,
+        sender: MSender<ApiMessage>,
+        message: ExtnMessage,
+        transient: bool,
+    ) {
+        if !transient {
+            self.start_request_metrics(&message, &extn_id);
+        }
+        self.in_flight_requests
+            .write()
+            .unwrap()
+            .insert(extn_id, message.clone());
+        if let Err(e) = sender.try_send(message.clone().into()) {
+            error!("Error forwarding request {:?}", e);
+            self.in_flight_requests.write().unwrap().remove(&message.id);
+            self.handle_no_processor_error(message);
+        }
+    }
+
     fn handle_no_processor_error(&self, message: ExtnMessage) {
         let req_sender = self.get_extn_sender_with_extn_id(&message.requestor.to_string());
         if let Ok(resp) = message.get_response(ExtnResponse::Error(RippleError::ProcessorError)) {
             if message.requestor.is_main() {
                 self.handle_message(resp);
-            } else if self.sender.respond(resp, req_sender).is_err() {
-                error!("Couldnt send no processor response");
+            } else {
+                self.finish_request_metrics(&resp);
+                if self.sender.respond(resp, req_sender).is_err() {
+                    error!("Couldnt send no processor response");
+                }
             }
         }
     }
@@ -582,6 +767,14 @@ impl ExtnClient {
         &self,
         contract: RippleContract,
     ) -> Option<MSender<ApiMessage>> {
+        self.get_extn_id_and_sender_with_contract(contract)
+            .map(|(_, sender)| sender)
+    }
+
+    fn get_extn_id_and_sender_with_contract(
+        &self,
+        contract: RippleContract,
+    ) -> Option<(String, MSender<ApiMessage>)> {
         let contract_str: String = contract.as_clear_string();
         let id = {
             self.contract_map
@@ -591,7 +784,9 @@ impl ExtnClient {
                 .cloned()
         };
         if let Some(extn_id) = id {
-            return self.get_extn_sender_with_extn_id(&extn_id);
+            return self
+                .get_extn_sender_with_extn_id(&extn_id)
+                .map(|sender| (extn_id, sender));
         }
 
         None
@@ -624,6 +819,7 @@ impl ExtnClient {
         } else if req.requestor.is_main() {
             self.handle_message(response);
         } else {
+            self.finish_request_metrics(&response);
             // if the requestor is not main then we need to send the response back to the requestor
             // using the sender which was used to send the request
             let _ = self
@@ -646,6 +842,9 @@ impl ExtnClient {
             self.handle_message(msg);
             Ok(())
         } else {
+            if msg.payload.is_response() {
+                self.finish_request_metrics(&msg);
+            }
             self.sender.respond(
                 msg.clone(),
                 self.get_extn_sender_with_extn_id(&msg.requestor.to_string()),
@@ -678,18 +877,77 @@ impl ExtnClient {
     pub async fn request(
         &mut self,
         payload: impl ExtnPayloadProvider,
+    ) -> Result<ExtnMessage, RippleError> {
+        self.send_and_wait(payload).await
+    }
+
+    /// Request counters and latency per contract and target of the requests sent by this client
+    pub fn get_metrics(&self) -> Vec<ExtnContractStats> {
+        self.metrics.get_stats()
+    }
+
+    /// Target of the requests for the contract, the extension fulfilling it or `main`
+    fn get_metrics_target(&self, contract: &RippleContract) -> String {
+        self.contract_map
+            .read()
+            .unwrap()
+            .get(&contract.as_clear_string())
+            .cloned()
+            .unwrap_or_else(|| "main".to_owned())
+    }
+
+    /// Measures a request sent or routed by this client until its response passes through it
+    fn start_request_metrics(&self, request: &ExtnMessage, target: &str) {
+        self.metrics
+            .start(&request.id, &request.target.as_clear_string(), target);
+    }
+
+    fn finish_request_metrics(&self, response: &ExtnMessage) {
+        let outcome = match response.payload.extract::<ExtnResponse>() {
+            Some(ExtnResponse::Error(_)) => ExtnRequestOutcome::Error,
+            _ => ExtnRequestOutcome::Success,
+        };
+        self.metrics.finish(&response.id, outcome);
+    }
+
+    /// Sends a request of an extension, measured until its response
+    fn send_extn_request(
+        &self,
+        id: &str,
+        payload: impl ExtnPayloadProvider,
+        other_sender: Option<MSender<ApiMessage>>,
+    ) -> RippleResponse {
+        let contract = payload.get_contract();
+        self.metrics.start(
+            id,
+            &contract.as_clear_string(),
+            &self.get_metrics_target(&contract),
+        );
+        let sent = self
+            .sender
+            .send_request(id.to_owned(), payload, other_sender);
+        if sent.is_err() {
+            self.metrics.finish(id, ExtnRequestOutcome::Error);
+        }
+        sent
+    }
+
+    async fn send_and_wait(
+        &mut self,
+        payload: impl ExtnPayloadProvider,
     ) -> Result<ExtnMessage, RippleError> {
         let id = uuid::Uuid::new_v4().to_string();
         let (tx, rx) = oneshot::channel();
+        // added before the request is sent so a forwarded request is tracked as in flight
+        add_single_processor(id.clone(), Some(tx), self.response_processors.clone());
+        let _guard = InFlightGuard::new(self, &id);
         if self.sender.get_cap().is_main() {
             let request = self.sender.get_message(id.clone(), payload);
             self.handle_message(request);
         } else {
             let other_sender = self.get_extn_sender_with_contract(payload.get_contract());
-            self.sender
-                .send_request(id.clone(), payload, other_sender)?;
+            self.send_extn_request(&id, payload, other_sender)?;
         }
-        add_single_processor(id.clone(), Some(tx), self.response_processors.clone());
 
         if let Ok(r) = rx.await {
             return Ok(r);
@@ -705,8 +963,9 @@ impl ExtnClient {
         let id = uuid::Uuid::new_v4().to_string();
         let (tx, rx) = oneshot::channel();
         add_single_processor(id.clone(), Some(tx), self.response_processors.clone());
+        let _guard = InFlightGuard::new(self, &id);
 
-        match self.sender.send_request(id, payload, None) {
+        match self.send_extn_request(&id, payload, None) {
             Ok(_) => {
                 if let Ok(r) = rx.await {
                     return Ok(r);
@@ -763,10 +1022,11 @@ impl ExtnClient {
         let id = uuid::Uuid::new_v4().to_string();
         let (tx, rx) = oneshot::channel();
         add_single_processor(id.clone(), Some(tx), self.response_processors.clone());
+        let _guard = InFlightGuard::new(self, &id);
 
         let other_sender = self.get_extn_sender_with_contract(payload.get_contract());
         if other_sender.is_some() {
-            match self.sender.send_request(id, payload, other_sender) {
+            match self.send_extn_request(&id, payload, other_sender) {
                 Ok(_) => {
                     trace!("Main internal request sent successfully");
                 }
@@ -826,18 +1086,18 @@ impl ExtnClient {
         {
             let resp = tokio::time::timeout(
                 std::time::Duration::from_millis(timeout_in_msecs),
-                self.request(payload),
+                self.send_and_wait(payload),
             )
             .await;
             match resp {
-                Ok(Ok(message)) => {
+                Ok(result) => {
+                    let message = result?;
                     if let Some(payload) = message.payload.extract() {
                         Ok(payload)
                     } else {
                         Err(RippleError::ParseError)
                     }
                 }
-                Ok(Err(e)) => Err(e),
                 Err(_) => Err(RippleError::TimeoutError),
             }
         }
@@ -937,14 +1197,19 @@ impl ExtnClient {
         payload: impl ExtnPayloadProvider,
     ) -> Result<String, RippleError> {
         let id = uuid::Uuid::new_v4().to_string();
+        let contract = payload.get_contract();
+        let target = self.get_metrics_target(&contract);
         let msg = self.sender.get_message(id.clone(), payload.clone());
-        if self.sender.get_cap().is_main() {
-            self.handle_message(msg);
+        let sent = if self.sender.get_cap().is_main() {
+            self.handle_request(msg, true);
+            Ok(())
         } else {
             let other_sender = self.get_extn_sender_with_contract(payload.get_contract());
-            self.sender
-                .send_request(id.clone(), payload, other_sender)?;
-        }
+            self.sender.send_request(id.clone(), payload, other_sender)
+        };
+        self.metrics
+            .record_transient(&contract.as_clear_string(), &target, sent.is_ok());
+        sent?;
 
         Ok(id)
     }
@@ -1055,6 +1320,8 @@ impl ExtnClient {
             response_processors: Arc::new(RwLock::new(HashMap::new())),
             request_processors: Arc::new(RwLock::new(HashMap::new())),
             event_processors: Arc::new(RwLock::new(HashMap::new())),
+            in_flight_requests: Arc::new(RwLock::new(InFlightRequests::default())),
+            metrics: ExtnMetrics::default(),
             ripple_context: Arc::new(RwLock::new(RippleContext::default())),
         }
     }
@@ -1259,6 +1526,42 @@ pub mod tests {
         );
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_add_request_and_event_channels() {
+        let symbol = ExtnSymbol {
+            id: ExtnId::new_channel(ExtnClassId::Distributor, "wasm".into()).to_string(),
+            uses: vec![],
+            fulfills: vec![RippleContract::Internal.as_clear_string()],
+            config: None,
+        };
+        let (mut extn_client, _tr) = ExtnClient::new_extn(symbol);
+        let (tx, mut rx) = mpsc::channel(1);
+        assert!(extn_client.add_request_channel(RippleContract::Internal, tx.clone()));
+        assert!(!extn_client.add_request_channel(RippleContract::DeviceInfo, tx.clone()));
+        assert!(extn_client.add_event_channel(RippleContract::Internal, tx.clone()));
+        assert!(!extn_client.add_event_channel(RippleContract::TelemetryEventsListener, tx));
+
+        let request = ExtnMessage {
+            id: "request".into(),
+            requestor: ExtnId::get_main_target("main".into()),
+            target: RippleContract::Internal,
+            target_id: None,
+            payload: ExtnPayload::Request(ExtnRequest::Device(DeviceRequest::DeviceInfo(
+                DeviceInfoRequest::Model,
+            ))),
+            ts: None,
+        };
+        extn_client.handle_message(request.clone());
+        assert_eq!(rx.recv().await.unwrap().id, "request");
+
+        extn_client.handle_message(ExtnMessage {
+            id: "event".into(),
+            payload: ExtnPayload::Event(ExtnEvent::String("ready".into())),
+            ..request
+        });
+        assert_eq!(rx.recv().await.unwrap().id, "event");
+    }
+
     #[rstest(cap, expected_len,
         case(ExtnId::get_main_target("main".into()), 1),
         case(ExtnId::new_channel(ExtnClassId::Internal, "test".into()), 1)
@@ -1573,6 +1876,216 @@ pub mod tests {
         }
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_remove_contracts_fails_in_flight_requests() {
+        let main_client = ExtnClient::new_main();
+        let extn_id = ExtnId::new_extn(ExtnClassId::Device, "info".into()).to_string();
+        let (tx, mut rx) = mpsc::channel(1);
+        main_client.clone().add_sender(
+            extn_id.clone(),
+            ExtnSymbol {
+                id: extn_id.clone(),
+                uses: Vec::new(),
+                fulfills: vec![RippleContract::DeviceInfo.as_clear_string()],
+                config: Some(HashMap::new()),
+            },
+            tx,
+        );
+
+        let mut client_c = main_client.clone();
+        let request = tokio::spawn(async move {
+            client_c
+                .request(MockRequest {
+                    app_id: "test_app_id".to_string(),
+                    contract: RippleContract::DeviceInfo,
+                    expected_response: None,
+                })
+                .await
+        });
+        // the extension got the request but never answers it
+        let _ = rx.recv().await.unwrap();
+        assert_eq!(main_client.in_flight_count(&extn_id), 1);
+
+        assert_eq!(
+            main_client.remove_contracts(&extn_id),
+            vec![RippleContract::DeviceInfo.as_clear_string()]
+        );
+        assert!(main_client
+            .get_extn_sender_with_contract(RippleContract::DeviceInfo)
+            .is_none());
+        assert_eq!(main_client.fail_in_flight(&extn_id), 1);
+        assert_eq!(main_client.in_flight_count(&extn_id), 0);
+
+        let response = request.await.unwrap().unwrap();
+        assert_eq!(
+            response.payload.extract::<ExtnResponse>(),
+            Some(ExtnResponse::Error(RippleError::ProcessorError))
+        );
+
+        let stats = main_client.get_metrics();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(
+            stats[0].contract,
+            RippleContract::DeviceInfo.as_clear_string()
+        );
+        assert_eq!(stats[0].target, extn_id);
+        assert_eq!(stats[0].requests, 1);
+        assert_eq!(stats[0].errors, 1);
+        assert_eq!(stats[0].in_flight, 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_metrics_of_requests_routed_by_main() {
+        let main_client = ExtnClient::new_main();
+        let extn_id = ExtnId::new_extn(ExtnClassId::Device, "info".into()).to_string();
+        let (tx, mut rx) = mpsc::channel(2);
+        main_client.clone().add_sender(
+            extn_id.clone(),
+            ExtnSymbol {
+                id: extn_id.clone(),
+                uses: Vec::new(),
+                fulfills: vec![RippleContract::DeviceInfo.as_clear_string()],
+                config: Some(HashMap::new()),
+            },
+            tx,
+        );
+        let request = MockRequest {
+            app_id: "test_app_id".to_string(),
+            contract: RippleContract::DeviceInfo,
+            expected_response: None,
+        };
+
+        // a request of another extension is measured by Main until the target answers
+        let mut message = main_client
+            .sender
+            .get_message("extn-request".into(), request.clone());
+        message.requestor = ExtnId::new_extn(ExtnClassId::Distributor, "general".into());
+        main_client.handle_message(message);
+        let forwarded = ExtnMessage::try_from(rx.recv().await.unwrap().jsonrpc_msg).unwrap();
+        assert_eq!(main_client.get_metrics()[0].in_flight, 1);
+        main_client.handle_message(
+            forwarded
+                .get_response(ExtnResponse::String("done".into()))
+                .unwrap(),
+        );
+
+        // a transient request is counted without being in flight
+        main_client.request_transient(request).unwrap();
+        let _ = rx.recv().await.unwrap();
+
+        // a request for a contract of Main without a processor fails
+        let mut client_c = main_client.clone();
+        let _ = client_c
+            .request(MockRequest {
+                app_id: "test_app_id".to_string(),
+                contract: RippleContract::Config,
+                expected_response: None,
+            })
+            .await;
+
+        let stats = main_client.get_metrics();
+        let device_info = stats.iter().find(|s| s.target == extn_id).unwrap();
+        assert_eq!(device_info.requests, 2);
+        assert_eq!(device_info.errors, 0);
+        assert_eq!(device_info.in_flight, 0);
+        assert_eq!(device_info.latency.count, 1);
+        let main = stats.iter().find(|s| s.target == "main").unwrap();
+        assert_eq!(main.requests, 1);
+        assert_eq!(main.errors, 1);
+        assert_eq!(main.in_flight, 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_forwarded_requests_in_flight() {
+        let main_client = ExtnClient::new_main();
+        let extn_id = ExtnId::new_extn(ExtnClassId::Device, "info".into()).to_string();
+        let (tx, mut rx) = mpsc::channel(1);
+        main_client.clone().add_sender(
+            extn_id.clone(),
+            ExtnSymbol {
+                id: extn_id.clone(),
+                uses: Vec::new(),
+                fulfills: vec![RippleContract::DeviceInfo.as_clear_string()],
+                config: Some(HashMap::new()),
+            },
+            tx,
+        );
+        let request = MockRequest {
+            app_id: "test_app_id".to_string(),
+            contract: RippleContract::DeviceInfo,
+            expected_response: None,
+        };
+
+        // the caller stops waiting before the extension answers
+        let mut client_c = main_client.clone();
+        let abandoned =
+            tokio::time::timeout(Duration::from_millis(50), client_c.request(request.clone()))
+                .await;
+        assert!(abandoned.is_err());
+        let _ = rx.recv().await.unwrap();
+        assert_eq!(main_client.in_flight_count(&extn_id), 0);
+
+        // nobody waits on a transient request, it is in flight until the extension answers
+        main_client.request_transient(request.clone()).unwrap();
+        let forwarded = ExtnMessage::try_from(rx.recv().await.unwrap().jsonrpc_msg).unwrap();
+        assert_eq!(main_client.in_flight_count(&extn_id), 1);
+        main_client.handle_message(
+            forwarded
+                .get_response(ExtnResponse::String("done".into()))
+                .unwrap(),
+        );
+        assert_eq!(main_client.in_flight_count(&extn_id), 0);
+
+        // a request of another extension is failed back to it when the target is unloaded
+        let requestor = ExtnId::new_extn(ExtnClassId::Distributor, "general".into());
+        let (requestor_tx, mut requestor_rx) = mpsc::channel(1);
+        main_client.clone().add_sender(
+            requestor.to_string(),
+            ExtnSymbol {
+                id: requestor.to_string(),
+                uses: Vec::new(),
+                fulfills: Vec::new(),
+                config: Some(HashMap::new()),
+            },
+            requestor_tx,
+        );
+        let mut message = main_client
+            .sender
+            .get_message("extn-request".into(), request.clone());
+        message.requestor = requestor;
+        main_client.handle_message(message);
+        let _ = rx.recv().await.unwrap();
+        assert_eq!(main_client.in_flight_count(&extn_id), 1);
+        assert_eq!(main_client.fail_in_flight(&extn_id), 1);
+        let failed = ExtnMessage::try_from(requestor_rx.recv().await.unwrap().jsonrpc_msg).unwrap();
+        assert_eq!(failed.id, "extn-request");
+        assert_eq!(
+            failed.payload.extract::<ExtnResponse>(),
+            Some(ExtnResponse::Error(RippleError::ProcessorError))
+        );
+
+        // the channel of the extension is full so the request fails right away
+        let (tx, _rx) = mpsc::channel(1);
+        tx.try_send(ExtnMessage::default().into()).unwrap();
+        main_client.clone().add_sender(
+            extn_id.clone(),
+            ExtnSymbol {
+                id: extn_id.clone(),
+                uses: Vec::new(),
+                fulfills: vec![RippleContract::DeviceInfo.as_clear_string()],
+                config: Some(HashMap::new()),
+            },
+            tx,
+        );
+        let mut client_c = main_client.clone();
+        let response = client_c.request(request).await.unwrap();
+        assert_eq!(
+            response.payload.extract::<ExtnResponse>(),
+            Some(ExtnResponse::Error(RippleError::ProcessorError))
+        );
+        assert_eq!(main_client.in_flight_count(&extn_id), 0);
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn test_request_between_extn_main() {
         // test case: extn <=> main
@@ -105,6 +105,18 @@ impl ExtnSender {
         }
     }
 
+    /// Events can be received for the contracts an extension uses or fulfills and for the event
+    /// contracts of the ones it uses, see [RippleContract::get_event_contract]
+    pub fn check_event_permission(&self, contract: RippleContract) -> bool {
+        self.check_contract_permission(contract.clone())
+            || self.check_contract_fulfillment(contract.clone())
+            || self
+                .permitted
+                .iter()
+                .filter_map(|c| RippleContract::from_manifest(c))
+                .any(|c| c.get_event_contract().as_ref() == Some(&contract))
+    }
+
     pub fn get_config(&self, key: &str) -> Option<String> {
         if let Some(c) = &self.config {
             if let Some(v) = c.get(key) {
@@ -447,6 +459,23 @@ pub mod tests {
         assert_eq!(cf, exp_resp, "{}", error_msg);
     }
 
+    #[rstest]
+    #[case(vec!["observability".to_string()], true)]
+    #[case(vec!["telemetry_events_listener".to_string()], true)]
+    #[case(vec!["config".to_string()], false)]
+    fn test_check_event_permission(#[case] permitted: Vec<String>, #[case] exp_resp: bool) {
+        let (sender, _mock_rx) = ExtnSender::mock_with_params(
+            ExtnId::new_channel(ExtnClassId::Distributor, "tm".to_string()),
+            permitted,
+            vec![],
+            None,
+        );
+        assert_eq!(
+            sender.check_event_permission(RippleContract::TelemetryEventsListener),
+            exp_resp
+        );
+    }
+
     #[rstest]
     #[case(vec![RippleContract::DeviceInfo.as_clear_string()], true)]
     #[case(vec![RippleContract::Config.as_clear_string()], false)]
@@ -0,0 +1,353 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    ffi::{c_char, CStr, CString},
+    sync::OnceLock,
+};
+
+use libloading::{Library, Symbol};
+use log::{debug, error, warn};
+use semver::Version;
+
+use crate::{
+    framework::ripple_contract::{ContractFulfiller, RippleContract},
+    utils::error::RippleError,
+};
+
+/// Layout version of [ExtnAbiDescriptor], bumped whenever the descriptor changes
+pub const EXTN_ABI_VERSION: u32 = 1;
+
+const fn parse_u32(value: &str) -> u32 {
+    let bytes = value.as_bytes();
+    let mut result = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        result = result * 10 + (bytes[i] - b'0') as u32;
+        i += 1;
+    }
+    result
+}
+
+/// FNV-1a hash, used to compare the rustc versions without passing strings around
+const fn fnv1a(value: &str) -> u64 {
+    let bytes = value.as_bytes();
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+        i += 1;
+    }
+    hash
+}
+
+/// Version of the ripple_sdk crate the caller is built with
+pub const SDK_VERSION: [u32; 3] = [
+    parse_u32(env!("CARGO_PKG_VERSION_MAJOR")),
+    parse_u32(env!("CARGO_PKG_VERSION_MINOR")),
+    parse_u32(env!("CARGO_PKG_VERSION_PATCH")),
+];
+
+/// Hash of the rustc version the caller is built with
+pub const RUSTC_VERSION_HASH: u64 = fnv1a(env!("RIPPLE_SDK_RUSTC_VERSION"));
+
+/// Descriptor exported by an extension library which Main checks before calling into it.
+/// `abi_version` has to stay the first field so a descriptor of any layout can be rejected.
+#[repr(C)]
+#[derive(Debug)]
+pub struct ExtnAbiDescriptor {
+    pub abi_version: u32,
+    pub sdk_version: [u32; 3],
+    pub rustc_version_hash: u64,
+    pub contracts: *const *const c_char,
+    pub contracts_len: usize,
+}
+
+/// Descriptor along with the contract names it points to
+struct OwnedExtnAbiDescriptor {
+    descriptor: ExtnAbiDescriptor,
+    _contracts: Vec<*const c_char>,
+    _names: Vec<CString>,
+}
+
+impl OwnedExtnAbiDescriptor {
+    fn new(contracts: &[&str]) -> OwnedExtnAbiDescriptor {
+        let names: Vec<CString> = contracts
+            .iter()
+            .filter_map(|c| CString::new(*c).ok())
+            .collect();
+        let contracts: Vec<*const c_char> = names.iter().map(|c| c.as_ptr()).collect();
+        OwnedExtnAbiDescriptor {
+            descriptor: ExtnAbiDescriptor {
+                abi_version: EXTN_ABI_VERSION,
+                sdk_version: SDK_VERSION,
+                rustc_version_hash: RUSTC_VERSION_HASH,
+                contracts: contracts.as_ptr(),
+                contracts_len: contracts.len(),
+            },
+            _contracts: contracts,
+            _names: names,
+        }
+    }
+}
+
+/// Descriptor of an extension library, built once on the first call so every call returns the
+/// same pointer which stays valid for as long as the library is loaded, see
+/// [crate::export_extn_abi].
+pub struct ExtnAbiDescriptorCell(OnceLock<OwnedExtnAbiDescriptor>);
+
+// The descriptor is never changed once built and only points into the data it owns
+unsafe impl Sync for ExtnAbiDescriptorCell {}
+unsafe impl Send for ExtnAbiDescriptorCell {}
+
+impl ExtnAbiDescriptorCell {
+    pub const fn new() -> ExtnAbiDescriptorCell {
+        ExtnAbiDescriptorCell(OnceLock::new())
+    }
+
+    /// Returns the descriptor with the manifest names of the contracts in the fulfiller the
+    /// extension already passes in its metadata
+    pub fn get_or_init(
+        &self,
+        fulfiller: impl FnOnce() -> ContractFulfiller,
+    ) -> *const ExtnAbiDescriptor {
+        let owned = self.0.get_or_init(|| {
+            let contracts: Vec<String> = fulfiller()
+                .contracts
+                .iter()
+                .map(|c| c.as_clear_string())
+                .collect();
+            let contracts: Vec<&str> = contracts.iter().map(|c| c.as_str()).collect();
+            OwnedExtnAbiDescriptor::new(&contracts)
+        });
+        &owned.descriptor
+    }
+}
+
+impl Default for ExtnAbiDescriptorCell {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Owned copy of an [ExtnAbiDescriptor]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtnAbiInfo {
+    pub abi_version: u32,
+    pub sdk_version: Version,
+    pub rustc_version_hash: u64,
+    pub contracts: Vec<String>,
+}
+
+impl ExtnAbiInfo {
+    /// Info of the sdk Main is built with
+    pub fn current() -> ExtnAbiInfo {
+        ExtnAbiInfo {
+            abi_version: EXTN_ABI_VERSION,
+            sdk_version: Version::new(
+                SDK_VERSION[0] as u64,
+                SDK_VERSION[1] as u64,
+                SDK_VERSION[2] as u64,
+            ),
+            rustc_version_hash: RUSTC_VERSION_HASH,
+            contracts: Vec::new(),
+        }
+    }
+
+    /// # Safety
+    /// `descriptor` must point to a descriptor created by [ExtnAbiDescriptorCell] of the same
+    /// `abi_version`.
+    pub unsafe fn from_descriptor(descriptor: &ExtnAbiDescriptor) -> ExtnAbiInfo {
+        let contracts = if descriptor.contracts.is_null() {
+            Vec::new()
+        } else {
+            std::slice::from_raw_parts(descriptor.contracts, descriptor.contracts_len)
+                .iter()
+                .filter(|c| !c.is_null())
+                .map(|c| CStr::from_ptr(*c).to_string_lossy().into_owned())
+                .collect()
+        };
+        ExtnAbiInfo {
+            abi_version: descriptor.abi_version,
+            sdk_version: Version::new(
+                descriptor.sdk_version[0] as u64,
+                descriptor.sdk_version[1] as u64,
+                descriptor.sdk_version[2] as u64,
+            ),
+            rustc_version_hash: descriptor.rustc_version_hash,
+            contracts,
+        }
+    }
+
+    /// Checks an extension built with `self` can be loaded by a Main built with `host`. The
+    /// extension has to be built with the same rustc and a sdk which is not newer than the
+    /// host within the same major version, minor version while the major version is 0.
+    pub fn check_compatible(&self, host: &ExtnAbiInfo) -> Result<(), String> {
+        if self.abi_version != host.abi_version {
+            return Err(format!(
+                "abi version {} is not supported, expected {}",
+                self.abi_version, host.abi_version
+            ));
+        }
+        if self.rustc_version_hash != host.rustc_version_hash {
+            return Err("built with a different rustc version".to_owned());
+        }
+        let same_series = self.sdk_version.major == host.sdk_version.major
+            && (host.sdk_version.major > 0 || self.sdk_version.minor == host.sdk_version.minor);
+        if !same_series || self.sdk_version > host.sdk_version {
+            return Err(format!(
+                "built with ripple_sdk {} which is not compatible with {}",
+                self.sdk_version, host.sdk_version
+            ));
+        }
+        Ok(())
+    }
+
+    /// Contracts declared in the manifest which the extension does not fulfill. Declared names
+    /// which are not a [RippleContract] are never routed by Main so they are only logged.
+    pub fn missing_contracts<'a>(&self, declared: impl Iterator<Item = &'a String>) -> Vec<String> {
+        let fulfilled: Vec<RippleContract> = self
+            .contracts
+            .iter()
+            .filter_map(|c| RippleContract::from_manifest(c))
+            .collect();
+        declared
+            .filter(|c| match RippleContract::from_manifest(c) {
+                Some(contract) => !fulfilled.contains(&contract),
+                None => {
+                    warn!("manifest declares unknown contract {}", c);
+                    false
+                }
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/// Reads the ABI descriptor exported with [crate::export_extn_abi] without calling anything
+/// else in the library.
+///
+/// # Safety
+/// The library must export `extn_abi_descriptor` with the signature of [crate::export_extn_abi].
+pub unsafe fn load_abi_descriptor(lib: &Library) -> Result<ExtnAbiInfo, RippleError> {
+    type DescriptorFfi = unsafe extern "C" fn() -> *const ExtnAbiDescriptor;
+    let symbol: Symbol<DescriptorFfi> = match lib.get(b"extn_abi_descriptor") {
+        Ok(symbol) => symbol,
+        Err(e) => {
+            error!("Extn ABI descriptor symbol loading failed {:?}", e);
+            return Err(RippleError::ExtnError);
+        }
+    };
+    let descriptor = symbol();
+    if descriptor.is_null() {
+        return Err(RippleError::ExtnError);
+    }
+    let abi_version = (*descriptor).abi_version;
+    if abi_version != EXTN_ABI_VERSION {
+        // layout is unknown so nothing beyond the version is read
+        return Ok(ExtnAbiInfo {
+            abi_version,
+            sdk_version: Version::new(0, 0, 0),
+            rustc_version_hash: 0,
+            contracts: Vec::new(),
+        });
+    }
+    debug!("Extn ABI descriptor extracted from library");
+    Ok(ExtnAbiInfo::from_descriptor(&*descriptor))
+}
+
+/// Macro used by Extensions to export the ABI descriptor along with the contracts they fulfill.
+/// It takes the same [ContractFulfiller] the extension passes in its symbol metadata so both
+/// stay in sync.
+///
+/// # Example
+/// ```
+/// use ripple_sdk::export_extn_abi;
+/// use ripple_sdk::framework::ripple_contract::{ContractFulfiller, RippleContract};
+///
+/// fn fulfills() -> ContractFulfiller {
+///     ContractFulfiller::new(vec![RippleContract::DeviceInfo, RippleContract::Wifi])
+/// }
+///
+/// export_extn_abi!(fulfills());
+/// ```
+#[macro_export]
+macro_rules! export_extn_abi {
+    ($fulfiller:expr) => {
+        #[no_mangle]
+        pub extern "C" fn extn_abi_descriptor(
+        ) -> *const $crate::extn::ffi::ffi_abi::ExtnAbiDescriptor {
+            static DESCRIPTOR: $crate::extn::ffi::ffi_abi::ExtnAbiDescriptorCell =
+                $crate::extn::ffi::ffi_abi::ExtnAbiDescriptorCell::new();
+            DESCRIPTOR.get_or_init(|| $fulfiller)
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::storage_property::StorageAdjective;
+
+    #[test]
+    fn test_descriptor_round_trip_and_compatibility() {
+        let cell = ExtnAbiDescriptorCell::new();
+        let descriptor = cell.get_or_init(|| {
+            ContractFulfiller::new(vec![
+                RippleContract::DeviceInfo,
+                RippleContract::Storage(StorageAdjective::Local),
+            ])
+        });
+        // the descriptor is only built once
+        assert_eq!(
+            cell.get_or_init(|| ContractFulfiller::new(Vec::new())),
+            descriptor
+        );
+        let info = unsafe { ExtnAbiInfo::from_descriptor(&*descriptor) };
+        let host = ExtnAbiInfo::current();
+        assert_eq!(info.contracts, vec!["device_info", "local.storage"]);
+        assert_eq!(info.sdk_version, host.sdk_version);
+        assert!(info.check_compatible(&host).is_ok());
+        let declared = [
+            "local.storage".to_owned(),
+            "browser".to_owned(),
+            "device_persistence".to_owned(),
+        ];
+        assert_eq!(info.missing_contracts(declared.iter()), vec!["browser"]);
+
+        let mut older = info.clone();
+        older.sdk_version = Version::new(host.sdk_version.major, 0, 0);
+        assert!(older.check_compatible(&host).is_ok());
+
+        let mut newer = info.clone();
+        newer.sdk_version.minor += 1;
+        assert!(newer.check_compatible(&host).is_err());
+
+        let mut next_major = info.clone();
+        next_major.sdk_version = Version::new(host.sdk_version.major + 1, 0, 0);
+        assert!(next_major.check_compatible(&host).is_err());
+
+        let mut other_rustc = info.clone();
+        other_rustc.rustc_version_hash ^= 1;
+        assert!(other_rustc.check_compatible(&host).is_err());
+
+        let mut other_abi = info;
+        other_abi.abi_version += 1;
+        assert!(other_abi.check_compatible(&host).is_err());
+    }
+}
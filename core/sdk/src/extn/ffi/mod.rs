@@ -15,4 +15,5 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
+pub mod ffi_abi;
 pub mod ffi_channel;
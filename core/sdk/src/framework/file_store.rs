@@ -15,20 +15,45 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
-use log::{debug, info, warn};
-use serde::{de::DeserializeOwned, Serialize};
+use log::{debug, error, info, warn};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
 use std::{
-    fs::{self, OpenOptions},
+    fs::{self, File},
     io::Write,
     path::Path,
+    sync::Arc,
 };
 
 use crate::utils::error::RippleError;
 
+use super::store_encryption::{self, StoreKeyProvider};
+
+/// Schema version of stores which do not declare one
+pub const DEFAULT_STORE_VERSION: u32 = 1;
+
+const TEMP_SUFFIX: &str = "tmp";
+const BACKUP_SUFFIX: &str = "bak";
+const CORRUPT_SUFFIX: &str = "corrupt";
+/// Marks a store which has been written encrypted, plaintext is no longer migrated once it exists
+const SEALED_SUFFIX: &str = "sealed";
+
+/// Upgrades stored content from the version it is registered for to the next version. Files
+/// written before stores were versioned are loaded as version 0.
+pub type FileStoreMigration = fn(Value) -> Result<Value, RippleError>;
+
+#[derive(Serialize, Deserialize)]
+struct VersionedContent<T> {
+    version: u32,
+    data: T,
+}
+
 #[derive(Debug, Clone)]
 pub struct FileStore<S> {
     pub value: S,
     path: String,
+    version: u32,
+    keys: Option<Arc<dyn StoreKeyProvider>>,
 }
 
 impl<S> FileStore<S>
@@ -36,61 +61,486 @@ where
     S: Serialize + DeserializeOwned + Clone,
 {
     pub fn new(path: String, value: S) -> FileStore<S> {
+        Self::new_versioned(path, value, DEFAULT_STORE_VERSION)
+    }
+
+    pub fn new_versioned(path: String, value: S, version: u32) -> FileStore<S> {
         FileStore {
             value,
             path: Path::new(&path).to_str().unwrap().into(),
+            version,
+            keys: None,
+        }
+    }
+
+    /// New store holding `value`, encrypted at rest when a key provider is given
+    pub fn new_encrypted(
+        path: String,
+        value: S,
+        keys: Option<Arc<dyn StoreKeyProvider>>,
+    ) -> FileStore<S> {
+        let store = Self::new(path, value);
+        match keys {
+            Some(keys) => store.with_encryption(keys),
+            None => store,
         }
     }
 
-    fn write_to_disk(&self, value: String) {
+    /// Encrypts the store at rest with keys from the provider
+    pub fn with_encryption(mut self, keys: Arc<dyn StoreKeyProvider>) -> FileStore<S> {
+        self.keys = Some(keys);
+        self
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.keys.is_some()
+    }
+
+    /// Associated data binding encrypted content to the store it belongs to
+    fn aad(path: &str) -> Vec<u8> {
+        Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().as_bytes().to_vec())
+            .unwrap_or_default()
+    }
+
+    pub fn get_version(&self) -> u32 {
+        self.version
+    }
+
+    fn sibling(&self, suffix: &str) -> String {
+        format!("{}.{}", self.path, suffix)
+    }
+
+    /// Writes to a temporary file which replaces the store with a rename, so the store is never
+    /// left partially written. The previous generation is kept as a backup.
+    fn write_to_disk(&self, value: String) -> bool {
         // Create the folder if it doesnt exist
         let p = Path::new(&self.path);
         if let Some(parent) = p.parent() {
             let _ = fs::create_dir_all(parent);
         }
-        match OpenOptions::new()
-            .create(true)
-            .truncate(true)
-            .write(true)
-            .open(self.path.clone())
+        if let Err(e) = self.write_atomic(p, value) {
+            warn!("Failed to write file store for {:?} {}", e, self.path);
+            return false;
+        }
+        true
+    }
+
+    fn write_atomic(&self, path: &Path, value: String) -> std::io::Result<()> {
+        let temp = self.sibling(TEMP_SUFFIX);
         {
-            Ok(mut file) => {
-                if let Err(e) = file.write_all(value.as_bytes()) {
-                    warn!("Failed to write file store for {:?} {}", e, self.path);
+            let mut file = File::create(&temp)?;
+            file.write_all(value.as_bytes())?;
+            file.sync_all()?;
+        }
+        if path.is_file() {
+            fs::copy(path, self.sibling(BACKUP_SUFFIX))?;
+        }
+        fs::rename(&temp, path)?;
+        // Persist the rename, directories cannot be opened for sync on every platform
+        if let Some(parent) = path.parent().and_then(|p| File::open(p).ok()) {
+            let _ = parent.sync_all();
+        }
+        Ok(())
+    }
+
+    pub fn sync(&mut self) {
+        let new_value_string = serde_json::to_string(&VersionedContent {
+            version: self.version,
+            data: &self.value,
+        })
+        .unwrap();
+        match &self.keys {
+            Some(keys) => {
+                match store_encryption::seal(
+                    keys.as_ref(),
+                    &Self::aad(&self.path),
+                    new_value_string.as_bytes(),
+                ) {
+                    Ok(sealed) => {
+                        let marker = self.sibling(SEALED_SUFFIX);
+                        if self.write_to_disk(sealed) && !Path::new(&marker).is_file() {
+                            if let Err(e) = File::create(&marker).and_then(|f| f.sync_all()) {
+                                warn!("Failed to mark file store {} as sealed {:?}", self.path, e);
+                            }
+                        }
+                    }
+                    // Never fall back to writing plaintext
+                    Err(e) => error!("Failed to encrypt file store {} {:?}", self.path, e),
                 }
             }
-            Err(e) => {
-                warn!("Failed to open file store for {} {:?}", self.path, e);
+            None => {
+                self.write_to_disk(new_value_string);
             }
         }
     }
 
-    pub fn sync(&mut self) {
-        let new_value_string = serde_json::to_string(&self.value).unwrap();
-        self.write_to_disk(new_value_string);
+    fn load_from_content(
+        contents: String,
+        version: u32,
+        migrations: &[(u32, FileStoreMigration)],
+    ) -> Result<(S, u32), RippleError> {
+        let value = serde_json::from_str::<Value>(&contents).map_err(|err| {
+            warn!("{:?} could not parse file content", err);
+            RippleError::ParseError
+        })?;
+        let (stored_version, mut data) = match value {
+            Value::Object(mut content)
+                if content.len() == 2
+                    && content.get("version").map_or(false, Value::is_u64)
+                    && content.contains_key("data") =>
+            {
+                let stored = content.get("version").and_then(Value::as_u64).unwrap();
+                (stored as u32, content.remove("data").unwrap())
+            }
+            unversioned => (0, unversioned),
+        };
+
+        if stored_version > version {
+            error!(
+                "file content version {} is newer than supported version {}",
+                stored_version, version
+            );
+            return Err(RippleError::InvalidAccess);
+        }
+        for from in stored_version..version {
+            if let Some((_, migration)) = migrations.iter().find(|(v, _)| *v == from) {
+                debug!("migrating file content from version {}", from);
+                data = migration(data)?;
+            }
+        }
+
+        let value = serde_json::from_value::<S>(data).map_err(|err| {
+            warn!("{:?} could not parse file content", err);
+            RippleError::ParseError
+        })?;
+        Ok((value, stored_version))
     }
 
-    fn load_from_content(contents: String) -> Result<S, RippleError> {
-        match serde_json::from_str::<S>(&contents) {
-            Ok(s) => Ok(s),
-            Err(err) => {
-                warn!("{:?} could not parse file content", err);
-                Err(RippleError::ParseError)
+    /// Returns the value, the version it was stored with and whether it has to be written again
+    fn read(
+        path: &str,
+        aad: &[u8],
+        version: u32,
+        migrations: &[(u32, FileStoreMigration)],
+        keys: Option<&dyn StoreKeyProvider>,
+        migrate_plaintext: bool,
+    ) -> Option<(S, u32, bool)> {
+        let contents = fs::read_to_string(path).ok()?;
+        let result =
+            store_encryption::open(keys, aad, contents, migrate_plaintext).and_then(|opened| {
+                Self::load_from_content(opened.contents, version, migrations)
+                    .map(|(s, stored_version)| (s, stored_version, opened.rewrite))
+            });
+        match result {
+            Ok(s) => {
+                debug!("valid filestore content from {}", path);
+                Some(s)
+            }
+            Err(e) => {
+                error!("file store {} is not valid {:?}", path, e);
+                None
             }
         }
     }
 
+    /// Moves an unreadable file aside so later writes never replace it, earlier corrupt files
+    /// are kept as well
+    fn move_aside(path: &str) {
+        let mut corrupt = format!("{}.{}", path, CORRUPT_SUFFIX);
+        let mut n = 0;
+        while Path::new(&corrupt).exists() {
+            n += 1;
+            corrupt = format!("{}.{}.{}", path, CORRUPT_SUFFIX, n);
+        }
+        error!("file store {} is corrupt, moving it to {}", path, corrupt);
+        if let Err(e) = fs::rename(path, &corrupt) {
+            error!("Failed to move {} aside {:?}", path, e);
+        }
+    }
+
     pub fn load(path: String) -> Result<FileStore<S>, RippleError> {
-        if let Ok(contents) = fs::read_to_string(&path) {
-            if let Ok(s) = Self::load_from_content(contents.clone()) {
-                debug!("valid filestore content {} from {}", contents, path);
-                Ok(FileStore { value: s, path })
-            } else {
-                Err(RippleError::InvalidAccess)
-            }
-        } else {
+        Self::load_versioned(path, DEFAULT_STORE_VERSION, &[])
+    }
+
+    /// Loads the store and migrates it to `version`. A store which can not be read is moved
+    /// aside with a `.corrupt` suffix and the backup generation is used instead. Returns
+    /// `MissingInput` when there is no store and `InvalidAccess` when neither the store nor its
+    /// backup is valid, both are then moved aside and nothing is written.
+    pub fn load_versioned(
+        path: String,
+        version: u32,
+        migrations: &[(u32, FileStoreMigration)],
+    ) -> Result<FileStore<S>, RippleError> {
+        Self::load_with_keys(path, version, migrations, None)
+    }
+
+    /// Loads an encrypted store. Plaintext stores are encrypted, once, and content encrypted with
+    /// a retired key is encrypted again with the current key. Plaintext found in a store which
+    /// has already been encrypted is treated as corrupt.
+    pub fn load_encrypted(
+        path: String,
+        version: u32,
+        migrations: &[(u32, FileStoreMigration)],
+        keys: Arc<dyn StoreKeyProvider>,
+    ) -> Result<FileStore<S>, RippleError> {
+        Self::load_with_keys(path, version, migrations, Some(keys))
+    }
+
+    fn load_with_keys(
+        path: String,
+        version: u32,
+        migrations: &[(u32, FileStoreMigration)],
+        keys: Option<Arc<dyn StoreKeyProvider>>,
+    ) -> Result<FileStore<S>, RippleError> {
+        let aad = Self::aad(&path);
+        let backup = format!("{}.{}", path, BACKUP_SUFFIX);
+        let has_store = Path::new(&path).is_file();
+        if !has_store && !Path::new(&backup).is_file() {
             info!("No file found in {}", path);
+            return Err(RippleError::MissingInput);
+        }
+
+        let migrate_plaintext = !Path::new(&format!("{}.{}", path, SEALED_SUFFIX)).is_file();
+        let mut value = None;
+        if has_store {
+            value = Self::read(
+                &path,
+                &aad,
+                version,
+                migrations,
+                keys.as_deref(),
+                migrate_plaintext,
+            );
+            if value.is_none() {
+                Self::move_aside(&path);
+            }
+        }
+        let recovered = value.is_none();
+        if recovered && Path::new(&backup).is_file() {
+            value = Self::read(
+                &backup,
+                &aad,
+                version,
+                migrations,
+                keys.as_deref(),
+                migrate_plaintext,
+            );
+            match value {
+                Some(_) => warn!("file store {} recovered from {}", path, backup),
+                // the next write would rotate a new store over the backup
+                None => Self::move_aside(&backup),
+            }
+        }
+
+        let (value, stored_version, rewrite) = value.ok_or_else(|| {
+            error!("file store {} can not be recovered", path);
+            RippleError::InvalidAccess
+        })?;
+        let mut store = Self::new_versioned(path, value, version);
+        store.keys = keys;
+        if recovered || rewrite || stored_version != version {
+            // Persist recovered or migrated content so the store carries its current version
+            // and key
+            store.sync();
+            if rewrite && store.keys.is_some() {
+                // The backup generation still holds plaintext or a retired key
+                store.sync();
+            }
+        }
+        Ok(store)
+    }
+
+    /// Loads the store, or creates a new store holding `value` when there is none. A store
+    /// which can not be recovered is an error, its files are kept aside.
+    pub fn load_or_new(path: String, value: S) -> Result<FileStore<S>, RippleError> {
+        Self::load_or_new_encrypted(path, value, None)
+    }
+
+    /// [FileStore::load_or_new] for a store which is encrypted when a key provider is given
+    pub fn load_or_new_encrypted(
+        path: String,
+        value: S,
+        keys: Option<Arc<dyn StoreKeyProvider>>,
+    ) -> Result<FileStore<S>, RippleError> {
+        match Self::load_with_keys(path.clone(), DEFAULT_STORE_VERSION, &[], keys.clone()) {
+            Err(RippleError::MissingInput) => Ok(Self::new_encrypted(path, value, keys)),
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn store_path(name: &str) -> String {
+        let dir =
+            std::env::temp_dir().join(format!("ripple_file_store_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("store").to_str().unwrap().to_owned()
+    }
+
+    #[test]
+    fn test_sync_and_load() {
+        let path = store_path("sync");
+        assert!(matches!(
+            FileStore::<HashMap<String, u32>>::load(path.clone()),
             Err(RippleError::MissingInput)
+        ));
+
+        let mut store = FileStore::new(path.clone(), HashMap::from([("a".to_owned(), 1)]));
+        store.sync();
+        store.value.insert("b".to_owned(), 2);
+        store.sync();
+
+        let contents: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(contents["version"], DEFAULT_STORE_VERSION);
+        assert_eq!(contents["data"]["b"], 2);
+        assert!(!Path::new(&format!("{}.{}", path, TEMP_SUFFIX)).exists());
+
+        let loaded = FileStore::<HashMap<String, u32>>::load(path).unwrap();
+        assert_eq!(loaded.value.len(), 2);
+    }
+
+    #[test]
+    fn test_truncated_store_recovers_from_backup() {
+        let path = store_path("truncated");
+        let mut store = FileStore::new(path.clone(), vec![1, 2, 3]);
+        store.sync();
+        store.value.push(4);
+        store.sync();
+        // Power cut in the middle of an in place write
+        fs::write(&path, "{\"version\":1,\"data\":[1,2").unwrap();
+
+        let loaded = FileStore::<Vec<u32>>::load(path.clone()).unwrap();
+        assert_eq!(loaded.value, vec![1, 2, 3]);
+        assert!(Path::new(&format!("{}.{}", path, CORRUPT_SUFFIX)).exists());
+
+        // Nothing valid left to recover from
+        fs::write(&path, "[").unwrap();
+        fs::write(format!("{}.{}", path, BACKUP_SUFFIX), "[").unwrap();
+        assert!(matches!(
+            FileStore::<Vec<u32>>::load(path.clone()),
+            Err(RippleError::InvalidAccess)
+        ));
+        // both generations are kept aside and never written over
+        assert!(!Path::new(&path).exists());
+        assert_eq!(
+            fs::read_to_string(format!("{}.{}.1", path, CORRUPT_SUFFIX)).unwrap(),
+            "["
+        );
+        assert_eq!(
+            fs::read_to_string(format!("{}.{}.{}", path, BACKUP_SUFFIX, CORRUPT_SUFFIX)).unwrap(),
+            "["
+        );
+        let mut store = FileStore::load_or_new(path.clone(), vec![9]).unwrap();
+        store.sync();
+        store.sync();
+        assert_eq!(
+            fs::read_to_string(format!("{}.{}.{}", path, BACKUP_SUFFIX, CORRUPT_SUFFIX)).unwrap(),
+            "["
+        );
+
+        fs::write(&path, "[").unwrap();
+        fs::write(format!("{}.{}", path, BACKUP_SUFFIX), "[").unwrap();
+        assert!(matches!(
+            FileStore::load_or_new(path.clone(), vec![9]),
+            Err(RippleError::InvalidAccess)
+        ));
+        assert!(Path::new(&format!("{}.{}.2", path, CORRUPT_SUFFIX)).exists());
+    }
+
+    #[test]
+    fn test_migrations() {
+        fn wrap_values(value: Value) -> Result<Value, RippleError> {
+            match value {
+                Value::Object(o) => Ok(Value::Object(
+                    o.into_iter()
+                        .map(|(k, v)| (k, serde_json::json!({ "value": v })))
+                        .collect(),
+                )),
+                _ => Err(RippleError::ParseError),
+            }
         }
+
+        let path = store_path("migrations");
+        fs::create_dir_all(Path::new(&path).parent().unwrap()).unwrap();
+        // Unversioned content written by earlier releases
+        fs::write(&path, "{\"a\":true}").unwrap();
+
+        let migrations: [(u32, FileStoreMigration); 1] = [(1, wrap_values)];
+        let store = FileStore::<HashMap<String, HashMap<String, bool>>>::load_versioned(
+            path.clone(),
+            2,
+            &migrations,
+        )
+        .unwrap();
+        assert_eq!(store.get_version(), 2);
+        assert!(store.value["a"]["value"]);
+
+        let contents: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(contents["version"], 2);
+
+        // Content written by a newer release is not loaded
+        assert!(FileStore::<HashMap<String, HashMap<String, bool>>>::load(path).is_err());
+    }
+
+    #[test]
+    fn test_encrypted_store() {
+        use crate::framework::store_encryption::{KeystoreKeyProvider, StoreKeyProvider};
+
+        let path = store_path("encrypted");
+        // Existing plaintext store
+        let mut plain = FileStore::new(path.clone(), vec!["granted".to_owned()]);
+        plain.sync();
+
+        let key_path = format!("{}.keys", path);
+        let keystore = Arc::new(KeystoreKeyProvider::new(key_path));
+        let first_key = keystore.current_key().unwrap();
+        let store = FileStore::<Vec<String>>::load_or_new_encrypted(
+            path.clone(),
+            Vec::new(),
+            Some(keystore.clone()),
+        )
+        .unwrap();
+        assert!(store.is_encrypted());
+        assert_eq!(store.value, vec!["granted".to_owned()]);
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("granted"));
+        assert!(contents.contains(&first_key.id));
+        let backup = fs::read_to_string(format!("{}.{}", path, BACKUP_SUFFIX)).unwrap();
+        assert!(!backup.contains("granted"));
+
+        // Encrypted stores can not be read without the key
+        assert!(FileStore::<Vec<String>>::load(path.clone()).is_err());
+        fs::rename(format!("{}.{}", path, CORRUPT_SUFFIX), &path).unwrap();
+
+        // Key rotation re-encrypts with the new key on load
+        let second_key = keystore.rotate().unwrap();
+        let store = FileStore::<Vec<String>>::load_encrypted(
+            path.clone(),
+            DEFAULT_STORE_VERSION,
+            &[],
+            keystore.clone(),
+        )
+        .unwrap();
+        assert_eq!(store.value, vec!["granted".to_owned()]);
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.contains(&second_key.id));
+
+        // Plaintext is not migrated a second time, the encrypted backup is used instead
+        fs::write(&path, "[\"forged\"]").unwrap();
+        let store = FileStore::<Vec<String>>::load_encrypted(
+            path.clone(),
+            DEFAULT_STORE_VERSION,
+            &[],
+            keystore,
+        )
+        .unwrap();
+        assert_eq!(store.value, vec!["granted".to_owned()]);
+        assert!(!fs::read_to_string(&path).unwrap().contains("forged"));
     }
 }
@@ -20,4 +20,5 @@ use crate::utils::error::RippleError;
 pub mod bootstrap;
 pub mod file_store;
 pub mod ripple_contract;
+pub mod store_encryption;
 pub type RippleResponse = Result<(), RippleError>;
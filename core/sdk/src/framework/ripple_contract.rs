@@ -17,6 +17,7 @@
 
 use crate::{
     api::{
+        firebolt::fb_capabilities::FireboltCap,
         session::{EventAdjective, SessionAdjective},
         storage_property::StorageAdjective,
     },
@@ -226,6 +227,39 @@ impl RippleContract {
             None
         }
     }
+
+    /// Firebolt capabilities which are unavailable while no extension fulfills this contract
+    pub fn get_capabilities(&self) -> Vec<FireboltCap> {
+        let caps: &[&str] = match self {
+            Self::DeviceInfo => &[
+                "device:info",
+                "device:make",
+                "device:model",
+                "device:name",
+                "device:sku",
+            ],
+            Self::Wifi => &["protocol:wifi"],
+            Self::Keyboard => &["input:keyboard"],
+            Self::PinChallenge => &["usergrant:pinchallenge"],
+            Self::Launcher => &["lifecycle:launch"],
+            Self::Storage(StorageAdjective::Secure) => &["storage:secure"],
+            Self::Session(SessionAdjective::Account) => {
+                &["token:account", "account:id", "account:uid"]
+            }
+            Self::DeviceEvents(EventAdjective::VoiceGuidance) => &["accessibility:voiceguidance"],
+            _ => &[],
+        };
+        caps.iter().map(|c| FireboltCap::short(*c)).collect()
+    }
+
+    /// Contract of the events Main sends to the extensions which use this contract, the
+    /// telemetry events of an `observability` subscription for instance
+    pub fn get_event_contract(&self) -> Option<RippleContract> {
+        match self {
+            RippleContract::Observability => Some(RippleContract::TelemetryEventsListener),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -273,7 +307,8 @@ impl From<ContractFulfiller> for String {
 #[cfg(test)]
 mod tests {
     use crate::{
-        api::storage_property::StorageAdjective, framework::ripple_contract::RippleContract,
+        api::{firebolt::fb_capabilities::FireboltCap, storage_property::StorageAdjective},
+        framework::ripple_contract::RippleContract,
     };
 
     #[test]
@@ -309,6 +344,18 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_get_capabilities() {
+        assert!(RippleContract::DeviceInfo
+            .get_capabilities()
+            .contains(&FireboltCap::Short("device:name".to_owned())));
+        assert_eq!(
+            RippleContract::Storage(StorageAdjective::Secure).get_capabilities(),
+            vec![FireboltCap::Short("storage:secure".to_owned())]
+        );
+        assert!(RippleContract::Config.get_capabilities().is_empty());
+    }
+
     #[test]
     fn test_from_manifest() {
         assert!(RippleContract::from_manifest("permissions").is_some());
@@ -0,0 +1,577 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::Arc,
+};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::utils::error::RippleError;
+
+pub const KEY_LENGTH: usize = 32;
+
+/// HKDF info labels, a store key is never used directly so encryption and signatures never
+/// share key material
+const ENCRYPTION_KEY_INFO: &[u8] = b"ripple store encryption";
+const SIGNATURE_KEY_INFO: &[u8] = b"ripple content signature";
+
+/// Key used to encrypt file stores. Stores record the id of the key they were encrypted with so
+/// older keys can still decrypt them after a rotation.
+#[derive(Clone)]
+pub struct StoreKey {
+    pub id: String,
+    key: [u8; KEY_LENGTH],
+}
+
+impl std::fmt::Debug for StoreKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "StoreKey {{ id: {} }}", self.id)
+    }
+}
+
+impl StoreKey {
+    pub fn new(id: String, key: [u8; KEY_LENGTH]) -> StoreKey {
+        StoreKey { id, key }
+    }
+
+    pub fn generate(id: String) -> StoreKey {
+        let key = Aes256Gcm::generate_key(OsRng);
+        StoreKey::new(id, key.into())
+    }
+
+    pub fn from_base64(id: &str, encoded: &str) -> Result<StoreKey, RippleError> {
+        let key: [u8; KEY_LENGTH] = STANDARD
+            .decode(encoded.trim())
+            .ok()
+            .and_then(|k| k.try_into().ok())
+            .ok_or_else(|| {
+                error!("store key {} is not a base64 encoded 256 bit key", id);
+                RippleError::InvalidInput
+            })?;
+        Ok(StoreKey::new(id.to_owned(), key))
+    }
+
+    pub fn to_base64(&self) -> String {
+        STANDARD.encode(self.key)
+    }
+
+    fn derive(&self, info: &[u8]) -> [u8; KEY_LENGTH] {
+        let mut derived = [0u8; KEY_LENGTH];
+        Hkdf::<Sha256>::new(None, &self.key)
+            .expand(info, &mut derived)
+            .expect("a 256 bit key is a valid HKDF output length");
+        derived
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        let key = self.derive(ENCRYPTION_KEY_INFO);
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+    }
+}
+
+/// Source of the keys used to encrypt file stores
+pub trait StoreKeyProvider: Send + Sync + std::fmt::Debug {
+    /// Key new content is encrypted with
+    fn current_key(&self) -> Result<StoreKey, RippleError>;
+    /// Key with the given id, retired keys are kept to decrypt stores written before a rotation
+    fn get_key(&self, id: &str) -> Result<StoreKey, RippleError>;
+}
+
+/// Keys by id along with the id of the current key
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KeyRing {
+    pub current: String,
+    pub keys: HashMap<String, String>,
+}
+
+impl KeyRing {
+    fn current_key(&self) -> Result<StoreKey, RippleError> {
+        self.get_key(&self.current)
+    }
+
+    fn get_key(&self, id: &str) -> Result<StoreKey, RippleError> {
+        let encoded = self.keys.get(id).ok_or_else(|| {
+            error!("store key {} not found", id);
+            RippleError::NotAvailable
+        })?;
+        StoreKey::from_base64(id, encoded)
+    }
+
+    fn load(path: &str) -> Result<KeyRing, RippleError> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            error!("could not read key ring {} {:?}", path, e);
+            RippleError::MissingInput
+        })?;
+        serde_json::from_str(&contents).map_err(|e| {
+            error!("could not parse key ring {} {:?}", path, e);
+            RippleError::ParseError
+        })
+    }
+}
+
+/// Reads a [KeyRing] from a json file on every use, so a key rotated on disk is picked up
+#[derive(Debug)]
+pub struct FileKeyProvider {
+    path: String,
+}
+
+impl FileKeyProvider {
+    pub fn new(path: String) -> FileKeyProvider {
+        FileKeyProvider { path }
+    }
+}
+
+impl StoreKeyProvider for FileKeyProvider {
+    fn current_key(&self) -> Result<StoreKey, RippleError> {
+        KeyRing::load(&self.path)?.current_key()
+    }
+
+    fn get_key(&self, id: &str) -> Result<StoreKey, RippleError> {
+        KeyRing::load(&self.path)?.get_key(id)
+    }
+}
+
+/// Reads keys from an environment variable holding comma separated `<id>:<base64 key>` pairs,
+/// the first pair is the current key
+#[derive(Debug)]
+pub struct EnvKeyProvider {
+    variable: String,
+}
+
+impl EnvKeyProvider {
+    pub fn new(variable: String) -> EnvKeyProvider {
+        EnvKeyProvider { variable }
+    }
+
+    fn keys(&self) -> Result<Vec<StoreKey>, RippleError> {
+        let value = std::env::var(&self.variable).map_err(|_| {
+            error!("store key variable {} is not set", self.variable);
+            RippleError::MissingInput
+        })?;
+        value
+            .split(',')
+            .filter(|entry| !entry.trim().is_empty())
+            .map(|entry| match entry.split_once(':') {
+                Some((id, key)) => StoreKey::from_base64(id.trim(), key),
+                None => Err(RippleError::InvalidInput),
+            })
+            .collect()
+    }
+}
+
+impl StoreKeyProvider for EnvKeyProvider {
+    fn current_key(&self) -> Result<StoreKey, RippleError> {
+        self.keys()?
+            .into_iter()
+            .next()
+            .ok_or(RippleError::MissingInput)
+    }
+
+    fn get_key(&self, id: &str) -> Result<StoreKey, RippleError> {
+        self.keys()?
+            .into_iter()
+            .find(|k| k.id == id)
+            .ok_or(RippleError::NotAvailable)
+    }
+}
+
+/// Stand-in for a device keystore. The key ring lives in a file only readable by the owner and a
+/// key is generated on first use.
+#[derive(Debug)]
+pub struct KeystoreKeyProvider {
+    path: String,
+}
+
+impl KeystoreKeyProvider {
+    pub fn new(path: String) -> KeystoreKeyProvider {
+        KeystoreKeyProvider { path }
+    }
+
+    fn key_ring(&self) -> Result<KeyRing, RippleError> {
+        if Path::new(&self.path).is_file() {
+            return KeyRing::load(&self.path);
+        }
+        info!("creating store key ring in {}", self.path);
+        self.rotate_ring(KeyRing::default())
+    }
+
+    /// Makes a newly generated key the current key, older keys are kept for decryption
+    pub fn rotate(&self) -> Result<StoreKey, RippleError> {
+        self.rotate_ring(self.key_ring()?)?.current_key()
+    }
+
+    fn rotate_ring(&self, mut ring: KeyRing) -> Result<KeyRing, RippleError> {
+        let key = StoreKey::generate(uuid::Uuid::new_v4().simple().to_string());
+        ring.keys.insert(key.id.clone(), key.to_base64());
+        ring.current = key.id;
+        self.write(&ring)?;
+        Ok(ring)
+    }
+
+    fn write(&self, ring: &KeyRing) -> Result<(), RippleError> {
+        if let Some(parent) = Path::new(&self.path).parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let mut options = OpenOptions::new();
+        options.create(true).truncate(true).write(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let contents = serde_json::to_string(ring).map_err(|_| RippleError::ParseError)?;
+        options
+            .open(&self.path)
+            .and_then(|mut file| {
+                file.write_all(contents.as_bytes())?;
+                file.sync_all()
+            })
+            .map_err(|e| {
+                error!("could not write key ring {} {:?}", self.path, e);
+                RippleError::InvalidOutput
+            })
+    }
+}
+
+impl StoreKeyProvider for KeystoreKeyProvider {
+    fn current_key(&self) -> Result<StoreKey, RippleError> {
+        self.key_ring()?.current_key()
+    }
+
+    fn get_key(&self, id: &str) -> Result<StoreKey, RippleError> {
+        self.key_ring()?.get_key(id)
+    }
+}
+
+/// Device manifest configuration of the key provider used to encrypt stores at rest
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "provider", rename_all = "lowercase")]
+pub enum StoreEncryptionConfig {
+    File { path: String },
+    Env { variable: String },
+    Keystore { path: String },
+}
+
+impl StoreEncryptionConfig {
+    /// Whether the keys are provisioned from outside the device, keystore keys are generated on
+    /// the device and lost on a factory reset
+    pub fn is_portable(&self) -> bool {
+        !matches!(self, StoreEncryptionConfig::Keystore { .. })
+    }
+
+    pub fn key_provider(&self) -> Arc<dyn StoreKeyProvider> {
+        match self {
+            StoreEncryptionConfig::File { path } => Arc::new(FileKeyProvider::new(path.clone())),
+            StoreEncryptionConfig::Env { variable } => {
+                Arc::new(EnvKeyProvider::new(variable.clone()))
+            }
+            StoreEncryptionConfig::Keystore { path } => {
+                Arc::new(KeystoreKeyProvider::new(path.clone()))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct EncryptedContent {
+    key_id: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EncryptedStore {
+    encrypted: EncryptedContent,
+}
+
+/// Encrypts with AES-256-GCM using the current key. `aad` binds the content to the store it was
+/// written for, so content copied between stores fails to decrypt.
+pub fn seal(
+    keys: &dyn StoreKeyProvider,
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Result<String, RippleError> {
+    let key = keys.current_key()?;
+    let cipher = key.cipher();
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| RippleError::InvalidOutput)?;
+    serde_json::to_string(&EncryptedStore {
+        encrypted: EncryptedContent {
+            key_id: key.id,
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        },
+    })
+    .map_err(|_| RippleError::ParseError)
+}
+
+/// Content read from a store along with whether it has to be written again, which is the case
+/// for migrated plaintext content and for content encrypted with a retired key.
+#[derive(Debug)]
+pub struct OpenedContent {
+    pub contents: String,
+    pub rewrite: bool,
+}
+
+/// Decrypts content written by [seal]. Without a key provider content is returned as is. With
+/// one, plaintext is only accepted when `migrate_plaintext` is set, which is the case until the
+/// store has been written encrypted once, so plaintext can not be swapped in afterwards.
+pub fn open(
+    keys: Option<&dyn StoreKeyProvider>,
+    aad: &[u8],
+    contents: String,
+    migrate_plaintext: bool,
+) -> Result<OpenedContent, RippleError> {
+    let Ok(store) = serde_json::from_str::<EncryptedStore>(&contents) else {
+        if keys.is_some() && !migrate_plaintext {
+            error!("store is expected to be encrypted but holds plaintext");
+            return Err(RippleError::InvalidAccess);
+        }
+        return Ok(OpenedContent {
+            contents,
+            rewrite: keys.is_some(),
+        });
+    };
+    let Some(keys) = keys else {
+        error!("store is encrypted but no key provider is configured");
+        return Err(RippleError::InvalidAccess);
+    };
+
+    let content = store.encrypted;
+    let key = keys.get_key(&content.key_id)?;
+    let nonce = STANDARD
+        .decode(&content.nonce)
+        .map_err(|_| RippleError::ParseError)?;
+    let ciphertext = STANDARD
+        .decode(&content.ciphertext)
+        .map_err(|_| RippleError::ParseError)?;
+    if nonce.len() != 12 {
+        return Err(RippleError::ParseError);
+    }
+    let plaintext = key
+        .cipher()
+        .decrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| {
+            error!("store content failed authentication");
+            RippleError::InvalidAccess
+        })?;
+    let contents = String::from_utf8(plaintext).map_err(|_| RippleError::ParseError)?;
+    let rewrite = keys.current_key()?.id != content.key_id;
+    Ok(OpenedContent { contents, rewrite })
+}
+
+/// HMAC-SHA256 of some content along with the id of the key used
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentSignature {
+    pub key_id: String,
+    pub signature: String,
+}
+
+fn hmac(key: &StoreKey, data: &[u8]) -> Hmac<Sha256> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&key.derive(SIGNATURE_KEY_INFO))
+        .expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac
+}
+
+/// Signs `data` with the current key, so content leaving the device can be checked by a device
+/// sharing the same keys. Use a provider whose keys are provisioned rather than generated on
+/// the device, see [StoreEncryptionConfig::is_portable].
+pub fn sign(keys: &dyn StoreKeyProvider, data: &[u8]) -> Result<ContentSignature, RippleError> {
+    let key = keys.current_key()?;
+    let signature = STANDARD.encode(hmac(&key, data).finalize().into_bytes());
+    Ok(ContentSignature {
+        key_id: key.id,
+        signature,
+    })
+}
+
+/// Checks a signature created by [sign], retired keys are accepted
+pub fn verify(
+    keys: &dyn StoreKeyProvider,
+    data: &[u8],
+    signature: &ContentSignature,
+) -> Result<(), RippleError> {
+    let key = keys.get_key(&signature.key_id)?;
+    let expected = STANDARD
+        .decode(&signature.signature)
+        .map_err(|_| RippleError::ParseError)?;
+    hmac(&key, data).verify_slice(&expected).map_err(|_| {
+        error!("content signature does not match");
+        RippleError::InvalidAccess
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct TestKeys(Vec<StoreKey>);
+
+    impl StoreKeyProvider for TestKeys {
+        fn current_key(&self) -> Result<StoreKey, RippleError> {
+            Ok(self.0[0].clone())
+        }
+
+        fn get_key(&self, id: &str) -> Result<StoreKey, RippleError> {
+            self.0
+                .iter()
+                .find(|k| k.id == id)
+                .cloned()
+                .ok_or(RippleError::NotAvailable)
+        }
+    }
+
+    #[test]
+    fn test_seal_and_open() {
+        let old = StoreKey::generate("old".to_owned());
+        let keys = TestKeys(vec![old.clone()]);
+        let sealed = seal(&keys, b"app_grants", b"{\"a\":1}").unwrap();
+        assert!(!sealed.contains("\"a\""));
+
+        let opened = open(Some(&keys), b"app_grants", sealed.clone(), false).unwrap();
+        assert_eq!(opened.contents, "{\"a\":1}");
+        assert!(!opened.rewrite);
+
+        // Bound to the store it was written for
+        assert!(open(Some(&keys), b"device_grants", sealed.clone(), false).is_err());
+        assert!(open(None, b"app_grants", sealed.clone(), false).is_err());
+
+        // Rotated keys still decrypt and ask for the content to be written again
+        let rotated = TestKeys(vec![StoreKey::generate("new".to_owned()), old]);
+        assert!(
+            open(Some(&rotated), b"app_grants", sealed, false)
+                .unwrap()
+                .rewrite
+        );
+
+        // Plaintext is migrated once and rejected afterwards
+        let plaintext = open(Some(&keys), b"app_grants", "{}".to_owned(), true).unwrap();
+        assert!(plaintext.rewrite);
+        assert!(matches!(
+            open(Some(&keys), b"app_grants", "{}".to_owned(), false),
+            Err(RippleError::InvalidAccess)
+        ));
+        assert!(
+            !open(None, b"app_grants", "{}".to_owned(), false)
+                .unwrap()
+                .rewrite
+        );
+    }
+
+    #[test]
+    fn test_key_providers() {
+        let key = StoreKey::generate("k1".to_owned());
+        let variable = format!("RIPPLE_TEST_STORE_KEYS_{}", std::process::id());
+        std::env::set_var(
+            &variable,
+            format!("k1:{}, k0:{}", key.to_base64(), key.to_base64()),
+        );
+        let env = EnvKeyProvider::new(variable);
+        assert_eq!(env.current_key().unwrap().id, "k1");
+        assert!(env.get_key("k0").is_ok());
+        assert!(env.get_key("k2").is_err());
+
+        let dir = std::env::temp_dir().join(format!("ripple_store_keys_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let keystore = KeystoreKeyProvider::new(dir.join("keys").to_str().unwrap().to_owned());
+        let first = keystore.current_key().unwrap();
+        assert_eq!(keystore.current_key().unwrap().id, first.id);
+        let second = keystore.rotate().unwrap();
+        assert_ne!(first.id, second.id);
+        assert!(keystore.get_key(&first.id).is_ok());
+
+        let file = FileKeyProvider::new(dir.join("keys").to_str().unwrap().to_owned());
+        assert_eq!(file.current_key().unwrap().id, second.id);
+
+        let config: StoreEncryptionConfig =
+            serde_json::from_str("{\"provider\": \"env\", \"variable\": \"KEYS\"}").unwrap();
+        assert_eq!(
+            config,
+            StoreEncryptionConfig::Env {
+                variable: "KEYS".to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let old = StoreKey::generate("old".to_owned());
+        let signature = sign(&TestKeys(vec![old.clone()]), b"bundle").unwrap();
+        assert_eq!(signature.key_id, "old");
+
+        let keys = TestKeys(vec![StoreKey::generate("new".to_owned()), old.clone()]);
+        assert!(verify(&keys, b"bundle", &signature).is_ok());
+        assert!(matches!(
+            verify(&keys, b"bundle!", &signature),
+            Err(RippleError::InvalidAccess)
+        ));
+        let other = TestKeys(vec![StoreKey::generate("old".to_owned())]);
+        assert!(verify(&other, b"bundle", &signature).is_err());
+
+        // Signatures and encryption use keys derived from the store key, not the key itself
+        let raw = <Hmac<Sha256> as Mac>::new_from_slice(&old.key)
+            .unwrap()
+            .chain_update(b"bundle")
+            .finalize()
+            .into_bytes();
+        assert_ne!(STANDARD.encode(raw), signature.signature);
+        assert_ne!(
+            old.derive(ENCRYPTION_KEY_INFO),
+            old.derive(SIGNATURE_KEY_INFO)
+        );
+        assert!(StoreEncryptionConfig::Env {
+            variable: "KEYS".to_owned()
+        }
+        .is_portable());
+        assert!(!StoreEncryptionConfig::Keystore {
+            path: "keys".to_owned()
+        }
+        .is_portable());
+    }
+}
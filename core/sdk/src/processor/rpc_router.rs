@@ -54,6 +54,14 @@ impl RouterState {
             error!("Failed to merge methods: {:?}", e);
         }
     }
+    pub fn get_method_names(&self) -> Vec<String> {
+        self.methods
+            .read()
+            .unwrap()
+            .method_names()
+            .map(str::to_owned)
+            .collect()
+    }
     pub fn get_method_entry(&self, method_name: &str) -> Option<(String, MethodCallback)> {
         // Acquire a read lock without cloning the entire Methods registry
         let methods_guard = self.methods.read().ok()?;
@@ -17,20 +17,199 @@
 use super::{
     internal_api_config::{load_internal_api_map, load_service_rules},
     rpc_router::{handle_jsonrpc_request, send_response},
+    traffic::{Direction, Traffic},
     types::{ClientInfo, Clients, PendingAggregate, PendingMap, RoutedMap, RoutedRequest},
 };
 
 use futures_util::{SinkExt, StreamExt};
+use log::{debug, error, info};
 use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration, Instant};
 use tokio::{net::TcpListener, sync::Mutex};
 use tokio_tungstenite::{accept_async, tungstenite::Message};
 use uuid::Uuid;
 
 const APPGW_BIND_ADDRESS: &str = "127.0.0.1:3474";
 
+/// Configuration of a Mock Application Gateway (AppGW) instance.
+///
+/// The `appgw` binary uses [`AppGwConfig::from_env`], tests build the configuration in code so
+/// they do not depend on the working directory, usually binding `127.0.0.1:0` to get a free port.
+#[derive(Debug, Clone)]
+pub struct AppGwConfig {
+    pub bind_address: String,
+    pub internal_api_map: HashMap<String, Value>,
+    pub service_rules: HashMap<String, String>,
+}
+
+impl AppGwConfig {
+    /// Configuration without internal APIs nor service rules
+    pub fn new(bind_address: &str) -> Self {
+        Self {
+            bind_address: bind_address.to_string(),
+            internal_api_map: HashMap::new(),
+            service_rules: HashMap::new(),
+        }
+    }
+
+    /// Configuration of the `appgw` binary. The bind address is taken from the `APPGW_ADDRESS`
+    /// environment variable, `127.0.0.1:3474` by default, the internal APIs and service rules
+    /// from `internal_apis.json` and `mock_service.rules` in the working directory.
+    pub fn from_env() -> Self {
+        Self {
+            bind_address: appgw_address(),
+            internal_api_map: (*load_internal_api_map()).clone(),
+            service_rules: load_service_rules(),
+        }
+    }
+
+    /// Answers `method` with the given `{"result": ..}` or `{"error": ..}` without involving a service
+    pub fn with_internal_api(mut self, method: &str, response: Value) -> Self {
+        self.internal_api_map.insert(method.to_string(), response);
+        self
+    }
+
+    /// Routes the methods matching `pattern`, either a method name or a `prefix.*`, to the service
+    pub fn with_service_rule(mut self, pattern: &str, service_id: &str) -> Self {
+        self.service_rules
+            .insert(pattern.to_string(), service_id.to_string());
+        self
+    }
+}
+
+/// Address of the AppGW, from the `APPGW_ADDRESS` environment variable or `127.0.0.1:3474`
+pub fn appgw_address() -> String {
+    std::env::var("APPGW_ADDRESS").unwrap_or_else(|_| APPGW_BIND_ADDRESS.to_string())
+}
+
+/// Handle of an AppGW started with [`AppGw::start`], the AppGW runs until it is stopped or dropped.
+pub struct AppGw {
+    local_addr: SocketAddr,
+    clients: Clients,
+    traffic: Traffic,
+    accept: Option<JoinHandle<()>>,
+    connections: Arc<std::sync::Mutex<Vec<JoinHandle<()>>>>,
+}
+
+impl AppGw {
+    /// Binds the configured address and accepts connections in the background
+    pub async fn start(config: AppGwConfig) -> std::io::Result<AppGw> {
+        let listener = TcpListener::bind(&config.bind_address).await?;
+        let local_addr = listener.local_addr()?;
+        let clients: Clients = Arc::new(Mutex::new(HashMap::new()));
+        let pending: PendingMap = Arc::new(Mutex::new(HashMap::new()));
+        let routed: RoutedMap = Arc::new(Mutex::new(HashMap::new()));
+        let internal_api_map = Arc::new(config.internal_api_map);
+        let service_rules = config.service_rules;
+        let traffic = Traffic::default();
+        let connections = Arc::new(std::sync::Mutex::new(Vec::<JoinHandle<()>>::new()));
+
+        info!("[AppGW] Waiting for connections on ws://{}", local_addr);
+
+        let accept_clients = Arc::clone(&clients);
+        let accept_traffic = traffic.clone();
+        let accept_connections = Arc::clone(&connections);
+        let accept = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let clients = Arc::clone(&accept_clients);
+                let pending = Arc::clone(&pending);
+                let routed = Arc::clone(&routed);
+                let internal_api_map = internal_api_map.clone();
+                let service_rules = service_rules.clone();
+                let traffic = accept_traffic.clone();
+
+                let connection = tokio::spawn(async move {
+                    handle_client_connection(
+                        stream,
+                        clients,
+                        pending,
+                        routed,
+                        internal_api_map,
+                        service_rules,
+                        traffic,
+                    )
+                    .await;
+                });
+                let mut connections = accept_connections.lock().unwrap();
+                connections.retain(|t| !t.is_finished());
+                connections.push(connection);
+            }
+        });
+
+        Ok(AppGw {
+            local_addr,
+            clients,
+            traffic,
+            accept: Some(accept),
+            connections,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Address the services and clients connect to, without the `ws://` scheme
+    pub fn address(&self) -> String {
+        self.local_addr.to_string()
+    }
+
+    /// Messages exchanged with the connected clients and services so far
+    pub fn traffic(&self) -> Traffic {
+        self.traffic.clone()
+    }
+
+    /// Ids of the services currently registered
+    pub async fn services(&self) -> Vec<String> {
+        let mut services: Vec<String> = self
+            .clients
+            .lock()
+            .await
+            .values()
+            .filter(|c| c.is_service)
+            .filter_map(|c| c.service_id.clone())
+            .collect();
+        services.sort();
+        services
+    }
+
+    /// Waits until the service is registered, false if it did not register in time
+    pub async fn wait_for_service(&self, service_id: &str, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.services().await.iter().any(|s| s == service_id) {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                return false;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    /// Stops accepting connections and closes the connections of all clients and services
+    pub fn stop(self) {
+        drop(self);
+    }
+}
+
+impl Drop for AppGw {
+    fn drop(&mut self) {
+        if let Some(accept) = self.accept.take() {
+            accept.abort();
+        }
+        for connection in self.connections.lock().unwrap().drain(..) {
+            connection.abort();
+        }
+        info!("[AppGW] Stopped ws://{}", self.local_addr);
+    }
+}
+
 /// Starts the Mock Application Gateway (AppGW) service.
 ///
 /// This function initializes the WebSocket server, listens for incoming client connections,
@@ -38,36 +217,9 @@ const APPGW_BIND_ADDRESS: &str = "127.0.0.1:3474";
 /// for managing connected clients, pending requests, routed requests, and service rules.
 ///
 pub async fn start_app_gw() {
-    let listener = TcpListener::bind(APPGW_BIND_ADDRESS).await.unwrap();
-    let clients: Clients = Arc::new(Mutex::new(HashMap::new()));
-    let pending: PendingMap = Arc::new(Mutex::new(HashMap::new()));
-    let routed: RoutedMap = Arc::new(Mutex::new(HashMap::new()));
-    let internal_api_map = load_internal_api_map();
-    let service_rules = load_service_rules();
-
-    println!(
-        "[AppGW] Waiting for connections on ws://{}",
-        APPGW_BIND_ADDRESS
-    );
-
-    while let Ok((stream, _)) = listener.accept().await {
-        let clients = Arc::clone(&clients);
-        let pending = Arc::clone(&pending);
-        let routed = Arc::clone(&routed);
-        let internal_api_map = internal_api_map.clone();
-        let service_rules = service_rules.clone();
-
-        tokio::spawn(async move {
-            handle_client_connection(
-                stream,
-                clients,
-                pending,
-                routed,
-                internal_api_map,
-                service_rules,
-            )
-            .await;
-        });
+    let mut appgw = AppGw::start(AppGwConfig::from_env()).await.unwrap();
+    if let Some(accept) = appgw.accept.as_mut() {
+        let _ = accept.await;
     }
 }
 /// Handles a single client connection to the Application Gateway (AppGW).
@@ -86,6 +238,7 @@ pub async fn start_app_gw() {
 /// - `routed`: A reference to the `RoutedMap`, which tracks routed requests awaiting responses.
 /// - `internal_api_map`: A reference to the internal API map, which contains predefined API responses.
 /// - `service_rules`: A map of service routing rules used to route client requests.
+/// - `traffic`: The record of the messages received from and sent to the clients.
 ///
 async fn handle_client_connection(
     stream: tokio::net::TcpStream,
@@ -94,8 +247,15 @@ async fn handle_client_connection(
     routed: RoutedMap,
     internal_api_map: Arc<HashMap<String, Value>>,
     service_rules: HashMap<String, String>,
+    traffic: Traffic,
 ) {
-    let ws_stream = accept_async(stream).await.unwrap();
+    let ws_stream = match accept_async(stream).await {
+        Ok(ws_stream) => ws_stream,
+        Err(e) => {
+            error!("[AppGW] WebSocket handshake failed: {:?}", e);
+            return;
+        }
+    };
     let (mut write, mut read) = ws_stream.split();
     let (tx, mut rx) = mpsc::channel::<Message>(32);
 
@@ -103,18 +263,26 @@ async fn handle_client_connection(
 
     let writer_clients = Arc::clone(&clients);
     let writer_id = client_id.clone();
-    tokio::spawn(async move {
+    let writer_traffic = traffic.clone();
+    // the writer is aborted with the connection so stopping the AppGW closes the socket
+    let _writer = AbortOnDrop(tokio::spawn(async move {
         while let Some(msg) = rx.recv().await {
+            if let Message::Text(text) = &msg {
+                let service_id = get_service_id(&writer_clients, &writer_id).await;
+                writer_traffic.record(Direction::Outbound, &writer_id, service_id, text);
+            }
             if let Err(e) = write.send(msg).await {
-                eprintln!("[AppGW] Write error for {}: {:?}", writer_id, e);
+                error!("[AppGW] Write error for {}: {:?}", writer_id, e);
                 writer_clients.lock().await.remove(&writer_id);
                 break;
             }
         }
-    });
+    }));
 
     while let Some(Ok(Message::Text(msg))) = read.next().await {
-        println!("[AppGW] Received Message from {}: {:#?}", client_id, msg);
+        debug!("[AppGW] Received Message from {}: {:#?}", client_id, msg);
+        let service_id = get_service_id(&clients, &client_id).await;
+        traffic.record(Direction::Inbound, &client_id, service_id, &msg);
         if let Ok(v) = serde_json::from_str::<Value>(&msg) {
             process_client_request(
                 &v,
@@ -130,7 +298,23 @@ async fn handle_client_connection(
     }
 
     clients.lock().await.remove(&client_id);
-    println!("[AppGW] Client {} disconnected", client_id);
+    info!("[AppGW] Client {} disconnected", client_id);
+}
+
+struct AbortOnDrop(JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+async fn get_service_id(clients: &Clients, client_id: &str) -> Option<String> {
+    clients
+        .lock()
+        .await
+        .get(client_id)
+        .and_then(|c| c.service_id.clone())
 }
 
 /// Adds a new client to the registry of connected clients.
@@ -148,7 +332,7 @@ async fn handle_client_connection(
 ///
 async fn add_client_to_registry(clients: &Clients, tx: mpsc::Sender<Message>) -> String {
     let client_id = Uuid::new_v4().to_string();
-    println!("[AppGW] New client connected: {}", client_id);
+    info!("[AppGW] New client connected: {}", client_id);
     clients.lock().await.insert(
         client_id.clone(),
         ClientInfo {
@@ -238,7 +422,7 @@ async fn handle_service_register_request(v: &Value, clients: &Clients, client_id
                 .and_then(|id| map.get(id).map(|c| c.tx.clone()));
 
             if let Some(ref old_client_id) = old_client {
-                println!(
+                info!(
                     "[AppGW] Service ID {} is already in use by client {}. Cleaning up old client.",
                     sid, old_client_id
                 );
@@ -249,14 +433,14 @@ async fn handle_service_register_request(v: &Value, clients: &Clients, client_id
             let response_value = if let Some(client_info) = map.get_mut(client_id) {
                 client_info.service_id = Some(sid.to_string());
                 client_info.is_service = true;
-                println!("[AppGW] Client {} registered as service {}", client_id, sid);
+                info!("[AppGW] Client {} registered as service {}", client_id, sid);
                 json!({
                     "result": {
                         "status": "success"
                     }
                 })
             } else {
-                println!("[AppGW] Client {} not found in map", client_id);
+                info!("[AppGW] Client {} not found in map", client_id);
                 json!({
                     "error": {
                         "code": -32602,
@@ -274,7 +458,7 @@ async fn handle_service_register_request(v: &Value, clients: &Clients, client_id
 
         send_response(v, response_value, clients, client_id).await;
     } else {
-        println!("[AppGW] Invalid register request: no service_id provided");
+        info!("[AppGW] Invalid register request: no service_id provided");
         let error_response_value = json!({
             "error": {
                 "code": -32602,
@@ -293,7 +477,7 @@ async fn handle_service_register_request(v: &Value, clients: &Clients, client_id
 /// - `clients`: A reference to the `Clients` map, which stores information about connected clients.
 /// - `client_id`: The identifier of the client requesting to unregister.
 async fn handle_service_unregister_request(v: &Value, clients: &Clients, client_id: &str) {
-    println!("[AppGW] Client {} requested unregister", client_id);
+    info!("[AppGW] Client {} requested unregister", client_id);
 
     // Gather response while holding the lock, but call send_response after releasing it
     let response_value = {
@@ -301,14 +485,14 @@ async fn handle_service_unregister_request(v: &Value, clients: &Clients, client_
         if let Some(client_info) = map.get_mut(client_id) {
             client_info.service_id = None;
             client_info.is_service = false;
-            println!("[AppGW] Client {} unregistered as a service", client_id);
+            info!("[AppGW] Client {} unregistered as a service", client_id);
             json!({
                 "result": {
                     "status": "success"
                 }
             })
         } else {
-            println!("[AppGW] Client {} not found in map", client_id);
+            info!("[AppGW] Client {} not found in map", client_id);
             json!({
                 "error": {
                     "code": -32602,
@@ -444,7 +628,7 @@ async fn process_direct_response(
         .and_then(|c| c.service_id.clone())
         .unwrap_or_else(|| "unknown".to_string());
 
-    println!(
+    info!(
         "[AppGW] Found routed request for id {} from service {}",
         routed_req.original_id, service_id
     );
@@ -14,6 +14,7 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 //
+use log::{error, info};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -23,12 +24,12 @@ pub fn load_internal_api_map() -> Arc<HashMap<String, Value>> {
         Ok(data) => match serde_json::from_str(&data) {
             Ok(parsed) => Arc::new(parsed),
             Err(e) => {
-                eprintln!("[AppGW] Failed to parse internal_apis.json: {e}");
+                error!("[AppGW] Failed to parse internal_apis.json: {e}");
                 Arc::new(HashMap::new())
             }
         },
         Err(_) => {
-            println!("[AppGW] internal_apis.json not found. Skipping internal API mapping.");
+            info!("[AppGW] internal_apis.json not found. Skipping internal API mapping.");
             Arc::new(HashMap::new())
         }
     }
@@ -17,4 +17,5 @@
 pub mod appgw_main;
 pub mod internal_api_config;
 pub mod rpc_router;
+pub mod traffic;
 pub mod types;
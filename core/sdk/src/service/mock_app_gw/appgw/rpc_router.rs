@@ -15,6 +15,7 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 use crate::appgw::types::*;
+use log::{debug, error};
 use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -286,7 +287,7 @@ async fn route_to_service(
     service_id: &str,
 ) {
     // Extract necessary data while holding the lock
-    println!("Routing message {:#?} to service: {}", v, service_id);
+    debug!("Routing message {:#?} to service: {}", v, service_id);
     let (svc_tx, sender_tx) = {
         let map = clients.lock().await;
 
@@ -332,7 +333,7 @@ async fn route_to_service(
         "params": v["params"]
     });
     if let Err(err) = svc_tx.send(Message::Text(req.to_string())).await {
-        eprintln!(
+        error!(
             "Failed to send message to service {} for method {}: {:?}",
             service_id, v["method"], err
         );
@@ -365,7 +366,7 @@ pub async fn send_response(v: &Value, resp: Value, clients: &Clients, client_id:
 }
 
 async fn send_internal_api_response(id: u64, response: &Value, clients: &Clients, sender_id: &str) {
-    println!("Request Processed by AppGW : Response: {:#?}", response);
+    debug!("Request Processed by AppGW : Response: {:#?}", response);
     let mut reply = json!({
         "jsonrpc": "2.0",
         "id": id,
@@ -428,7 +429,7 @@ async fn cleanup_disconnected_service(context: CleanupContext<'_>) {
 
 // Error handling functions
 async fn send_invalid_id_error(v: &Value, clients: &Clients, sender_id: &str) {
-    eprintln!(
+    error!(
         "Invalid id format. Expected u64, but received: {:?}",
         v["id"]
     );
@@ -443,7 +444,7 @@ async fn send_invalid_id_error(v: &Value, clients: &Clients, sender_id: &str) {
 }
 
 async fn send_no_services_error(id: u64, sender: mpsc::Sender<Message>) {
-    eprintln!("No services available for processing the request");
+    error!("No services available for processing the request");
     let err = json!({
         "jsonrpc": "2.0",
         "id": id,
@@ -470,7 +471,7 @@ async fn send_service_not_available_error(
     sender_id: &str,
     service_id: &str,
 ) {
-    eprintln!(
+    error!(
         "The Service {} is not available for processing the method",
         service_id
     );
@@ -0,0 +1,138 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use tokio::time::{sleep, Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Received by the AppGW from a client or service
+    Inbound,
+    /// Sent by the AppGW to a client or service
+    Outbound,
+}
+
+/// A message exchanged between the AppGW and one of its connections
+#[derive(Debug, Clone)]
+pub struct TrafficRecord {
+    pub direction: Direction,
+    pub client_id: String,
+    /// Id of the service when the connection had registered as one
+    pub service_id: Option<String>,
+    /// The message, a JSON string when it was not valid JSON
+    pub message: Value,
+}
+
+impl TrafficRecord {
+    pub fn method(&self) -> Option<&str> {
+        self.message.get("method").and_then(|m| m.as_str())
+    }
+
+    fn is_service(&self, service_id: &str) -> bool {
+        self.service_id.as_deref() == Some(service_id)
+    }
+}
+
+/// Record of the traffic of an AppGW, used by tests to assert on what was routed where
+#[derive(Debug, Clone, Default)]
+pub struct Traffic {
+    records: Arc<Mutex<Vec<TrafficRecord>>>,
+}
+
+impl Traffic {
+    pub fn record(
+        &self,
+        direction: Direction,
+        client_id: &str,
+        service_id: Option<String>,
+        text: &str,
+    ) {
+        let message: Value =
+            serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()));
+        // the registration is received before the connection is known as the service
+        let service_id = service_id.or_else(|| match message.get("method") {
+            Some(method) if method == "register" => message["params"]["service_id"]
+                .as_str()
+                .map(|s| s.to_string()),
+            _ => None,
+        });
+        self.records.lock().unwrap().push(TrafficRecord {
+            direction,
+            client_id: client_id.to_string(),
+            service_id,
+            message,
+        });
+    }
+
+    pub fn records(&self) -> Vec<TrafficRecord> {
+        self.records.lock().unwrap().clone()
+    }
+
+    pub fn clear(&self) {
+        self.records.lock().unwrap().clear();
+    }
+
+    /// Requests the AppGW routed to the service
+    pub fn requests_to(&self, service_id: &str) -> Vec<Value> {
+        self.filter(|r| {
+            r.direction == Direction::Outbound && r.is_service(service_id) && r.method().is_some()
+        })
+    }
+
+    /// Requests the service sent to the AppGW, registration included
+    pub fn requests_from(&self, service_id: &str) -> Vec<Value> {
+        self.filter(|r| {
+            r.direction == Direction::Inbound && r.is_service(service_id) && r.method().is_some()
+        })
+    }
+
+    /// Methods routed to the service in the order they were sent
+    pub fn methods_routed_to(&self, service_id: &str) -> Vec<String> {
+        self.requests_to(service_id)
+            .iter()
+            .filter_map(|r| r["method"].as_str().map(|m| m.to_string()))
+            .collect()
+    }
+
+    fn filter(&self, f: impl Fn(&TrafficRecord) -> bool) -> Vec<Value> {
+        self.records
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| f(r))
+            .map(|r| r.message.clone())
+            .collect()
+    }
+
+    /// Waits until the recorded traffic satisfies the predicate, false if it did not in time
+    pub async fn wait_for(
+        &self,
+        predicate: impl Fn(&[TrafficRecord]) -> bool,
+        timeout: Duration,
+    ) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if predicate(&self.records.lock().unwrap()) {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                return false;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+    }
+}
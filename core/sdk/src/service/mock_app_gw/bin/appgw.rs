@@ -18,5 +18,9 @@
 
 #[tokio::main]
 async fn main() {
+    let _ = fern::Dispatch::new()
+        .level(log::LevelFilter::Debug)
+        .chain(std::io::stdout())
+        .apply();
     mock_app_gw::appgw::appgw_main::start_app_gw().await;
 }
@@ -0,0 +1,515 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+//! In-process test harness around the mock AppGW.
+//!
+//! Tests start an [`AppGw`] on a free port, connect [`ScriptedService`]s which answer with canned
+//! responses, drive requests through a [`TestClient`] or a [`Scenario`] and assert on the
+//! [`Traffic`] the AppGW routed. The scripted services use the same `service_handshake` as the
+//! services of the SDK so they can also be connected to the WebSocket endpoint of Ripple Main,
+//! see [`ScriptedService::with_service_message`].
+//!
+//! [`Traffic`]: crate::appgw::traffic::Traffic
+
+use futures_util::{
+    stream::{SplitSink, SplitStream},
+    SinkExt, StreamExt,
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::{
+    net::TcpStream,
+    sync::mpsc,
+    task::JoinHandle,
+    time::{timeout, Duration},
+};
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+pub use crate::appgw::appgw_main::{AppGw, AppGwConfig};
+pub use crate::appgw::traffic::{Direction, Traffic, TrafficRecord};
+use crate::service_trait::Service;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Service answering every method with a scripted `{"result": ..}` or `{"error": ..}`, other
+/// methods fail with -32601.
+#[derive(Debug, Clone)]
+pub struct ScriptedService {
+    id: String,
+    responses: HashMap<String, Value>,
+    // responses to the requests sent with [ScriptedServiceHandle::call]
+    response_tx: Option<mpsc::Sender<Value>>,
+    envelope: bool,
+    // methods of the requests the service was sent, in order
+    received: Arc<Mutex<Vec<String>>>,
+}
+
+impl ScriptedService {
+    pub fn new(service_id: &str) -> Self {
+        Self {
+            id: service_id.to_string(),
+            responses: HashMap::new(),
+            response_tx: None,
+            envelope: false,
+            received: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Sends the messages in the `{"message": ..}` envelope of the `ServiceMessage`s Ripple Main
+    /// expects from its services, the requests sent with [ScriptedServiceHandle::call] carry the
+    /// call context of a service request
+    pub fn with_service_message(mut self) -> Self {
+        self.envelope = true;
+        self
+    }
+
+    fn wrap(envelope: bool, message: Value) -> Value {
+        if envelope {
+            json!({ "message": message })
+        } else {
+            message
+        }
+    }
+
+    /// Call context of a request of the service, as the services of the SDK send it
+    fn service_context(service_id: &str, id: u64, method: &str) -> Value {
+        json!({
+            "session_id": service_id,
+            "request_id": id.to_string(),
+            "app_id": service_id,
+            "call_id": id,
+            "protocol": "Service",
+            "method": method,
+            "cid": null,
+            "gateway_secure": false,
+            "context": [id.to_string(), service_id]
+        })
+    }
+
+    pub fn with_result(mut self, method: &str, result: Value) -> Self {
+        self.responses
+            .insert(method.to_string(), json!({ "result": result }));
+        self
+    }
+
+    pub fn with_error(mut self, method: &str, code: i64, message: &str) -> Self {
+        self.responses.insert(
+            method.to_string(),
+            json!({ "error": { "code": code, "message": message } }),
+        );
+        self
+    }
+
+    /// Connects to the AppGW at `address` (`host:port`) and serves requests in the background
+    pub fn start(mut self, address: &str) -> ScriptedServiceHandle {
+        let (inbound_tx, mut inbound_rx) = mpsc::channel::<Value>(32);
+        let (outbound_tx, mut outbound_rx) = mpsc::channel::<Message>(32);
+        let (response_tx, response_rx) = mpsc::channel::<Value>(32);
+        self.response_tx = Some(response_tx);
+        let envelope = self.envelope;
+        let service_id = self.id.clone();
+        let received = Arc::clone(&self.received);
+        let service = Arc::new(self);
+        let address = address.to_string();
+
+        let responder = Arc::clone(&service);
+        let responder_tx = outbound_tx.clone();
+        let task = tokio::spawn(async move {
+            let responses = tokio::spawn(async move {
+                while let Some(request) = inbound_rx.recv().await {
+                    let response = Self::wrap(
+                        responder.envelope,
+                        responder.handle_inbound_request(request).await,
+                    );
+                    if responder_tx
+                        .send(Message::Text(response.to_string()))
+                        .await
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+            service.run(&address, &mut outbound_rx, inbound_tx).await;
+            responses.abort();
+        });
+
+        ScriptedServiceHandle {
+            service_id,
+            outbound_tx,
+            response_rx,
+            next_id: 1,
+            envelope,
+            received,
+            task,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Service for ScriptedService {
+    fn service_id(&self) -> &str {
+        &self.id
+    }
+
+    async fn handle_inbound_request(&self, request: Value) -> Value {
+        let method = request["method"].as_str().unwrap_or_default();
+        self.received.lock().unwrap().push(method.to_string());
+        let mut reply = json!({ "jsonrpc": "2.0", "id": request["id"] });
+        match self.responses.get(method) {
+            Some(response) if response.get("result").is_some() => {
+                reply["result"] = response["result"].clone()
+            }
+            Some(response) => reply["error"] = response["error"].clone(),
+            None => {
+                reply["error"] = json!({
+                    "code": -32601,
+                    "message": format!("Method {} not found in {}", method, self.id)
+                })
+            }
+        }
+        reply
+    }
+
+    async fn handle_response(&self, response: Value) {
+        if let Some(response_tx) = &self.response_tx {
+            let _ = response_tx.try_send(response);
+        }
+    }
+}
+
+/// A running [`ScriptedService`]
+pub struct ScriptedServiceHandle {
+    service_id: String,
+    outbound_tx: mpsc::Sender<Message>,
+    response_rx: mpsc::Receiver<Value>,
+    next_id: u64,
+    envelope: bool,
+    received: Arc<Mutex<Vec<String>>>,
+    task: JoinHandle<()>,
+}
+
+impl ScriptedServiceHandle {
+    /// Methods of the requests the service was sent so far, in order
+    pub fn methods_received(&self) -> Vec<String> {
+        self.received.lock().unwrap().clone()
+    }
+
+    /// Sends a request of the service to the AppGW, its response is not awaited
+    pub async fn send(&self, request: Value) {
+        let request = ScriptedService::wrap(self.envelope, request);
+        let _ = self
+            .outbound_tx
+            .send(Message::Text(request.to_string()))
+            .await;
+    }
+
+    /// Sends a request of the service and returns the whole response, with either `result` or
+    /// `error`
+    pub async fn call(&mut self, method: &str, params: Option<Value>) -> Result<Value, String> {
+        self.call_with_timeout(method, params, DEFAULT_TIMEOUT)
+            .await
+    }
+
+    pub async fn call_with_timeout(
+        &mut self,
+        method: &str,
+        params: Option<Value>,
+        wait: Duration,
+    ) -> Result<Value, String> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let mut request = json!({ "jsonrpc": "2.0", "id": id, "method": method });
+        if let Some(params) = params {
+            request["params"] = params;
+        }
+        if self.envelope {
+            let mut message = ScriptedService::wrap(true, request);
+            message["context"] = ScriptedService::service_context(&self.service_id, id, method);
+            let _ = self
+                .outbound_tx
+                .send(Message::Text(message.to_string()))
+                .await;
+        } else {
+            self.send(request).await;
+        }
+
+        let response_rx = &mut self.response_rx;
+        let response = timeout(wait, async move {
+            while let Some(response) = response_rx.recv().await {
+                // Ripple Main answers with the request id of the call context as a string
+                if response["id"].as_u64() == Some(id)
+                    || response["id"].as_str() == Some(id.to_string().as_str())
+                {
+                    return Some(response);
+                }
+            }
+            None
+        })
+        .await;
+        match response {
+            Ok(Some(response)) => Ok(response),
+            Ok(None) => Err(format!("Service stopped before the response to {}", method)),
+            Err(_) => Err(format!("No response to {} within {:?}", method, wait)),
+        }
+    }
+
+    /// Closes the connection and waits for the service to stop
+    pub async fn stop(self) {
+        let _ = self.outbound_tx.send(Message::Close(None)).await;
+        let mut task = self.task;
+        if timeout(DEFAULT_TIMEOUT, &mut task).await.is_err() {
+            task.abort();
+        }
+    }
+}
+
+/// A client of the AppGW sending JSON-RPC requests and waiting for their responses
+pub struct TestClient {
+    write: SplitSink<WsStream, Message>,
+    read: SplitStream<WsStream>,
+    next_id: u64,
+}
+
+impl TestClient {
+    /// Connects to the AppGW at `address` (`host:port`)
+    pub async fn connect(address: &str) -> Result<TestClient, String> {
+        let (ws_stream, _) = connect_async(format!("ws://{}", address))
+            .await
+            .map_err(|e| format!("Failed to connect to {}: {}", address, e))?;
+        let (write, read) = ws_stream.split();
+        Ok(TestClient {
+            write,
+            read,
+            next_id: 1,
+        })
+    }
+
+    /// Sends the request and returns the whole response, with either `result` or `error`
+    pub async fn call(&mut self, method: &str, params: Option<Value>) -> Result<Value, String> {
+        self.call_with_timeout(method, params, DEFAULT_TIMEOUT)
+            .await
+    }
+
+    pub async fn call_with_timeout(
+        &mut self,
+        method: &str,
+        params: Option<Value>,
+        wait: Duration,
+    ) -> Result<Value, String> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let mut request = json!({ "jsonrpc": "2.0", "id": id, "method": method });
+        if let Some(params) = params {
+            request["params"] = params;
+        }
+        self.write
+            .send(Message::Text(request.to_string()))
+            .await
+            .map_err(|e| format!("Failed to send {}: {}", method, e))?;
+
+        let read = &mut self.read;
+        let response = timeout(wait, async move {
+            while let Some(Ok(msg)) = read.next().await {
+                if let Message::Text(text) = msg {
+                    if let Ok(v) = serde_json::from_str::<Value>(&text) {
+                        if v["id"].as_u64() == Some(id) {
+                            return Some(v);
+                        }
+                    }
+                }
+            }
+            None
+        })
+        .await;
+        match response {
+            Ok(Some(response)) => Ok(response),
+            Ok(None) => Err(format!(
+                "Connection closed before the response to {}",
+                method
+            )),
+            Err(_) => Err(format!("No response to {} within {:?}", method, wait)),
+        }
+    }
+}
+
+/// What a [`Scenario`] step expects as the response
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expectation {
+    /// A result equal to the value
+    Result(Value),
+    /// Any error
+    Error,
+    /// An error with the code, either its own or the one of the service it comes from
+    ErrorCode(i64),
+}
+
+impl Expectation {
+    fn check(&self, response: &Value) -> Result<(), String> {
+        let error = response.get("error");
+        let matched = match self {
+            Expectation::Result(expected) => response.get("result") == Some(expected),
+            Expectation::Error => error.is_some(),
+            Expectation::ErrorCode(code) => {
+                error
+                    .and_then(|e| e.get("code").or_else(|| e["response"].get("code")))
+                    .and_then(|c| c.as_i64())
+                    == Some(*code)
+            }
+        };
+        if matched {
+            Ok(())
+        } else {
+            Err(format!("expected {:?}, got {}", self, response))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ScenarioStep {
+    method: String,
+    params: Option<Value>,
+    expectation: Expectation,
+}
+
+/// Scripted sequence of requests and the responses they are expected to get
+#[derive(Debug, Clone, Default)]
+pub struct Scenario {
+    steps: Vec<ScenarioStep>,
+}
+
+impl Scenario {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn step(mut self, method: &str, params: Option<Value>, expectation: Expectation) -> Self {
+        self.steps.push(ScenarioStep {
+            method: method.to_string(),
+            params,
+            expectation,
+        });
+        self
+    }
+
+    /// Runs the steps in order and fails on the first one which does not get the expected response
+    pub async fn run(&self, client: &mut TestClient) -> Result<(), String> {
+        for (index, step) in self.steps.iter().enumerate() {
+            let response = client.call(&step.method, step.params.clone()).await?;
+            step.expectation
+                .check(&response)
+                .map_err(|e| format!("step {} {}: {}", index + 1, step.method, e))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERVICE1: &str = "mock:service:appgw:service1";
+    const SERVICE2: &str = "mock:service:appgw:service2";
+
+    async fn start_appgw() -> AppGw {
+        let config = AppGwConfig::new("127.0.0.1:0")
+            .with_internal_api("Device.name", json!({ "result": "Living Room" }))
+            .with_service_rule("service1.*", SERVICE1)
+            .with_service_rule("service2.*", SERVICE2);
+        AppGw::start(config).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_scenario_and_routed_traffic() {
+        let appgw = start_appgw().await;
+        let service1 = ScriptedService::new(SERVICE1)
+            .with_result("service1.get_status", json!({ "status": "running" }))
+            .with_error("service1.compute", -32602, "bad params")
+            .start(&appgw.address());
+        assert!(appgw.wait_for_service(SERVICE1, DEFAULT_TIMEOUT).await);
+
+        let mut client = TestClient::connect(&appgw.address()).await.unwrap();
+        Scenario::new()
+            .step(
+                "Device.name",
+                None,
+                Expectation::Result(json!("Living Room")),
+            )
+            .step(
+                "service1.get_status",
+                None,
+                Expectation::Result(json!({ "status": "running" })),
+            )
+            .step(
+                "service1.compute",
+                Some(json!({ "a": 1 })),
+                Expectation::ErrorCode(-32602),
+            )
+            .step("service1.unknown", None, Expectation::ErrorCode(-32601))
+            // no service2 connected
+            .step("service2.get_status", None, Expectation::ErrorCode(-32001))
+            .step("other.method", None, Expectation::ErrorCode(-32601))
+            .run(&mut client)
+            .await
+            .unwrap();
+
+        let traffic = appgw.traffic();
+        assert_eq!(
+            traffic.methods_routed_to(SERVICE1),
+            vec![
+                "service1.get_status",
+                "service1.compute",
+                "service1.unknown"
+            ]
+        );
+        assert_eq!(
+            traffic.requests_to(SERVICE1)[1]["params"],
+            json!({ "a": 1 })
+        );
+        assert!(traffic.requests_to(SERVICE2).is_empty());
+        assert_eq!(
+            service1.methods_received(),
+            traffic.methods_routed_to(SERVICE1)
+        );
+        assert_eq!(traffic.requests_from(SERVICE1)[0]["method"], "register");
+
+        // a failing step reports what it got
+        let error = Scenario::new()
+            .step("Device.name", None, Expectation::Error)
+            .run(&mut client)
+            .await
+            .unwrap_err();
+        assert!(error.contains("step 1 Device.name"));
+
+        service1.stop().await;
+        timeout(DEFAULT_TIMEOUT, async {
+            while !appgw.services().await.is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        // stopping the AppGW closes the connections and the listener
+        let address = appgw.address();
+        appgw.stop();
+        assert!(client.call("Device.name", None).await.is_err());
+        assert!(TestClient::connect(&address).await.is_err());
+    }
+}
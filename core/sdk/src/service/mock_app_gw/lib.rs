@@ -15,6 +15,7 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 pub mod appgw;
+pub mod harness;
 pub mod service1_impl;
 pub mod service2_impl;
 pub mod service_trait;
@@ -20,11 +20,10 @@ use serde_json::{json, Value};
 use tokio::sync::mpsc;
 use tokio_tungstenite::tungstenite::Message;
 
-use crate::service_trait::Service;
+use crate::{appgw::appgw_main::appgw_address, service_trait::Service};
 
 // Define the service ID as a constant
 const SERVICE1_ID: &str = "mock:service:appgw:service1";
-const APPGW_WS_URL: &str = "127.0.0.1:3474";
 
 pub struct MockService {
     id: String,
@@ -225,6 +224,6 @@ pub async fn start_service1() {
     });
 
     svc.clone()
-        .run(APPGW_WS_URL, &mut outbound_rx, inbound_tx)
+        .run(&appgw_address(), &mut outbound_rx, inbound_tx)
         .await;
 }
@@ -20,9 +20,7 @@ use serde_json::{json, Value};
 use tokio::sync::mpsc;
 use tokio_tungstenite::tungstenite::Message;
 
-use crate::service_trait::Service;
-
-const APPGW_WS_URL: &str = "127.0.0.1:3474";
+use crate::{appgw::appgw_main::appgw_address, service_trait::Service};
 
 struct Service2;
 
@@ -105,6 +103,6 @@ pub async fn start_service2() {
     });
 
     svc.clone()
-        .run(APPGW_WS_URL, &mut outbound_rx, inbound_tx)
+        .run(&appgw_address(), &mut outbound_rx, inbound_tx)
         .await;
 }
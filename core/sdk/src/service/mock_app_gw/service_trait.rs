@@ -71,6 +71,8 @@ use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, Web
 /// # Notes
 /// - The `run` method is designed to manage the WebSocket connection and handle communication
 ///   with the Application Gateway. It includes automatic reconnection logic for resilience.
+/// - `run` returns once the service or the AppGW closes the connection, so services can also run
+///   inside a test process, see [`crate::harness`].
 #[async_trait]
 pub trait Service: Send + Sync + 'static {
     fn service_id(&self) -> &str;
@@ -87,7 +89,7 @@ pub trait Service: Send + Sync + 'static {
         outbound_rx: &mut Receiver<Message>,
         inbound_tx: Sender<Value>,
     ) {
-        let ws_stream = establish_connection_to_appgw_with_backoff(url).await;
+        let ws_stream = establish_connection_to_appgw_with_backoff(url, self.service_id()).await;
         let (mut ws_tx, mut ws_rx) = ws_stream.split();
 
         let register_msg = json!({
@@ -114,9 +116,13 @@ pub trait Service: Send + Sync + 'static {
         let sid_c1 = sid.clone();
         let sid_c2 = sid.clone();
 
-        tokio::spawn(async move {
+        let mut reader = tokio::spawn(async move {
             while let Some(Ok(Message::Text(msg))) = ws_rx.next().await {
-                if let Ok(value) = serde_json::from_str::<Value>(&msg) {
+                if let Ok(mut value) = serde_json::from_str::<Value>(&msg) {
+                    // Ripple Main wraps its messages in a `{"message": ..}` envelope
+                    if let Some(message) = value.get("message").filter(|m| m.is_object()) {
+                        value = message.clone();
+                    }
                     if value.get("method").is_some() {
                         if let Err(e) = inbound_tx_clone.send(value).await {
                             eprintln!("[{}] Failed to forward inbound: {}", sid_c1, e);
@@ -127,10 +133,17 @@ pub trait Service: Send + Sync + 'static {
                 }
             }
             println!("[{}] Connection closed", sid_c1.clone());
-            std::process::exit(0);
         });
 
-        while let Some(msg) = outbound_rx.recv().await {
+        // runs until the service closes the connection or the AppGW does
+        loop {
+            let msg = tokio::select! {
+                _ = &mut reader => break,
+                msg = outbound_rx.recv() => match msg {
+                    Some(msg) => msg,
+                    None => break,
+                },
+            };
             match msg {
                 Message::Close(_) => {
                     println!("[{}] Sending Close message and exiting...", sid_c2);
@@ -146,6 +159,7 @@ pub trait Service: Send + Sync + 'static {
                 }
             }
         }
+        reader.abort();
         println!("[{}] Service exiting...", sid);
     }
 }
@@ -153,11 +167,12 @@ pub trait Service: Send + Sync + 'static {
 // Utility function for resilient WebSocket connection
 async fn establish_connection_to_appgw_with_backoff(
     url: &str,
+    service_id: &str,
 ) -> WebSocketStream<MaybeTlsStream<TcpStream>> {
     let path = tokio_tungstenite::tungstenite::http::Uri::builder()
         .scheme("ws")
         .authority(url)
-        .path_and_query(format!("/?service_handshake={}", 9999))
+        .path_and_query(format!("/?service_handshake={}", service_id))
         .build()
         .unwrap();
 
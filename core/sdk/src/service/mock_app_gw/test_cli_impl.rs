@@ -19,13 +19,14 @@ use serde_json::{json, Value};
 use std::io::{self, BufRead, Write};
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 
+use crate::appgw::appgw_main::appgw_address;
+
 use std::sync::atomic::{AtomicU64, Ordering};
 
 static REQ_ID_COUNTER: AtomicU64 = AtomicU64::new(5000);
-const APPGW_WS_URL: &str = "ws://127.0.0.1:3474";
 
 pub async fn start_test_cli() {
-    let (ws_stream, _) = connect_async(APPGW_WS_URL)
+    let (ws_stream, _) = connect_async(format!("ws://{}", appgw_address()))
         .await
         .expect("Failed to connect to AppGW");
     let (mut write, mut read) = ws_stream.split();
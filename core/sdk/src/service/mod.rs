@@ -14,7 +14,10 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 //
+pub mod openrpc_codegen;
 pub mod service_client;
+pub mod service_connection;
 pub mod service_event_state;
 pub mod service_message;
+pub mod service_routing;
 pub mod service_rpc_router;
@@ -0,0 +1,712 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Generates a typed client for [super::service_client::ServiceClient] from the OpenRPC
+//! documents loaded by Main, meant to be used from the `build.rs` of a service:
+//!
+//! ```no_run
+//! use ripple_sdk::service::openrpc_codegen::ServiceClientCodegen;
+//!
+//! ServiceClientCodegen::new("RippleMainClient")
+//!     .with_open_rpc_file("../../core/main/src/state/ripple-rpc.json")
+//!     .unwrap()
+//!     .with_methods(vec!["LifecycleManagement.".into()])
+//!     .write_to_out_dir("ripple_main_client.rs")
+//!     .unwrap();
+//! ```
+//!
+//! and included in the service with
+//! `include!(concat!(env!("OUT_DIR"), "/ripple_main_client.rs"));`.
+//! Methods become typed async calls and events become subscriptions returning a receiver of
+//! the typed event values.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::error;
+use serde_json::Value;
+
+use crate::utils::error::RippleError;
+
+use super::service_message::{JsonRpcMessage, ServiceMessage};
+
+const DEFAULT_TIMEOUT_MS: u64 = 5000;
+
+const RUST_KEYWORDS: [&str; 38] = [
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+/// Value of an event notification sent by Main, the `result` of its params or the params
+/// without the `sender_id` used for routing
+pub fn event_value(message: &ServiceMessage) -> Option<Value> {
+    let params = match &message.message {
+        JsonRpcMessage::Notification(notification) => notification.params.clone()?,
+        _ => return None,
+    };
+    let value = match params {
+        Value::Object(mut map) => match map.remove("result") {
+            Some(result) => result,
+            None => {
+                map.remove("sender_id");
+                Value::Object(map)
+            }
+        },
+        value => value,
+    };
+    // some events are sent as their serialized value
+    match value {
+        Value::String(s) => Some(serde_json::from_str(&s).unwrap_or(Value::String(s))),
+        value => Some(value),
+    }
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut result = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            if prev_lower {
+                result.push('_');
+            }
+            result.push(c.to_ascii_lowercase());
+            prev_lower = false;
+        } else if c.is_ascii_alphanumeric() {
+            result.push(c);
+            prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        } else {
+            if !result.ends_with('_') && !result.is_empty() {
+                result.push('_');
+            }
+            prev_lower = false;
+        }
+    }
+    result
+}
+
+fn to_ident(name: &str) -> String {
+    let ident = to_snake_case(name);
+    if RUST_KEYWORDS.contains(&ident.as_str()) {
+        format!("r#{}", ident)
+    } else {
+        ident
+    }
+}
+
+fn to_type_name(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let mut chars = s.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+fn ref_name(reference: &str) -> Option<String> {
+    reference.rsplit('/').next().map(to_type_name)
+}
+
+pub struct ServiceClientCodegen {
+    trait_name: String,
+    sdk_path: String,
+    timeout_ms: u64,
+    method_prefixes: Vec<String>,
+    documents: Vec<Value>,
+}
+
+impl ServiceClientCodegen {
+    pub fn new(trait_name: &str) -> Self {
+        ServiceClientCodegen {
+            trait_name: trait_name.to_owned(),
+            sdk_path: "ripple_sdk".to_owned(),
+            timeout_ms: DEFAULT_TIMEOUT_MS,
+            method_prefixes: Vec::new(),
+            documents: Vec::new(),
+        }
+    }
+
+    /// Path of the sdk crate in the generated code, `ripple_sdk` by default
+    pub fn with_sdk_path(mut self, sdk_path: &str) -> Self {
+        self.sdk_path = sdk_path.to_owned();
+        self
+    }
+
+    pub fn with_timeout_ms(mut self, timeout_ms: u64) -> Self {
+        self.timeout_ms = timeout_ms;
+        self
+    }
+
+    /// Only the methods starting with one of the prefixes are generated, all when empty
+    pub fn with_methods(mut self, method_prefixes: Vec<String>) -> Self {
+        self.method_prefixes = method_prefixes;
+        self
+    }
+
+    /// Adds an OpenRPC document, a Firebolt version manifest adds the documents of its apis
+    pub fn with_open_rpc(mut self, document: Value) -> Self {
+        match document.get("apis").and_then(|a| a.as_object()) {
+            Some(apis) => self.documents.extend(apis.values().cloned()),
+            None => self.documents.push(document),
+        }
+        self
+    }
+
+    pub fn with_open_rpc_file<P: AsRef<Path>>(self, path: P) -> Result<Self, RippleError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|e| {
+            error!("unable to read open rpc {:?} {:?}", path, e);
+            RippleError::MissingInput
+        })?;
+        let document = serde_json::from_str(&content).map_err(|e| {
+            error!("unable to parse open rpc {:?} {:?}", path, e);
+            RippleError::ParseError
+        })?;
+        Ok(self.with_open_rpc(document))
+    }
+
+    /// Generates the client into `OUT_DIR`, to be called from a build script
+    pub fn write_to_out_dir(&self, file_name: &str) -> Result<PathBuf, RippleError> {
+        let out_dir = std::env::var("OUT_DIR").map_err(|_| RippleError::MissingInput)?;
+        let path = Path::new(&out_dir).join(file_name);
+        fs::write(&path, self.generate()?).map_err(|_| RippleError::InvalidOutput)?;
+        Ok(path)
+    }
+
+    fn value_type(&self) -> String {
+        format!("{}::serde_json::Value", self.sdk_path)
+    }
+
+    fn is_included(&self, method: &str) -> bool {
+        self.method_prefixes.is_empty()
+            || self.method_prefixes.iter().any(|p| method.starts_with(p))
+    }
+
+    fn named_schemas(&self) -> BTreeMap<String, Value> {
+        let mut schemas = BTreeMap::new();
+        for document in &self.documents {
+            let components = document
+                .get("components")
+                .and_then(|c| c.get("schemas"))
+                .and_then(|s| s.as_object());
+            for (name, schema) in components.into_iter().flatten() {
+                schemas
+                    .entry(to_type_name(name))
+                    .or_insert_with(|| schema.clone());
+            }
+            let modules = document.get("x-schemas").and_then(|s| s.as_object());
+            for module in modules.into_iter().flatten().map(|(_, m)| m) {
+                for (name, schema) in module.as_object().into_iter().flatten() {
+                    if schema.is_object() {
+                        schemas
+                            .entry(to_type_name(name))
+                            .or_insert_with(|| schema.clone());
+                    }
+                }
+            }
+        }
+        schemas
+    }
+
+    /// Rust type of a schema, references are resolved to the generated types
+    fn rust_type(&self, schema: &Value, known: &HashSet<String>) -> String {
+        if let Some(name) = schema
+            .get("$ref")
+            .and_then(|r| r.as_str())
+            .and_then(ref_name)
+        {
+            if known.contains(&name) {
+                return name;
+            }
+            return self.value_type();
+        }
+        if schema.get("const").map_or(false, |c| c.is_null()) {
+            return "()".to_owned();
+        }
+        match schema.get("type").and_then(|t| t.as_str()) {
+            Some("string") => "String".to_owned(),
+            Some("boolean") => "bool".to_owned(),
+            Some("integer") => "i64".to_owned(),
+            Some("number") => "f64".to_owned(),
+            Some("null") => "()".to_owned(),
+            Some("array") => match schema.get("items") {
+                Some(items) if items.is_object() => {
+                    format!("Vec<{}>", self.rust_type(items, known))
+                }
+                _ => format!("Vec<{}>", self.value_type()),
+            },
+            _ => self.value_type(),
+        }
+    }
+
+    /// Type of an event, the branch of the `anyOf` which is not the listen response
+    fn event_type(&self, schema: &Value, known: &HashSet<String>) -> String {
+        if let Some(branches) = schema.get("anyOf").and_then(|a| a.as_array()) {
+            let branch = branches.iter().find(|b| {
+                !b.get("$ref")
+                    .and_then(|r| r.as_str())
+                    .map_or(false, |r| r.ends_with("/ListenResponse"))
+            });
+            if let Some(branch) = branch {
+                return self.rust_type(branch, known);
+            }
+        }
+        self.rust_type(schema, known)
+    }
+
+    fn write_types(
+        &self,
+        out: &mut String,
+        schemas: &BTreeMap<String, Value>,
+        known: &HashSet<String>,
+    ) {
+        let sdk = &self.sdk_path;
+        for (name, schema) in schemas {
+            let properties = schema.get("properties").and_then(|p| p.as_object());
+            let is_struct = schema.get("type").and_then(|t| t.as_str()) == Some("object")
+                && properties.map_or(false, |p| !p.is_empty());
+            if !is_struct {
+                let _ = writeln!(
+                    out,
+                    "pub type {} = {};\n",
+                    name,
+                    self.rust_type(schema, known)
+                );
+                continue;
+            }
+            let required: HashSet<&str> = schema
+                .get("required")
+                .and_then(|r| r.as_array())
+                .map(|r| r.iter().filter_map(|v| v.as_str()).collect())
+                .unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "#[derive(Debug, Clone, {sdk}::serde::Serialize, {sdk}::serde::Deserialize)]"
+            );
+            let _ = writeln!(out, "#[serde(crate = \"{sdk}::serde\")]");
+            let _ = writeln!(out, "pub struct {} {{", name);
+            for (property, property_schema) in properties.into_iter().flatten() {
+                let ident = to_ident(property);
+                let mut field_type = self.rust_type(property_schema, known);
+                if field_type == *name {
+                    field_type = format!("Box<{}>", field_type);
+                }
+                let optional = !required.contains(property.as_str());
+                let mut attributes = Vec::new();
+                if ident.trim_start_matches("r#") != property {
+                    attributes.push(format!("rename = \"{}\"", property));
+                }
+                if optional {
+                    attributes.push("default".to_owned());
+                    attributes.push("skip_serializing_if = \"Option::is_none\"".to_owned());
+                    field_type = format!("Option<{}>", field_type);
+                }
+                if !attributes.is_empty() {
+                    let _ = writeln!(out, "    #[serde({})]", attributes.join(", "));
+                }
+                let _ = writeln!(out, "    pub {}: {},", ident, field_type);
+            }
+            let _ = writeln!(out, "}}\n");
+        }
+    }
+
+    fn is_event(method: &Value) -> bool {
+        method
+            .get("tags")
+            .and_then(|t| t.as_array())
+            .map_or(false, |tags| {
+                tags.iter()
+                    .any(|t| t.get("name").and_then(|n| n.as_str()) == Some("event"))
+            })
+    }
+
+    fn methods(&self) -> Vec<Value> {
+        let mut seen = HashSet::new();
+        let mut methods = Vec::new();
+        for document in &self.documents {
+            let document_methods = document.get("methods").and_then(|m| m.as_array());
+            for method in document_methods.into_iter().flatten() {
+                let name = match method.get("name").and_then(|n| n.as_str()) {
+                    Some(name) => name,
+                    None => continue,
+                };
+                if self.is_included(name) && seen.insert(name.to_owned()) {
+                    methods.push(method.clone());
+                }
+            }
+        }
+        methods.sort_by_key(|m| m.get("name").and_then(|n| n.as_str()).map(str::to_owned));
+        methods
+    }
+
+    fn write_doc(out: &mut String, method: &Value, indent: &str) {
+        if let Some(summary) = method.get("summary").and_then(|s| s.as_str()) {
+            for line in summary.lines() {
+                let _ = writeln!(out, "{}/// {}", indent, line.trim());
+            }
+        }
+    }
+
+    /// Generates the types, the client trait and its implementation for `ServiceClient`
+    pub fn generate(&self) -> Result<String, RippleError> {
+        if self.documents.is_empty() {
+            return Err(RippleError::MissingInput);
+        }
+        let sdk = &self.sdk_path;
+        let schemas = self.named_schemas();
+        let known: HashSet<String> = schemas.keys().cloned().collect();
+        let methods = self.methods();
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "// @generated by ripple_sdk::service::openrpc_codegen, do not edit\n"
+        );
+        self.write_types(&mut out, &schemas, &known);
+
+        let mut signatures = Vec::new();
+        let mut bodies = Vec::new();
+        for method in &methods {
+            let name = method
+                .get("name")
+                .and_then(|n| n.as_str())
+                .unwrap_or_default();
+            let fn_name = to_ident(name);
+            let result_schema = method.get("result").and_then(|r| r.get("schema"));
+            if Self::is_event(method) {
+                let event_type = result_schema
+                    .map(|s| self.event_type(s, &known))
+                    .unwrap_or_else(|| self.value_type());
+                let signature = format!(
+                    "async fn {fn_name}(&mut self, ctx: Option<&{sdk}::api::gateway::rpc_gateway_api::CallContext>) -> Result<{sdk}::tokio::sync::mpsc::Receiver<{event_type}>, {sdk}::JsonRpcErrorType>"
+                );
+                let body = format!(
+                    r#"        let (event_tx, mut event_rx) =
+            {sdk}::tokio::sync::mpsc::channel::<{sdk}::service::service_message::ServiceMessage>(16);
+        let (tx, rx) = {sdk}::tokio::sync::mpsc::channel::<{event_type}>(16);
+        let service_id = self.service_id.as_ref().map(|id| id.to_string()).unwrap_or_default();
+        let subscribed = self
+            .call_and_parse_ripple_event_subscription_req_rpc(
+                "{name}",
+                Some({sdk}::serde_json::json!({{"listen": true}})),
+                ctx,
+                {timeout},
+                &service_id,
+                "{name} failed",
+                event_tx,
+            )
+            .await?;
+        if !subscribed {{
+            return Err({sdk}::JsonRpcErrorType::Custom("{name} subscription failed".to_owned()));
+        }}
+        {sdk}::tokio::spawn(async move {{
+            while let Some(message) = event_rx.recv().await {{
+                let event = {sdk}::service::openrpc_codegen::event_value(&message)
+                    .and_then(|value| {sdk}::serde_json::from_value::<{event_type}>(value).ok());
+                if let Some(event) = event {{
+                    if tx.send(event).await.is_err() {{
+                        break;
+                    }}
+                }}
+            }}
+        }});
+        Ok(rx)
+"#,
+                    timeout = self.timeout_ms
+                );
+                signatures.push((method, signature.clone()));
+                bodies.push((signature, body));
+                continue;
+            }
+
+            let result_type = result_schema
+                .map(|s| self.rust_type(s, &known))
+                .unwrap_or_else(|| self.value_type());
+            let params: Vec<(String, String, bool)> = method
+                .get("params")
+                .and_then(|p| p.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|p| {
+                    let param_name = p.get("name")?.as_str()?.to_owned();
+                    let required = p.get("required").and_then(|r| r.as_bool()) == Some(true);
+                    let param_type = p
+                        .get("schema")
+                        .map(|s| self.rust_type(s, &known))
+                        .unwrap_or_else(|| self.value_type());
+                    Some((param_name, param_type, required))
+                })
+                .collect();
+            let mut arguments = String::new();
+            let mut insert_params = String::new();
+            for (param_name, param_type, required) in &params {
+                let ident = to_ident(param_name);
+                if *required {
+                    let _ = write!(arguments, ", {}: {}", ident, param_type);
+                    let _ = writeln!(
+                        insert_params,
+                        "        params.insert(\"{param_name}\".to_owned(), {sdk}::serde_json::to_value(&{ident}).map_err(|e| {sdk}::JsonRpcErrorType::Custom(e.to_string()))?);"
+                    );
+                } else {
+                    let _ = write!(arguments, ", {}: Option<{}>", ident, param_type);
+                    let _ = writeln!(
+                        insert_params,
+                        "        if let Some({ident}) = {ident} {{\n            params.insert(\"{param_name}\".to_owned(), {sdk}::serde_json::to_value(&{ident}).map_err(|e| {sdk}::JsonRpcErrorType::Custom(e.to_string()))?);\n        }}"
+                    );
+                }
+            }
+            let signature = format!(
+                "async fn {fn_name}(&mut self, ctx: Option<&{sdk}::api::gateway::rpc_gateway_api::CallContext>{arguments}) -> Result<{result_type}, {sdk}::JsonRpcErrorType>"
+            );
+            let params_mut = if params.is_empty() { "" } else { "mut " };
+            let body = format!(
+                r#"        let {params_mut}params = {sdk}::serde_json::Map::new();
+{insert_params}        let service_id = self.service_id.as_ref().map(|id| id.to_string()).unwrap_or_default();
+        self.call_and_parse_ripple_main_rpc::<{result_type}>(
+            "{name}",
+            Some({sdk}::serde_json::Value::Object(params)),
+            ctx,
+            {timeout},
+            &service_id,
+            "{name} failed",
+        )
+        .await
+"#,
+                timeout = self.timeout_ms
+            );
+            signatures.push((method, signature.clone()));
+            bodies.push((signature, body));
+        }
+
+        let _ = writeln!(out, "#[{sdk}::async_trait::async_trait]");
+        let _ = writeln!(out, "pub trait {} {{", self.trait_name);
+        for (method, signature) in &signatures {
+            Self::write_doc(&mut out, method, "    ");
+            let _ = writeln!(out, "    {};", signature);
+        }
+        let _ = writeln!(out, "}}\n");
+        let _ = writeln!(out, "#[{sdk}::async_trait::async_trait]");
+        let _ = writeln!(
+            out,
+            "impl {} for {sdk}::service::service_client::ServiceClient {{",
+            self.trait_name
+        );
+        for (i, (signature, body)) in bodies.iter().enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+            let _ = writeln!(out, "    {} {{", signature);
+            out.push_str(body);
+            let _ = writeln!(out, "    }}");
+        }
+        let _ = writeln!(out, "}}");
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+fn test_document() -> Value {
+    serde_json::json!({
+        "openrpc": "1.2.4",
+        "info": {"title": "Test", "version": "1.0.0"},
+        "methods": [
+            {
+                "name": "Device.name",
+                "summary": "Name of the device",
+                "params": [],
+                "result": {"name": "name", "schema": {"type": "string"}}
+            },
+            {
+                "name": "Device.setName",
+                "params": [
+                    {"name": "value", "required": true, "schema": {"type": "string"}},
+                    {"name": "type", "schema": {"$ref": "#/x-schemas/Device/NameType"}}
+                ],
+                "result": {"name": "result", "schema": {"const": null}}
+            },
+            {
+                "name": "Device.info",
+                "params": [],
+                "result": {"name": "info", "schema": {"$ref": "#/components/schemas/DeviceInfo"}}
+            },
+            {
+                "name": "Device.onNameChanged",
+                "params": [{"name": "listen", "required": true, "schema": {"type": "boolean"}}],
+                "tags": [{"name": "event"}],
+                "result": {
+                    "name": "name",
+                    "schema": {"anyOf": [
+                        {"$ref": "#/x-schemas/Types/ListenResponse"},
+                        {"type": "string"}
+                    ]}
+                }
+            },
+            {
+                "name": "Other.ignored",
+                "params": [],
+                "result": {"name": "result", "schema": {"type": "boolean"}}
+            }
+        ],
+        "components": {
+            "schemas": {
+                "DeviceInfo": {
+                    "type": "object",
+                    "required": ["modelName"],
+                    "properties": {
+                        "modelName": {"type": "string"},
+                        "screenResolution": {"type": "array", "items": {"type": "integer"}},
+                        "type": {"$ref": "#/x-schemas/Device/NameType"}
+                    }
+                }
+            }
+        },
+        "x-schemas": {
+            "Device": {
+                "uri": "https://meta.comcast.com/firebolt/device",
+                "NameType": {"type": "string", "enum": ["friendly", "model"]}
+            },
+            "Types": {
+                "ListenResponse": {
+                    "type": "object",
+                    "properties": {"event": {"type": "string"}, "listening": {"type": "boolean"}}
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+#[path = "openrpc_codegen_test_client.rs"]
+mod generated_client;
+
+#[cfg(test)]
+mod tests {
+    use super::generated_client::{DeviceInfo, TestClient};
+    use super::*;
+    use crate::{
+        api::gateway::rpc_gateway_api::{ApiProtocol, CallContext},
+        service::{
+            service_client::{tests::Mockable, ServiceClient},
+            service_message::Id,
+        },
+        utils::mock_utils::queue_mock_service_response,
+    };
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn codegen() -> ServiceClientCodegen {
+        ServiceClientCodegen::new("TestClient")
+            .with_sdk_path("crate")
+            .with_methods(vec!["Device.".to_owned()])
+            .with_open_rpc(test_document())
+    }
+
+    #[test]
+    fn test_generated_client_is_up_to_date() {
+        let generated = codegen().generate().unwrap();
+        // the checked in client has a license header on top of the generated code
+        let checked_in = include_str!("openrpc_codegen_test_client.rs");
+        assert!(
+            checked_in.ends_with(&generated),
+            "regenerate openrpc_codegen_test_client.rs:\n{}",
+            generated
+        );
+        assert!(!generated.contains("other_ignored"));
+    }
+
+    #[test]
+    fn test_naming() {
+        assert_eq!(to_ident("Device.setName"), "device_set_name");
+        assert_eq!(to_ident("type"), "r#type");
+        assert_eq!(to_type_name("app-session"), "AppSession");
+        assert!(ServiceClientCodegen::new("T").generate().is_err());
+    }
+
+    fn context(id: &str) -> CallContext {
+        CallContext::new(
+            id.to_owned(),
+            id.to_owned(),
+            "app1".to_owned(),
+            1,
+            ApiProtocol::Service,
+            "Device.info".to_owned(),
+            None,
+            false,
+        )
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_generated_client_parses_results() {
+        let mut client = ServiceClient::mock();
+        let id = Uuid::new_v4().to_string();
+        queue_mock_service_response(
+            &id,
+            Ok(ServiceMessage::new_success(
+                json!({"modelName": "xi6", "type": "model"}),
+                Id::Null,
+            )),
+        );
+        let info: DeviceInfo = client.device_info(Some(&context(&id))).await.unwrap();
+        assert_eq!(info.model_name, "xi6");
+        assert_eq!(info.r#type.as_deref(), Some("model"));
+        assert!(info.screen_resolution.is_none());
+
+        queue_mock_service_response(&id, Ok(ServiceMessage::new_success(json!("tv"), Id::Null)));
+        let name = client.device_name(Some(&context(&id))).await.unwrap();
+        assert_eq!(name, "tv");
+
+        queue_mock_service_response(&id, Err(RippleError::ServiceError));
+        assert!(client
+            .device_set_name(Some(&context(&id)), "living room".to_owned(), None)
+            .await
+            .is_err());
+        // no subscription response is queued so listening fails
+        assert!(client
+            .device_on_name_changed(Some(&context(&id)))
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_event_value() {
+        let event = ServiceMessage::new_notification(
+            "Device.onNameChanged".to_owned(),
+            Some(json!({"result": "kitchen", "sender_id": "1"})),
+        );
+        assert_eq!(event_value(&event), Some(json!("kitchen")));
+        let event = ServiceMessage::new_notification(
+            "Device.onNameChanged".to_owned(),
+            Some(json!({"name": "kitchen", "sender_id": "1"})),
+        );
+        assert_eq!(event_value(&event), Some(json!({"name": "kitchen"})));
+        let event = ServiceMessage::new_notification(
+            "Device.onNameChanged".to_owned(),
+            Some(json!("{\"name\":\"kitchen\"}")),
+        );
+        assert_eq!(event_value(&event), Some(json!({"name": "kitchen"})));
+    }
+}
@@ -0,0 +1,131 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+// @generated by ripple_sdk::service::openrpc_codegen, do not edit
+
+#[derive(Debug, Clone, crate::serde::Serialize, crate::serde::Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct DeviceInfo {
+    #[serde(rename = "modelName")]
+    pub model_name: String,
+    #[serde(rename = "screenResolution", default, skip_serializing_if = "Option::is_none")]
+    pub screen_resolution: Option<Vec<i64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub r#type: Option<NameType>,
+}
+
+#[derive(Debug, Clone, crate::serde::Serialize, crate::serde::Deserialize)]
+#[serde(crate = "crate::serde")]
+pub struct ListenResponse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub listening: Option<bool>,
+}
+
+pub type NameType = String;
+
+#[crate::async_trait::async_trait]
+pub trait TestClient {
+    async fn device_info(&mut self, ctx: Option<&crate::api::gateway::rpc_gateway_api::CallContext>) -> Result<DeviceInfo, crate::JsonRpcErrorType>;
+    /// Name of the device
+    async fn device_name(&mut self, ctx: Option<&crate::api::gateway::rpc_gateway_api::CallContext>) -> Result<String, crate::JsonRpcErrorType>;
+    async fn device_on_name_changed(&mut self, ctx: Option<&crate::api::gateway::rpc_gateway_api::CallContext>) -> Result<crate::tokio::sync::mpsc::Receiver<String>, crate::JsonRpcErrorType>;
+    async fn device_set_name(&mut self, ctx: Option<&crate::api::gateway::rpc_gateway_api::CallContext>, value: String, r#type: Option<NameType>) -> Result<(), crate::JsonRpcErrorType>;
+}
+
+#[crate::async_trait::async_trait]
+impl TestClient for crate::service::service_client::ServiceClient {
+    async fn device_info(&mut self, ctx: Option<&crate::api::gateway::rpc_gateway_api::CallContext>) -> Result<DeviceInfo, crate::JsonRpcErrorType> {
+        let params = crate::serde_json::Map::new();
+        let service_id = self.service_id.as_ref().map(|id| id.to_string()).unwrap_or_default();
+        self.call_and_parse_ripple_main_rpc::<DeviceInfo>(
+            "Device.info",
+            Some(crate::serde_json::Value::Object(params)),
+            ctx,
+            5000,
+            &service_id,
+            "Device.info failed",
+        )
+        .await
+    }
+
+    async fn device_name(&mut self, ctx: Option<&crate::api::gateway::rpc_gateway_api::CallContext>) -> Result<String, crate::JsonRpcErrorType> {
+        let params = crate::serde_json::Map::new();
+        let service_id = self.service_id.as_ref().map(|id| id.to_string()).unwrap_or_default();
+        self.call_and_parse_ripple_main_rpc::<String>(
+            "Device.name",
+            Some(crate::serde_json::Value::Object(params)),
+            ctx,
+            5000,
+            &service_id,
+            "Device.name failed",
+        )
+        .await
+    }
+
+    async fn device_on_name_changed(&mut self, ctx: Option<&crate::api::gateway::rpc_gateway_api::CallContext>) -> Result<crate::tokio::sync::mpsc::Receiver<String>, crate::JsonRpcErrorType> {
+        let (event_tx, mut event_rx) =
+            crate::tokio::sync::mpsc::channel::<crate::service::service_message::ServiceMessage>(16);
+        let (tx, rx) = crate::tokio::sync::mpsc::channel::<String>(16);
+        let service_id = self.service_id.as_ref().map(|id| id.to_string()).unwrap_or_default();
+        let subscribed = self
+            .call_and_parse_ripple_event_subscription_req_rpc(
+                "Device.onNameChanged",
+                Some(crate::serde_json::json!({"listen": true})),
+                ctx,
+                5000,
+                &service_id,
+                "Device.onNameChanged failed",
+                event_tx,
+            )
+            .await?;
+        if !subscribed {
+            return Err(crate::JsonRpcErrorType::Custom("Device.onNameChanged subscription failed".to_owned()));
+        }
+        crate::tokio::spawn(async move {
+            while let Some(message) = event_rx.recv().await {
+                let event = crate::service::openrpc_codegen::event_value(&message)
+                    .and_then(|value| crate::serde_json::from_value::<String>(value).ok());
+                if let Some(event) = event {
+                    if tx.send(event).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+        Ok(rx)
+    }
+
+    async fn device_set_name(&mut self, ctx: Option<&crate::api::gateway::rpc_gateway_api::CallContext>, value: String, r#type: Option<NameType>) -> Result<(), crate::JsonRpcErrorType> {
+        let mut params = crate::serde_json::Map::new();
+        params.insert("value".to_owned(), crate::serde_json::to_value(&value).map_err(|e| crate::JsonRpcErrorType::Custom(e.to_string()))?);
+        if let Some(r#type) = r#type {
+            params.insert("type".to_owned(), crate::serde_json::to_value(&r#type).map_err(|e| crate::JsonRpcErrorType::Custom(e.to_string()))?);
+        }
+        let service_id = self.service_id.as_ref().map(|id| id.to_string()).unwrap_or_default();
+        self.call_and_parse_ripple_main_rpc::<()>(
+            "Device.setName",
+            Some(crate::serde_json::Value::Object(params)),
+            ctx,
+            5000,
+            &service_id,
+            "Device.setName failed",
+        )
+        .await
+    }
+}
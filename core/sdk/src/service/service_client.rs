@@ -27,7 +27,10 @@ use crate::api::{
     manifest::extn_manifest::ExtnSymbol,
 };
 use crate::extn::extn_id::{ExtnClassId, ExtnId};
-use crate::extn::{client::extn_client::ExtnClient, extn_client_message::ExtnMessage};
+use crate::extn::{
+    client::extn_client::ExtnClient,
+    extn_client_message::{ExtnMessage, ExtnResponse},
+};
 use crate::processor::rpc_router::RouterState;
 use crate::service::service_message::{Id, JsonRpcMessage};
 use crate::service::service_rpc_router::route_service_message;
@@ -41,12 +44,18 @@ use log::{debug, error, info, trace, warn};
 use serde::de::DeserializeOwned;
 use serde_json::Value;
 use std::sync::{Arc, RwLock};
-use tokio::sync::{mpsc, oneshot};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, watch};
 use tokio::sync::{mpsc::Sender as MSender, oneshot::Sender as OSender};
 use tokio_tungstenite::tungstenite::Message;
 use uuid::Uuid;
 
+use super::service_connection::{
+    disconnected_error, ConnectionStateTx, OutboundPolicy, OutboundQueue, ServiceConnectionState,
+    ServiceReconnectPolicy, ServiceSubscription,
+};
 use super::service_message::{JsonRpcSuccess, ServiceMessage};
+use super::service_routing::ServiceMethods;
 #[derive(Debug, Clone, Default)]
 pub struct ServiceClient {
     pub service_sender: Option<MSender<ServiceMessage>>,
@@ -60,12 +69,16 @@ pub struct ServiceClient {
     pub outbound_service_rx: Arc<RwLock<Option<mpsc::Receiver<ServiceMessage>>>>,
     extn_manifest: ExtnManifest,
     device_manifest: DeviceManifest,
+    reconnect_policy: ServiceReconnectPolicy,
+    connection_state: ConnectionStateTx,
+    subscriptions: Arc<RwLock<Vec<ServiceSubscription>>>,
 }
 
 pub struct ServiceClientBuilder {
     extn_symbol: Option<ExtnSymbol>,
     service_name: String,
     extn_class_id: ExtnClassId,
+    reconnect_policy: ServiceReconnectPolicy,
 }
 
 impl Default for ServiceClientBuilder {
@@ -80,6 +93,7 @@ impl ServiceClientBuilder {
             extn_symbol: None,
             service_name,
             extn_class_id,
+            reconnect_policy: ServiceReconnectPolicy::default(),
         }
     }
 
@@ -87,6 +101,13 @@ impl ServiceClientBuilder {
         self.extn_symbol = Some(symbol);
         self
     }
+
+    /// Backoff between connection attempts and handling of the messages sent while
+    /// disconnected
+    pub fn with_reconnect_policy(mut self, policy: ServiceReconnectPolicy) -> Self {
+        self.reconnect_policy = policy;
+        self
+    }
     fn get_symbol(
         extn_manifest: ExtnManifest,
         service_name: String,
@@ -134,6 +155,9 @@ impl ServiceClientBuilder {
                 outbound_service_rx: Arc::new(RwLock::new(Some(service_tr))),
                 extn_manifest,
                 device_manifest,
+                reconnect_policy: self.reconnect_policy.clone(),
+                connection_state: ConnectionStateTx::default(),
+                subscriptions: Arc::new(RwLock::new(Vec::new())),
             })
         } else {
             Ok(ServiceClient {
@@ -147,6 +171,9 @@ impl ServiceClientBuilder {
                 outbound_service_rx: Arc::new(RwLock::new(None)),
                 extn_manifest,
                 device_manifest,
+                reconnect_policy: self.reconnect_policy.clone(),
+                connection_state: ConnectionStateTx::default(),
+                subscriptions: Arc::new(RwLock::new(Vec::new())),
             })
         }
     }
@@ -163,9 +190,16 @@ impl ServiceClient {
         self.device_manifest.clone()
     }
 
+    /// Adds the methods handled by the service, Main routes the calls of other services to them
     pub fn set_service_rpc_route(&mut self, methods: Methods) -> Result<(), RippleError> {
         let service_routes = self.service_router.write().unwrap();
         service_routes.update_methods(methods.clone());
+        let registration = ServiceMethods::new(service_routes.get_method_names());
+        if let Some(sender) = &self.service_sender {
+            if let Err(e) = sender.try_send(registration.to_notification()) {
+                warn!("Failed to register service methods with Main: {:?}", e);
+            }
+        }
         Ok(())
     }
 
@@ -191,6 +225,11 @@ impl ServiceClient {
         self.service_router.read().unwrap().clone()
     }
 
+    /// Watch of the connection to Ripple Main
+    pub fn connection_state(&self) -> watch::Receiver<ServiceConnectionState> {
+        self.connection_state.subscribe()
+    }
+
     /// Initializes the service client, handling both extension and service messages.
     pub async fn initialize(&self) {
         debug!("Starting Service Client initialize");
@@ -223,25 +262,148 @@ impl ServiceClient {
             }
         };
 
+        let mut pending = OutboundQueue::new(self.reconnect_policy.queue_size);
+        let mut pending_extn = OutboundQueue::new(self.reconnect_policy.queue_size);
         let mut retry_count = 0u32;
         loop {
             debug!("Connecting to WebSocket at {}", path);
-            Self::connect_websocket(self, &path, &mut outbound_service_rx, &mut outbound_extn_rx)
-                .await;
-
-            debug!("Initialize Ended Abruptly");
+            self.connection_state
+                .set(ServiceConnectionState::Connecting);
+            let connected = Self::connect_websocket(
+                self,
+                &path,
+                &mut outbound_service_rx,
+                &mut outbound_extn_rx,
+                &mut pending,
+                &mut pending_extn,
+            )
+            .await;
+            self.connection_state
+                .set(ServiceConnectionState::Disconnected);
+            if connected {
+                retry_count = 0;
+            }
 
+            let backoff = self.reconnect_policy.backoff(retry_count);
+            debug!("Initialize Ended Abruptly, reconnecting in {:?}", backoff);
             retry_count = retry_count.saturating_add(1);
+            self.hold_outbound(
+                &mut outbound_service_rx,
+                &mut outbound_extn_rx,
+                &mut pending,
+                &mut pending_extn,
+                backoff,
+            )
+            .await;
         }
     }
 
+    /// Holds the outbound messages as per the reconnect policy until the backoff elapsed
+    async fn hold_outbound(
+        &self,
+        outbound_service_rx: &mut mpsc::Receiver<ServiceMessage>,
+        outbound_extn_rx: &mut Option<mpsc::Receiver<ApiMessage>>,
+        pending: &mut OutboundQueue,
+        pending_extn: &mut OutboundQueue<ApiMessage>,
+        backoff: Duration,
+    ) {
+        let delay = tokio::time::sleep(backoff);
+        tokio::pin!(delay);
+        loop {
+            tokio::select! {
+                _ = &mut delay => break,
+                Some(message) = outbound_service_rx.recv() => {
+                    let failed = match self.reconnect_policy.outbound {
+                        OutboundPolicy::Buffer => pending.push(message),
+                        OutboundPolicy::Fail => Some(message),
+                    };
+                    if let Some(error) = failed.as_ref().and_then(disconnected_error) {
+                        self.send_service_response(error);
+                    }
+                }
+                Some(message) = async {
+                    match outbound_extn_rx.as_mut() {
+                        Some(rx) => rx.recv().await,
+                        None => None,
+                    }
+                }, if outbound_extn_rx.is_some() => {
+                    let failed = match self.reconnect_policy.outbound {
+                        OutboundPolicy::Buffer => pending_extn.push(message),
+                        OutboundPolicy::Fail => Some(message),
+                    };
+                    if let Some(message) = failed {
+                        self.fail_extn_request(message);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Responds with an error to a request of the extension client which could not be sent
+    fn fail_extn_request(&self, message: ApiMessage) {
+        let extn_client = match &self.extn_client {
+            Some(extn_client) => extn_client,
+            None => return,
+        };
+        if let Ok(request) = ExtnMessage::try_from(message.jsonrpc_msg) {
+            if let Ok(response) =
+                request.get_response(ExtnResponse::Error(RippleError::SendFailure))
+            {
+                extn_client.handle_message(response);
+            }
+        }
+    }
+
+    /// Messages to send once connected, the queued ones followed by the registration of the
+    /// service methods and the subscriptions. A subscription or registration made while
+    /// disconnected is only sent once.
+    fn get_messages_on_connect(&self, pending: &mut OutboundQueue) -> Vec<ServiceMessage> {
+        let subscriptions = self.subscriptions.read().unwrap().clone();
+        let is_subscription = |message: &ServiceMessage| match &message.message {
+            JsonRpcMessage::Request(request) => subscriptions
+                .iter()
+                .any(|s| matches!(&request.id, Id::String(id) if id == &s.id)),
+            _ => false,
+        };
+        let mut messages: Vec<ServiceMessage> = pending
+            .drain()
+            .into_iter()
+            .filter(|m| !is_subscription(m) && !ServiceMethods::is_registration(m))
+            .collect();
+        let methods = self.service_router.read().unwrap().get_method_names();
+        if !methods.is_empty() {
+            messages.push(ServiceMethods::new(methods).to_notification());
+        }
+        messages.extend(subscriptions.iter().map(|s| {
+            Self::new_service_request(
+                &s.method,
+                s.params.clone(),
+                &s.ctx,
+                &s.id,
+                s.service_id.clone(),
+            )
+        }));
+        messages
+    }
+
+    /// Returns whether the connection was established
     async fn connect_websocket(
         &self,
         path: &str,
         outbound_service_rx: &mut mpsc::Receiver<ServiceMessage>,
         outbound_extn_rx: &mut Option<mpsc::Receiver<ApiMessage>>,
-    ) {
+        pending: &mut OutboundQueue,
+        pending_extn: &mut OutboundQueue<ApiMessage>,
+    ) -> bool {
         if let Ok((mut ws_tx, mut ws_rx)) = WebSocketUtils::get_ws_stream(path, None).await {
+            self.connection_state.set(ServiceConnectionState::Connected);
+            for message in self.get_messages_on_connect(pending) {
+                let _feed = ws_tx.feed(Message::Text(message.into())).await;
+            }
+            for message in pending_extn.drain() {
+                let _feed = ws_tx.feed(Message::Text(message.jsonrpc_msg)).await;
+            }
+            let _flush = ws_tx.flush().await;
             let handle_ws_message = |msg: Message| {
                 if let Message::Text(message) = msg.clone() {
                     // Service message
@@ -385,7 +547,9 @@ impl ServiceClient {
                     }
                 }
             }
+            return true;
         }
+        false
     }
 
     fn send_service_response(&self, sm: ServiceMessage) {
@@ -592,16 +756,18 @@ impl ServiceClient {
         event_sender: Option<MSender<ServiceMessage>>,
     ) -> Result<ServiceMessage, RippleError> {
         let id = uuid::Uuid::new_v4().to_string();
-        let mut service_req =
-            ServiceMessage::new_request(method.to_owned(), params, Id::String(id.clone()));
-        let mut context = ctx.clone();
-        context.protocol = ApiProtocol::Service;
-
-        let vec = vec![id.clone(), service_id];
-        context.context = vec;
-
-        let service_message_context = serde_json::to_value(context).unwrap();
-        service_req.set_context(Some(service_message_context));
+        let service_req =
+            Self::new_service_request(&method, params.clone(), ctx, &id, service_id.clone());
+        if let Some(event_sender) = &event_sender {
+            self.track_subscription(ServiceSubscription {
+                id: id.clone(),
+                method,
+                params,
+                ctx: ctx.clone(),
+                service_id,
+                event_sender: event_sender.clone(),
+            });
+        }
         if event_sender.is_none() {
             let (tx, rx) = oneshot::channel();
             add_response_processor(id, Some(tx), self.response_processors.clone());
@@ -650,6 +816,32 @@ impl ServiceClient {
         }
     }
 
+    fn new_service_request(
+        method: &str,
+        params: Option<Value>,
+        ctx: &CallContext,
+        id: &str,
+        service_id: String,
+    ) -> ServiceMessage {
+        let mut service_req =
+            ServiceMessage::new_request(method.to_owned(), params, Id::String(id.to_owned()));
+        let mut context = ctx.clone();
+        context.protocol = ApiProtocol::Service;
+        context.context = vec![id.to_owned(), service_id];
+        service_req.set_context(Some(serde_json::to_value(context).unwrap()));
+        service_req
+    }
+
+    /// Keeps the subscription to register it again after a reconnect, a request with
+    /// `listen: false` drops the subscription of the method with the same params
+    fn track_subscription(&self, subscription: ServiceSubscription) {
+        let mut subscriptions = self.subscriptions.write().unwrap();
+        subscriptions.retain(|s| !s.is_same(&subscription));
+        if !ServiceSubscription::is_unlisten(&subscription.params) {
+            subscriptions.push(subscription);
+        }
+    }
+
     pub fn get_default_service_call_context(method: String) -> CallContext {
         CallContext::new(
             Uuid::new_v4().to_string(),
@@ -791,6 +983,9 @@ pub mod tests {
                 event_processors: Arc::new(RwLock::new(HashMap::new())),
                 extn_manifest: ExtnManifest::default(),
                 device_manifest: DeviceManifest::default(),
+                reconnect_policy: ServiceReconnectPolicy::default(),
+                connection_state: ConnectionStateTx::default(),
+                subscriptions: Arc::new(RwLock::new(Vec::new())),
             }
         }
 
@@ -836,6 +1031,130 @@ pub mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_subscriptions_registered_again_on_connect() {
+        let mut client = ServiceClient::mock();
+        assert_eq!(
+            *client.connection_state().borrow(),
+            ServiceConnectionState::Disconnected
+        );
+        let mut outbound = client.get_outbound_service_rx().unwrap();
+        let (event_tx, _event_rx) = mpsc::channel::<ServiceMessage>(1);
+        let ctx = ServiceClient::get_default_service_call_context("device.onNameChanged".into());
+        client
+            .send_rpc_main(
+                "device.onNameChanged".into(),
+                Some(json!({"listen": true})),
+                &ctx,
+                "service1".into(),
+                Some(event_tx.clone()),
+            )
+            .await
+            .unwrap();
+
+        // both requests were sent while disconnected
+        let mut pending = OutboundQueue::new(4);
+        pending.push(outbound.recv().await.unwrap());
+        let id = client.request_transient("device.name".into(), None, None, "service1".into());
+        pending.push(outbound.recv().await.unwrap());
+
+        let messages = client.get_messages_on_connect(&mut pending);
+        let ids: Vec<String> = messages
+            .iter()
+            .filter_map(|m| match &m.message {
+                JsonRpcMessage::Request(r) => match &r.id {
+                    Id::String(id) => Some(id.clone()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+        let subscription_id = client.subscriptions.read().unwrap()[0].id.clone();
+        assert_eq!(ids, vec![id.unwrap(), subscription_id]);
+
+        // stopping to listen drops the subscription
+        client
+            .send_rpc_main(
+                "device.onNameChanged".into(),
+                Some(json!({"listen": false})),
+                &ctx,
+                "service1".into(),
+                Some(event_tx),
+            )
+            .await
+            .unwrap();
+        assert!(client.subscriptions.read().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions_kept_apart_by_params() {
+        let mut client = ServiceClient::mock();
+        let _outbound = client.get_outbound_service_rx().unwrap();
+        let (event_tx, _event_rx) = mpsc::channel::<ServiceMessage>(1);
+        let ctx = ServiceClient::get_default_service_call_context("device.onChanged".into());
+        for params in [
+            json!({"listen": true, "property": "name"}),
+            json!({"listen": true, "property": "model"}),
+            // the same subscription again
+            json!({"listen": true, "property": "name"}),
+        ] {
+            client
+                .send_rpc_main(
+                    "device.onChanged".into(),
+                    Some(params),
+                    &ctx,
+                    "service1".into(),
+                    Some(event_tx.clone()),
+                )
+                .await
+                .unwrap();
+        }
+        assert_eq!(client.subscriptions.read().unwrap().len(), 2);
+
+        client
+            .send_rpc_main(
+                "device.onChanged".into(),
+                Some(json!({"listen": false, "property": "name"})),
+                &ctx,
+                "service1".into(),
+                Some(event_tx),
+            )
+            .await
+            .unwrap();
+        let subscriptions = client.subscriptions.read().unwrap();
+        assert_eq!(subscriptions.len(), 1);
+        assert_eq!(
+            subscriptions[0].params,
+            Some(json!({"listen": true, "property": "model"}))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_extn_messages_held_while_disconnected() {
+        let client = ServiceClient::mock();
+        let mut outbound_service_rx = client.get_outbound_service_rx().unwrap();
+        let (extn_tx, extn_rx) = mpsc::channel::<ApiMessage>(4);
+        let mut outbound_extn_rx = Some(extn_rx);
+        let message = ApiMessage::new(ApiProtocol::Extn, "{}".to_owned(), "1".to_owned());
+        extn_tx.send(message).await.unwrap();
+
+        let mut pending = OutboundQueue::new(4);
+        let mut pending_extn = OutboundQueue::new(4);
+        client
+            .hold_outbound(
+                &mut outbound_service_rx,
+                &mut outbound_extn_rx,
+                &mut pending,
+                &mut pending_extn,
+                Duration::from_millis(50),
+            )
+            .await;
+        assert!(pending.is_empty());
+        let held = pending_extn.drain();
+        assert_eq!(held.len(), 1);
+        assert_eq!(held[0].request_id, "1");
+    }
+
     #[tokio::test]
     async fn test_get_outbound_service_rx() {
         let client = ServiceClient::mock();
@@ -1093,6 +1412,31 @@ pub mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_service_methods_registered_with_main() {
+        let mut client = ServiceClient::mock();
+        let mut outbound = client.get_outbound_service_rx().unwrap();
+        let mut module = jsonrpsee::RpcModule::new(());
+        module
+            .register_method("Device.name", |_, _| Ok("Living Room"))
+            .unwrap();
+        client.set_service_rpc_route(module.into()).unwrap();
+        let registration = outbound.recv().await.unwrap();
+        assert!(ServiceMethods::is_registration(&registration));
+
+        // the registration sent while disconnected is replaced by the current one
+        let mut pending = OutboundQueue::new(4);
+        pending.push(registration);
+        let messages = client.get_messages_on_connect(&mut pending);
+        assert_eq!(messages.len(), 1);
+        match &messages[0].message {
+            JsonRpcMessage::Notification(n) => {
+                assert_eq!(n.params, Some(json!({"methods": ["Device.name"]})))
+            }
+            _ => panic!("expected the method registration"),
+        }
+    }
+
     #[tokio::test]
     async fn test_send_notification_with_params() {
         let client = ServiceClient::mock();
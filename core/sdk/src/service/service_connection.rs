@@ -0,0 +1,241 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{collections::VecDeque, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::{mpsc::Sender as MSender, watch};
+
+use crate::api::gateway::rpc_gateway_api::CallContext;
+
+use super::service_message::{JsonRpcMessage, ServiceMessage};
+
+/// Error code of the response to a request which could not be sent to Ripple Main
+pub const SERVICE_DISCONNECTED_ERROR_CODE: i64 = -32001;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ServiceConnectionState {
+    #[default]
+    Disconnected,
+    Connecting,
+    Connected,
+}
+
+/// What happens to the outbound messages while the service is not connected
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutboundPolicy {
+    /// Messages are queued and sent once connected, the oldest one fails when the queue is full
+    #[default]
+    Buffer,
+    /// Messages fail right away
+    Fail,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceReconnectPolicy {
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    pub outbound: OutboundPolicy,
+    pub queue_size: usize,
+}
+
+impl Default for ServiceReconnectPolicy {
+    fn default() -> Self {
+        ServiceReconnectPolicy {
+            initial_backoff_ms: 100,
+            max_backoff_ms: 30000,
+            outbound: OutboundPolicy::default(),
+            queue_size: 64,
+        }
+    }
+}
+
+impl ServiceReconnectPolicy {
+    /// Delay before the next attempt after `retry_count` failed attempts, doubles every time
+    pub fn backoff(&self, retry_count: u32) -> Duration {
+        let factor = 2u64.saturating_pow(retry_count.min(32));
+        Duration::from_millis(
+            self.initial_backoff_ms
+                .saturating_mul(factor)
+                .min(self.max_backoff_ms),
+        )
+    }
+}
+
+/// Publishes the connection state of the service client
+#[derive(Debug, Clone)]
+pub struct ConnectionStateTx(watch::Sender<ServiceConnectionState>);
+
+impl Default for ConnectionStateTx {
+    fn default() -> Self {
+        ConnectionStateTx(watch::Sender::new(ServiceConnectionState::default()))
+    }
+}
+
+impl ConnectionStateTx {
+    pub fn set(&self, state: ServiceConnectionState) {
+        self.0.send_if_modified(|current| {
+            let changed = *current != state;
+            *current = state;
+            changed
+        });
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<ServiceConnectionState> {
+        self.0.subscribe()
+    }
+}
+
+/// Bounded queue of the messages sent while disconnected, the service messages by default and
+/// the messages of the extension client of the service in a queue of their own
+#[derive(Debug)]
+pub struct OutboundQueue<T = ServiceMessage> {
+    messages: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T> OutboundQueue<T> {
+    pub fn new(capacity: usize) -> Self {
+        OutboundQueue {
+            messages: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// Queues the message and returns the one which has to fail, either the oldest queued
+    /// message when the queue is full or the message itself when nothing can be queued
+    pub fn push(&mut self, message: T) -> Option<T> {
+        if self.capacity == 0 {
+            return Some(message);
+        }
+        let evicted = if self.messages.len() >= self.capacity {
+            self.messages.pop_front()
+        } else {
+            None
+        };
+        self.messages.push_back(message);
+        evicted
+    }
+
+    pub fn drain(&mut self) -> Vec<T> {
+        self.messages.drain(..).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+}
+
+/// Error response for a request which could not be sent, None for other messages
+pub fn disconnected_error(message: &ServiceMessage) -> Option<ServiceMessage> {
+    if let JsonRpcMessage::Request(request) = &message.message {
+        let mut error = ServiceMessage::new_error(
+            SERVICE_DISCONNECTED_ERROR_CODE,
+            "Service is not connected to Ripple Main".to_owned(),
+            None,
+            request.id.clone(),
+        );
+        error.set_context(message.context.clone());
+        return Some(error);
+    }
+    None
+}
+
+/// Event subscription made through the service client, registered again after a reconnect
+#[derive(Debug, Clone)]
+pub struct ServiceSubscription {
+    /// Id of the subscription request, notifications carry it as their `sender_id`. The same
+    /// id is used when the subscription is registered again so its event processor stays valid.
+    pub id: String,
+    pub method: String,
+    pub params: Option<Value>,
+    pub ctx: CallContext,
+    pub service_id: String,
+    pub event_sender: MSender<ServiceMessage>,
+}
+
+impl ServiceSubscription {
+    /// Whether the params of a subscription request stop listening
+    pub fn is_unlisten(params: &Option<Value>) -> bool {
+        params
+            .as_ref()
+            .and_then(|p| p.get("listen"))
+            .and_then(|l| l.as_bool())
+            .map_or(false, |listen| !listen)
+    }
+
+    /// Params of a subscription request without `listen`, None when nothing else is left
+    fn key_params(params: &Option<Value>) -> Option<Value> {
+        match params {
+            Some(Value::Object(map)) => {
+                let mut map = map.clone();
+                map.remove("listen");
+                (!map.is_empty()).then_some(Value::Object(map))
+            }
+            params => params.clone(),
+        }
+    }
+
+    /// Whether both requests are about the same subscription, the subscriptions of a method
+    /// with other params are kept apart
+    pub fn is_same(&self, other: &ServiceSubscription) -> bool {
+        self.method == other.method
+            && Self::key_params(&self.params) == Self::key_params(&other.params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::service_message::Id;
+
+    #[test]
+    fn test_backoff_is_bounded() {
+        let policy = ServiceReconnectPolicy {
+            initial_backoff_ms: 100,
+            max_backoff_ms: 1000,
+            ..Default::default()
+        };
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(3), Duration::from_millis(800));
+        assert_eq!(policy.backoff(4), Duration::from_millis(1000));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_outbound_queue_fails_oldest() {
+        let mut queue = OutboundQueue::new(2);
+        let request = |id| ServiceMessage::new_request("m".into(), None, Id::Number(id));
+        assert!(queue.push(request(1)).is_none());
+        assert!(queue.push(request(2)).is_none());
+        let evicted = queue.push(request(3)).unwrap();
+        assert_eq!(evicted.get_request_id(), 1);
+        let error = disconnected_error(&evicted).unwrap();
+        assert!(matches!(error.message, JsonRpcMessage::Error(_)));
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.drain().len(), 2);
+        assert!(queue.is_empty());
+
+        let mut no_queue = OutboundQueue::new(0);
+        assert!(no_queue.push(request(4)).is_some());
+    }
+}
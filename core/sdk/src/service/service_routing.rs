@@ -0,0 +1,52 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use serde::{Deserialize, Serialize};
+
+use super::service_message::{JsonRpcMessage, ServiceMessage};
+
+/// Notification through which a service tells Main the methods it handles, so Main can route
+/// the calls of other services to it
+pub const REGISTER_SERVICE_METHODS: &str = "ripple.registerServiceMethods";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServiceMethods {
+    pub methods: Vec<String>,
+}
+
+impl ServiceMethods {
+    pub fn new(mut methods: Vec<String>) -> Self {
+        methods.sort();
+        methods.dedup();
+        ServiceMethods { methods }
+    }
+
+    pub fn to_notification(&self) -> ServiceMessage {
+        ServiceMessage::new_notification(
+            REGISTER_SERVICE_METHODS.to_owned(),
+            serde_json::to_value(self).ok(),
+        )
+    }
+
+    pub fn is_registration(message: &ServiceMessage) -> bool {
+        matches!(
+            &message.message,
+            JsonRpcMessage::Notification(n)
+                if n.method == REGISTER_SERVICE_METHODS
+        )
+    }
+}
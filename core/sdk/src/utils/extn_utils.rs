@@ -15,9 +15,15 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{
+    future::Future,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
-use tokio::runtime::{Builder, Runtime};
+use tokio::{
+    runtime::{Builder, Runtime},
+    task::JoinSet,
+};
 
 pub struct ExtnUtils;
 const MIN_STACK_SIZE: usize = 512 * 1024;
@@ -81,4 +87,49 @@ impl ExtnUtils {
             .build()
             .unwrap()
     }
+
+    /// Waits for `main` while watching the tasks an extension spawned on its own runtime. The
+    /// runtime swallows the panic of a task, so it is resumed here to unwind out of `start` and
+    /// reach the supervisor of Main. The tasks still running are aborted when `main` returns.
+    pub async fn supervise_tasks<F: Future>(main: F, mut tasks: JoinSet<()>) -> F::Output {
+        tokio::pin!(main);
+        loop {
+            tokio::select! {
+                output = &mut main => return output,
+                Some(result) = tasks.join_next(), if !tasks.is_empty() => {
+                    if let Err(e) = result {
+                        if e.is_panic() {
+                            std::panic::resume_unwind(e.into_panic());
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supervise_tasks_resumes_panics() {
+        let runtime = ExtnUtils::get_runtime("e-test".to_owned(), None);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            runtime.block_on(async {
+                let mut tasks = JoinSet::new();
+                tasks.spawn(async { panic!("task died") });
+                ExtnUtils::supervise_tasks(std::future::pending::<()>(), tasks).await
+            })
+        }));
+        assert!(result.is_err());
+
+        let output = runtime.block_on(async {
+            let mut tasks = JoinSet::new();
+            tasks.spawn(async {});
+            tasks.spawn(std::future::pending());
+            ExtnUtils::supervise_tasks(async { 1 }, tasks).await
+        });
+        assert_eq!(output, 1);
+    }
 }
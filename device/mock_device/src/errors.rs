@@ -65,6 +65,7 @@ pub enum BootFailedError {
     BadHostname,
     GetPlatformGatewayFailed,
     ServerStartFailed(MockServerWebSocketError),
+    RecordUpstreamMissing,
 }
 
 impl Display for BootFailedError {
@@ -80,6 +81,9 @@ impl Display for BootFailedError {
             Self::ServerStartFailed(err) => {
                 format!("Failed to start the WebSocket server. Error: {err}")
             }
+            Self::RecordUpstreamMissing => {
+                "The record mock_mode requires the record_upstream config.".to_owned()
+            }
         };
 
         f.write_str(msg.as_str())
@@ -100,6 +104,8 @@ pub enum LoadMockDataError {
     MockDataNotValidJson,
     MockDataNotArray,
     MockDataError(MockDataError),
+    ScenarioNotValid,
+    FaultsNotValid,
 }
 
 impl Display for LoadMockDataError {
@@ -115,6 +121,8 @@ impl Display for LoadMockDataError {
             Self::MockDataError(err) => {
                 format!("Failed to parse message in mock data. Error: {err:?}")
             }
+            Self::ScenarioNotValid => "The mock scenario is not a valid scenario.".to_owned(),
+            Self::FaultsNotValid => "The mock faults are not a valid fault config.".to_owned(),
         };
 
         f.write_str(msg.as_str())
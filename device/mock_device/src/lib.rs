@@ -20,6 +20,10 @@ pub mod mock_config;
 pub mod mock_data;
 pub mod mock_device_controller;
 pub mod mock_device_ffi;
+pub mod mock_faults;
+pub mod mock_matcher;
+pub mod mock_recorder;
+pub mod mock_scenario;
 pub mod mock_server;
 pub mod mock_web_socket_server;
 
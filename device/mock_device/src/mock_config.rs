@@ -16,11 +16,21 @@
 //
 
 use serde::Deserialize;
+use serde_json::Value;
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct MockConfig {
     pub activate_all_plugins: bool,
     pub stats_file: String,
+    pub mode: MockMode,
+    /// Endpoint the server proxies to in [MockMode::Record]
+    pub record_upstream: Option<String>,
+    /// File written in [MockMode::Record] and served in [MockMode::Replay]
+    pub capture_file: String,
+    /// Fields which change between calls and are left out of recorded and matched params
+    pub volatile_fields: Vec<String>,
+    /// Error returned for requests which have no matching mock data
+    pub default_error: Option<Value>,
 }
 
 impl Default for MockConfig {
@@ -28,6 +38,35 @@ impl Default for MockConfig {
         Self {
             activate_all_plugins: true,
             stats_file: "stats.json".to_string(),
+            mode: MockMode::Mock,
+            record_upstream: None,
+            capture_file: "mock-device-capture.json".to_string(),
+            volatile_fields: Vec::new(),
+            default_error: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MockMode {
+    /// Serve the mock data file and runtime mocks
+    Mock,
+    /// Proxy to `record_upstream` and capture the traffic into `capture_file`
+    Record,
+    /// Serve a capture made in record mode
+    Replay,
+}
+
+impl std::str::FromStr for MockMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mock" => Ok(MockMode::Mock),
+            "record" => Ok(MockMode::Record),
+            "replay" => Ok(MockMode::Replay),
+            _ => Err(()),
         }
     }
 }
@@ -22,6 +22,7 @@ use std::{collections::HashMap, fmt::Display, sync::Arc};
 
 use crate::{
     errors::{LoadMockDataError, MockDeviceError},
+    mock_matcher::ParamMatcher,
     mock_server::{MessagePayload, PayloadType, PayloadTypeError},
     mock_web_socket_server::{MockWebSocketServer, ThunderRegisterParams},
 };
@@ -45,6 +46,8 @@ pub struct ParamResponse {
     pub result: Option<Value>,
     pub error: Option<Value>,
     pub events: Option<Vec<EventValue>>,
+    #[serde(rename = "match", default, skip_serializing_if = "Option::is_none")]
+    pub matcher: Option<ParamMatcher>,
 }
 
 #[derive(Debug)]
@@ -54,6 +57,19 @@ pub struct ResponseSink {
 }
 
 impl ParamResponse {
+    /// Checks the request params against the [ParamMatcher] of the entry, falling back to
+    /// equality of params when the entry has no matcher.
+    pub fn matches(&self, params: &Value) -> bool {
+        match &self.matcher {
+            Some(matcher) => matcher.matches(self.params.as_ref(), params),
+            None => self.get_key(params).is_some(),
+        }
+    }
+
+    pub fn priority(&self) -> i64 {
+        self.matcher.as_ref().map_or(0, |m| m.priority)
+    }
+
     pub fn get_key(&self, key: &Value) -> Option<Self> {
         match &self.params {
             Some(v) => {
@@ -220,6 +236,7 @@ mod tests {
     #[test]
     fn test_param_response_get_key() {
         let response = ParamResponse {
+            matcher: None,
             result: None,
             error: None,
             events: None,
@@ -227,6 +244,7 @@ mod tests {
         };
         assert!(response.get_key(&Value::Null).is_some());
         let response = ParamResponse {
+            matcher: None,
             result: None,
             error: None,
             events: None,
@@ -241,6 +259,7 @@ mod tests {
     #[test]
     fn test_param_response_get_notif_id() {
         let response = ParamResponse {
+            matcher: None,
             result: None,
             error: None,
             events: None,
@@ -248,6 +267,7 @@ mod tests {
         };
         assert!(response.get_notification_id().is_none());
         let response = ParamResponse {
+            matcher: None,
             result: None,
             error: None,
             events: None,
@@ -256,6 +276,7 @@ mod tests {
         assert!(response.get_notification_id().is_none());
 
         let response = ParamResponse {
+            matcher: None,
             result: None,
             error: None,
             events: None,
@@ -274,6 +295,7 @@ mod tests {
     #[test]
     fn test_get_all() {
         let pr = ParamResponse {
+            matcher: None,
             result: None,
             error: Some(json!({"code": -32010, "message": "Error Message"})),
             events: None,
@@ -290,6 +312,7 @@ mod tests {
         assert!(response.eq(&-32010));
 
         let pr = ParamResponse {
+            matcher: None,
             result: Some(json!({"code": 0})),
             error: None,
             events: Some(vec![EventValue {
@@ -20,8 +20,11 @@ use std::fmt::Display;
 use crate::{
     mock_data::MockData,
     mock_data::MockDeviceState,
+    mock_faults::{FaultConfig, MethodFaults},
+    mock_scenario::Scenario,
     mock_server::{
-        AddRequestResponseResponse, EmitEventParams, EmitEventResponse, RemoveRequestResponse,
+        AddRequestResponseResponse, EmitEventParams, EmitEventResponse, FaultsResponse,
+        RemoveRequestResponse, ScenarioStateResponse,
     },
 };
 use jsonrpsee::{core::RpcResult, proc_macros::rpc};
@@ -31,6 +34,7 @@ use ripple_sdk::{
     extn::extn_id::ExtnProviderResponse,
     utils::{error::RippleError, rpc_utils::rpc_err},
 };
+use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
 enum MockDeviceControllerError {
@@ -79,6 +83,43 @@ pub trait MockDeviceController {
         ctx: CallContext,
         req: MockData,
     ) -> RpcResult<ExtnProviderResponse>;
+
+    #[method(name = "mockdevice.setScenario")]
+    async fn set_scenario(
+        &self,
+        ctx: CallContext,
+        req: Scenario,
+    ) -> RpcResult<ExtnProviderResponse>;
+
+    #[method(name = "mockdevice.resetScenario")]
+    async fn reset_scenario(&self, ctx: CallContext) -> RpcResult<ExtnProviderResponse>;
+
+    #[method(name = "mockdevice.getScenarioState")]
+    async fn get_scenario_state(&self, ctx: CallContext) -> RpcResult<ExtnProviderResponse>;
+
+    #[method(name = "mockdevice.setFaults")]
+    async fn set_faults(
+        &self,
+        ctx: CallContext,
+        req: FaultConfig,
+    ) -> RpcResult<ExtnProviderResponse>;
+
+    #[method(name = "mockdevice.addFaults")]
+    async fn add_faults(
+        &self,
+        ctx: CallContext,
+        req: HashMap<String, MethodFaults>,
+    ) -> RpcResult<ExtnProviderResponse>;
+
+    #[method(name = "mockdevice.removeFaults")]
+    async fn remove_faults(
+        &self,
+        ctx: CallContext,
+        req: Vec<String>,
+    ) -> RpcResult<ExtnProviderResponse>;
+
+    #[method(name = "mockdevice.clearFaults")]
+    async fn clear_faults(&self, ctx: CallContext) -> RpcResult<ExtnProviderResponse>;
 }
 
 pub struct MockDeviceController {
@@ -89,6 +130,16 @@ impl MockDeviceController {
     pub fn new(state: MockDeviceState) -> MockDeviceController {
         MockDeviceController { state }
     }
+
+    fn faults_response(&self) -> ExtnProviderResponse {
+        ExtnProviderResponse {
+            value: serde_json::to_value(FaultsResponse {
+                success: true,
+                faults: self.state.server.fault_config(),
+            })
+            .unwrap(),
+        }
+    }
 }
 
 #[async_trait]
@@ -158,4 +209,81 @@ impl MockDeviceControllerServer for MockDeviceController {
             value: serde_json::to_value(EmitEventResponse { success: true }).unwrap(),
         })
     }
+
+    async fn set_scenario(
+        &self,
+        _ctx: CallContext,
+        req: Scenario,
+    ) -> RpcResult<ExtnProviderResponse> {
+        self.state.server.set_scenario(Some(req));
+        Ok(ExtnProviderResponse {
+            value: serde_json::to_value(ScenarioStateResponse {
+                success: true,
+                state: self.state.server.scenario_state(),
+            })
+            .unwrap(),
+        })
+    }
+
+    async fn reset_scenario(&self, _ctx: CallContext) -> RpcResult<ExtnProviderResponse> {
+        if !self.state.server.reset_scenario() {
+            return Err(rpc_err(MockDeviceControllerError::RequestFailed(
+                RippleError::NotAvailable,
+            )));
+        }
+        Ok(ExtnProviderResponse {
+            value: serde_json::to_value(ScenarioStateResponse {
+                success: true,
+                state: self.state.server.scenario_state(),
+            })
+            .unwrap(),
+        })
+    }
+
+    async fn get_scenario_state(&self, _ctx: CallContext) -> RpcResult<ExtnProviderResponse> {
+        match self.state.server.scenario_state() {
+            Some(state) => Ok(ExtnProviderResponse {
+                value: serde_json::to_value(ScenarioStateResponse {
+                    success: true,
+                    state: Some(state),
+                })
+                .unwrap(),
+            }),
+            None => Err(rpc_err(MockDeviceControllerError::RequestFailed(
+                RippleError::NotAvailable,
+            ))),
+        }
+    }
+
+    async fn set_faults(
+        &self,
+        _ctx: CallContext,
+        req: FaultConfig,
+    ) -> RpcResult<ExtnProviderResponse> {
+        self.state.server.set_faults(Some(req));
+        Ok(self.faults_response())
+    }
+
+    async fn add_faults(
+        &self,
+        _ctx: CallContext,
+        req: HashMap<String, MethodFaults>,
+    ) -> RpcResult<ExtnProviderResponse> {
+        self.state.server.add_faults(req);
+        Ok(self.faults_response())
+    }
+
+    async fn remove_faults(
+        &self,
+        _ctx: CallContext,
+        req: Vec<String>,
+    ) -> RpcResult<ExtnProviderResponse> {
+        self.state.server.remove_faults(&req);
+        Ok(self.faults_response())
+    }
+
+    async fn clear_faults(&self, _ctx: CallContext) -> RpcResult<ExtnProviderResponse> {
+        self.state.server.set_faults(None);
+        Ok(self.faults_response())
+    }
 }
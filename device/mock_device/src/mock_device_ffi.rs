@@ -19,15 +19,16 @@ use jsonrpsee::core::server::rpc_module::Methods;
 use ripple_sdk::service::service_client::ServiceClient;
 use ripple_sdk::{
     api::manifest::ripple_manifest_loader::RippleManifestLoader,
-    export_extn_channel,
+    export_extn_abi, export_extn_channel,
     extn::{
         extn_id::{ExtnClassId, ExtnId},
         ffi::ffi_channel::ExtnChannel,
     },
+    framework::ripple_contract::{ContractFulfiller, RippleContract},
     log::{error, info},
     processor::rpc_request_processor::RPCRequestProcessor,
-    tokio::{self, runtime::Runtime},
-    utils::logger::init_and_configure_logger,
+    tokio::{runtime::Runtime, task::JoinSet},
+    utils::{extn_utils::ExtnUtils, logger::init_and_configure_logger},
 };
 
 use crate::{
@@ -63,7 +64,8 @@ pub async fn start_service() {
 async fn init(client: ServiceClient) {
     if let Some(mut extn_client) = client.get_extn_client() {
         let client_c_for_init = client.clone();
-        tokio::spawn(async move {
+        let mut tasks = JoinSet::new();
+        tasks.spawn(async move {
             match boot_ws_server(extn_client.clone()).await {
                 Ok(server) => {
                     let state = MockDeviceState::new(server);
@@ -81,7 +83,7 @@ async fn init(client: ServiceClient) {
             };
         });
 
-        client_c_for_init.initialize().await;
+        ExtnUtils::supervise_tasks(client_c_for_init.initialize(), tasks).await;
     } else {
         error!("Service client does not hold an extn client. Cannot start eos extension.");
     }
@@ -127,3 +129,4 @@ fn init_extn_channel() -> ExtnChannel {
 }
 
 export_extn_channel!(ExtnChannel, init_extn_channel);
+export_extn_abi!(ContractFulfiller::new(vec![RippleContract::JsonRpsee]));
@@ -0,0 +1,335 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use ripple_sdk::log::debug;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::mock_data::ResponseSink;
+
+/// Key of the faults applied to methods which do not have their own entry
+pub const ALL_METHODS: &str = "*";
+
+const DEFAULT_REORDER_DELAY: u64 = 200;
+
+/// Faults injected by the mock device server. Probabilities are drawn from a random number
+/// generator seeded with `seed`, so a test using the same seed and the same request order sees
+/// the same faults on every run.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FaultConfig {
+    pub seed: Option<u64>,
+    /// Close a connection when it receives this many messages
+    pub reset_after: Option<u64>,
+    /// Faults keyed by the method name, `*` applies to every other method
+    #[serde(default)]
+    pub methods: HashMap<String, MethodFaults>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MethodFaults {
+    /// Probability of not sending the response
+    pub drop: Option<f64>,
+    /// Probability of replacing the response with an error
+    pub error: Option<ErrorFault>,
+    /// Probability of sending a response which is not valid JSON
+    pub malformed: Option<f64>,
+    /// Probability of holding the response back so responses to later requests overtake it
+    pub reorder: Option<ReorderFault>,
+    /// Number of extra copies sent for every event
+    pub duplicate_events: Option<u32>,
+    /// Close the connection on this call of the method, counted across connections
+    pub reset_after: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct ErrorFault {
+    pub probability: f64,
+    pub code: i64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct ReorderFault {
+    pub probability: f64,
+    /// Milliseconds the response is held back
+    pub delay: Option<u64>,
+}
+
+/// Message written to the websocket after faults have been applied
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutgoingMessage {
+    pub delay: u64,
+    pub text: String,
+}
+
+impl From<ResponseSink> for OutgoingMessage {
+    fn from(value: ResponseSink) -> Self {
+        Self {
+            delay: value.delay,
+            text: value.data.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct FaultOutcome {
+    pub messages: Vec<OutgoingMessage>,
+    pub reset: bool,
+}
+
+#[derive(Debug)]
+pub struct FaultInjector {
+    config: FaultConfig,
+    rng: StdRng,
+    calls: HashMap<String, u64>,
+}
+
+impl FaultInjector {
+    pub fn new(config: FaultConfig) -> Self {
+        let seed = config.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or_default()
+        });
+        debug!("Fault injection seed {seed}");
+        let mut injector = Self {
+            config: FaultConfig {
+                methods: HashMap::new(),
+                ..config.clone()
+            },
+            rng: StdRng::seed_from_u64(seed),
+            calls: HashMap::new(),
+        };
+        injector.add(config.methods);
+        injector
+    }
+
+    pub fn add(&mut self, methods: HashMap<String, MethodFaults>) {
+        self.config
+            .methods
+            .extend(methods.into_iter().map(|(k, v)| (k.to_lowercase(), v)));
+    }
+
+    pub fn remove(&mut self, methods: &[String]) {
+        for method in methods {
+            self.config.methods.remove(&method.to_lowercase());
+        }
+    }
+
+    pub fn get_config(&self) -> FaultConfig {
+        self.config.clone()
+    }
+
+    pub fn should_reset_connection(&self, received: u64) -> bool {
+        self.config.reset_after.map_or(false, |n| received >= n)
+    }
+
+    pub fn apply(&mut self, method: &str, responses: Vec<ResponseSink>) -> FaultOutcome {
+        let key = method.to_lowercase();
+        let Some(faults) = self
+            .config
+            .methods
+            .get(&key)
+            .or_else(|| self.config.methods.get(ALL_METHODS))
+            .cloned()
+        else {
+            return FaultOutcome {
+                messages: responses.into_iter().map(OutgoingMessage::from).collect(),
+                reset: false,
+            };
+        };
+
+        let count = self.calls.entry(key).or_insert(0);
+        *count += 1;
+        if faults.reset_after.map_or(false, |n| *count >= n) {
+            debug!("Fault: resetting connection on call {} of {method}", count);
+            return FaultOutcome {
+                messages: Vec::new(),
+                reset: true,
+            };
+        }
+
+        let mut messages = Vec::new();
+        for response in responses {
+            if response.data.get("id").is_none() {
+                let copies = 1 + faults.duplicate_events.unwrap_or(0);
+                for _ in 0..copies {
+                    messages.push(OutgoingMessage::from(ResponseSink {
+                        delay: response.delay,
+                        data: response.data.clone(),
+                    }));
+                }
+                continue;
+            }
+
+            if self.happens(faults.drop) {
+                debug!("Fault: dropping response for {method}");
+                // Events follow the response they belong to
+                return FaultOutcome::default();
+            }
+
+            let injected_error = faults
+                .error
+                .as_ref()
+                .map_or(false, |e| self.happens(Some(e.probability)));
+            let mut message = match &faults.error {
+                Some(e) if injected_error => {
+                    debug!("Fault: error response for {method}");
+                    OutgoingMessage::from(ResponseSink {
+                        delay: response.delay,
+                        data: json!({
+                            "jsonrpc": "2.0",
+                            "id": response.data.get("id").cloned().unwrap_or(Value::Null),
+                            "error": {"code": e.code, "message": e.message}
+                        }),
+                    })
+                }
+                _ => OutgoingMessage::from(response),
+            };
+
+            if self.happens(faults.malformed) {
+                debug!("Fault: malformed response for {method}");
+                let mut end = message.text.len() / 2;
+                while !message.text.is_char_boundary(end) {
+                    end -= 1;
+                }
+                message.text.truncate(end);
+            }
+
+            if let Some(reorder) = &faults.reorder {
+                if self.happens(Some(reorder.probability)) {
+                    debug!("Fault: holding back response for {method}");
+                    message.delay += reorder.delay.unwrap_or(DEFAULT_REORDER_DELAY);
+                }
+            }
+
+            messages.push(message);
+            if injected_error {
+                // Events are not sent when the call failed
+                break;
+            }
+        }
+
+        FaultOutcome {
+            messages,
+            reset: false,
+        }
+    }
+
+    fn happens(&mut self, probability: Option<f64>) -> bool {
+        match probability {
+            Some(p) if p >= 1.0 => true,
+            Some(p) if p > 0.0 => self.rng.gen_bool(p),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn responses() -> Vec<ResponseSink> {
+        vec![
+            ResponseSink {
+                delay: 0,
+                data: json!({"jsonrpc": "2.0", "id": 1, "result": 0}),
+            },
+            ResponseSink {
+                delay: 0,
+                data: json!({"jsonrpc": "2.0", "method": "client.events.onChanged", "params": {}}),
+            },
+        ]
+    }
+
+    fn fault_injector(faults: Value) -> FaultInjector {
+        FaultInjector::new(serde_json::from_value(faults).unwrap())
+    }
+
+    #[test]
+    fn test_no_faults_for_method() {
+        let mut injector = fault_injector(json!({"methods": {"other": {"drop": 1.0}}}));
+        let outcome = injector.apply("method", responses());
+        assert_eq!(outcome.messages.len(), 2);
+        assert!(!outcome.reset);
+    }
+
+    #[test]
+    fn test_drop_and_error() {
+        let mut injector = fault_injector(json!({"methods": {"Drop": {"drop": 1.0}}}));
+        assert!(injector.apply("drop", responses()).messages.is_empty());
+
+        let mut injector = fault_injector(json!({"methods": {"*": {
+            "error": {"probability": 1.0, "code": -32602, "message": "Invalid params"}
+        }}}));
+        let outcome = injector.apply("method", responses());
+        assert_eq!(outcome.messages.len(), 1);
+        let response: Value = serde_json::from_str(&outcome.messages[0].text).unwrap();
+        assert_eq!(
+            response,
+            json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32602, "message": "Invalid params"}})
+        );
+    }
+
+    #[test]
+    fn test_malformed_reorder_and_duplicates() {
+        let mut injector = fault_injector(json!({"methods": {"method": {
+            "malformed": 1.0,
+            "reorder": {"probability": 1.0, "delay": 50},
+            "duplicateEvents": 2
+        }}}));
+        let outcome = injector.apply("method", responses());
+        assert_eq!(outcome.messages.len(), 4);
+        assert!(serde_json::from_str::<Value>(&outcome.messages[0].text).is_err());
+        assert_eq!(outcome.messages[0].delay, 50);
+        assert!(outcome.messages[1..]
+            .iter()
+            .all(|m| m.text.contains("onChanged")));
+    }
+
+    #[test]
+    fn test_reset_after() {
+        let mut injector =
+            fault_injector(json!({"resetAfter": 3, "methods": {"method": {"resetAfter": 2}}}));
+        assert!(!injector.apply("method", responses()).reset);
+        assert!(injector.apply("method", responses()).reset);
+        assert!(!injector.should_reset_connection(2));
+        assert!(injector.should_reset_connection(3));
+    }
+
+    #[test]
+    fn test_seed_is_deterministic() {
+        let faults = json!({"seed": 42, "methods": {"method": {"drop": 0.5}}});
+        let run = |mut injector: FaultInjector| -> Vec<bool> {
+            (0..32)
+                .map(|_| injector.apply("method", responses()).messages.is_empty())
+                .collect()
+        };
+        let first = run(fault_injector(faults.clone()));
+        assert_eq!(first, run(fault_injector(faults)));
+        assert!(first.contains(&true) && first.contains(&false));
+    }
+}
@@ -0,0 +1,227 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::collections::HashMap;
+
+use jaq_interpret::{Ctx, Filter, FilterT, ParseCtx, RcIter, Val};
+use regex::Regex;
+use ripple_sdk::log::error;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Param value which matches any value of a field, as long as the field is present
+pub const WILDCARD: &str = "*";
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchMode {
+    /// The request params must equal the mock params
+    #[default]
+    Exact,
+    /// The mock params must be contained in the request params, extra fields are ignored
+    Subset,
+    /// Any request params match, only `jq` and `regex` are checked
+    Any,
+}
+
+/// Rules deciding whether a mock entry answers a request. Without a matcher the request params
+/// must equal the mock params.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(from = "ParamMatcherSpec")]
+pub struct ParamMatcher {
+    pub mode: MatchMode,
+    /// JQ filter run on the request params which must return `true`
+    pub jq: Option<String>,
+    /// Regular expressions which string fields of the request params must match, keyed by the
+    /// dotted path of the field
+    pub regex: HashMap<String, String>,
+    /// When several entries match, the one with the highest priority is used
+    pub priority: i64,
+    /// Filters compiled once when the mock data is loaded
+    #[serde(skip)]
+    compiled: CompiledMatcher,
+}
+
+#[derive(Deserialize)]
+struct ParamMatcherSpec {
+    #[serde(default)]
+    mode: MatchMode,
+    jq: Option<String>,
+    #[serde(default)]
+    regex: HashMap<String, String>,
+    #[serde(default)]
+    priority: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+struct CompiledMatcher {
+    // an invalid pattern or filter is None and never matches
+    regex: Vec<(String, Option<Regex>)>,
+    jq: Option<Option<Filter>>,
+}
+
+impl From<ParamMatcherSpec> for ParamMatcher {
+    fn from(spec: ParamMatcherSpec) -> Self {
+        let compiled = CompiledMatcher {
+            regex: spec
+                .regex
+                .iter()
+                .map(|(path, pattern)| (path.clone(), compile_regex(pattern)))
+                .collect(),
+            jq: spec.jq.as_deref().map(compile_jq),
+        };
+        ParamMatcher {
+            mode: spec.mode,
+            jq: spec.jq,
+            regex: spec.regex,
+            priority: spec.priority,
+            compiled,
+        }
+    }
+}
+
+impl PartialEq for ParamMatcher {
+    fn eq(&self, other: &Self) -> bool {
+        self.mode == other.mode
+            && self.jq == other.jq
+            && self.regex == other.regex
+            && self.priority == other.priority
+    }
+}
+
+impl ParamMatcher {
+    pub fn matches(&self, expected: Option<&Value>, params: &Value) -> bool {
+        let params_match = match (&self.mode, expected) {
+            (MatchMode::Any, _) | (_, None) => true,
+            (MatchMode::Exact, Some(expected)) => value_matches(expected, params, false),
+            (MatchMode::Subset, Some(expected)) => value_matches(expected, params, true),
+        };
+
+        params_match
+            && self.compiled.regex.iter().all(|(path, regex)| {
+                regex.as_ref().map_or(false, |r| {
+                    regex_matches(r, &lookup(path, params).unwrap_or(Value::Null))
+                })
+            })
+            && self.compiled.jq.as_ref().map_or(true, |filter| {
+                filter.as_ref().map_or(false, |f| jq_predicate(f, params))
+            })
+    }
+}
+
+fn value_matches(expected: &Value, actual: &Value, subset: bool) -> bool {
+    match (expected, actual) {
+        (Value::String(s), _) if s == WILDCARD => !actual.is_null(),
+        (Value::Object(e), Value::Object(a)) => {
+            (subset || e.len() == a.len())
+                && e.iter().all(|(k, v)| {
+                    a.get(k)
+                        .map_or(false, |actual| value_matches(v, actual, subset))
+                })
+        }
+        (Value::Array(e), Value::Array(a)) => {
+            e.len() == a.len() && e.iter().zip(a).all(|(e, a)| value_matches(e, a, subset))
+        }
+        (e, a) => e == a,
+    }
+}
+
+fn lookup(path: &str, value: &Value) -> Option<Value> {
+    let pointer = format!("/{}", path.trim().replace('.', "/"));
+    value.pointer(&pointer).cloned()
+}
+
+fn compile_regex(pattern: &str) -> Option<Regex> {
+    Regex::new(pattern)
+        .map_err(|e| error!("Invalid mock matcher regex {pattern}: {e:?}"))
+        .ok()
+}
+
+fn regex_matches(regex: &Regex, value: &Value) -> bool {
+    value.as_str().map_or(false, |s| regex.is_match(s))
+}
+
+fn compile_jq(filter: &str) -> Option<Filter> {
+    let (f, errs) = jaq_parse::parse(filter, jaq_parse::main());
+    if !errs.is_empty() {
+        error!("Invalid mock matcher jq {filter}: {errs:?}");
+        return None;
+    }
+    let mut defs = ParseCtx::new(Vec::new());
+    defs.insert_natives(jaq_core::core());
+    defs.insert_defs(jaq_std::std());
+    let f = defs.compile(f?);
+    if !defs.errs.is_empty() {
+        error!("Failed to compile mock matcher jq {filter}");
+        return None;
+    }
+    Some(f)
+}
+
+fn jq_predicate(filter: &Filter, input: &Value) -> bool {
+    let inputs = RcIter::new(core::iter::empty());
+    let mut out = filter.run((Ctx::new([], &inputs), Val::from(input.clone())));
+    matches!(out.next(), Some(Ok(Val::Bool(true))))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn matcher(value: Value) -> ParamMatcher {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_exact_and_wildcard() {
+        let m = matcher(json!({}));
+        let expected = json!({"videoDisplay": "HDMI0"});
+        assert!(m.matches(Some(&expected), &json!({"videoDisplay": "HDMI0"})));
+        assert!(!m.matches(
+            Some(&expected),
+            &json!({"videoDisplay": "HDMI0", "extra": true})
+        ));
+        let expected = json!({"videoDisplay": "*"});
+        assert!(m.matches(Some(&expected), &json!({"videoDisplay": "HDMI1"})));
+        assert!(!m.matches(Some(&expected), &json!({})));
+    }
+
+    #[test]
+    fn test_subset_and_any() {
+        let m = matcher(json!({"mode": "subset"}));
+        let expected = json!({"a": {"b": 1}});
+        assert!(m.matches(Some(&expected), &json!({"a": {"b": 1, "c": 2}, "d": 3})));
+        assert!(!m.matches(Some(&expected), &json!({"a": {"b": 2}})));
+        assert!(matcher(json!({"mode": "any"})).matches(Some(&expected), &Value::Null));
+    }
+
+    #[test]
+    fn test_regex_and_jq() {
+        let m = matcher(json!({
+            "mode": "any",
+            "regex": {"key.name": "^app[0-9]+$"},
+            "jq": ".count > 2"
+        }));
+        assert!(m.matches(None, &json!({"key": {"name": "app12"}, "count": 3})));
+        assert!(!m.matches(None, &json!({"key": {"name": "app12"}, "count": 1})));
+        assert!(!m.matches(None, &json!({"key": {"name": "other"}, "count": 3})));
+        assert!(!matcher(json!({"jq": "]["})).matches(None, &json!({})));
+        assert!(!matcher(json!({"regex": {"a": "("}})).matches(None, &json!({"a": "("})));
+    }
+}
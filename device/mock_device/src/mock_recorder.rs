@@ -0,0 +1,304 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::{collections::HashMap, time::Instant};
+
+use ripple_sdk::{
+    api::gateway::rpc_gateway_api::JsonRpcApiRequest,
+    log::{debug, error, info},
+    tokio::sync::mpsc,
+};
+use serde_json::{json, Value};
+
+use crate::{
+    mock_data::{EventValue, MockData, ParamResponse},
+    mock_web_socket_server::ThunderRegisterParams,
+    utils::strip_fields,
+};
+
+/// Traffic seen by the proxy while recording. The peer identifies the client connection as
+/// request ids are only unique within a connection.
+#[derive(Debug)]
+pub enum CaptureMessage {
+    Request { peer: String, message: Value },
+    Upstream { peer: String, message: Value },
+}
+
+#[derive(Debug)]
+struct PendingRequest {
+    method: String,
+    params: Option<Value>,
+    register: Option<ThunderRegisterParams>,
+}
+
+#[derive(Debug)]
+struct Registration {
+    method: String,
+    index: usize,
+    registered_at: Instant,
+}
+
+/// Folds proxied request, response and event traffic into [MockData] which is written to the
+/// capture file after every update, so a capture can be replayed by the mock device.
+///
+/// Normalisation applied to the capture
+/// - request ids are dropped, replay answers with the id of the incoming request
+/// - fields named in `volatile_fields` are removed from the recorded params
+/// - `register` params only keep the event name, which is how replay looks them up
+/// - only the first response is kept for a method and params pair
+pub struct Recorder {
+    capture: MockData,
+    pending: HashMap<(String, u64), PendingRequest>,
+    registrations: HashMap<String, Registration>,
+    volatile_fields: Vec<String>,
+    capture_file: String,
+    messages: mpsc::Receiver<CaptureMessage>,
+}
+
+impl Recorder {
+    pub fn new(
+        messages: mpsc::Receiver<CaptureMessage>,
+        capture_file: String,
+        volatile_fields: Vec<String>,
+    ) -> Self {
+        info!("starting mock Recorder with capture file: {}", capture_file);
+        Self {
+            capture: MockData::new(),
+            pending: HashMap::new(),
+            registrations: HashMap::new(),
+            volatile_fields,
+            capture_file,
+            messages,
+        }
+    }
+
+    pub async fn start(mut self) {
+        while let Some(message) = self.messages.recv().await {
+            if self.record(message) {
+                self.write_capture();
+            }
+        }
+    }
+
+    /// Returns true when the capture changed
+    fn record(&mut self, message: CaptureMessage) -> bool {
+        match message {
+            CaptureMessage::Request { peer, message } => {
+                if let Ok(request) = serde_json::from_value::<JsonRpcApiRequest>(message) {
+                    if let Some(id) = request.id {
+                        let register = request
+                            .params
+                            .clone()
+                            .filter(|_| request.method.ends_with(".register"))
+                            .and_then(|p| serde_json::from_value(p).ok());
+                        let params = self.normalise_params(&request.method, request.params);
+                        self.pending.insert(
+                            (peer, id),
+                            PendingRequest {
+                                method: request.method,
+                                params,
+                                register,
+                            },
+                        );
+                    }
+                }
+                false
+            }
+            CaptureMessage::Upstream { peer, message } => {
+                match message.get("id").and_then(Value::as_u64) {
+                    Some(id) => match self.pending.remove(&(peer, id)) {
+                        Some(request) => self.record_response(request, &message),
+                        None => {
+                            debug!("No pending request for upstream response {}", message);
+                            false
+                        }
+                    },
+                    None => self.record_event(&message),
+                }
+            }
+        }
+    }
+
+    fn normalise_params(&self, method: &str, params: Option<Value>) -> Option<Value> {
+        let mut params = params?;
+        if method.ends_with(".register") {
+            if let Some(event) = params.get("event").cloned() {
+                return Some(json!({ "event": event }));
+            }
+        }
+        strip_fields(&mut params, &self.volatile_fields);
+        Some(params)
+    }
+
+    fn record_response(&mut self, request: PendingRequest, message: &Value) -> bool {
+        let responses = self.capture.entry(request.method.clone()).or_default();
+        let (index, changed) = match responses.iter().position(|r| r.params == request.params) {
+            Some(index) => (index, false),
+            None => {
+                responses.push(ParamResponse {
+                    params: request.params,
+                    result: message.get("result").cloned(),
+                    error: message.get("error").cloned(),
+                    events: None,
+                    matcher: None,
+                });
+                (responses.len() - 1, true)
+            }
+        };
+
+        if let Some(register) = request.register {
+            self.registrations.insert(
+                format!("{}.{}", register.id, register.event),
+                Registration {
+                    method: request.method,
+                    index,
+                    registered_at: Instant::now(),
+                },
+            );
+        }
+        changed
+    }
+
+    fn record_event(&mut self, message: &Value) -> bool {
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            return false;
+        };
+        let Some(registration) = self.registrations.get(method) else {
+            debug!("No registration recorded for event {}", method);
+            return false;
+        };
+        let Some(response) = self
+            .capture
+            .get_mut(&registration.method)
+            .and_then(|responses| responses.get_mut(registration.index))
+        else {
+            return false;
+        };
+
+        response
+            .events
+            .get_or_insert_with(Vec::new)
+            .push(EventValue {
+                delay: Some(registration.registered_at.elapsed().as_millis() as u64),
+                data: message.get("params").cloned().unwrap_or(Value::Null),
+            });
+        true
+    }
+
+    fn write_capture(&self) {
+        use std::fs::File;
+        use std::io::{BufWriter, Write};
+        match File::create(&self.capture_file) {
+            Ok(file) => {
+                let mut writer = BufWriter::new(file);
+                let _ = serde_json::to_writer_pretty(&mut writer, &self.capture);
+                let _ = writer.flush();
+            }
+            Err(e) => error!("Failed to write capture file {}: {e:?}", self.capture_file),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(peer: &str, message: Value) -> CaptureMessage {
+        CaptureMessage::Request {
+            peer: peer.to_owned(),
+            message,
+        }
+    }
+
+    fn upstream(peer: &str, message: Value) -> CaptureMessage {
+        CaptureMessage::Upstream {
+            peer: peer.to_owned(),
+            message,
+        }
+    }
+
+    fn recorder() -> Recorder {
+        let (_, rx) = mpsc::channel(1);
+        Recorder::new(rx, "capture.json".to_owned(), vec!["timestamp".to_owned()])
+    }
+
+    #[test]
+    fn test_record_request_response() {
+        let mut recorder = recorder();
+        let method = "org.rdk.System.1.getSystemVersions";
+        assert!(!recorder.record(request(
+            "a",
+            json!({"jsonrpc": "2.0", "id": 7, "method": method, "params": {"v": 1, "timestamp": 123}})
+        )));
+        // Same id on another connection is not the response to this request
+        assert!(!recorder.record(upstream(
+            "b",
+            json!({"jsonrpc": "2.0", "id": 7, "result": 0})
+        )));
+        assert!(recorder.record(upstream(
+            "a",
+            json!({"jsonrpc": "2.0", "id": 7, "result": {"stbVersion": "1"}})
+        )));
+
+        let responses = recorder.capture.get(method).unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].params, Some(json!({"v": 1})));
+        assert_eq!(responses[0].result, Some(json!({"stbVersion": "1"})));
+
+        // Only the first response for the same params is kept
+        recorder.record(request(
+            "a",
+            json!({"jsonrpc": "2.0", "id": 8, "method": method, "params": {"v": 1, "timestamp": 456}}),
+        ));
+        assert!(!recorder.record(upstream(
+            "a",
+            json!({"jsonrpc": "2.0", "id": 8, "result": {"stbVersion": "2"}})
+        )));
+        assert_eq!(recorder.capture.get(method).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_record_events() {
+        let mut recorder = recorder();
+        let method = "org.rdk.System.1.register";
+        recorder.record(request(
+            "a",
+            json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": {"event": "onTimeZoneDSTChanged", "id": "client.org.rdk.System.events"}}),
+        ));
+        assert!(recorder.record(upstream(
+            "a",
+            json!({"jsonrpc": "2.0", "id": 1, "result": 0})
+        )));
+        assert!(recorder.record(upstream(
+            "a",
+            json!({"jsonrpc": "2.0", "method": "client.org.rdk.System.events.onTimeZoneDSTChanged", "params": {"newTimeZone": "Europe/London"}})
+        )));
+        assert!(!recorder.record(upstream(
+            "a",
+            json!({"jsonrpc": "2.0", "method": "client.org.rdk.System.events.unknown", "params": {}})
+        )));
+
+        let response = &recorder.capture.get(method).unwrap()[0];
+        assert_eq!(
+            response.params,
+            Some(json!({"event": "onTimeZoneDSTChanged"}))
+        );
+        let events = response.events.as_ref().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, json!({"newTimeZone": "Europe/London"}));
+    }
+}
@@ -0,0 +1,418 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::collections::HashMap;
+
+use ripple_sdk::log::debug;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::mock_data::ResponseSink;
+
+/// A stateful description of device behaviour. Unlike [crate::mock_data::MockData] which maps
+/// a request to a fixed response, a scenario keeps a set of state variables which methods can
+/// read from and write to, so flows like `setFriendlyName` followed by `getFriendlyName` behave
+/// like they would on a real device.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Scenario {
+    /// Initial values of the state variables
+    #[serde(default)]
+    pub state: Map<String, Value>,
+    /// Handlers keyed by the Thunder method name
+    #[serde(default)]
+    pub methods: HashMap<String, MethodScenario>,
+    /// Events emitted when a state variable changes, keyed by the state variable name
+    #[serde(default)]
+    pub on_change: HashMap<String, Vec<ScenarioEvent>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MethodScenario {
+    /// State variables to assign on every call. Values are templates.
+    #[serde(default)]
+    pub set: Map<String, Value>,
+    /// Numeric state variables to increment on every call
+    #[serde(default)]
+    pub increment: HashMap<String, i64>,
+    /// Responses returned for consecutive calls of the method
+    #[serde(default)]
+    pub responses: Vec<ScenarioResponse>,
+    /// What happens once all the responses in the sequence have been used
+    #[serde(default)]
+    pub repeat: SequenceRepeat,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ScenarioResponse {
+    pub result: Option<Value>,
+    pub error: Option<Value>,
+    pub delay: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SequenceRepeat {
+    /// Keep returning the last response of the sequence
+    #[default]
+    Last,
+    /// Start again from the first response of the sequence
+    Cycle,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ScenarioEvent {
+    /// Thunder event name, the notification is sent to the listener registered for it
+    pub event: String,
+    /// Event payload template
+    pub data: Value,
+    pub delay: Option<u64>,
+}
+
+/// Runtime of a [Scenario], holding the current state variables, per method call counts and
+/// the Thunder event listeners registered by the client.
+#[derive(Debug, Clone, Default)]
+pub struct ScenarioEngine {
+    scenario: Scenario,
+    state: Map<String, Value>,
+    calls: HashMap<String, u64>,
+    listeners: HashMap<String, String>,
+}
+
+impl ScenarioEngine {
+    pub fn new(scenario: Scenario) -> Self {
+        let methods = scenario
+            .methods
+            .into_iter()
+            .map(|(k, v)| (k.to_lowercase(), v))
+            .collect();
+        let state = scenario.state.clone();
+        Self {
+            scenario: Scenario {
+                methods,
+                ..scenario
+            },
+            state,
+            ..Default::default()
+        }
+    }
+
+    pub fn get_state(&self) -> Map<String, Value> {
+        self.state.clone()
+    }
+
+    /// Restores the initial state and call counts, registered listeners are kept.
+    pub fn reset(&mut self) {
+        self.state = self.scenario.state.clone();
+        self.calls.clear();
+    }
+
+    pub fn has_method(&self, method: &str) -> bool {
+        self.scenario.methods.contains_key(&method.to_lowercase())
+    }
+
+    pub fn register_listener(&mut self, event: &str, id: &str) {
+        self.listeners.insert(event.to_owned(), id.to_owned());
+    }
+
+    pub fn unregister_listener(&mut self, event: &str) {
+        self.listeners.remove(event);
+    }
+
+    pub fn handle(
+        &mut self,
+        method: &str,
+        params: Option<&Value>,
+        id: u64,
+    ) -> Option<Vec<ResponseSink>> {
+        let key = method.to_lowercase();
+        let method_scenario = self.scenario.methods.get(&key)?.clone();
+        let count = self.calls.entry(key).or_insert(0);
+        *count += 1;
+        let calls = *count;
+
+        let params = params.cloned().unwrap_or(Value::Null);
+        let previous = self.state.clone();
+
+        let context = json!({"params": params, "state": previous, "calls": calls});
+        for (name, template) in &method_scenario.set {
+            self.state
+                .insert(name.clone(), render_template(template, &context));
+        }
+        for (name, step) in &method_scenario.increment {
+            let current = self.state.get(name).and_then(Value::as_i64).unwrap_or(0);
+            self.state.insert(name.clone(), json!(current + step));
+        }
+
+        let context = json!({
+            "params": params,
+            "state": self.state,
+            "previous": previous,
+            "calls": calls
+        });
+        let mut sink_responses = vec![method_scenario.response_for_call(calls, id, &context)];
+        sink_responses.extend(self.change_events(&previous, &context));
+        debug!("Scenario responses {:?}", sink_responses);
+        Some(sink_responses)
+    }
+
+    fn change_events(&self, previous: &Map<String, Value>, context: &Value) -> Vec<ResponseSink> {
+        let mut events = Vec::new();
+        for (name, value) in &self.state {
+            if previous.get(name) == Some(value) {
+                continue;
+            }
+            for event in self.scenario.on_change.get(name).into_iter().flatten() {
+                match self.listeners.get(&event.event) {
+                    Some(id) => events.push(ResponseSink {
+                        delay: event.delay.unwrap_or(0),
+                        data: json!({
+                            "jsonrpc": "2.0",
+                            "method": format!("{}.{}", id, event.event),
+                            "params": render_template(&event.data, context)
+                        }),
+                    }),
+                    None => debug!("No listener registered for event {}", event.event),
+                }
+            }
+        }
+        events
+    }
+}
+
+impl MethodScenario {
+    fn response_for_call(&self, calls: u64, id: u64, context: &Value) -> ResponseSink {
+        let response = if self.responses.is_empty() {
+            None
+        } else {
+            let position = (calls - 1) as usize;
+            let index = match self.repeat {
+                SequenceRepeat::Last => position.min(self.responses.len() - 1),
+                SequenceRepeat::Cycle => position % self.responses.len(),
+            };
+            self.responses.get(index)
+        };
+
+        match response {
+            Some(ScenarioResponse {
+                error: Some(e),
+                delay,
+                ..
+            }) => ResponseSink {
+                delay: delay.unwrap_or(0),
+                data: json!({"jsonrpc": "2.0", "id": id, "error": render_template(e, context)}),
+            },
+            Some(ScenarioResponse { result, delay, .. }) => ResponseSink {
+                delay: delay.unwrap_or(0),
+                data: json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "result": result.as_ref().map(|r| render_template(r, context))
+                }),
+            },
+            None => ResponseSink {
+                delay: 0,
+                data: json!({"jsonrpc": "2.0", "id": id, "result": null}),
+            },
+        }
+    }
+}
+
+/// Replaces `{{path}}` placeholders in string values of the template with values looked up
+/// in the context. A string which only holds a single placeholder is replaced with the raw
+/// value so that numbers, booleans and objects keep their type. Unknown paths render as null.
+pub fn render_template(template: &Value, context: &Value) -> Value {
+    match template {
+        Value::String(s) => render_string(s, context),
+        Value::Array(a) => Value::Array(a.iter().map(|v| render_template(v, context)).collect()),
+        Value::Object(o) => Value::Object(
+            o.iter()
+                .map(|(k, v)| (k.clone(), render_template(v, context)))
+                .collect(),
+        ),
+        v => v.clone(),
+    }
+}
+
+fn lookup(path: &str, context: &Value) -> Value {
+    let pointer = format!("/{}", path.trim().replace('.', "/"));
+    context.pointer(&pointer).cloned().unwrap_or(Value::Null)
+}
+
+fn render_string(s: &str, context: &Value) -> Value {
+    let trimmed = s.trim();
+    if trimmed.starts_with("{{") && trimmed.ends_with("}}") && trimmed.matches("{{").count() == 1 {
+        return lookup(&trimmed[2..trimmed.len() - 2], context);
+    }
+
+    let mut rendered = String::new();
+    let mut rest = s;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        rendered.push_str(&rest[..start]);
+        match lookup(&rest[start + 2..start + end], context) {
+            Value::String(v) => rendered.push_str(&v),
+            Value::Null => {}
+            v => rendered.push_str(&v.to_string()),
+        }
+        rest = &rest[start + end + 2..];
+    }
+    rendered.push_str(rest);
+    Value::String(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_scenario(value: Value) -> Scenario {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_render_template() {
+        let context = json!({"params": {"name": "Den", "count": 2}, "state": {"on": true}});
+        assert_eq!(
+            render_template(&json!("{{params.count}}"), &context),
+            json!(2)
+        );
+        assert_eq!(
+            render_template(&json!({"v": ["{{state.on}}"]}), &context),
+            json!({"v": [true]})
+        );
+        assert_eq!(
+            render_template(&json!("Name {{params.name}} x{{params.count}}"), &context),
+            json!("Name Den x2")
+        );
+        assert_eq!(
+            render_template(&json!("{{params.missing}}"), &context),
+            Value::Null
+        );
+    }
+
+    #[test]
+    fn test_set_and_get_state() {
+        let mut engine = ScenarioEngine::new(get_scenario(json!({
+            "state": {"friendlyName": "Living Room"},
+            "methods": {
+                "org.rdk.System.1.setFriendlyName": {
+                    "set": {"friendlyName": "{{params.friendlyName}}"},
+                    "responses": [{"result": {"success": true}}]
+                },
+                "org.rdk.System.1.getFriendlyName": {
+                    "responses": [{"result": {"friendlyName": "{{state.friendlyName}}", "success": true}}]
+                }
+            }
+        })));
+
+        let get = engine
+            .handle("org.rdk.System.1.getFriendlyName", None, 1)
+            .unwrap();
+        assert_eq!(get[0].data["result"]["friendlyName"], json!("Living Room"));
+
+        engine
+            .handle(
+                "org.rdk.System.1.setFriendlyName",
+                Some(&json!({"friendlyName": "Den"})),
+                2,
+            )
+            .unwrap();
+        let get = engine
+            .handle("org.rdk.system.1.getfriendlyname", None, 3)
+            .unwrap();
+        assert_eq!(get[0].data["id"], json!(3));
+        assert_eq!(get[0].data["result"]["friendlyName"], json!("Den"));
+
+        engine.reset();
+        assert_eq!(engine.get_state()["friendlyName"], json!("Living Room"));
+    }
+
+    #[test]
+    fn test_sequence_and_counter() {
+        let mut engine = ScenarioEngine::new(get_scenario(json!({
+            "methods": {
+                "last": {
+                    "increment": {"counter": 1},
+                    "responses": [{"result": "{{state.counter}}"}, {"error": {"code": -1, "message": "call {{calls}}"}}]
+                },
+                "cycle": {
+                    "repeat": "cycle",
+                    "responses": [{"result": 1}, {"result": 2}]
+                }
+            }
+        })));
+
+        assert_eq!(
+            engine.handle("last", None, 1).unwrap()[0].data["result"],
+            json!(1)
+        );
+        let second = engine.handle("last", None, 2).unwrap();
+        assert_eq!(second[0].data["error"]["message"], json!("call 2"));
+        let third = engine.handle("last", None, 3).unwrap();
+        assert_eq!(third[0].data["error"]["message"], json!("call 3"));
+        assert_eq!(engine.get_state()["counter"], json!(3));
+
+        let results: Vec<Value> = (0..3)
+            .map(|i| engine.handle("cycle", None, i).unwrap()[0].data["result"].clone())
+            .collect();
+        assert_eq!(results, vec![json!(1), json!(2), json!(1)]);
+        assert!(engine.handle("unknown", None, 1).is_none());
+    }
+
+    #[test]
+    fn test_events_on_change() {
+        let mut engine = ScenarioEngine::new(get_scenario(json!({
+            "state": {"friendlyName": "Living Room"},
+            "methods": {
+                "setFriendlyName": {
+                    "set": {"friendlyName": "{{params.friendlyName}}"},
+                    "responses": [{"result": {"success": true}}]
+                }
+            },
+            "onChange": {
+                "friendlyName": [{
+                    "event": "onFriendlyNameChanged",
+                    "data": {"friendlyName": "{{state.friendlyName}}", "old": "{{previous.friendlyName}}"}
+                }]
+            }
+        })));
+
+        let params = json!({"friendlyName": "Den"});
+        let responses = engine.handle("setFriendlyName", Some(&params), 1).unwrap();
+        assert_eq!(responses.len(), 1);
+
+        engine.register_listener("onFriendlyNameChanged", "client.org.rdk.System.events");
+        let params = json!({"friendlyName": "Kitchen"});
+        let responses = engine.handle("setFriendlyName", Some(&params), 2).unwrap();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(
+            responses[1].data,
+            json!({
+                "jsonrpc": "2.0",
+                "method": "client.org.rdk.System.events.onFriendlyNameChanged",
+                "params": {"friendlyName": "Kitchen", "old": "Den"}
+            })
+        );
+
+        // Unchanged state does not emit
+        let responses = engine.handle("setFriendlyName", Some(&params), 3).unwrap();
+        assert_eq!(responses.len(), 1);
+    }
+}
@@ -18,9 +18,9 @@
 use std::fmt::Display;
 
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
 
-use crate::mock_data::MockData;
+use crate::{mock_data::MockData, mock_faults::FaultConfig};
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum PayloadTypeError {
@@ -139,6 +139,18 @@ pub struct EmitEventResponse {
     pub success: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScenarioStateResponse {
+    pub success: bool,
+    pub state: Option<Map<String, Value>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FaultsResponse {
+    pub success: bool,
+    pub faults: Option<FaultConfig>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -25,7 +25,10 @@ use http::{HeaderMap, StatusCode};
 use jsonrpsee::tracing::info;
 use ripple_sdk::{
     api::gateway::rpc_gateway_api::JsonRpcApiRequest,
-    futures::{stream::SplitSink, SinkExt, StreamExt},
+    futures::{
+        stream::{SplitSink, SplitStream},
+        SinkExt, StreamExt,
+    },
     log::{debug, error, warn},
     tokio::{
         self,
@@ -33,7 +36,7 @@ use ripple_sdk::{
         sync::Mutex,
     },
     tokio_tungstenite::{
-        accept_hdr_async,
+        accept_hdr_async, connect_async,
         tungstenite::{handshake, Error, Message, Result},
         WebSocketStream,
     },
@@ -43,9 +46,12 @@ use serde_json::{json, Value};
 
 use crate::{
     errors::MockServerWebSocketError,
-    mock_config::MockConfig,
+    mock_config::{MockConfig, MockMode},
     mock_data::{MockData, MockDataError, ParamResponse, ResponseSink},
-    utils::is_value_jsonrpc,
+    mock_faults::{FaultConfig, FaultInjector, MethodFaults, OutgoingMessage},
+    mock_recorder::{CaptureMessage, Recorder},
+    mock_scenario::{Scenario, ScenarioEngine},
+    utils::{is_value_jsonrpc, strip_fields},
 };
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -107,6 +113,8 @@ type WSConnection = Arc<Mutex<HashMap<String, SplitSink<WebSocketStream<TcpStrea
 #[derive(Debug)]
 pub struct MockWebSocketServer {
     mock_data_v2: Arc<RwLock<MockData>>,
+    scenario: Arc<RwLock<Option<ScenarioEngine>>>,
+    faults: Arc<RwLock<Option<FaultInjector>>>,
     listener: TcpListener,
     conn_path: String,
     conn_headers: HeaderMap,
@@ -118,6 +126,10 @@ pub struct MockWebSocketServer {
     track thunder methods called and their count per method
     */
     stats_channel: ripple_sdk::tokio::sync::mpsc::Sender<String>,
+    /*
+    traffic proxied to the upstream endpoint in record mode
+    */
+    capture_channel: Option<ripple_sdk::tokio::sync::mpsc::Sender<CaptureMessage>>,
 }
 pub struct StatsCollector {
     thunder_histogram: Arc<RwLock<HashMap<String, u32>>>,
@@ -194,6 +206,20 @@ impl MockWebSocketServer {
             .port();
         let (stats_tx, stats_rx) = tokio::sync::mpsc::channel(10);
         tokio::spawn(StatsCollector::new(stats_rx, config.clone().stats_file).start());
+        let capture_channel = if config.mode == MockMode::Record {
+            let (capture_tx, capture_rx) = tokio::sync::mpsc::channel(32);
+            tokio::spawn(
+                Recorder::new(
+                    capture_rx,
+                    config.capture_file.clone(),
+                    config.volatile_fields.clone(),
+                )
+                .start(),
+            );
+            Some(capture_tx)
+        } else {
+            None
+        };
 
         Ok(Self {
             listener,
@@ -209,7 +235,10 @@ impl MockWebSocketServer {
                     .map(|(k, v)| (k.to_lowercase(), v))
                     .collect(),
             )),
+            scenario: Arc::new(RwLock::new(None)),
+            faults: Arc::new(RwLock::new(None)),
             stats_channel: stats_tx,
+            capture_channel,
         })
     }
 
@@ -300,8 +329,15 @@ impl MockWebSocketServer {
 
         debug!("New WebSocket connection: {peer}");
 
+        if let (Some(capture), Some(upstream)) =
+            (self.capture_channel.clone(), &self.config.record_upstream)
+        {
+            return Self::proxy_connection(peer, send, recv, upstream, capture).await;
+        }
+
         self.add_connected_peer(&peer, send).await;
 
+        let mut received: u64 = 0;
         while let Some(msg) = recv.next().await {
             debug!("incoming message");
             let msg = msg?;
@@ -312,23 +348,48 @@ impl MockWebSocketServer {
             }
 
             if msg.is_text() || msg.is_binary() {
+                received += 1;
+                if self.should_reset_connection(received) {
+                    warn!("Fault: resetting connection peer={peer} after {received} messages");
+                    break;
+                }
+
                 let msg = msg.to_string();
-                let request_message = match serde_json::from_str::<Value>(msg.as_str()).ok() {
-                    Some(key) => key,
-                    None => {
+                let (request_message, responses) = match serde_json::from_str::<Value>(msg.as_str())
+                {
+                    Ok(request_message) => {
+                        debug!("Parsed message: {:?}", request_message);
+                        match self.find_responses(request_message.clone()).await {
+                            Some(value) => (request_message, value),
+                            None => {
+                                warn!("No mock response found for request: {msg}");
+                                continue;
+                            }
+                        }
+                    }
+                    Err(_) => {
                         warn!("Request is not valid JSON. Request: {msg}");
-                        continue;
+                        let error = ResponseSink {
+                            delay: 0,
+                            data: json!({"jsonrpc": "2.0", "id": null, "error": {"code": -32700, "message": "Parse error"}}),
+                        };
+                        (Value::Null, vec![error])
                     }
                 };
-
-                debug!("Parsed message: {:?}", request_message);
-
-                let responses = match self.find_responses(request_message.clone()).await {
-                    Some(value) => value,
-                    None => {
-                        warn!("No mock response found for request: {msg}");
-                        continue;
+                let method = request_message
+                    .get("method")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default();
+                let responses = match self.faults.write().unwrap().as_mut() {
+                    Some(injector) => {
+                        let outcome = injector.apply(method, responses);
+                        if outcome.reset {
+                            warn!("Fault: resetting connection peer={peer} on {method}");
+                            break;
+                        }
+                        outcome.messages
                     }
+                    None => responses.into_iter().map(OutgoingMessage::from).collect(),
                 };
                 let connected_peer = self.connected_peer_sinks.clone();
                 let context = request_message.clone();
@@ -349,28 +410,92 @@ impl MockWebSocketServer {
         Ok(())
     }
 
+    /// Forwards the connection to the upstream endpoint, passing all traffic in both directions
+    /// to the recorder.
+    async fn proxy_connection(
+        peer: SocketAddr,
+        mut send: SplitSink<WebSocketStream<TcpStream>, Message>,
+        mut recv: SplitStream<WebSocketStream<TcpStream>>,
+        upstream: &str,
+        capture: ripple_sdk::tokio::sync::mpsc::Sender<CaptureMessage>,
+    ) -> Result<()> {
+        let (upstream_stream, _) = connect_async(upstream).await.map_err(|e| {
+            error!("Failed to connect to record upstream {upstream}: {e:?}");
+            e
+        })?;
+        debug!("Proxying {peer} to {upstream}");
+        let (mut upstream_send, mut upstream_recv) = upstream_stream.split();
+        let peer = peer.to_string();
+
+        let to_upstream = async {
+            while let Some(msg) = recv.next().await {
+                let msg = msg?;
+                if msg.is_close() {
+                    break;
+                }
+                if let Ok(message) = serde_json::from_str::<Value>(msg.to_text().unwrap_or("")) {
+                    let _ = capture
+                        .send(CaptureMessage::Request {
+                            peer: peer.clone(),
+                            message,
+                        })
+                        .await;
+                }
+                upstream_send.send(msg).await?;
+            }
+            Ok::<(), Error>(())
+        };
+
+        let from_upstream = async {
+            while let Some(msg) = upstream_recv.next().await {
+                let msg = msg?;
+                if msg.is_close() {
+                    break;
+                }
+                if let Ok(message) = serde_json::from_str::<Value>(msg.to_text().unwrap_or("")) {
+                    let _ = capture
+                        .send(CaptureMessage::Upstream {
+                            peer: peer.clone(),
+                            message,
+                        })
+                        .await;
+                }
+                send.send(msg).await?;
+            }
+            Ok::<(), Error>(())
+        };
+
+        let result = tokio::select! {
+            r = to_upstream => r,
+            r = from_upstream => r,
+        };
+        debug!("Proxy connection closed peer={peer}");
+        result
+    }
+
     async fn send_to_sink(
         connection: WSConnection,
         peer: &str,
-        responses: Vec<ResponseSink>,
+        responses: Vec<OutgoingMessage>,
         request: Value,
     ) -> Result<()> {
-        let mut clients = connection.lock().await;
-        let sink = clients.get_mut(peer);
-        if let Some(sink) = sink {
-            for resp in responses {
-                let response = resp.data.to_string();
-                if resp.delay > 0 {
-                    tokio::time::sleep(Duration::from_millis(resp.delay)).await
-                }
-                if let Err(e) = sink.send(Message::Text(response.clone())).await {
-                    error!("Error sending response={e:?} for request={request}");
-                } else {
-                    debug!("sent response={response:?} for request={request}");
-                }
+        for resp in responses {
+            let response = resp.text;
+            // The lock is only held to write, so the responses to later requests can overtake
+            // a delayed one
+            if resp.delay > 0 {
+                tokio::time::sleep(Duration::from_millis(resp.delay)).await
+            }
+            let mut clients = connection.lock().await;
+            let Some(sink) = clients.get_mut(peer) else {
+                error!("No sink found for peer={peer:?}");
+                break;
+            };
+            if let Err(e) = sink.send(Message::Text(response.clone())).await {
+                error!("Error sending response={e:?} for request={request}");
+            } else {
+                debug!("sent response={response:?} for request={request}");
             }
-        } else {
-            error!("No sink found for peer={peer:?}");
         }
         Ok(())
     }
@@ -399,6 +524,8 @@ impl MockWebSocketServer {
                         delay: 0,
                         data: json!({"jsonrpc":"2.0","id":id,"result":[{"callsign": callsign,"classname":classname,"state":"activated", "locator": "mock_thunder"}]}),
                     }]);
+                } else if let Some(v) = self.scenario_responses(&request, id) {
+                    return Some(v);
                 } else if let Some(v) = self.responses_for_key_v2(&request) {
                     if v.events.is_some() {
                         if let Some(params) = request.params {
@@ -411,9 +538,12 @@ impl MockWebSocketServer {
                     }
                     return Some(v.get_all(Some(id), None));
                 }
+                let error = self.config.default_error.clone().unwrap_or_else(|| {
+                    json!({"code": -32001, "message":format!("mock data for request:{} , params: {:?} not found",request.method,request.params)})
+                });
                 return Some(vec![ResponseSink {
                     delay: 0,
-                    data: json!({"jsonrpc": "2.0", "id": id, "error": {"code": -32001, "message":format!("mock data for request:{} , params: {:?} not found",request.method,request.params)}}),
+                    data: json!({"jsonrpc": "2.0", "id": id, "error": error}),
                 }]);
             } else {
                 error!("Failed to get id from request {:?}", request_message);
@@ -423,31 +553,70 @@ impl MockWebSocketServer {
                 "Failed to parse into a json rpc request {:?}",
                 request_message
             );
+            return Some(vec![ResponseSink {
+                delay: 0,
+                data: json!({"jsonrpc": "2.0", "id": null, "error": {"code": -32600, "message": "Invalid Request"}}),
+            }]);
         }
 
         None
     }
 
+    fn scenario_responses(&self, req: &JsonRpcApiRequest, id: u64) -> Option<Vec<ResponseSink>> {
+        let mut scenario = self.scenario.write().unwrap();
+        let engine = scenario.as_mut()?;
+        let register = req.method.ends_with(".register");
+        if register || req.method.ends_with(".unregister") {
+            if let Some(t) = req
+                .params
+                .clone()
+                .and_then(|p| serde_json::from_value::<ThunderRegisterParams>(p).ok())
+            {
+                if register {
+                    engine.register_listener(&t.event, &t.id);
+                } else {
+                    engine.unregister_listener(&t.event);
+                }
+            }
+            if !engine.has_method(&req.method) && !self.has_mock_data(&req.method) {
+                return Some(vec![ResponseSink {
+                    delay: 0,
+                    data: json!({"jsonrpc": "2.0", "id": id, "result": 0}),
+                }]);
+            }
+        }
+        engine.handle(&req.method, req.params.as_ref(), id)
+    }
+
+    fn has_mock_data(&self, method: &str) -> bool {
+        self.mock_data_v2
+            .read()
+            .unwrap()
+            .contains_key(&method.to_lowercase())
+    }
+
     fn responses_for_key_v2(&self, req: &JsonRpcApiRequest) -> Option<ParamResponse> {
         let mock_data = self.mock_data_v2.read().unwrap();
-        if let Some(v) = mock_data.get(&req.method.to_lowercase()).cloned() {
-            if v.len() == 1 {
-                return v.first().cloned();
-            } else if let Some(params) = &req.params {
-                let mut new_params = params.clone();
-                if req.method.ends_with(".register") {
-                    if let Some(v) = params.get("event").cloned() {
-                        new_params = json!({"event": v})
-                    }
-                }
-                for response in v {
-                    if response.get_key(&new_params).is_some() {
-                        return Some(response);
-                    }
-                }
+        let v = mock_data.get(&req.method.to_lowercase())?;
+        if v.len() == 1 && v[0].matcher.is_none() {
+            return v.first().cloned();
+        }
+
+        let mut new_params = req.params.clone().unwrap_or(Value::Null);
+        if req.method.ends_with(".register") {
+            if let Some(v) = new_params.get("event").cloned() {
+                new_params = json!({"event": v})
             }
+        } else {
+            strip_fields(&mut new_params, &self.config.volatile_fields);
         }
-        None
+
+        // Highest priority wins, the first entry wins between equal priorities
+        v.iter()
+            .enumerate()
+            .filter(|(_, response)| response.matches(&new_params))
+            .max_by_key(|(i, response)| (response.priority(), std::cmp::Reverse(*i)))
+            .map(|(_, response)| response.clone())
     }
 
     async fn add_connected_peer(
@@ -505,6 +674,63 @@ impl MockWebSocketServer {
         Ok(())
     }
 
+    fn should_reset_connection(&self, received: u64) -> bool {
+        self.faults
+            .read()
+            .unwrap()
+            .as_ref()
+            .map_or(false, |injector| injector.should_reset_connection(received))
+    }
+
+    pub fn set_faults(&self, faults: Option<FaultConfig>) {
+        let mut current = self.faults.write().unwrap();
+        *current = faults.map(FaultInjector::new);
+    }
+
+    pub fn add_faults(&self, methods: HashMap<String, MethodFaults>) {
+        let mut current = self.faults.write().unwrap();
+        current
+            .get_or_insert_with(|| FaultInjector::new(FaultConfig::default()))
+            .add(methods);
+    }
+
+    pub fn remove_faults(&self, methods: &[String]) {
+        if let Some(injector) = self.faults.write().unwrap().as_mut() {
+            injector.remove(methods);
+        }
+    }
+
+    pub fn fault_config(&self) -> Option<FaultConfig> {
+        self.faults
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|injector| injector.get_config())
+    }
+
+    pub fn set_scenario(&self, scenario: Option<Scenario>) {
+        let mut current = self.scenario.write().unwrap();
+        *current = scenario.map(ScenarioEngine::new);
+    }
+
+    pub fn reset_scenario(&self) -> bool {
+        match self.scenario.write().unwrap().as_mut() {
+            Some(engine) => {
+                engine.reset();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn scenario_state(&self) -> Option<serde_json::Map<String, Value>> {
+        self.scenario
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|engine| engine.get_state())
+    }
+
     pub async fn emit_event(&self, event: &Value, delay: u64) {
         let mut peers = self.connected_peer_sinks.lock().await;
         let event_value = event.to_string();
@@ -677,4 +903,265 @@ mod tests {
         );
         assert_eq!(&response, &expected);
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_scenario_takes_precedence_over_mock_data() {
+        let method = "org.rdk.System.1.getFriendlyName";
+        let mock_data = get_mock_data(json!({
+            method: [{"result": {"friendlyName": "static", "success": true}}]
+        }));
+        let server = start_server(mock_data).await;
+        let request =
+            Message::Text(json!({"jsonrpc": "2.0", "id": 1, "method": method}).to_string());
+
+        let response = request_response_with_timeout(server.clone(), request.clone())
+            .await
+            .expect("no response from server within timeout")
+            .expect("connection to server was closed")
+            .expect("error in server response");
+        assert_eq!(
+            response,
+            Message::Text(
+                json!({"id":1,"jsonrpc":"2.0","result":{"friendlyName":"static","success":true}})
+                    .to_string()
+            )
+        );
+
+        server.set_scenario(Some(
+            serde_json::from_value(json!({
+                "state": {"friendlyName": "Living Room"},
+                "methods": {
+                    method: {
+                        "responses": [{"result": {"friendlyName": "{{state.friendlyName}}", "success": true}}]
+                    }
+                }
+            }))
+            .unwrap(),
+        ));
+
+        let response = request_response_with_timeout(server.clone(), request)
+            .await
+            .expect("no response from server within timeout")
+            .expect("connection to server was closed")
+            .expect("error in server response");
+        assert_eq!(
+            response,
+            Message::Text(
+                json!({"id":1,"jsonrpc":"2.0","result":{"friendlyName":"Living Room","success":true}})
+                    .to_string()
+            )
+        );
+        assert!(server.reset_scenario());
+        assert_eq!(
+            server.scenario_state().unwrap()["friendlyName"],
+            json!("Living Room")
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_record_proxies_and_captures() {
+        let method = "org.rdk.System.1.getSystemVersions";
+        let upstream = start_server(get_mock_data(json!({
+            method: [{"result": {"stbVersion": "1", "success": true}}]
+        })))
+        .await;
+
+        let capture_file =
+            std::env::temp_dir().join(format!("mock-device-capture-{}.json", upstream.port()));
+        let config = MockConfig {
+            mode: MockMode::Record,
+            record_upstream: Some(format!("ws://127.0.0.1:{}", upstream.port())),
+            capture_file: capture_file.display().to_string(),
+            volatile_fields: vec!["timestamp".to_owned()],
+            ..Default::default()
+        };
+        let mut server_config = WsServerParameters::new();
+        server_config.port(0);
+        let recorder = MockWebSocketServer::new(MockData::new(), server_config, config)
+            .await
+            .expect("Unable to start server")
+            .into_arc();
+        tokio::spawn(recorder.clone().start_server());
+
+        let response = request_response_with_timeout(
+            recorder,
+            Message::Text(
+                json!({"jsonrpc": "2.0", "id": 3, "method": method, "params": {"timestamp": 1}})
+                    .to_string(),
+            ),
+        )
+        .await
+        .expect("no response from server within timeout")
+        .expect("connection to server was closed")
+        .expect("error in server response");
+        assert_eq!(
+            response,
+            Message::Text(
+                json!({"id":3,"jsonrpc":"2.0","result":{"stbVersion":"1","success":true}})
+                    .to_string()
+            )
+        );
+
+        let mut capture = None;
+        for _ in 0..20 {
+            if let Ok(contents) = std::fs::read_to_string(&capture_file) {
+                capture = serde_json::from_str::<MockData>(&contents).ok();
+                if capture.is_some() {
+                    break;
+                }
+            }
+            time::sleep(Duration::from_millis(50)).await;
+        }
+        let _ = std::fs::remove_file(&capture_file);
+        let capture = capture.expect("capture file not written");
+        let responses = capture.get(method).unwrap();
+        assert_eq!(responses[0].params, Some(json!({})));
+        assert_eq!(
+            responses[0].result,
+            Some(json!({"stbVersion": "1", "success": true}))
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_faults_reset_connection() {
+        let method = "org.rdk.System.1.getSystemVersions";
+        let server = start_server(get_mock_data(json!({
+            method: [{"result": {"success": true}}]
+        })))
+        .await;
+        server.set_faults(Some(
+            serde_json::from_value(json!({"seed": 1, "resetAfter": 2})).unwrap(),
+        ));
+
+        let (mut send, mut receive) = WebSocketUtils::get_ws_stream(
+            format!("ws://127.0.0.1:{}", server.port()).as_str(),
+            None,
+        )
+        .await
+        .unwrap();
+        let request =
+            Message::Text(json!({"jsonrpc": "2.0", "id": 1, "method": method}).to_string());
+        send.send(request.clone()).await.unwrap();
+        let response = time::timeout(Duration::from_secs(1), receive.next())
+            .await
+            .expect("no response from server within timeout");
+        assert!(matches!(response, Some(Ok(Message::Text(_)))));
+
+        send.send(request).await.unwrap();
+        let response = time::timeout(Duration::from_secs(1), receive.next())
+            .await
+            .expect("connection was not closed within timeout");
+        assert!(!matches!(response, Some(Ok(Message::Text(_)))));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_matchers_and_default_error() {
+        let method = "org.rdk.PersistentStore.1.getValue";
+        let mock_data = get_mock_data(json!({
+            method: [
+                {
+                    "params": {"namespace": "Settings"},
+                    "match": {"mode": "subset"},
+                    "result": {"value": "subset", "success": true}
+                },
+                {
+                    "params": {"namespace": "Settings", "key": "*"},
+                    "match": {"regex": {"key": "^voice"}, "priority": 1},
+                    "result": {"value": "regex", "success": true}
+                }
+            ]
+        }));
+        let mut server_config = WsServerParameters::new();
+        server_config.port(0);
+        let config = MockConfig {
+            default_error: Some(json!({"code": -32601, "message": "Unknown method"})),
+            ..Default::default()
+        };
+        let server = MockWebSocketServer::new(mock_data, server_config, config)
+            .await
+            .expect("Unable to start server")
+            .into_arc();
+        tokio::spawn(server.clone().start_server());
+
+        let call = |params: Value| {
+            let server = server.clone();
+            async move {
+                let response = request_response_with_timeout(
+                    server,
+                    Message::Text(
+                        json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params})
+                            .to_string(),
+                    ),
+                )
+                .await
+                .expect("no response from server within timeout")
+                .expect("connection to server was closed")
+                .expect("error in server response");
+                serde_json::from_str::<Value>(response.to_text().unwrap()).unwrap()
+            }
+        };
+
+        let response = call(json!({"namespace": "Settings", "key": "voiceGuidance"})).await;
+        assert_eq!(response["result"]["value"], json!("regex"));
+        let response =
+            call(json!({"namespace": "Settings", "key": "other", "scope": "device"})).await;
+        assert_eq!(response["result"]["value"], json!("subset"));
+        let response = call(json!({"namespace": "Other"})).await;
+        assert_eq!(
+            response["error"],
+            json!({"code": -32601, "message": "Unknown method"})
+        );
+
+        let response = request_response_with_timeout(server, Message::Text("{".to_owned()))
+            .await
+            .expect("no response from server within timeout")
+            .expect("connection to server was closed")
+            .expect("error in server response");
+        let response = serde_json::from_str::<Value>(response.to_text().unwrap()).unwrap();
+        assert_eq!(response["error"]["code"], json!(-32700));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_faults_reorder_responses() {
+        let held = "org.rdk.System.1.getSystemVersions";
+        let other = "org.rdk.System.1.getDeviceInfo";
+        let server = start_server(get_mock_data(json!({
+            held: [{"result": {"success": true}}],
+            other: [{"result": {"success": true}}]
+        })))
+        .await;
+        server.set_faults(Some(
+            serde_json::from_value(json!({
+                "seed": 1,
+                "methods": {held: {"reorder": {"probability": 1.0, "delay": 200}}}
+            }))
+            .unwrap(),
+        ));
+
+        let (mut send, mut receive) = WebSocketUtils::get_ws_stream(
+            format!("ws://127.0.0.1:{}", server.port()).as_str(),
+            None,
+        )
+        .await
+        .unwrap();
+        for (id, method) in [(1, held), (2, other)] {
+            send.send(Message::Text(
+                json!({"jsonrpc": "2.0", "id": id, "method": method}).to_string(),
+            ))
+            .await
+            .unwrap();
+        }
+
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let response = time::timeout(Duration::from_secs(1), receive.next())
+                .await
+                .expect("no response from server within timeout")
+                .expect("connection to server was closed")
+                .expect("error in server response");
+            let response = serde_json::from_str::<Value>(response.to_text().unwrap()).unwrap();
+            ids.push(response["id"].clone());
+        }
+        assert_eq!(ids, vec![json!(2), json!(1)]);
+    }
 }
@@ -24,13 +24,16 @@ use ripple_sdk::{
     tokio,
     utils::error::RippleError,
 };
+use serde::de::DeserializeOwned;
 use serde_json::Value;
 use url::{Host, Url};
 
 use crate::{
     errors::{BootFailedError, LoadMockDataError, MockDeviceError},
-    mock_config::MockConfig,
+    mock_config::{MockConfig, MockMode},
     mock_data::MockData,
+    mock_faults::FaultConfig,
+    mock_scenario::Scenario,
     mock_web_socket_server::{MockWebSocketServer, WsServerParameters},
 };
 
@@ -48,10 +51,24 @@ pub async fn boot_ws_server(
         return Err(BootFailedError::BadHostname)?;
     }
 
-    let config = load_config(&client);
+    let mut config = load_config(&client);
+    // only record and replay use the capture file
+    if matches!(config.mode, MockMode::Record | MockMode::Replay) {
+        let capture_file = resolve_mock_file(client.clone(), config.capture_file.clone()).await?;
+        config.capture_file = capture_file.display().to_string();
+    }
 
     let mut server_config = WsServerParameters::new();
-    let mock_data_v2 = load_mock_data_v2(client.clone()).await?;
+    let mock_data_v2 = match config.mode {
+        MockMode::Mock => load_mock_data_v2(client.clone()).await?,
+        MockMode::Record => {
+            if config.record_upstream.is_none() {
+                return Err(BootFailedError::RecordUpstreamMissing)?;
+            }
+            MockData::new()
+        }
+        MockMode::Replay => read_mock_data(PathBuf::from(&config.capture_file))?,
+    };
     server_config
         .port(gateway.port().unwrap_or(0))
         .path(gateway.path());
@@ -59,6 +76,9 @@ pub async fn boot_ws_server(
         .await
         .map_err(BootFailedError::ServerStartFailed)?;
 
+    ws_server.set_scenario(load_scenario(client.clone()).await?);
+    ws_server.set_faults(load_faults(client.clone()).await?);
+
     let ws_server = Arc::new(ws_server);
     let server = ws_server.clone();
 
@@ -94,10 +114,17 @@ fn is_valid_host(host: Option<Host<&str>>) -> bool {
     }
 }
 
-async fn find_mock_device_data_file(mut client: ExtnClient) -> Result<PathBuf, MockDeviceError> {
+async fn find_mock_device_data_file(client: ExtnClient) -> Result<PathBuf, MockDeviceError> {
     let file = client
         .get_config("mock_data_file")
         .unwrap_or("mock-device.json".to_owned());
+    resolve_mock_file(client, file).await
+}
+
+async fn resolve_mock_file(
+    mut client: ExtnClient,
+    file: String,
+) -> Result<PathBuf, MockDeviceError> {
     let path = PathBuf::from(file);
 
     debug!(
@@ -142,14 +169,41 @@ pub fn load_config(client: &ExtnClient) -> MockConfig {
     if let Some(c) = client.get_config("activate_all_plugins") {
         config.activate_all_plugins = c.parse::<bool>().unwrap_or(false);
     }
+    if let Some(c) = client.get_config("mock_mode") {
+        match c.parse::<MockMode>() {
+            Ok(mode) => config.mode = mode,
+            Err(_) => error!("Unknown mock_mode {c}, using {:?}", config.mode),
+        }
+    }
+    config.record_upstream = client.get_config("record_upstream");
+    if let Some(c) = client.get_config("capture_file") {
+        config.capture_file = c;
+    }
+    if let Some(c) = client.get_config("default_error") {
+        match serde_json::from_str::<Value>(&c) {
+            Ok(error) => config.default_error = Some(error),
+            Err(e) => error!("Invalid default_error {c}: {e:?}"),
+        }
+    }
+    if let Some(c) = client.get_config("volatile_fields") {
+        config.volatile_fields = c
+            .split(',')
+            .map(|f| f.trim().to_owned())
+            .filter(|f| !f.is_empty())
+            .collect();
+    }
     config
 }
 
 pub async fn load_mock_data_v2(client: ExtnClient) -> Result<MockData, MockDeviceError> {
     let path = find_mock_device_data_file(client).await?;
+    read_mock_data(path)
+}
+
+fn read_mock_data(path: PathBuf) -> Result<MockData, MockDeviceError> {
     debug!("path={:?}", path);
     if !path.is_file() {
-        return Err(LoadMockDataError::PathDoesNotExist(path))?;
+        return Err(LoadMockDataError::PathDoesNotExist(path).into());
     }
 
     let file = File::open(path.clone()).map_err(|e| {
@@ -166,6 +220,71 @@ pub async fn load_mock_data_v2(client: ExtnClient) -> Result<MockData, MockDevic
     ))
 }
 
+/// Loads the scenario file named by the optional `mock_scenario_file` config. Scenario mode is
+/// off when the config is not set.
+pub async fn load_scenario(client: ExtnClient) -> Result<Option<Scenario>, MockDeviceError> {
+    load_optional_file(
+        client,
+        "mock_scenario_file",
+        LoadMockDataError::ScenarioNotValid,
+    )
+    .await
+}
+
+/// Loads the fault injection file named by the optional `mock_faults_file` config.
+pub async fn load_faults(client: ExtnClient) -> Result<Option<FaultConfig>, MockDeviceError> {
+    load_optional_file(
+        client,
+        "mock_faults_file",
+        LoadMockDataError::FaultsNotValid,
+    )
+    .await
+}
+
+async fn load_optional_file<T: DeserializeOwned>(
+    client: ExtnClient,
+    config_key: &str,
+    invalid: LoadMockDataError,
+) -> Result<Option<T>, MockDeviceError> {
+    let Some(file) = client.get_config(config_key) else {
+        return Ok(None);
+    };
+    let path = resolve_mock_file(client, file).await?;
+    debug!("{config_key} path={:?}", path);
+    if !path.is_file() {
+        return Err(LoadMockDataError::PathDoesNotExist(path))?;
+    }
+
+    let file = File::open(path.clone()).map_err(|e| {
+        error!("Failed to open {config_key} {e:?}");
+        LoadMockDataError::FileOpenFailed(path)
+    })?;
+    let reader = BufReader::new(file);
+
+    serde_json::from_reader(reader).map(Some).map_err(|e| {
+        error!("Failed to parse {config_key} {e:?}");
+        MockDeviceError::LoadMockDataFailed(invalid)
+    })
+}
+
+/// Removes the named fields from the value at any depth
+pub fn strip_fields(value: &mut Value, fields: &[String]) {
+    match value {
+        Value::Object(o) => {
+            o.retain(|k, _| !fields.contains(k));
+            for v in o.values_mut() {
+                strip_fields(v, fields);
+            }
+        }
+        Value::Array(a) => {
+            for v in a.iter_mut() {
+                strip_fields(v, fields);
+            }
+        }
+        _ => {}
+    }
+}
+
 pub fn is_value_jsonrpc(value: &Value) -> bool {
     value.as_object().map_or(false, |req| {
         req.contains_key("jsonrpc") && req.contains_key("id") && req.contains_key("method")
@@ -185,6 +304,13 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_strip_fields() {
+        let mut value = json!({"a": 1, "ts": 2, "b": [{"ts": 3, "c": 4}]});
+        strip_fields(&mut value, &["ts".to_owned()]);
+        assert_eq!(value, json!({"a": 1, "b": [{"c": 4}]}));
+    }
+
     #[test]
     fn test_is_value_jsonrpc_false() {
         assert!(!is_value_jsonrpc(&json!({"key": "value"})));
@@ -18,7 +18,7 @@
 use thunder_ripple_sdk::ripple_sdk::{
     api::{session::EventAdjective, storage_property::StorageAdjective},
     async_channel::Receiver as CReceiver,
-    export_extn_channel, export_extn_metadata,
+    export_extn_abi, export_extn_channel, export_extn_metadata,
     extn::{
         client::{extn_client::ExtnClient, extn_sender::ExtnSender},
         extn_id::{ExtnClassId, ExtnId},
@@ -31,28 +31,33 @@ use thunder_ripple_sdk::ripple_sdk::{
     framework::ripple_contract::{ContractFulfiller, RippleContract},
     log::{debug, info},
     semver::Version,
-    tokio,
+    tokio::task::JoinSet,
     utils::{error::RippleError, extn_utils::ExtnUtils, logger::init_logger},
 };
 
 use crate::bootstrap::boot_thunder_channel::boot_thunder_channel;
 
+fn fulfills() -> ContractFulfiller {
+    ContractFulfiller::new(vec![
+        RippleContract::DeviceInfo,
+        RippleContract::WindowManager,
+        RippleContract::Browser,
+        RippleContract::RippleContext,
+        RippleContract::AppEvents,
+        RippleContract::DeviceEvents(EventAdjective::Input),
+        RippleContract::DeviceEvents(EventAdjective::VoiceGuidance),
+        RippleContract::DeviceEvents(EventAdjective::Audio),
+        RippleContract::Storage(StorageAdjective::Local),
+        RippleContract::RemoteAccessory,
+        RippleContract::Wifi,
+    ])
+}
+
 fn init_library() -> CExtnMetadata {
     let _ = init_logger("device_channel".into());
     let thunder_channel_meta = ExtnSymbolMetadata::get(
         ExtnId::new_channel(ExtnClassId::Device, "thunder".into()),
-        ContractFulfiller::new(vec![
-            RippleContract::DeviceInfo,
-            RippleContract::WindowManager,
-            RippleContract::Browser,
-            RippleContract::RippleContext,
-            RippleContract::DeviceEvents(EventAdjective::Input),
-            RippleContract::DeviceEvents(EventAdjective::VoiceGuidance),
-            RippleContract::DeviceEvents(EventAdjective::Audio),
-            RippleContract::Storage(StorageAdjective::Local),
-            RippleContract::RemoteAccessory,
-            RippleContract::Wifi,
-        ]),
+        fulfills(),
         Version::new(1, 1, 0),
     );
 
@@ -64,6 +69,7 @@ fn init_library() -> CExtnMetadata {
     extn_metadata.into()
 }
 export_extn_metadata!(CExtnMetadata, init_library);
+export_extn_abi!(fulfills());
 
 pub fn start(sender: ExtnSender, receiver: CReceiver<CExtnMessage>) {
     let _ = init_logger("device_channel".into());
@@ -73,8 +79,9 @@ pub fn start(sender: ExtnSender, receiver: CReceiver<CExtnMessage>) {
     runtime.block_on(async move {
         let client_for_receiver = client.clone();
         let client_for_thunder = client.clone();
-        tokio::spawn(async move { boot_thunder_channel(client_for_thunder).await });
-        client_for_receiver.initialize().await;
+        let mut tasks = JoinSet::new();
+        tasks.spawn(async move { boot_thunder_channel(client_for_thunder).await });
+        ExtnUtils::supervise_tasks(client_for_receiver.initialize(), tasks).await;
     });
 }
 
@@ -40,6 +40,7 @@ impl SetupThunderProcessor {
         extn_client
             .add_request_processor(ThunderDeviceInfoRequestProcessor::new(state.clone().state));
         extn_client.add_request_processor(ThunderStorageRequestProcessor::new(state.clone().state));
+        ThunderStorageRequestProcessor::watch_changes(state.clone().state);
         extn_client.add_request_processor(ThunderOpenEventsProcessor::new(state.clone().state));
 
         if extn_client.get_bool_config("rdk_telemetry") {
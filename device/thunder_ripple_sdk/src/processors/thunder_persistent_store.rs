@@ -17,12 +17,19 @@
 
 use crate::{
     client::{
-        device_operator::{DeviceCallRequest, DeviceChannelParams, DeviceOperator},
+        device_operator::{
+            DeviceCallRequest, DeviceChannelParams, DeviceOperator, DeviceResponseMessage,
+            DeviceSubscribeRequest,
+        },
         thunder_plugin::ThunderPlugin,
     },
     ripple_sdk::{
-        api::device::device_peristence::{
-            DeleteStorageProperty, DevicePersistenceRequest, GetStorageProperty, SetStorageProperty,
+        api::{
+            device::device_peristence::{
+                DeleteStorageProperty, DevicePersistenceRequest, GetStorageProperty,
+                SetStorageProperty,
+            },
+            storage_property::{StorageManagerRequest, StorageProperty},
         },
         async_trait::async_trait,
         extn::{
@@ -34,7 +41,7 @@ use crate::{
         },
         log::{debug, error, info},
         serde_json::{self, json, Value},
-        tokio::sync::mpsc,
+        tokio::{self, sync::mpsc},
         utils::error::RippleError,
     },
     thunder_state::ThunderState,
@@ -54,6 +61,12 @@ struct WifiConnectError {
     code: u32,
 }
 
+#[derive(Debug, Deserialize)]
+struct ThunderValueChangedEvent {
+    namespace: String,
+    key: String,
+}
+
 #[derive(Debug)]
 pub struct ThunderStorageRequestProcessor {
     state: ThunderState,
@@ -81,6 +94,54 @@ impl ThunderStorageRequestProcessor {
         }
     }
 
+    /// Values set in the persistent store by other clients of the plugin do not go through
+    /// Main, so Main is told to drop its cached copy of the changed property. Needs
+    /// `manager.storage` in the `uses` of the extension manifest.
+    pub fn watch_changes(state: ThunderState) {
+        tokio::spawn(async move {
+            let (sub_tx, mut sub_rx) = mpsc::channel::<DeviceResponseMessage>(32);
+            let subscribed = state
+                .get_thunder_client()
+                .subscribe(
+                    DeviceSubscribeRequest {
+                        module: ThunderPlugin::PersistentStorage.callsign_and_version(),
+                        event_name: "onValueChanged".into(),
+                        params: None,
+                        sub_id: None,
+                    },
+                    sub_tx,
+                )
+                .await;
+            if subscribed.is_err() {
+                error!("could not subscribe to persistent store changes");
+                return;
+            }
+
+            let mut client = state.get_client();
+            while let Some(m) = sub_rx.recv().await {
+                let Ok(event) = serde_json::from_value::<ThunderValueChangedEvent>(m.message)
+                else {
+                    continue;
+                };
+                let Some(property) =
+                    StorageProperty::from_namespace_key(&event.namespace, &event.key)
+                else {
+                    continue;
+                };
+                debug!("persistent store changed {:?}", property);
+                if let Err(e) = client
+                    .request(StorageManagerRequest::InvalidateCache(Some(property)))
+                    .await
+                {
+                    error!(
+                        "could not invalidate cached storage property, the extension needs manager.storage in its uses {:?}",
+                        e
+                    );
+                }
+            }
+        });
+    }
+
     pub async fn delete_key_in_persistent_store(
         state: &ThunderState,
         data: DeleteStorageProperty,
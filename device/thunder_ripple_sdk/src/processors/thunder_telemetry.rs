@@ -85,8 +85,9 @@ fn telemetry_event(event_name: &str, event_payload: String) -> DeviceChannelPara
     .into()
 }
 
-fn get_event_name(event: &TelemetryPayload) -> &'static str {
-    match event {
+/// Event name of the payload in the Telemetry plugin, None for payloads it does not know
+fn get_event_name(event: &TelemetryPayload) -> Option<&'static str> {
+    let name = match event {
         TelemetryPayload::AppLoadStart(_) => "app_load_start_split",
         TelemetryPayload::AppLoadStop(_) => "app_load_stop_split",
         TelemetryPayload::AppSDKLoaded(_) => "app_sdk_loaded_split",
@@ -97,7 +98,10 @@ fn get_event_name(event: &TelemetryPayload) -> &'static str {
         TelemetryPayload::InternalInitialize(_) => "app_internal_initialize_split",
         TelemetryPayload::FireboltInteraction(_) => "app_firebolt_split",
         TelemetryPayload::FireboltEvent(_) => "app_firebolt_event_split",
-    }
+        TelemetryPayload::ExtnStats(_) => "ripple_extn_stats_split",
+        _ => return None,
+    };
+    Some(name)
 }
 
 pub enum ThunderResponseStatus {
@@ -158,6 +162,7 @@ impl ExtnEventProcessor for ThunderTelemetryProcessor {
         if let TelemetryPayload::FireboltEvent(_) = extracted_message {
             return None;
         }
+        let event_name = get_event_name(&extracted_message)?;
 
         if let Ok(data) = render_event_data(&extracted_message) {
             info!("Sending telemetry event: {}", data);
@@ -165,7 +170,7 @@ impl ExtnEventProcessor for ThunderTelemetryProcessor {
                 .get_thunder_client()
                 .call(DeviceCallRequest {
                     method: ThunderPlugin::Telemetry.unversioned_method("logApplicationEvent"),
-                    params: Some(telemetry_event(get_event_name(&extracted_message), data)),
+                    params: Some(telemetry_event(event_name, data)),
                 })
                 .await;
         }
@@ -18,7 +18,7 @@
 use ripple_sdk::{
     api::{config::Config, status_update::ExtnStatus, storage_property::StorageAdjective},
     async_channel::Receiver as CReceiver,
-    export_extn_channel, export_extn_metadata,
+    export_extn_abi, export_extn_channel, export_extn_metadata,
     extn::{
         client::{extn_client::ExtnClient, extn_sender::ExtnSender},
         extn_client_message::ExtnResponse,
@@ -32,21 +32,25 @@ use ripple_sdk::{
     framework::ripple_contract::{ContractFulfiller, RippleContract},
     log::{debug, info},
     semver::Version,
-    tokio,
+    tokio::task::JoinSet,
     utils::{error::RippleError, extn_utils::ExtnUtils, logger::init_logger},
 };
 
 use crate::general_permission_processor::DistributorPermissionProcessor;
 
+fn fulfills() -> ContractFulfiller {
+    ContractFulfiller::new(vec![
+        RippleContract::Permissions,
+        RippleContract::Storage(StorageAdjective::Secure),
+    ])
+}
+
 fn init_library() -> CExtnMetadata {
     let _ = init_logger("distributor_general".into());
 
     let dist_meta = ExtnSymbolMetadata::get(
         ExtnId::new_channel(ExtnClassId::Distributor, "general".into()),
-        ContractFulfiller::new(vec![
-            RippleContract::Permissions,
-            RippleContract::Storage(StorageAdjective::Secure),
-        ]),
+        fulfills(),
         Version::new(1, 1, 0),
     );
 
@@ -59,6 +63,7 @@ fn init_library() -> CExtnMetadata {
 }
 
 export_extn_metadata!(CExtnMetadata, init_library);
+export_extn_abi!(fulfills());
 
 fn start_launcher(sender: ExtnSender, receiver: CReceiver<CExtnMessage>) {
     let _ = init_logger("distributor_general".into());
@@ -67,15 +72,18 @@ fn start_launcher(sender: ExtnSender, receiver: CReceiver<CExtnMessage>) {
     let runtime = ExtnUtils::get_runtime("e-dg".to_owned(), client.get_stack_size());
     runtime.block_on(async move {
         let client_c = client.clone();
-        tokio::spawn(async move {
+        let mut tasks = JoinSet::new();
+        tasks.spawn(async move {
             if let Ok(response) = client.request(Config::SavedDir).await {
                 if let Some(ExtnResponse::String(_value)) = response.payload.extract() {
                     // Stubbed out - DistributorPrivacyProcessor replaced with direct RPC calls
                     // Privacy operations now handled by distributor RPC API
                     debug!("Privacy processor stubbed out - using direct RPC calls instead");
+                    // let keys = DistributorPrivacyProcessor::store_keys(&mut client).await;
                     // client.add_request_processor(DistributorPrivacyProcessor::new(
                     //     client.clone(),
                     //     value.clone(),
+                    //     keys,
                     // ));
                 }
             }
@@ -84,7 +92,7 @@ fn start_launcher(sender: ExtnSender, receiver: CReceiver<CExtnMessage>) {
             // Lets Main know that the distributor channel is ready
             let _ = client.event(ExtnStatus::Ready);
         });
-        client_c.initialize().await;
+        ExtnUtils::supervise_tasks(client_c.initialize(), tasks).await;
     });
 }
 
@@ -21,9 +21,12 @@ use std::{
 };
 
 use ripple_sdk::{
-    api::distributor::distributor_privacy::{
-        ExclusionPolicy, GetPropertyParams, PrivacyCloudRequest, PrivacySetting, PrivacySettings,
-        SetPropertyParams,
+    api::{
+        config::Config,
+        distributor::distributor_privacy::{
+            ExclusionPolicy, GetPropertyParams, PrivacyCloudRequest, PrivacySetting,
+            PrivacySettings, SetPropertyParams,
+        },
     },
     async_trait::async_trait,
     extn::{
@@ -35,7 +38,11 @@ use ripple_sdk::{
         },
         extn_client_message::{ExtnPayloadProvider, ExtnResponse},
     },
-    framework::file_store::FileStore,
+    framework::{
+        file_store::FileStore,
+        store_encryption::{StoreEncryptionConfig, StoreKeyProvider},
+    },
+    log::error,
 };
 use serde::{Deserialize, Serialize};
 
@@ -58,13 +65,19 @@ pub struct PrivacyState {
 }
 
 impl PrivacyState {
-    fn new(client: ExtnClient, path: String) -> Self {
+    /// Settings are encrypted at rest when the device has a key provider, a plaintext store
+    /// written by an earlier release is encrypted on load
+    fn new(client: ExtnClient, path: String, keys: Option<Arc<dyn StoreKeyProvider>>) -> Self {
         let path = get_privacy_path(path);
-        let store = if let Ok(v) = FileStore::load(path.clone()) {
-            v
-        } else {
-            FileStore::new(path, PrivacyData::new())
-        };
+        let store =
+            FileStore::load_or_new_encrypted(path.clone(), PrivacyData::new(), keys.clone())
+                .unwrap_or_else(|e| {
+                    error!(
+                        "privacy settings {} are corrupt {:?}, starting new ones",
+                        path, e
+                    );
+                    FileStore::new_encrypted(path, PrivacyData::new(), keys)
+                });
 
         Self {
             client,
@@ -114,6 +127,7 @@ impl PrivacyState {
             }
             _ => data.value.set_setting(params.setting, params.value),
         }
+        data.sync();
         false
     }
 
@@ -181,12 +195,29 @@ fn get_privacy_path(saved_dir: String) -> String {
 }
 
 impl DistributorPrivacyProcessor {
-    pub fn new(client: ExtnClient, path: String) -> DistributorPrivacyProcessor {
+    pub fn new(
+        client: ExtnClient,
+        path: String,
+        keys: Option<Arc<dyn StoreKeyProvider>>,
+    ) -> DistributorPrivacyProcessor {
         DistributorPrivacyProcessor {
-            state: PrivacyState::new(client, path),
+            state: PrivacyState::new(client, path, keys),
             streamer: DefaultExtnStreamer::new(),
         }
     }
+
+    /// Key provider of the device, None when its stores are not encrypted
+    pub async fn store_keys(client: &mut ExtnClient) -> Option<Arc<dyn StoreKeyProvider>> {
+        let response = client.request(Config::StoreEncryption).await.ok()?;
+        match response.payload.extract() {
+            Some(ExtnResponse::Value(value)) => {
+                serde_json::from_value::<StoreEncryptionConfig>(value)
+                    .map(|config| config.key_provider())
+                    .ok()
+            }
+            _ => None,
+        }
+    }
 }
 
 impl ExtnStreamProcessor for DistributorPrivacyProcessor {
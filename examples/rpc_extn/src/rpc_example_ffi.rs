@@ -18,7 +18,7 @@
 use jsonrpsee::core::server::rpc_module::Methods;
 use ripple_sdk::{
     async_channel::Receiver,
-    export_extn_metadata, export_jsonrpc_extn_builder,
+    export_extn_abi, export_extn_metadata, export_jsonrpc_extn_builder,
     extn::{
         client::{extn_client::ExtnClient, extn_sender::ExtnSender},
         extn_id::{ExtnClassId, ExtnId},
@@ -39,12 +39,16 @@ use crate::rpc::{
     legacy_jsonrpsee_extn::{LegacyImpl, LegacyServer},
 };
 
+fn fulfills() -> ContractFulfiller {
+    ContractFulfiller::new(vec![RippleContract::JsonRpsee])
+}
+
 fn init_library() -> CExtnMetadata {
     let _ = init_logger("rpc_extn".into());
 
     let json_rpsee_extn_meta = ExtnSymbolMetadata::get(
         ExtnId::new_extn(ExtnClassId::Jsonrpsee, "custom".into()),
-        ContractFulfiller::new(vec![RippleContract::JsonRpsee]),
+        fulfills(),
         Version::new(1, 1, 0),
     );
 
@@ -57,6 +61,7 @@ fn init_library() -> CExtnMetadata {
 }
 
 export_extn_metadata!(CExtnMetadata, init_library);
+export_extn_abi!(fulfills());
 
 fn get_rpc_extns(sender: ExtnSender, receiver: Receiver<CExtnMessage>) -> Methods {
     let mut methods = Methods::new();
@@ -18,7 +18,7 @@
 use ripple_sdk::{
     api::{firebolt::fb_telemetry::OperationalMetricRequest, status_update::ExtnStatus},
     async_channel::Receiver as CReceiver,
-    export_extn_channel, export_extn_metadata,
+    export_extn_abi, export_extn_channel, export_extn_metadata,
     extn::{
         client::{extn_client::ExtnClient, extn_sender::ExtnSender},
         extn_id::{ExtnClassId, ExtnId},
@@ -31,7 +31,7 @@ use ripple_sdk::{
     framework::ripple_contract::{ContractFulfiller, RippleContract},
     log::{debug, error, info},
     semver::Version,
-    tokio::{self, sync::mpsc::channel},
+    tokio::{sync::mpsc::channel, task::JoinSet},
     utils::{error::RippleError, extn_utils::ExtnUtils, logger::init_logger},
 };
 use tokio_tungstenite::tungstenite::{connect, Message};
@@ -53,6 +53,7 @@ fn init_library() -> CExtnMetadata {
 }
 
 export_extn_metadata!(CExtnMetadata, init_library);
+export_extn_abi!(ContractFulfiller::new(Vec::new()));
 
 fn start_launcher(sender: ExtnSender, receiver: CReceiver<CExtnMessage>) {
     let _ = init_logger("tm".into());
@@ -66,7 +67,8 @@ fn start_launcher(sender: ExtnSender, receiver: CReceiver<CExtnMessage>) {
 
         runtime.block_on(async move {
             let client_c = client.clone();
-            tokio::spawn(async move {
+            let mut tasks = JoinSet::new();
+            tasks.spawn(async move {
                 if (client.request(OperationalMetricRequest::Subscribe).await).is_ok() {
                     let (tx, mut tr) = channel(3);
                     client.add_event_processor(TelemetryProcessor::new(tx));
@@ -83,7 +85,7 @@ fn start_launcher(sender: ExtnSender, receiver: CReceiver<CExtnMessage>) {
                     let _ = client.event(ExtnStatus::Error);
                 }
             });
-            client_c.initialize().await;
+            ExtnUtils::supervise_tasks(client_c.initialize(), tasks).await;
         });
     } else {
         error!("no ws_url");
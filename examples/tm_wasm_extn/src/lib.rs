@@ -0,0 +1,80 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Port of `tm_extn` to the WebAssembly extension runtime. Build it with
+//! `cargo build --release --target wasm32-unknown-unknown` and declare it in the extension
+//! manifest with `"runtime": {"type": "wasm"}`, see `extn-manifest-tm-wasm-example.json`.
+//!
+//! A sandboxed module cannot open sockets, so the telemetry which `tm_extn` sends to `ws_url`
+//! is written to the log of Main instead.
+
+mod ripple;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use ripple::{Level, Message};
+use serde_json::{json, Value};
+
+const SUBSCRIBE_ID: &str = "subscribe";
+
+static FORWARDED: AtomicU64 = AtomicU64::new(0);
+
+fn set_status(status: &str) {
+    if let Err(e) = ripple::event("extn_status", json!({ "Status": status })) {
+        ripple::log(Level::Error, &format!("error sending status {:?}", e));
+    }
+}
+
+fn forward(payload: &Value) {
+    if let Some(telemetry) = payload.get("OperationalMetrics") {
+        let count = FORWARDED.fetch_add(1, Ordering::Relaxed) + 1;
+        ripple::log(Level::Info, &format!("telemetry {} {}", count, telemetry));
+    }
+}
+
+pub(crate) fn on_message(message: Message) {
+    match message {
+        Message::Start { id, .. } => {
+            ripple::log(Level::Info, &format!("Starting {}", id));
+            let subscribed =
+                ripple::add_event_processor("telemetry_events_listener").and_then(|_| {
+                    ripple::request(
+                        SUBSCRIBE_ID,
+                        "observability",
+                        json!({ "OperationalMetricsRequest": "Subscribe" }),
+                    )
+                });
+            if let Err(e) = subscribed {
+                ripple::log(Level::Error, &format!("error subscribing {:?}", e));
+                set_status("Error");
+            }
+        }
+        Message::Response { id, payload } if id == SUBSCRIBE_ID => {
+            // Lets Main know that the distributor channel is ready
+            if payload.get("Error").is_some() {
+                set_status("Error");
+            } else {
+                set_status("Ready");
+            }
+        }
+        Message::Event { payload, .. } => forward(&payload),
+        Message::Request { id, .. } => {
+            let _ = ripple::respond(&id, json!({ "Error": "ProcessorError" }));
+        }
+        Message::Response { .. } => {}
+    }
+}
@@ -0,0 +1,148 @@
+// Copyright 2023 Comcast Cable Communications Management, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Bindings to the host functions of the Ripple WebAssembly extension runtime
+
+// not every binding is used by this sample
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+const ABI_VERSION: i32 = 1;
+
+#[link(wasm_import_module = "ripple")]
+extern "C" {
+    #[link_name = "add_request_processor"]
+    fn host_add_request_processor(ptr: *const u8, len: usize) -> i32;
+    #[link_name = "add_event_processor"]
+    fn host_add_event_processor(ptr: *const u8, len: usize) -> i32;
+    #[link_name = "request"]
+    fn host_request(ptr: *const u8, len: usize) -> i32;
+    #[link_name = "respond"]
+    fn host_respond(ptr: *const u8, len: usize) -> i32;
+    #[link_name = "event"]
+    fn host_event(ptr: *const u8, len: usize) -> i32;
+    #[link_name = "log"]
+    fn host_log(level: i32, ptr: *const u8, len: usize) -> i32;
+}
+
+/// Message given by Main, the payloads are the JSON of the `ExtnRequest`, `ExtnEvent` and
+/// `ExtnResponse` of the SDK
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    Start {
+        id: String,
+        config: HashMap<String, String>,
+    },
+    Request {
+        id: String,
+        contract: String,
+        payload: Value,
+    },
+    Event {
+        contract: String,
+        payload: Value,
+    },
+    Response {
+        id: String,
+        payload: Value,
+    },
+}
+
+pub enum Level {
+    Error = 0,
+    Warn = 1,
+    Info = 2,
+    Debug = 3,
+    Trace = 4,
+}
+
+/// Negative code returned by the host
+#[derive(Debug)]
+pub struct HostError(pub i32);
+
+fn call(
+    f: unsafe extern "C" fn(*const u8, usize) -> i32,
+    value: &impl Serialize,
+) -> Result<(), HostError> {
+    let bytes = serde_json::to_vec(value).map_err(|_| HostError(-2))?;
+    match unsafe { f(bytes.as_ptr(), bytes.len()) } {
+        0 => Ok(()),
+        code => Err(HostError(code)),
+    }
+}
+
+pub fn log(level: Level, message: &str) {
+    unsafe {
+        host_log(level as i32, message.as_ptr(), message.len());
+    }
+}
+
+pub fn add_request_processor(contract: &str) -> Result<(), HostError> {
+    call(host_add_request_processor, &contract)
+}
+
+pub fn add_event_processor(contract: &str) -> Result<(), HostError> {
+    call(host_add_event_processor, &contract)
+}
+
+/// Sends a request, its response is given as a [Message::Response] with the same id
+pub fn request(id: &str, contract: &str, payload: Value) -> Result<(), HostError> {
+    call(
+        host_request,
+        &serde_json::json!({"id": id, "contract": contract, "payload": payload}),
+    )
+}
+
+pub fn respond(id: &str, payload: Value) -> Result<(), HostError> {
+    call(
+        host_respond,
+        &serde_json::json!({"id": id, "payload": payload}),
+    )
+}
+
+pub fn event(contract: &str, payload: Value) -> Result<(), HostError> {
+    call(
+        host_event,
+        &serde_json::json!({"contract": contract, "payload": payload}),
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn ripple_abi_version() -> i32 {
+    ABI_VERSION
+}
+
+#[no_mangle]
+pub extern "C" fn ripple_alloc(len: usize) -> *mut u8 {
+    Box::into_raw(vec![0u8; len].into_boxed_slice()) as *mut u8
+}
+
+/// # Safety
+/// `ptr` and `len` must come from [ripple_alloc], the buffer is freed here
+#[no_mangle]
+pub unsafe extern "C" fn ripple_on_message(ptr: *mut u8, len: usize) {
+    let bytes = Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len));
+    match serde_json::from_slice::<Message>(&bytes) {
+        Ok(message) => crate::on_message(message),
+        Err(e) => log(Level::Error, &format!("invalid message {}", e)),
+    }
+}